@@ -0,0 +1,32 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use lurk::analysis::synthetic::synthetic_events;
+use lurk::analysis::{BigramTimingComparison, FilterConfig, FrequencyAnalysis, TimingAnalysis};
+
+const EVENT_COUNT: usize = 500_000;
+
+fn analysis_benches(c: &mut Criterion) {
+    let events = synthetic_events(EVENT_COUNT);
+    let config = FilterConfig::default();
+
+    let mut group = c.benchmark_group("analysis_500k");
+    group.sample_size(10);
+
+    group.bench_function("frequency_from_events", |b| {
+        b.iter(|| FrequencyAnalysis::from_events(black_box(&events)))
+    });
+
+    group.bench_function("timing_from_events", |b| {
+        b.iter(|| TimingAnalysis::from_events(black_box(&events), config.clone()))
+    });
+
+    let (before, after) = events.split_at(events.len() / 2);
+    group.bench_function("bigram_timing_windows", |b| {
+        b.iter(|| BigramTimingComparison::from_events(black_box(before), black_box(after), &config, 5))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, analysis_benches);
+criterion_main!(benches);
@@ -0,0 +1,228 @@
+use anyhow::anyhow;
+use std::collections::HashMap;
+
+use crate::analysis::FrequencyAnalysis;
+use crate::models::keycode::{KeyCategory, KeyCode};
+
+/// Letter frequencies of written English in percent (Lewand, "Cryptological
+/// Mathematics"), summing to about 100.
+pub const ENGLISH_LETTER_PCT: [(char, f64); 26] = [
+    ('E', 12.70),
+    ('T', 9.06),
+    ('A', 8.17),
+    ('O', 7.51),
+    ('I', 6.97),
+    ('N', 6.75),
+    ('S', 6.33),
+    ('H', 6.09),
+    ('R', 5.99),
+    ('D', 4.25),
+    ('L', 4.03),
+    ('C', 2.78),
+    ('U', 2.76),
+    ('M', 2.41),
+    ('W', 2.36),
+    ('F', 2.23),
+    ('G', 2.02),
+    ('Y', 1.97),
+    ('P', 1.93),
+    ('B', 1.49),
+    ('V', 0.98),
+    ('K', 0.77),
+    ('J', 0.15),
+    ('X', 0.15),
+    ('Q', 0.10),
+    ('Z', 0.07),
+];
+
+/// The 30 most common English letter pairs in percent of all letter pairs
+/// (Norvig's Google Books counts). Pairs not listed are left out of the
+/// comparison rather than assumed rare.
+pub const ENGLISH_BIGRAM_PCT: [(&str, f64); 30] = [
+    ("TH", 3.56),
+    ("HE", 3.07),
+    ("IN", 2.43),
+    ("ER", 2.05),
+    ("AN", 1.99),
+    ("RE", 1.85),
+    ("ON", 1.76),
+    ("AT", 1.49),
+    ("EN", 1.45),
+    ("ND", 1.35),
+    ("TI", 1.34),
+    ("ES", 1.34),
+    ("OR", 1.28),
+    ("TE", 1.20),
+    ("OF", 1.17),
+    ("ED", 1.17),
+    ("IS", 1.13),
+    ("IT", 1.12),
+    ("AL", 1.09),
+    ("AR", 1.07),
+    ("ST", 1.05),
+    ("TO", 1.04),
+    ("NT", 1.04),
+    ("NG", 0.95),
+    ("SE", 0.93),
+    ("HA", 0.93),
+    ("AS", 0.87),
+    ("OU", 0.87),
+    ("IO", 0.83),
+    ("LE", 0.83),
+];
+
+/// A reference corpus for `analyze --baseline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Baseline {
+    English,
+}
+
+impl Baseline {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Baseline::English => "English",
+        }
+    }
+
+    fn letter_pct(&self) -> &'static [(char, f64)] {
+        match self {
+            Baseline::English => &ENGLISH_LETTER_PCT,
+        }
+    }
+
+    fn bigram_pct(&self) -> &'static [(&'static str, f64)] {
+        match self {
+            Baseline::English => &ENGLISH_BIGRAM_PCT,
+        }
+    }
+}
+
+impl std::str::FromStr for Baseline {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "english" | "en" => Ok(Baseline::English),
+            _ => Err(anyhow!("Unknown baseline '{}'. Use english", s)),
+        }
+    }
+}
+
+/// How much more (positive `delta`) or less often something was typed than
+/// the baseline expects, in percentage points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaselineDelta {
+    pub display: String,
+    pub observed_pct: f64,
+    pub expected_pct: f64,
+}
+
+impl BaselineDelta {
+    pub fn delta(&self) -> f64 {
+        self.observed_pct - self.expected_pct
+    }
+}
+
+/// Observed letter and letter-pair shares next to a baseline's, largest
+/// deviation first either way. Shares are of letters and letter pairs only,
+/// as in the baseline tables, so Space, Shift and shortcuts don't dilute them.
+#[derive(Debug, Clone, Default)]
+pub struct BaselineComparison {
+    pub letters: Vec<BaselineDelta>,
+    pub bigrams: Vec<BaselineDelta>,
+}
+
+impl BaselineComparison {
+    pub fn new(freq: &FrequencyAnalysis, baseline: Baseline) -> Self {
+        let is_letter = |code: u32| KeyCode(code).category() == KeyCategory::Letter;
+
+        let letter_counts: HashMap<u32, u64> = freq
+            .key_frequencies
+            .iter()
+            .filter(|k| is_letter(k.key_code))
+            .map(|k| (k.key_code, k.count))
+            .collect();
+        let letters = deltas(&letter_counts, baseline.letter_pct().iter().map(|(letter, pct)| {
+            let name = letter.to_string();
+            (KeyCode::from_name(&name).map(|code| code.0), name, *pct)
+        }));
+
+        let bigram_counts: HashMap<(u32, u32), u64> = freq
+            .bigram_frequencies
+            .iter()
+            .filter(|b| is_letter(b.first_key) && is_letter(b.second_key))
+            .map(|b| ((b.first_key, b.second_key), b.count))
+            .collect();
+        let bigrams = deltas(&bigram_counts, baseline.bigram_pct().iter().map(|(pair, pct)| {
+            let (first, second) = pair.split_at(1);
+            let codes = KeyCode::from_name(first).zip(KeyCode::from_name(second));
+            let display = format!("{} -> {}", first, second);
+            (codes.map(|(a, b)| (a.0, b.0)), display, *pct)
+        }));
+
+        Self { letters, bigrams }
+    }
+}
+
+/// Pairs each baseline entry with its observed share of `counts`, sorted by
+/// absolute deviation. Nothing observed gives an empty list.
+fn deltas<K, I>(counts: &HashMap<K, u64>, expected: I) -> Vec<BaselineDelta>
+where
+    K: std::hash::Hash + Eq,
+    I: Iterator<Item = (Option<K>, String, f64)>,
+{
+    let total: u64 = counts.values().sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    let mut deltas: Vec<BaselineDelta> = expected
+        .filter_map(|(key, display, expected_pct)| {
+            let count = counts.get(&key?).copied().unwrap_or(0);
+            Some(BaselineDelta {
+                display,
+                observed_pct: count as f64 / total as f64 * 100.0,
+                expected_pct,
+            })
+        })
+        .collect();
+    deltas.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+    deltas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::typed;
+
+    #[test]
+    fn test_vim_heavy_typing_stands_out_against_english() {
+        // Lots of j/k movement on top of ordinary text.
+        let text = "the cat sat on the mat jjjjjjjjjjkkkkkkkkkk";
+        let keys: Vec<u32> = text
+            .chars()
+            .map(|c| {
+                let name = if c == ' ' { "Space".to_string() } else { c.to_uppercase().to_string() };
+                KeyCode::from_name(&name).unwrap().0
+            })
+            .collect();
+        let freq = FrequencyAnalysis::from_events(&typed(&keys, 100));
+        let comparison = BaselineComparison::new(&freq, Baseline::English);
+
+        assert_eq!(comparison.letters.len(), 26);
+        let top = &comparison.letters[0];
+        assert!(top.display == "J" || top.display == "K", "{:?}", top);
+        assert!(top.delta() > 20.0);
+        // 37 letters typed, 10 of them J.
+        let j = comparison.letters.iter().find(|d| d.display == "J").unwrap();
+        assert!((j.observed_pct - 1000.0 / 37.0).abs() < 1e-9);
+        let e = comparison.letters.iter().find(|d| d.display == "E").unwrap();
+        assert!(e.delta() < 0.0);
+
+        let th = comparison.bigrams.iter().find(|d| d.display == "T -> H").unwrap();
+        assert!(th.observed_pct > th.expected_pct);
+        assert!(comparison.letters.windows(2).all(|w| w[0].delta().abs() >= w[1].delta().abs()));
+
+        assert_eq!("English".parse::<Baseline>().unwrap(), Baseline::English);
+        assert!("klingon".parse::<Baseline>().is_err());
+    }
+}
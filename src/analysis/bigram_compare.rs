@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crate::analysis::filters::FilterConfig;
+use crate::models::keycode::KeyCode;
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Median inter-key time for one bigram in two windows of data.
+#[derive(Debug, Clone)]
+pub struct BigramTimingDelta {
+    pub from_key: u32,
+    pub to_key: u32,
+    pub display: String,
+    pub before_median_ms: i64,
+    pub after_median_ms: i64,
+    pub before_samples: usize,
+    pub after_samples: usize,
+}
+
+impl BigramTimingDelta {
+    /// Negative when the transition got faster.
+    pub fn delta_ms(&self) -> i64 {
+        self.after_median_ms - self.before_median_ms
+    }
+
+    /// Change relative to the "before" median, `None` if that median is zero.
+    pub fn change_pct(&self) -> Option<f64> {
+        if self.before_median_ms <= 0 {
+            return None;
+        }
+        Some(self.delta_ms() as f64 / self.before_median_ms as f64 * 100.0)
+    }
+}
+
+/// Bigrams whose median timing changed between two windows ("before" and
+/// "after"). Only bigrams with at least `min_samples` intervals in both
+/// windows are compared, so one-off transitions don't dominate the report.
+#[derive(Debug, Default)]
+pub struct BigramTimingComparison {
+    /// Bigrams that got faster, largest improvement first.
+    pub improved: Vec<BigramTimingDelta>,
+    /// Bigrams that got slower, largest regression first.
+    pub regressed: Vec<BigramTimingDelta>,
+    /// Bigrams with enough samples in both windows.
+    pub compared: usize,
+}
+
+impl BigramTimingComparison {
+    pub fn from_events(
+        before: &[KeystrokeEvent],
+        after: &[KeystrokeEvent],
+        config: &FilterConfig,
+        min_samples: usize,
+    ) -> Self {
+        let min_samples = min_samples.max(1);
+        let before_medians = bigram_medians(before, config);
+        let after_medians = bigram_medians(after, config);
+
+        let mut deltas: Vec<BigramTimingDelta> = before_medians
+            .iter()
+            .filter_map(|(&(from_key, to_key), &(before_median_ms, before_samples))| {
+                let &(after_median_ms, after_samples) = after_medians.get(&(from_key, to_key))?;
+                if before_samples < min_samples || after_samples < min_samples {
+                    return None;
+                }
+                Some(BigramTimingDelta {
+                    from_key,
+                    to_key,
+                    display: format!("{} -> {}", KeyCode(from_key).to_name(), KeyCode(to_key).to_name()),
+                    before_median_ms,
+                    after_median_ms,
+                    before_samples,
+                    after_samples,
+                })
+            })
+            .collect();
+
+        let compared = deltas.len();
+        deltas.sort_by(|a, b| {
+            a.delta_ms()
+                .cmp(&b.delta_ms())
+                .then(a.from_key.cmp(&b.from_key))
+                .then(a.to_key.cmp(&b.to_key))
+        });
+
+        let improved: Vec<_> = deltas.iter().filter(|d| d.delta_ms() < 0).cloned().collect();
+        let regressed: Vec<_> = deltas.into_iter().rev().filter(|d| d.delta_ms() > 0).collect();
+
+        Self {
+            improved,
+            regressed,
+            compared,
+        }
+    }
+}
+
+/// Median interval and sample count per (from, to) key pair.
+fn bigram_medians(events: &[KeystrokeEvent], config: &FilterConfig) -> HashMap<(u32, u32), (i64, usize)> {
+    let press_events: Vec<_> = events
+        .iter()
+        .filter(|e| matches!(e.event_type, EventType::Press))
+        .collect();
+
+    let mut pair_intervals: HashMap<(u32, u32), Vec<i64>> = HashMap::new();
+    for pair in press_events.windows(2) {
+        let interval = pair[1].timestamp - pair[0].timestamp;
+        if config.is_valid_interval(interval) {
+            pair_intervals
+                .entry((pair[0].key_code, pair[1].key_code))
+                .or_default()
+                .push(interval);
+        }
+    }
+
+    pair_intervals
+        .into_iter()
+        .map(|(pair, mut intervals)| {
+            intervals.sort_unstable();
+            (pair, (intervals[intervals.len() / 2], intervals.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::press;
+
+    const T: u32 = 0x11;
+    const H: u32 = 0x04;
+    const Q: u32 = 0x0C;
+    const U: u32 = 0x20;
+    const Z: u32 = 0x06;
+
+    /// Repeats `from -> to` `times` times with `interval` ms between the two
+    /// presses and a long pause before the next repetition.
+    fn repeated(from: u32, to: u32, interval: i64, times: usize, start: i64) -> Vec<KeystrokeEvent> {
+        (0..times)
+            .flat_map(|i| {
+                let t = start + i as i64 * 10_000;
+                [press(t, from), press(t + interval, to)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reports_improvements_and_regressions() {
+        let mut before = repeated(T, H, 150, 5, 0);
+        before.extend(repeated(Q, U, 100, 5, 100_000));
+        let mut after = repeated(T, H, 90, 5, 0);
+        after.extend(repeated(Q, U, 130, 5, 100_000));
+
+        let cmp = BigramTimingComparison::from_events(&before, &after, &FilterConfig::default(), 3);
+        assert_eq!(cmp.compared, 2);
+
+        assert_eq!(cmp.improved.len(), 1);
+        assert_eq!((cmp.improved[0].from_key, cmp.improved[0].to_key), (T, H));
+        assert_eq!(cmp.improved[0].delta_ms(), -60);
+        assert_eq!(cmp.improved[0].change_pct(), Some(-40.0));
+
+        assert_eq!(cmp.regressed.len(), 1);
+        assert_eq!((cmp.regressed[0].from_key, cmp.regressed[0].to_key), (Q, U));
+        assert_eq!(cmp.regressed[0].delta_ms(), 30);
+    }
+
+    #[test]
+    fn test_requires_min_samples_in_both_windows() {
+        let before = repeated(T, H, 150, 5, 0);
+        let after = repeated(T, H, 90, 2, 0);
+
+        let cmp = BigramTimingComparison::from_events(&before, &after, &FilterConfig::default(), 3);
+        assert_eq!(cmp.compared, 0);
+        assert!(cmp.improved.is_empty());
+    }
+
+    #[test]
+    fn test_empty_windows() {
+        let cmp = BigramTimingComparison::from_events(&[], &[], &FilterConfig::default(), 0);
+        assert_eq!(cmp.compared, 0);
+
+        let only_before = repeated(Z, T, 120, 4, 0);
+        let cmp = BigramTimingComparison::from_events(&only_before, &[], &FilterConfig::default(), 1);
+        assert_eq!(cmp.compared, 0);
+    }
+
+    #[test]
+    fn test_change_pct_guards_zero_baseline() {
+        let delta = BigramTimingDelta {
+            from_key: T,
+            to_key: H,
+            display: String::new(),
+            before_median_ms: 0,
+            after_median_ms: 50,
+            before_samples: 3,
+            after_samples: 3,
+        };
+        assert_eq!(delta.change_pct(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use crate::models::keycode::{KeyCategory, KeyCode};
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Presses this close together, with the first key still held, count as a
+/// chord rather than a sequential bigram.
+pub const DEFAULT_CHORD_WINDOW_MS: i64 = 50;
+
+/// Two keys down at once. Keys are stored in key-code order, so A+S and
+/// S+A are the same chord.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChordCount {
+    pub keys: (u32, u32),
+    pub display: String,
+    pub count: u64,
+}
+
+/// Overlapping presses found by replaying the event stream: a press lands
+/// while another key, pressed at most `window_ms` earlier, is still held.
+///
+/// Modifiers are ignored, since Shift or Command held under another key is
+/// ordinary use rather than a roll. Needs release events; with
+/// `capture.record_releases` off every key looks held and nothing is counted.
+#[derive(Debug, Default)]
+pub struct ChordAnalysis {
+    pub total_presses: u64,
+    /// Presses that landed on another held key.
+    pub chord_presses: u64,
+    /// Most frequent first.
+    pub chords: Vec<ChordCount>,
+}
+
+impl ChordAnalysis {
+    pub fn from_events(events: &[KeystrokeEvent], window_ms: i64) -> Self {
+        if !events.iter().any(|e| e.event_type == EventType::Release) {
+            return Self::default();
+        }
+
+        // Held key -> when it was (last) pressed.
+        let mut held: HashMap<u32, i64> = HashMap::new();
+        let mut counts: HashMap<(u32, u32), u64> = HashMap::new();
+        let mut total_presses = 0;
+        let mut chord_presses = 0;
+
+        for event in events {
+            if KeyCode(event.key_code).category() == KeyCategory::Modifier {
+                continue;
+            }
+            match event.event_type {
+                EventType::Release => {
+                    held.remove(&event.key_code);
+                }
+                EventType::Press => {
+                    total_presses += 1;
+                    let mut overlapped = false;
+                    for (&other, &pressed_at) in &held {
+                        if other != event.key_code && event.timestamp - pressed_at <= window_ms {
+                            let pair = (other.min(event.key_code), other.max(event.key_code));
+                            *counts.entry(pair).or_insert(0) += 1;
+                            overlapped = true;
+                        }
+                    }
+                    if overlapped {
+                        chord_presses += 1;
+                    }
+                    held.insert(event.key_code, event.timestamp);
+                }
+            }
+        }
+
+        let mut chords: Vec<ChordCount> = counts
+            .into_iter()
+            .map(|((first, second), count)| ChordCount {
+                keys: (first, second),
+                display: format!("{} + {}", KeyCode(first).to_name(), KeyCode(second).to_name()),
+                count,
+            })
+            .collect();
+        chords.sort_by(|a, b| b.count.cmp(&a.count).then(a.keys.cmp(&b.keys)));
+
+        Self {
+            total_presses,
+            chord_presses,
+            chords,
+        }
+    }
+
+    pub fn top_chords(&self, n: usize) -> &[ChordCount] {
+        &self.chords[..n.min(self.chords.len())]
+    }
+
+    pub fn chord_pct(&self) -> f64 {
+        if self.total_presses == 0 {
+            return 0.0;
+        }
+        self.chord_presses as f64 / self.total_presses as f64 * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{press, release};
+
+    const A: u32 = 0x00;
+    const S: u32 = 0x01;
+    const D: u32 = 0x02;
+    const SHIFT: u32 = 0x38;
+
+    #[test]
+    fn test_overlapping_presses_are_chords() {
+        let events = vec![
+            // Roll: S goes down before A is released.
+            press(0, A),
+            press(20, S),
+            release(40, A),
+            release(60, S),
+            // Sequential: A released before S.
+            press(200, A),
+            release(230, A),
+            press(250, S),
+            release(280, S),
+            // Same roll in the other order counts as the same chord.
+            press(400, S),
+            press(410, A),
+            release(420, S),
+            release(430, A),
+        ];
+
+        let analysis = ChordAnalysis::from_events(&events, DEFAULT_CHORD_WINDOW_MS);
+        assert_eq!(analysis.total_presses, 6);
+        assert_eq!(analysis.chord_presses, 2);
+        assert_eq!(analysis.chords.len(), 1);
+        assert_eq!(analysis.chords[0].keys, (A, S));
+        assert_eq!(analysis.chords[0].count, 2);
+    }
+
+    #[test]
+    fn test_three_key_chord_and_window() {
+        let events = vec![
+            press(0, A),
+            press(10, S),
+            press(20, D),
+            release(30, A),
+            release(30, S),
+            release(30, D),
+            // S held long before D: outside the window, so not a chord.
+            press(100, S),
+            press(300, D),
+            release(310, S),
+            release(320, D),
+        ];
+
+        let analysis = ChordAnalysis::from_events(&events, DEFAULT_CHORD_WINDOW_MS);
+        let pairs: Vec<_> = analysis.chords.iter().map(|c| (c.keys, c.count)).collect();
+        assert_eq!(pairs, vec![((A, S), 1), ((A, D), 1), ((S, D), 1)]);
+        assert_eq!(analysis.chord_presses, 2);
+    }
+
+    #[test]
+    fn test_modifiers_and_missing_releases_are_ignored() {
+        let shifted = vec![press(0, SHIFT), press(10, A), release(20, A), release(30, SHIFT)];
+        assert!(ChordAnalysis::from_events(&shifted, 50).chords.is_empty());
+
+        let presses_only = vec![press(0, A), press(10, S)];
+        assert!(ChordAnalysis::from_events(&presses_only, 50).chords.is_empty());
+    }
+}
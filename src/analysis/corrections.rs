@@ -0,0 +1,72 @@
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Backspace and forward Delete.
+pub const DELETE_KEYS: [u32; 2] = [0x33, 0x75];
+
+/// Presses minus the ones that were undone: each delete press and the
+/// character it removed.
+///
+/// Each delete is assumed to remove exactly one character. Deleting a
+/// selection, Option+Backspace word deletes and key repeat all break that,
+/// so treat the result as an approximation of productive output.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EffectiveKeystrokes {
+    pub presses: u64,
+    pub deletions: u64,
+}
+
+impl EffectiveKeystrokes {
+    pub fn from_events(events: &[KeystrokeEvent]) -> Self {
+        let mut counts = Self::default();
+        for event in events.iter().filter(|e| e.event_type == EventType::Press) {
+            counts.presses += 1;
+            if DELETE_KEYS.contains(&event.key_code) {
+                counts.deletions += 1;
+            }
+        }
+        counts
+    }
+
+    pub fn effective(&self) -> u64 {
+        self.presses.saturating_sub(self.deletions * 2)
+    }
+
+    pub fn effective_pct(&self) -> f64 {
+        if self.presses == 0 {
+            return 0.0;
+        }
+        self.effective() as f64 / self.presses as f64 * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{press, release};
+
+    const BACKSPACE: u32 = 0x33;
+
+    #[test]
+    fn test_type_then_correct() {
+        // "helo", two backspaces, "llo": 9 presses leaving "hello".
+        let (h, e, l, o) = (0x04, 0x0E, 0x25, 0x1F);
+        let keys = [h, e, l, o, BACKSPACE, BACKSPACE, l, l, o];
+        let mut events: Vec<_> = keys.iter().enumerate().map(|(i, &k)| press(i as i64, k)).collect();
+        events.push(release(10, BACKSPACE));
+
+        let counts = EffectiveKeystrokes::from_events(&events);
+        assert_eq!(counts.presses, 9);
+        assert_eq!(counts.deletions, 2);
+        assert_eq!(counts.effective(), 5);
+    }
+
+    #[test]
+    fn test_more_deletes_than_text_floors_at_zero() {
+        let counts = EffectiveKeystrokes {
+            presses: 3,
+            deletions: 2,
+        };
+        assert_eq!(counts.effective(), 0);
+        assert_eq!(counts.effective_pct(), 0.0);
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp::Reverse;
+
+use chrono::{DateTime, NaiveDate, TimeZone};
+
+/// Local hours, `[start, end)`, when a long silence most likely means the
+/// daemon wasn't running rather than that nobody was typing.
+pub const ACTIVE_HOURS: (u32, u32) = (9, 21);
+
+/// Gaps reported by `CoverageScan` overlap active hours by at least this much.
+pub const MIN_ACTIVE_GAP_MS: i64 = 6 * 60 * 60 * 1000;
+
+/// Time between two consecutive events with no capture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureGap {
+    pub start: i64,
+    pub end: i64,
+    /// Part of the gap that falls within `ACTIVE_HOURS`.
+    pub active_ms: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageReport {
+    /// Calendar days with at least one event.
+    pub captured_days: u64,
+    /// Calendar days from the first event to the last, inclusive.
+    pub elapsed_days: u64,
+    /// Longest first.
+    pub gaps: Vec<CaptureGap>,
+}
+
+impl CoverageReport {
+    pub fn coverage_pct(&self) -> f64 {
+        if self.elapsed_days == 0 {
+            return 0.0;
+        }
+        self.captured_days as f64 / self.elapsed_days as f64 * 100.0
+    }
+}
+
+/// Finds blind spots in the capture history: feed event timestamps in
+/// ascending order with `push`, then call `finish`. Days and active hours
+/// are bucketed in `tz`, normally `Local`.
+pub struct CoverageScan<Tz: TimeZone> {
+    tz: Tz,
+    first_day: Option<NaiveDate>,
+    last_day: Option<NaiveDate>,
+    last_timestamp: Option<i64>,
+    captured_days: u64,
+    gaps: Vec<CaptureGap>,
+}
+
+impl<Tz: TimeZone> CoverageScan<Tz> {
+    pub fn new(tz: Tz) -> Self {
+        Self {
+            tz,
+            first_day: None,
+            last_day: None,
+            last_timestamp: None,
+            captured_days: 0,
+            gaps: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, timestamp: i64) {
+        let Some(day) = self.day_of(timestamp) else {
+            return;
+        };
+        if self.last_day != Some(day) {
+            self.captured_days += 1;
+            self.first_day.get_or_insert(day);
+            self.last_day = Some(day);
+        }
+
+        if let Some(previous) = self.last_timestamp {
+            let active_ms = self.active_overlap_ms(previous, timestamp);
+            if active_ms >= MIN_ACTIVE_GAP_MS {
+                self.gaps.push(CaptureGap {
+                    start: previous,
+                    end: timestamp,
+                    active_ms,
+                });
+            }
+        }
+        self.last_timestamp = Some(timestamp);
+    }
+
+    pub fn finish(mut self) -> CoverageReport {
+        let elapsed_days = match (self.first_day, self.last_day) {
+            (Some(first), Some(last)) => (last - first).num_days() as u64 + 1,
+            _ => 0,
+        };
+        self.gaps.sort_by_key(|gap| Reverse(gap.active_ms));
+        CoverageReport {
+            captured_days: self.captured_days,
+            elapsed_days,
+            gaps: self.gaps,
+        }
+    }
+
+    fn day_of(&self, timestamp: i64) -> Option<NaiveDate> {
+        DateTime::from_timestamp_millis(timestamp)
+            .map(|dt| dt.with_timezone(&self.tz).date_naive())
+    }
+
+    /// How much of `[start, end)` lies inside each day's active hours.
+    fn active_overlap_ms(&self, start: i64, end: i64) -> i64 {
+        let (Some(first), Some(last)) = (self.day_of(start), self.day_of(end)) else {
+            return 0;
+        };
+        let (open, close) = ACTIVE_HOURS;
+        first
+            .iter_days()
+            .take_while(|day| *day <= last)
+            .filter_map(|day| {
+                let window_start = self.local_millis(day, open)?;
+                let window_end = self.local_millis(day, close)?;
+                Some((end.min(window_end) - start.max(window_start)).max(0))
+            })
+            .sum()
+    }
+
+    fn local_millis(&self, day: NaiveDate, hour: u32) -> Option<i64> {
+        let local = day.and_hms_opt(hour, 0, 0)?;
+        self.tz
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|dt| dt.timestamp_millis())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    const HOUR: i64 = 60 * 60 * 1000;
+
+    /// Milliseconds for `hour`:00 UTC on January `day`, 2024.
+    fn at(day: u32, hour: u32) -> i64 {
+        Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn scan(timestamps: &[i64]) -> CoverageReport {
+        let mut scan = CoverageScan::new(Utc);
+        for &ts in timestamps {
+            scan.push(ts);
+        }
+        scan.finish()
+    }
+
+    #[test]
+    fn test_multi_day_gap_is_reported() {
+        let report = scan(&[at(1, 10), at(1, 14), at(1, 18), at(5, 11), at(5, 12)]);
+
+        assert_eq!(report.elapsed_days, 5);
+        assert_eq!(report.captured_days, 2);
+        assert!((report.coverage_pct() - 40.0).abs() < 0.01);
+
+        assert_eq!(report.gaps.len(), 1);
+        let gap = &report.gaps[0];
+        assert_eq!((gap.start, gap.end), (at(1, 18), at(5, 11)));
+        // 3h on the 1st, 12h on each of the 2nd to 4th, 2h on the 5th.
+        assert_eq!(gap.active_ms, 41 * HOUR);
+    }
+
+    #[test]
+    fn test_overnight_silence_is_not_a_gap() {
+        let report = scan(&[at(1, 20), at(2, 9), at(2, 14)]);
+        assert!(report.gaps.is_empty());
+        assert_eq!(report.captured_days, 2);
+        assert!((report.coverage_pct() - 100.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let report = scan(&[]);
+        assert_eq!(report, CoverageReport::default());
+        assert_eq!(report.coverage_pct(), 0.0);
+    }
+}
@@ -1,18 +1,183 @@
-use crate::models::KeystrokeEvent;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, Clone)]
+use crate::models::event::Modifier;
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Presses further apart than this belong to different typing bursts: the
+/// interval isn't timed and the two keys don't form a bigram.
+pub const DEFAULT_MAX_GAP_MS: i64 = 5000;
+
+/// `events` in ascending timestamp order. Queries already return them that
+/// way, so this only copies when a caller passes a shuffled slice (merged or
+/// hand-built data). The sort is stable: presses in the same millisecond
+/// keep their order.
+pub fn sorted_by_time(events: &[KeystrokeEvent]) -> Cow<'_, [KeystrokeEvent]> {
+    if events.is_sorted_by_key(|e| e.timestamp) {
+        return Cow::Borrowed(events);
+    }
+    let mut sorted = events.to_vec();
+    sorted.sort_by_key(|e| e.timestamp);
+    Cow::Owned(sorted)
+}
+
+/// Whether `application` contains any of `patterns`, ignoring case, so
+/// `terminal` matches `com.apple.Terminal`.
+pub fn is_excluded_app(application: &str, patterns: &[String]) -> bool {
+    if patterns.is_empty() {
+        return false;
+    }
+    let application = application.to_lowercase();
+    patterns
+        .iter()
+        .any(|pattern| application.contains(&pattern.to_lowercase()))
+}
+
+/// Whether an event recorded with `input_source` was typed with `wanted`,
+/// given in full (`com.apple.keylayout.French`) or as its last part
+/// (`French`), ignoring case.
+pub fn is_input_source(input_source: Option<&str>, wanted: &str) -> bool {
+    input_source.is_some_and(|source| {
+        source.eq_ignore_ascii_case(wanted)
+            || source.rsplit('.').next().is_some_and(|last| last.eq_ignore_ascii_case(wanted))
+    })
+}
+
+/// Shown when `--with-modifier` runs over data without releases.
+pub const MISSING_RELEASES_WARNING: &str = "Warning: no key releases recorded, so --with-modifier \
+    treats a modifier as held until max_gap_ms passes; combos are approximate.";
+
+/// Keeps keys typed while all `required` modifiers are held, for looking at
+/// shortcuts. The daemon stores a modifier only on the modifier key's own
+/// events, so which ones are held is followed through the (time-ordered)
+/// stream. Modifier keys themselves are dropped, and a kept press keeps its
+/// release even if the modifier was let go first. No `required` keeps all.
+///
+/// A modifier pressed more than `max_held_ms` ago counts as released: its
+/// release may never have been recorded (`capture.record_releases` off,
+/// sampling, or a release in a skipped app), and without a limit every later
+/// key would look like part of a combo.
+#[derive(Debug, Default)]
+pub struct ModifierFilter {
+    required: Vec<Modifier>,
+    max_held_ms: i64,
+    /// Modifier keys currently down, by key code, with when they were pressed.
+    held: HashMap<u32, (i64, Vec<Modifier>)>,
+    /// Keys whose press was kept and whose release hasn't been seen yet.
+    kept_down: HashSet<u32>,
+    releases_seen: bool,
+}
+
+impl ModifierFilter {
+    /// `max_held_ms` is normally `FilterConfig::max_gap_ms`.
+    pub fn new(required: &[Modifier], max_held_ms: i64) -> Self {
+        Self {
+            required: required.to_vec(),
+            max_held_ms,
+            ..Self::default()
+        }
+    }
+
+    /// Whether filtering ran over events without a single release, so held
+    /// modifiers could only be guessed from `max_held_ms`.
+    pub fn missed_releases(&self) -> bool {
+        self.is_active() && !self.releases_seen
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.required.is_empty()
+    }
+
+    pub fn keep(&mut self, event: &KeystrokeEvent) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        if event.event_type == EventType::Release {
+            self.releases_seen = true;
+        }
+        let max_held_ms = self.max_held_ms;
+        self.held.retain(|_, (since, _)| event.timestamp - *since <= max_held_ms);
+
+        if !event.modifiers.is_empty() {
+            match event.event_type {
+                EventType::Press => {
+                    let held = (event.timestamp, event.modifiers.clone());
+                    self.held.insert(event.key_code, held);
+                }
+                EventType::Release => {
+                    self.held.remove(&event.key_code);
+                }
+            }
+            return false;
+        }
+
+        match event.event_type {
+            EventType::Press => {
+                let all_held = self
+                    .required
+                    .iter()
+                    .all(|modifier| self.held.values().any(|(_, held)| held.contains(modifier)));
+                if all_held {
+                    self.kept_down.insert(event.key_code);
+                }
+                all_held
+            }
+            EventType::Release => self.kept_down.remove(&event.key_code),
+        }
+    }
+}
+
+/// `events` typed under all of `required`; see `ModifierFilter`.
+pub fn with_modifiers(
+    events: &[KeystrokeEvent],
+    required: &[Modifier],
+    max_held_ms: i64,
+) -> Vec<KeystrokeEvent> {
+    let mut filter = ModifierFilter::new(required, max_held_ms);
+    events.iter().filter(|e| filter.keep(e)).cloned().collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FilterConfig {
     pub max_gap_ms: i64,
     pub min_hold_ms: i64,
     pub max_hold_ms: i64,
+    /// Key pairs with fewer intervals than this get no per-pair timing stats.
+    pub min_pair_samples: usize,
+    /// Bigrams and trigrams seen fewer times than this are left out of listings.
+    pub min_ngram_count: u64,
+    /// Per-pair timings from fewer samples than this are shown but flagged:
+    /// a median over a handful of intervals says little about the pair.
+    pub low_confidence_samples: usize,
+    /// With more inter-key intervals than this, the overall percentiles are
+    /// estimated (`StreamingPercentiles`) instead of collecting and sorting
+    /// every interval. Only that one vector is saved: per-pair intervals and
+    /// hold durations are still kept per sample, so memory still grows with
+    /// the number of events. `None` always computes them exactly.
+    pub streaming_percentiles_above: Option<usize>,
+    /// Count pressing the same key twice ("ee", "ll") as a same-finger
+    /// bigram. Layout analysis usually doesn't, since the finger doesn't
+    /// move, and including repeats raises the percentage noticeably.
+    pub same_finger_repeats: bool,
+    /// List and analyze only bigrams and trigrams made entirely of letters,
+    /// leaving out transitions through Space, Shift, Backspace and the like.
+    pub letters_only_ngrams: bool,
 }
 
 impl Default for FilterConfig {
     fn default() -> Self {
         Self {
-            max_gap_ms: 5000,
+            max_gap_ms: DEFAULT_MAX_GAP_MS,
             min_hold_ms: 10,
             max_hold_ms: 2000,
+            min_pair_samples: 3,
+            min_ngram_count: 2,
+            low_confidence_samples: 30,
+            streaming_percentiles_above: None,
+            same_finger_repeats: false,
+            letters_only_ngrams: false,
         }
     }
 }
@@ -26,6 +191,10 @@ impl FilterConfig {
         duration_ms >= self.min_hold_ms && duration_ms <= self.max_hold_ms
     }
 
+    pub fn is_low_confidence(&self, samples: usize) -> bool {
+        samples < self.low_confidence_samples
+    }
+
     pub fn filter_events_by_gap<'a>(
         &self,
         events: &'a [KeystrokeEvent],
@@ -70,6 +239,16 @@ pub fn calculate_percentiles(values: &mut [i64]) -> Option<(i64, i64, i64, i64)>
     Some((p50, p90, p95, p99))
 }
 
+/// The middle of `values`, which get sorted in place; the upper of the two
+/// middle values for an even count.
+pub fn median(values: &mut [i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    Some(values[values.len() / 2])
+}
+
 fn calculate_percentile_sorted(sorted_values: &[i64], percentile: f64) -> Option<i64> {
     if sorted_values.is_empty() {
         return None;
@@ -82,15 +261,92 @@ fn calculate_percentile_sorted(sorted_values: &[i64], percentile: f64) -> Option
 mod tests {
     use super::*;
     use crate::models::{EventType, KeystrokeEvent};
+    use crate::testutil::press;
 
-    fn make_event(timestamp: i64) -> KeystrokeEvent {
-        KeystrokeEvent {
-            timestamp,
-            key_code: 0x00,
-            event_type: EventType::Press,
-            modifiers: vec![],
-            application: "test".to_string(),
-        }
+    #[test]
+    fn test_low_confidence_boundary() {
+        let config = FilterConfig::default();
+        assert!(config.is_low_confidence(0));
+        assert!(config.is_low_confidence(29));
+        assert!(!config.is_low_confidence(30));
+
+        let config = FilterConfig {
+            low_confidence_samples: 5,
+            ..FilterConfig::default()
+        };
+        assert!(config.is_low_confidence(4));
+        assert!(!config.is_low_confidence(5));
+    }
+
+    #[test]
+    fn test_excluded_app_substring_ignores_case() {
+        let patterns = vec!["terminal".to_string(), "com.slack".to_string()];
+        assert!(is_excluded_app("com.apple.Terminal", &patterns));
+        assert!(is_excluded_app("com.Slack.Desktop", &patterns));
+        assert!(!is_excluded_app("com.microsoft.VSCode", &patterns));
+        assert!(!is_excluded_app("com.apple.Terminal", &[]));
+    }
+
+    #[test]
+    fn test_input_source_matches_full_id_or_name() {
+        let french = Some("com.apple.keylayout.French");
+        assert!(is_input_source(french, "french"));
+        assert!(is_input_source(french, "com.apple.keylayout.French"));
+        assert!(!is_input_source(french, "French-PC"));
+        assert!(!is_input_source(None, "French"));
+    }
+
+    #[test]
+    fn test_with_modifiers_keeps_only_combos() {
+        let event = |timestamp, key_code, event_type, modifiers: &[Modifier]| KeystrokeEvent {
+            key_code,
+            event_type,
+            modifiers: modifiers.to_vec(),
+            ..press(timestamp, 0x00)
+        };
+        const CMD: u32 = 0x37;
+        const SHIFT: u32 = 0x38;
+        let (c, v, a) = (0x08, 0x09, 0x00);
+        let events = vec![
+            event(0, a, EventType::Press, &[]),
+            event(50, a, EventType::Release, &[]),
+            event(100, CMD, EventType::Press, &[Modifier::Command]),
+            event(150, c, EventType::Press, &[]),
+            event(200, CMD, EventType::Release, &[Modifier::Command]),
+            event(250, c, EventType::Release, &[]),
+            event(300, a, EventType::Press, &[]),
+            event(400, CMD, EventType::Press, &[Modifier::Command]),
+            event(450, SHIFT, EventType::Press, &[Modifier::Shift]),
+            event(500, v, EventType::Press, &[]),
+        ];
+
+        let kept = |required: &[Modifier]| -> Vec<(i64, u32)> {
+            with_modifiers(&events, required, DEFAULT_MAX_GAP_MS)
+                .iter()
+                .map(|e| (e.timestamp, e.key_code))
+                .collect()
+        };
+        assert_eq!(kept(&[Modifier::Command]), vec![(150, c), (250, c), (500, v)]);
+        assert_eq!(kept(&[Modifier::Command, Modifier::Shift]), vec![(500, v)]);
+        assert_eq!(kept(&[]).len(), events.len());
+    }
+
+    #[test]
+    fn test_modifier_without_release_expires() {
+        const SHIFT: u32 = 0x38;
+        let shift = KeystrokeEvent {
+            key_code: SHIFT,
+            modifiers: vec![Modifier::Shift],
+            ..press(0, SHIFT)
+        };
+        // Releases weren't recorded, so Shift is never let go.
+        let events = vec![shift, press(100, 0x00), press(200, 0x01), press(9_000, 0x02)];
+
+        let mut filter = ModifierFilter::new(&[Modifier::Shift], DEFAULT_MAX_GAP_MS);
+        let kept: Vec<i64> =
+            events.iter().filter(|e| filter.keep(e)).map(|e| e.timestamp).collect();
+        assert_eq!(kept, vec![100, 200]);
+        assert!(filter.missed_releases());
     }
 
     #[test]
@@ -135,7 +391,7 @@ mod tests {
     #[test]
     fn test_filter_events_by_gap_single() {
         let config = FilterConfig::default();
-        let events = vec![make_event(100)];
+        let events = vec![press(100, 0x00)];
         let segments = config.filter_events_by_gap(&events);
         assert_eq!(segments.len(), 1);
         assert_eq!(segments[0].len(), 1);
@@ -145,9 +401,9 @@ mod tests {
     fn test_filter_events_by_gap_continuous() {
         let config = FilterConfig::default();
         let events = vec![
-            make_event(100),
-            make_event(200),
-            make_event(300),
+            press(100, 0x00),
+            press(200, 0x00),
+            press(300, 0x00),
         ];
         
         let segments = config.filter_events_by_gap(&events);
@@ -159,10 +415,10 @@ mod tests {
     fn test_filter_events_by_gap_with_break() {
         let config = FilterConfig::default();
         let events = vec![
-            make_event(100),
-            make_event(200),
-            make_event(10000),
-            make_event(10100),
+            press(100, 0x00),
+            press(200, 0x00),
+            press(10000, 0x00),
+            press(10100, 0x00),
         ];
         
         let segments = config.filter_events_by_gap(&events);
@@ -200,4 +456,11 @@ mod tests {
         assert_eq!(p95, 42);
         assert_eq!(p99, 42);
     }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&mut [30, 10, 20]), Some(20));
+        assert_eq!(median(&mut [40, 10, 30, 20]), Some(30));
+        assert_eq!(median(&mut []), None);
+    }
 }
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::keyboard_layout::Finger;
+
+/// Share of presses (percent) each finger should ideally carry. Index and
+/// middle fingers are strongest and take the most; pinkies the least. Values
+/// are normalized before use, so they don't need to sum to exactly 100.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FingerTargets {
+    pub left_pinky: f64,
+    pub left_ring: f64,
+    pub left_middle: f64,
+    pub left_index: f64,
+    pub right_index: f64,
+    pub right_middle: f64,
+    pub right_ring: f64,
+    pub right_pinky: f64,
+}
+
+impl Default for FingerTargets {
+    fn default() -> Self {
+        Self {
+            left_pinky: 8.0,
+            left_ring: 11.0,
+            left_middle: 15.0,
+            left_index: 16.0,
+            right_index: 16.0,
+            right_middle: 15.0,
+            right_ring: 11.0,
+            right_pinky: 8.0,
+        }
+    }
+}
+
+impl FingerTargets {
+    /// Target for a finger; the thumb has none.
+    pub fn target(&self, finger: Finger) -> Option<f64> {
+        match finger {
+            Finger::LeftPinky => Some(self.left_pinky),
+            Finger::LeftRing => Some(self.left_ring),
+            Finger::LeftMiddle => Some(self.left_middle),
+            Finger::LeftIndex => Some(self.left_index),
+            Finger::RightIndex => Some(self.right_index),
+            Finger::RightMiddle => Some(self.right_middle),
+            Finger::RightRing => Some(self.right_ring),
+            Finger::RightPinky => Some(self.right_pinky),
+            Finger::Thumb => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FingerDeviation {
+    pub finger: Finger,
+    /// Share of finger-column presses on this finger, in percent.
+    pub load_pct: f64,
+    pub target_pct: f64,
+}
+
+impl FingerDeviation {
+    /// Positive when the finger works more than its target.
+    pub fn deviation(&self) -> f64 {
+        self.load_pct - self.target_pct
+    }
+}
+
+/// Finger loads compared against `FingerTargets`.
+///
+/// The score is 100 minus the share of presses that would have to move to
+/// other fingers to hit the targets exactly (half the summed absolute
+/// deviation), so 100 is a perfect match and 0 the worst possible.
+#[derive(Debug, Clone, Default)]
+pub struct FingerBalance {
+    pub fingers: Vec<FingerDeviation>,
+    pub score: f64,
+}
+
+impl FingerBalance {
+    /// `loads` are per-finger press percentages, e.g. from the dashboard.
+    /// Both loads and targets are renormalized over the eight finger columns;
+    /// thumb keys are ignored. `None` if there is no load to compare.
+    pub fn from_loads(loads: &[(Finger, f64)], targets: &FingerTargets) -> Option<Self> {
+        let columns: Vec<_> = loads
+            .iter()
+            .filter_map(|&(finger, load)| {
+                targets.target(finger).map(|target| (finger, load, target))
+            })
+            .collect();
+
+        let load_total: f64 = columns.iter().map(|(_, load, _)| load).sum();
+        let target_total: f64 = columns.iter().map(|(_, _, target)| target).sum();
+        if load_total <= 0.0 || target_total <= 0.0 {
+            return None;
+        }
+
+        let fingers: Vec<_> = columns
+            .into_iter()
+            .map(|(finger, load, target)| FingerDeviation {
+                finger,
+                load_pct: load / load_total * 100.0,
+                target_pct: target / target_total * 100.0,
+            })
+            .collect();
+
+        let moved: f64 = fingers.iter().map(|f| f.deviation().abs()).sum::<f64>() / 2.0;
+        Some(Self {
+            fingers,
+            score: (100.0 - moved).clamp(0.0, 100.0),
+        })
+    }
+
+    pub fn deviation_for(&self, finger: Finger) -> Option<&FingerDeviation> {
+        self.fingers.iter().find(|f| f.finger == finger)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERS: [Finger; 8] = [
+        Finger::LeftPinky,
+        Finger::LeftRing,
+        Finger::LeftMiddle,
+        Finger::LeftIndex,
+        Finger::RightIndex,
+        Finger::RightMiddle,
+        Finger::RightRing,
+        Finger::RightPinky,
+    ];
+
+    fn loads_from_targets(targets: &FingerTargets) -> Vec<(Finger, f64)> {
+        FINGERS
+            .iter()
+            .map(|&f| (f, targets.target(f).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_matching_targets_score_full() {
+        let targets = FingerTargets::default();
+        let balance = FingerBalance::from_loads(&loads_from_targets(&targets), &targets).unwrap();
+        assert!((balance.score - 100.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_lopsided_distribution_scores_low() {
+        let targets = FingerTargets::default();
+        let mut loads: Vec<_> = FINGERS.iter().map(|&f| (f, 0.0)).collect();
+        loads[0].1 = 90.0;
+        loads[7].1 = 10.0;
+
+        let balance = FingerBalance::from_loads(&loads, &targets).unwrap();
+        // Pinkies hold 100% against a 16% target: 84% of presses are misplaced.
+        assert!((balance.score - 16.0).abs() < 0.01);
+        let left_pinky = balance.deviation_for(Finger::LeftPinky).unwrap();
+        assert!((left_pinky.deviation() - 82.0).abs() < 0.01);
+        assert!(balance.deviation_for(Finger::LeftIndex).unwrap().deviation() < 0.0);
+    }
+
+    #[test]
+    fn test_no_load_has_no_balance() {
+        let loads: Vec<_> = FINGERS.iter().map(|&f| (f, 0.0)).collect();
+        assert!(FingerBalance::from_loads(&loads, &FingerTargets::default()).is_none());
+    }
+}
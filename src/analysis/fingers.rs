@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use crate::analysis::frequency::BigramCount;
+use crate::models::keyboard_layout::{Finger, Hand, KeyboardLayout};
+
+/// The eight non-thumb fingers, left pinky to right pinky.
+pub const HAND_FINGERS: [Finger; 8] = [
+    Finger::LeftPinky,
+    Finger::LeftRing,
+    Finger::LeftMiddle,
+    Finger::LeftIndex,
+    Finger::RightIndex,
+    Finger::RightMiddle,
+    Finger::RightRing,
+    Finger::RightPinky,
+];
+
+/// Share of presses on each of `HAND_FINGERS`, summed from per-key
+/// percentages (e.g. `FrequencyAnalysis::key_percentages`). Keys the layout
+/// doesn't assign, and thumb keys, are left out, so the loads need not sum to 100.
+pub fn finger_loads(
+    key_percentages: &HashMap<u32, f64>,
+    layout: &dyn KeyboardLayout,
+) -> Vec<(Finger, f64)> {
+    let mut totals: HashMap<Finger, f64> = HashMap::new();
+    for (&key_code, &pct) in key_percentages {
+        if let Some(finger) = layout.get_finger(key_code) {
+            *totals.entry(finger).or_insert(0.0) += pct;
+        }
+    }
+
+    HAND_FINGERS
+        .into_iter()
+        .map(|finger| (finger, totals.get(&finger).copied().unwrap_or(0.0)))
+        .collect()
+}
+
+/// Left and right shares of the finger loads, in percent of both hands.
+/// Even (50/50) when there is no load at all.
+pub fn hand_balance(loads: &[(Finger, f64)]) -> (f64, f64) {
+    let hand_total = |hand: Hand| -> f64 {
+        loads
+            .iter()
+            .filter(|(finger, _)| *finger != Finger::Thumb && finger.hand() == hand)
+            .map(|(_, pct)| pct)
+            .sum()
+    };
+    let left = hand_total(Hand::Left);
+    let right = hand_total(Hand::Right);
+
+    let total = left + right;
+    if total > 0.0 {
+        (left / total * 100.0, right / total * 100.0)
+    } else {
+        (50.0, 50.0)
+    }
+}
+
+/// Share of finger (not thumb) presses that land on the home row, in
+/// percent of the same per-key percentages `finger_loads` takes. 0 when no
+/// finger keys were pressed.
+pub fn home_row_pct(key_percentages: &HashMap<u32, f64>, layout: &dyn KeyboardLayout) -> f64 {
+    let Some(home_row) = layout.home_row() else {
+        return 0.0;
+    };
+    let (mut home, mut total) = (0.0, 0.0);
+    for (&key_code, &pct) in key_percentages {
+        if matches!(layout.get_finger(key_code), Some(finger) if finger != Finger::Thumb) {
+            total += pct;
+            if layout.key_row(key_code) == Some(home_row) {
+                home += pct;
+            }
+        }
+    }
+    if total > 0.0 {
+        home / total * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Rows away from the home row per finger (not thumb) press, weighted by
+/// the same per-key percentages `finger_loads` takes. 0 when no finger keys
+/// were pressed.
+pub fn mean_row_distance(key_percentages: &HashMap<u32, f64>, layout: &dyn KeyboardLayout) -> f64 {
+    let Some(home_row) = layout.home_row() else {
+        return 0.0;
+    };
+    let (mut distance, mut total) = (0.0, 0.0);
+    for (&key_code, &pct) in key_percentages {
+        if !matches!(layout.get_finger(key_code), Some(finger) if finger != Finger::Thumb) {
+            continue;
+        }
+        if let Some(row) = layout.key_row(key_code) {
+            total += pct;
+            distance += pct * row.abs_diff(home_row) as f64;
+        }
+    }
+    if total > 0.0 {
+        distance / total
+    } else {
+        0.0
+    }
+}
+
+/// A same-finger bigram (SFB) types two different keys in a row with one
+/// finger, which has to travel between them. With `include_repeats`, the
+/// same key twice counts too. Thumb keys never count.
+pub fn is_same_finger(
+    layout: &dyn KeyboardLayout,
+    first: u32,
+    second: u32,
+    include_repeats: bool,
+) -> bool {
+    (include_repeats || first != second)
+        && matches!(
+            (layout.get_finger(first), layout.get_finger(second)),
+            (Some(a), Some(b)) if a == b && a != Finger::Thumb
+        )
+}
+
+/// Both keys typed by fingers (not thumbs) of different hands.
+pub fn is_alternating(layout: &dyn KeyboardLayout, first: u32, second: u32) -> bool {
+    matches!(
+        (layout.get_finger(first), layout.get_finger(second)),
+        (Some(a), Some(b)) if a != Finger::Thumb && b != Finger::Thumb && a.hand() != b.hand()
+    )
+}
+
+/// A roll: two fingers (not thumbs) of the same hand one after the other.
+pub fn is_roll(layout: &dyn KeyboardLayout, first: u32, second: u32) -> bool {
+    matches!(
+        (layout.get_finger(first), layout.get_finger(second)),
+        (Some(a), Some(b))
+            if a != b && a != Finger::Thumb && b != Finger::Thumb && a.hand() == b.hand()
+    )
+}
+
+/// Same-finger, roll and hand-alternating shares of all bigrams, computed
+/// the same way as `ScissorAnalysis`.
+#[derive(Debug, Default)]
+pub struct SameFingerAnalysis {
+    pub total_bigrams: u64,
+    pub same_finger_count: u64,
+    pub roll_count: u64,
+    pub alternating_count: u64,
+    /// Same-finger bigrams by count, most frequent first.
+    pub worst: Vec<BigramCount>,
+}
+
+impl SameFingerAnalysis {
+    /// `include_repeats` is `FilterConfig::same_finger_repeats`; see `is_same_finger`.
+    pub fn from_bigrams(
+        bigrams: &[BigramCount],
+        layout: &dyn KeyboardLayout,
+        include_repeats: bool,
+    ) -> Self {
+        let total_bigrams = bigrams.iter().map(|b| b.count).sum();
+        let alternating_count = bigrams
+            .iter()
+            .filter(|b| is_alternating(layout, b.first_key, b.second_key))
+            .map(|b| b.count)
+            .sum();
+        let roll_count = bigrams
+            .iter()
+            .filter(|b| is_roll(layout, b.first_key, b.second_key))
+            .map(|b| b.count)
+            .sum();
+
+        // Input is already sorted by count, so the filtered list is too.
+        let worst: Vec<BigramCount> = bigrams
+            .iter()
+            .filter(|b| is_same_finger(layout, b.first_key, b.second_key, include_repeats))
+            .cloned()
+            .collect();
+        let same_finger_count = worst.iter().map(|b| b.count).sum();
+
+        Self {
+            total_bigrams,
+            same_finger_count,
+            roll_count,
+            alternating_count,
+            worst,
+        }
+    }
+
+    pub fn same_finger_pct(&self) -> f64 {
+        pct(self.same_finger_count, self.total_bigrams)
+    }
+
+    pub fn roll_pct(&self) -> f64 {
+        pct(self.roll_count, self.total_bigrams)
+    }
+
+    pub fn alternation_pct(&self) -> f64 {
+        pct(self.alternating_count, self.total_bigrams)
+    }
+}
+
+fn pct(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    count as f64 / total as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::keyboard_layout::QwertyLayout;
+
+    const A: u32 = 0x00;
+    const E: u32 = 0x0E;
+    const D: u32 = 0x02;
+    const J: u32 = 0x26;
+    const SPACE: u32 = 0x31;
+
+    fn bigram(first_key: u32, second_key: u32, count: u64) -> BigramCount {
+        BigramCount {
+            first_key,
+            second_key,
+            display: format!("{:02X}->{:02X}", first_key, second_key),
+            count,
+            percentage: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_finger_loads_skip_thumbs_and_unmapped_keys() {
+        let percentages = HashMap::from([(A, 30.0), (E, 10.0), (D, 20.0), (SPACE, 25.0), (0x7A, 15.0)]);
+        let loads: HashMap<Finger, f64> = finger_loads(&percentages, &QwertyLayout::new())
+            .into_iter()
+            .collect();
+
+        assert_eq!(loads.len(), 8);
+        assert_eq!(loads[&Finger::LeftPinky], 30.0);
+        assert_eq!(loads[&Finger::LeftMiddle], 30.0);
+        assert_eq!(loads[&Finger::RightIndex], 0.0);
+    }
+
+    #[test]
+    fn test_home_row_pct_counts_finger_keys_only() {
+        let percentages = HashMap::from([(A, 30.0), (D, 10.0), (E, 40.0), (SPACE, 20.0)]);
+        assert_eq!(home_row_pct(&percentages, &QwertyLayout::new()), 50.0);
+        assert_eq!(home_row_pct(&HashMap::new(), &QwertyLayout::new()), 0.0);
+    }
+
+    #[test]
+    fn test_hand_balance_normalizes_to_both_hands() {
+        let loads = [(Finger::LeftPinky, 30.0), (Finger::RightIndex, 10.0)];
+        assert_eq!(hand_balance(&loads), (75.0, 25.0));
+        assert_eq!(hand_balance(&[]), (50.0, 50.0));
+    }
+
+    #[test]
+    fn test_same_finger_and_alternation_shares() {
+        let bigrams = [
+            bigram(E, D, 3),     // left middle twice: SFB
+            bigram(A, J, 4),     // alternating
+            bigram(D, D, 2),     // repeated key: neither
+            bigram(SPACE, J, 1), // thumb: neither
+        ];
+        let analysis = SameFingerAnalysis::from_bigrams(&bigrams, &QwertyLayout::new(), false);
+
+        assert_eq!(analysis.total_bigrams, 10);
+        assert_eq!(analysis.same_finger_count, 3);
+        assert_eq!(analysis.alternating_count, 4);
+        assert_eq!(analysis.roll_count, 0);
+        assert_eq!(analysis.same_finger_pct(), 30.0);
+        assert_eq!(analysis.alternation_pct(), 40.0);
+        assert_eq!(analysis.worst.len(), 1);
+    }
+
+    #[test]
+    fn test_rolls_and_row_distance() {
+        let layout = QwertyLayout::new();
+        let bigrams = [bigram(A, D, 3), bigram(E, D, 1), bigram(A, J, 4)];
+        let analysis = SameFingerAnalysis::from_bigrams(&bigrams, &layout, false);
+        assert_eq!(analysis.roll_count, 3);
+        assert!(!is_roll(&layout, SPACE, A));
+
+        // E is one row up from the home row; A and D are on it.
+        let percentages = HashMap::from([(A, 30.0), (D, 20.0), (E, 50.0), (SPACE, 40.0)]);
+        assert_eq!(mean_row_distance(&percentages, &layout), 0.5);
+    }
+
+    #[test]
+    fn test_repeats_count_as_same_finger_only_when_asked() {
+        let layout = QwertyLayout::new();
+        assert!(!is_same_finger(&layout, E, E, false));
+        assert!(is_same_finger(&layout, E, E, true));
+        assert!(is_same_finger(&layout, E, D, false));
+        assert!(is_same_finger(&layout, E, D, true));
+        assert!(!is_same_finger(&layout, SPACE, SPACE, true));
+
+        let bigrams = [bigram(E, E, 6), bigram(E, D, 2), bigram(A, J, 2)];
+        let excluded = SameFingerAnalysis::from_bigrams(&bigrams, &layout, false);
+        let included = SameFingerAnalysis::from_bigrams(&bigrams, &layout, true);
+        assert_eq!(excluded.same_finger_pct(), 20.0);
+        assert_eq!(included.same_finger_pct(), 80.0);
+        assert_eq!(included.worst[0].first_key, E);
+        assert_eq!(included.worst[0].second_key, E);
+    }
+}
@@ -0,0 +1,105 @@
+use std::cmp::Ordering;
+
+use crate::analysis::frequency::BigramCount;
+use crate::models::keyboard_layout::KeyboardLayout;
+
+/// Key centers closer than this horizontally (in layout width units, where a
+/// standard key is 2 wide plus a 1 unit gap) count as the same column.
+const SAME_COLUMN_UNITS: f64 = 1.5;
+
+/// A common bigram placed on the keyboard: which way the hand moves from
+/// the first key to the second.
+#[derive(Debug, Clone)]
+pub struct BigramFlow {
+    pub bigram: BigramCount,
+    /// One of the eight arrows, e.g. `↓` for E -> D on QWERTY.
+    pub arrow: char,
+}
+
+/// The `n` most common bigrams between two different keys on `layout`, with
+/// the direction of travel. `bigrams` is expected sorted by count, as
+/// `FrequencyAnalysis` returns them; keys off the layout and repeats are skipped.
+pub fn top_flows(bigrams: &[BigramCount], layout: &dyn KeyboardLayout, n: usize) -> Vec<BigramFlow> {
+    let centers: Vec<(u32, usize, f64)> = layout
+        .key_positions()
+        .into_iter()
+        .map(|(row, x, key)| (key.keycode, row, x as f64 + key.width as f64 / 2.0))
+        .collect();
+    let center = |key_code: u32| {
+        centers
+            .iter()
+            .find(|(code, _, _)| *code == key_code)
+            .map(|&(_, row, x)| (row, x))
+    };
+
+    bigrams
+        .iter()
+        .filter(|b| b.first_key != b.second_key)
+        .filter_map(|b| {
+            let from = center(b.first_key)?;
+            let to = center(b.second_key)?;
+            Some(BigramFlow {
+                bigram: b.clone(),
+                arrow: arrow(from, to),
+            })
+        })
+        .take(n)
+        .collect()
+}
+
+/// Rows grow downward, so a larger row index means moving toward the user.
+fn arrow((from_row, from_x): (usize, f64), (to_row, to_x): (usize, f64)) -> char {
+    let dx = to_x - from_x;
+    let horizontal = if dx.abs() < SAME_COLUMN_UNITS { 0 } else { dx.signum() as i8 };
+    match (to_row.cmp(&from_row), horizontal) {
+        (Ordering::Less, -1) => '↖',
+        (Ordering::Less, 1) => '↗',
+        (Ordering::Less, _) => '↑',
+        (Ordering::Greater, -1) => '↙',
+        (Ordering::Greater, 1) => '↘',
+        (Ordering::Greater, _) => '↓',
+        (Ordering::Equal, -1) => '←',
+        (Ordering::Equal, _) => '→',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::keyboard_layout::QwertyLayout;
+
+    const E: u32 = 0x0E;
+    const D: u32 = 0x02;
+    const T: u32 = 0x11;
+    const H: u32 = 0x04;
+    const R: u32 = 0x0F;
+
+    fn bigram(first_key: u32, second_key: u32, count: u64) -> BigramCount {
+        BigramCount {
+            first_key,
+            second_key,
+            display: format!("{:02X}->{:02X}", first_key, second_key),
+            count,
+            percentage: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_flow_directions_on_qwerty() {
+        let bigrams = [
+            bigram(E, E, 9),    // repeat: skipped
+            bigram(E, D, 8),    // straight down
+            bigram(T, H, 7),    // down and right
+            bigram(0x7A, E, 6), // F1 isn't on the layout
+            bigram(E, R, 5),    // right along the row
+            bigram(D, E, 4),
+        ];
+        let flows = top_flows(&bigrams, &QwertyLayout::new(), 3);
+
+        let arrows: Vec<_> = flows
+            .iter()
+            .map(|f| (f.bigram.first_key, f.bigram.second_key, f.arrow))
+            .collect();
+        assert_eq!(arrows, vec![(E, D, '↓'), (T, H, '↘'), (E, R, '→')]);
+    }
+}
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
-use crate::models::keycode::KeyCode;
+use serde::Serialize;
+
+use crate::analysis::filters::{sorted_by_time, FilterConfig, DEFAULT_MAX_GAP_MS};
+use crate::analysis::parallel::count_windows;
+use crate::models::keycode::{KeyCategory, KeyCode};
 use crate::models::{EventType, KeystrokeEvent};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct KeyCount {
     pub key_code: u32,
     pub key_name: String,
@@ -11,7 +15,7 @@ pub struct KeyCount {
     pub percentage: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BigramCount {
     pub first_key: u32,
     pub second_key: u32,
@@ -20,7 +24,7 @@ pub struct BigramCount {
     pub percentage: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TrigramCount {
     pub keys: (u32, u32, u32),
     pub display: String,
@@ -37,7 +41,25 @@ pub struct FrequencyAnalysis {
 }
 
 impl FrequencyAnalysis {
+    /// Events are sorted by timestamp first if they aren't already, so
+    /// bigrams and trigrams pair keys in the order they were typed.
     pub fn from_events(events: &[KeystrokeEvent]) -> Self {
+        Self::from_events_with_gap(events, DEFAULT_MAX_GAP_MS)
+    }
+
+    /// Like `from_events`, with n-grams broken at `filter.max_gap_ms` and,
+    /// with `filter.letters_only_ngrams`, limited to letters. `analyze` and
+    /// the dashboard both go through this so they list the same n-grams.
+    pub fn with_filter(events: &[KeystrokeEvent], filter: &FilterConfig) -> Self {
+        let mut analysis = Self::from_events_with_gap(events, filter.max_gap_ms);
+        if filter.letters_only_ngrams {
+            analysis.keep_letter_ngrams();
+        }
+        analysis
+    }
+
+    fn from_events_with_gap(events: &[KeystrokeEvent], max_gap_ms: i64) -> Self {
+        let events = sorted_by_time(events);
         let press_events: Vec<_> = events
             .iter()
             .filter(|e| matches!(e.event_type, EventType::Press))
@@ -46,8 +68,8 @@ impl FrequencyAnalysis {
         let total_presses = press_events.len() as u64;
 
         let key_frequencies = Self::calculate_key_frequencies(&press_events, total_presses);
-        let bigram_frequencies = Self::calculate_bigram_frequencies(&press_events, total_presses);
-        let trigram_frequencies = Self::calculate_trigram_frequencies(&press_events, total_presses);
+        let bigram_frequencies = Self::calculate_bigram_frequencies(&press_events, max_gap_ms);
+        let trigram_frequencies = Self::calculate_trigram_frequencies(&press_events, max_gap_ms);
 
         Self {
             total_presses,
@@ -58,11 +80,7 @@ impl FrequencyAnalysis {
     }
 
     fn calculate_key_frequencies(events: &[&KeystrokeEvent], total: u64) -> Vec<KeyCount> {
-        let mut counts: HashMap<u32, u64> = HashMap::new();
-
-        for event in events {
-            *counts.entry(event.key_code).or_insert(0) += 1;
-        }
+        let counts = count_windows(events, 1, |window| Some(window[0].key_code));
 
         let mut result: Vec<_> = counts
             .into_iter()
@@ -78,20 +96,18 @@ impl FrequencyAnalysis {
             })
             .collect();
 
-        result.sort_by(|a, b| b.count.cmp(&a.count));
+        result.sort_by(|a, b| b.count.cmp(&a.count).then(a.key_code.cmp(&b.key_code)));
         result
     }
 
-    fn calculate_bigram_frequencies(events: &[&KeystrokeEvent], _total: u64) -> Vec<BigramCount> {
-        let mut counts: HashMap<(u32, u32), u64> = HashMap::new();
-
-        for window in events.windows(2) {
+    fn calculate_bigram_frequencies(
+        events: &[&KeystrokeEvent],
+        max_gap_ms: i64,
+    ) -> Vec<BigramCount> {
+        let counts = count_windows(events, 2, |window| {
             let gap = window[1].timestamp - window[0].timestamp;
-            if gap < 5000 {
-                let bigram = (window[0].key_code, window[1].key_code);
-                *counts.entry(bigram).or_insert(0) += 1;
-            }
-        }
+            (gap < max_gap_ms).then(|| (window[0].key_code, window[1].key_code))
+        });
 
         let bigram_total: u64 = counts.values().sum();
 
@@ -110,21 +126,24 @@ impl FrequencyAnalysis {
             })
             .collect();
 
-        result.sort_by(|a, b| b.count.cmp(&a.count));
+        result.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then((a.first_key, a.second_key).cmp(&(b.first_key, b.second_key)))
+        });
         result
     }
 
-    fn calculate_trigram_frequencies(events: &[&KeystrokeEvent], _total: u64) -> Vec<TrigramCount> {
-        let mut counts: HashMap<(u32, u32, u32), u64> = HashMap::new();
-
-        for window in events.windows(3) {
+    fn calculate_trigram_frequencies(
+        events: &[&KeystrokeEvent],
+        max_gap_ms: i64,
+    ) -> Vec<TrigramCount> {
+        let counts = count_windows(events, 3, |window| {
             let gap1 = window[1].timestamp - window[0].timestamp;
             let gap2 = window[2].timestamp - window[1].timestamp;
-            if gap1 < 5000 && gap2 < 5000 {
-                let trigram = (window[0].key_code, window[1].key_code, window[2].key_code);
-                *counts.entry(trigram).or_insert(0) += 1;
-            }
-        }
+            (gap1 < max_gap_ms && gap2 < max_gap_ms)
+                .then(|| (window[0].key_code, window[1].key_code, window[2].key_code))
+        });
 
         let trigram_total: u64 = counts.values().sum();
 
@@ -147,10 +166,88 @@ impl FrequencyAnalysis {
             })
             .collect();
 
-        result.sort_by(|a, b| b.count.cmp(&a.count));
+        result.sort_by(|a, b| b.count.cmp(&a.count).then(a.keys.cmp(&b.keys)));
         result
     }
 
+    /// Drops bigrams and trigrams with any non-letter key (Space, Shift,
+    /// Backspace, ...), so the lists show letter combinations, and makes the
+    /// remaining percentages shares of letter n-grams.
+    fn keep_letter_ngrams(&mut self) {
+        let is_letter = |key_code: u32| KeyCode(key_code).category() == KeyCategory::Letter;
+
+        self.bigram_frequencies
+            .retain(|b| is_letter(b.first_key) && is_letter(b.second_key));
+        let bigram_total: u64 = self.bigram_frequencies.iter().map(|b| b.count).sum();
+        for bigram in &mut self.bigram_frequencies {
+            bigram.percentage = bigram.count as f64 / bigram_total as f64 * 100.0;
+        }
+
+        self.trigram_frequencies.retain(|t| {
+            let (first, second, third) = t.keys;
+            is_letter(first) && is_letter(second) && is_letter(third)
+        });
+        let trigram_total: u64 = self.trigram_frequencies.iter().map(|t| t.count).sum();
+        for trigram in &mut self.trigram_frequencies {
+            trigram.percentage = trigram.count as f64 / trigram_total as f64 * 100.0;
+        }
+    }
+
+    /// Percentage of presses per key code, for heatmaps.
+    pub fn key_percentages(&self) -> HashMap<u32, f64> {
+        self.key_frequencies
+            .iter()
+            .map(|key| (key.key_code, key.percentage))
+            .collect()
+    }
+
+    /// Like `key_percentages`, but over character-producing keys only, so
+    /// the result still sums to 100%.
+    pub fn character_key_percentages(&self) -> HashMap<u32, f64> {
+        let character_keys: Vec<_> = self
+            .key_frequencies
+            .iter()
+            .filter(|key| KeyCode(key.key_code).category().produces_character())
+            .collect();
+        let total: u64 = character_keys.iter().map(|key| key.count).sum();
+        if total == 0 {
+            return HashMap::new();
+        }
+
+        character_keys
+            .into_iter()
+            .map(|key| (key.key_code, key.count as f64 / total as f64 * 100.0))
+            .collect()
+    }
+
+    /// Presses of key codes without a name, which show up as `Unknown(0x..)`.
+    pub fn unnamed_presses(&self) -> u64 {
+        self.key_frequencies
+            .iter()
+            .filter(|k| !KeyCode(k.key_code).is_named())
+            .map(|k| k.count)
+            .sum()
+    }
+
+    pub fn unnamed_pct(&self) -> f64 {
+        if self.total_presses == 0 {
+            return 0.0;
+        }
+        self.unnamed_presses() as f64 / self.total_presses as f64 * 100.0
+    }
+
+    /// Presses per key category, most pressed first.
+    pub fn category_counts(&self) -> Vec<(KeyCategory, u64)> {
+        let mut counts: HashMap<KeyCategory, u64> = HashMap::new();
+        for key in &self.key_frequencies {
+            *counts.entry(KeyCode(key.key_code).category()).or_insert(0) += key.count;
+        }
+
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.label().cmp(b.0.label())));
+        counts
+    }
+
     pub fn top_keys(&self, n: usize) -> &[KeyCount] {
         &self.key_frequencies[..n.min(self.key_frequencies.len())]
     }
@@ -162,32 +259,104 @@ impl FrequencyAnalysis {
     pub fn top_trigrams(&self, n: usize) -> &[TrigramCount] {
         &self.trigram_frequencies[..n.min(self.trigram_frequencies.len())]
     }
+
+    /// Like `top_bigrams`, leaving out bigrams seen fewer than `min_count` times.
+    pub fn top_bigrams_min(&self, n: usize, min_count: u64) -> &[BigramCount] {
+        let frequent = self.bigram_frequencies.partition_point(|b| b.count >= min_count);
+        &self.bigram_frequencies[..n.min(frequent)]
+    }
+
+    pub fn top_trigrams_min(&self, n: usize, min_count: u64) -> &[TrigramCount] {
+        let frequent = self.trigram_frequencies.partition_point(|t| t.count >= min_count);
+        &self.trigram_frequencies[..n.min(frequent)]
+    }
+}
+
+/// Per-key percentages of `(key_code, presses)` counts, over character keys
+/// only when `character_keys_only`, as `FrequencyAnalysis` gives them. For
+/// press counts aggregated in SQL (`Database::get_top_keys_since`).
+pub fn count_percentages(counts: &[(u32, i64)], character_keys_only: bool) -> HashMap<u32, f64> {
+    let counts: Vec<_> = counts
+        .iter()
+        .filter(|(key_code, _)| {
+            !character_keys_only || KeyCode(*key_code).category().produces_character()
+        })
+        .collect();
+    let total: i64 = counts.iter().map(|(_, count)| count).sum();
+    if total == 0 {
+        return HashMap::new();
+    }
+    counts
+        .into_iter()
+        .map(|&(key_code, count)| (key_code, count as f64 / total as f64 * 100.0))
+        .collect()
+}
+
+/// The value heatmaps treat as full intensity: the largest value, or with
+/// `cap_percentile` the value at that percentile (nearest rank) of the
+/// non-zero values. Capping keeps one or two dominant keys such as Space
+/// from washing out the differences between the rest; keys above the cap
+/// are drawn at full intensity.
+pub fn heat_reference<I>(values: I, cap_percentile: Option<f64>) -> f64
+where
+    I: IntoIterator<Item = f64>,
+{
+    let mut values: Vec<f64> = values.into_iter().filter(|v| *v > 0.0).collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+
+    match cap_percentile {
+        Some(pct) => {
+            let pct = pct.clamp(0.0, 100.0);
+            let rank = (pct / 100.0 * values.len() as f64).ceil() as usize;
+            values[rank.clamp(1, values.len()) - 1]
+        }
+        None => values[values.len() - 1],
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::{press, release};
 
-    fn make_press(timestamp: i64, key_code: u32) -> KeystrokeEvent {
-        KeystrokeEvent {
-            timestamp,
-            key_code,
-            event_type: EventType::Press,
-            modifiers: vec![],
-            application: "test".to_string(),
-        }
+    #[test]
+    fn test_heat_reference_caps_at_percentile() {
+        // Nine letters at 1..=9% and Space at 50%.
+        let values: Vec<f64> = (1..=9).map(f64::from).chain([50.0, 0.0]).collect();
+
+        assert_eq!(heat_reference(values.iter().copied(), None), 50.0);
+        assert_eq!(heat_reference(values.iter().copied(), Some(90.0)), 9.0);
+        assert_eq!(heat_reference(values.iter().copied(), Some(50.0)), 5.0);
+        assert_eq!(heat_reference(values.iter().copied(), Some(100.0)), 50.0);
+        assert_eq!(heat_reference(values.iter().copied(), Some(0.0)), 1.0);
+        assert_eq!(heat_reference(std::iter::empty(), Some(95.0)), 0.0);
     }
 
-    fn make_release(timestamp: i64, key_code: u32) -> KeystrokeEvent {
-        KeystrokeEvent {
-            timestamp,
-            key_code,
-            event_type: EventType::Release,
-            modifiers: vec![],
-            application: "test".to_string(),
-        }
+    #[test]
+    fn test_shuffled_events_pair_in_typing_order() {
+        let (t, h, e) = (0x11, 0x04, 0x0E);
+        let shuffled = vec![
+            press(300, e),
+            release(150, t),
+            press(100, t),
+            press(200, h),
+        ];
+
+        let analysis = FrequencyAnalysis::from_events(&shuffled);
+        let bigrams: Vec<_> = analysis
+            .bigram_frequencies
+            .iter()
+            .map(|b| (b.first_key, b.second_key))
+            .collect();
+        assert_eq!(bigrams, vec![(h, e), (t, h)]);
+        assert_eq!(analysis.trigram_frequencies.len(), 1);
+        assert_eq!(analysis.trigram_frequencies[0].keys, (t, h, e));
     }
 
+
     #[test]
     fn test_empty_events() {
         let analysis = FrequencyAnalysis::from_events(&[]);
@@ -198,9 +367,9 @@ mod tests {
     #[test]
     fn test_key_frequency_count() {
         let events = vec![
-            make_press(100, 0x00),
-            make_press(200, 0x00),
-            make_press(300, 0x01),
+            press(100, 0x00),
+            press(200, 0x00),
+            press(300, 0x01),
         ];
 
         let analysis = FrequencyAnalysis::from_events(&events);
@@ -213,13 +382,60 @@ mod tests {
         assert_eq!(top[1].count, 1);
     }
 
+    #[test]
+    fn test_unnamed_presses() {
+        let events = vec![
+            press(100, 0x00),
+            press(200, 0xF0),
+            press(300, 0xF0),
+            press(400, 0x01),
+        ];
+
+        let analysis = FrequencyAnalysis::from_events(&events);
+        assert_eq!(analysis.unnamed_presses(), 2);
+        assert!((analysis.unnamed_pct() - 50.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_category_counts() {
+        let events = vec![
+            press(100, 0x00),
+            press(200, 0x01),
+            press(300, 0x48),
+            press(400, 0x31),
+        ];
+
+        let analysis = FrequencyAnalysis::from_events(&events);
+        let counts = analysis.category_counts();
+        assert_eq!(counts[0], (KeyCategory::Letter, 2));
+        assert!(counts.contains(&(KeyCategory::MediaFunction, 1)));
+        assert!(counts.contains(&(KeyCategory::Whitespace, 1)));
+    }
+
+    #[test]
+    fn test_character_key_percentages_renormalize() {
+        let events = vec![
+            press(100, 0x00),
+            press(200, 0x00),
+            press(300, 0x31),
+            press(400, 0x38),
+            press(500, 0x7B),
+        ];
+
+        let analysis = FrequencyAnalysis::from_events(&events);
+        let pcts = analysis.character_key_percentages();
+        assert_eq!(pcts.len(), 2);
+        assert!((pcts[&0x00] - 200.0 / 3.0).abs() < 0.01);
+        assert!((pcts.values().sum::<f64>() - 100.0).abs() < 0.01);
+    }
+
     #[test]
     fn test_only_counts_presses() {
         let events = vec![
-            make_press(100, 0x00),
-            make_release(150, 0x00),
-            make_press(200, 0x01),
-            make_release(250, 0x01),
+            press(100, 0x00),
+            release(150, 0x00),
+            press(200, 0x01),
+            release(250, 0x01),
         ];
 
         let analysis = FrequencyAnalysis::from_events(&events);
@@ -229,10 +445,10 @@ mod tests {
     #[test]
     fn test_bigram_detection() {
         let events = vec![
-            make_press(100, 0x00),
-            make_press(200, 0x01),
-            make_press(300, 0x00),
-            make_press(400, 0x01),
+            press(100, 0x00),
+            press(200, 0x01),
+            press(300, 0x00),
+            press(400, 0x01),
         ];
 
         let analysis = FrequencyAnalysis::from_events(&events);
@@ -247,20 +463,36 @@ mod tests {
     #[test]
     fn test_bigram_filters_large_gaps() {
         let events = vec![
-            make_press(100, 0x00),
-            make_press(10000, 0x01),
+            press(100, 0x00),
+            press(10000, 0x01),
         ];
 
         let analysis = FrequencyAnalysis::from_events(&events);
         assert!(analysis.bigram_frequencies.is_empty());
     }
 
+    #[test]
+    fn test_top_bigrams_min_count() {
+        let events = vec![
+            press(100, 0x00),
+            press(200, 0x01),
+            press(300, 0x00),
+            press(400, 0x01),
+        ];
+
+        let analysis = FrequencyAnalysis::from_events(&events);
+        let frequent = analysis.top_bigrams_min(10, 2);
+        assert_eq!(frequent.len(), 1);
+        assert_eq!((frequent[0].first_key, frequent[0].second_key), (0x00, 0x01));
+        assert_eq!(analysis.top_bigrams_min(10, 1).len(), 2);
+    }
+
     #[test]
     fn test_trigram_detection() {
         let events = vec![
-            make_press(100, 0x00),
-            make_press(200, 0x01),
-            make_press(300, 0x02),
+            press(100, 0x00),
+            press(200, 0x01),
+            press(300, 0x02),
         ];
 
         let analysis = FrequencyAnalysis::from_events(&events);
@@ -273,10 +505,10 @@ mod tests {
     #[test]
     fn test_percentage_calculation() {
         let events = vec![
-            make_press(100, 0x00),
-            make_press(200, 0x00),
-            make_press(300, 0x01),
-            make_press(400, 0x02),
+            press(100, 0x00),
+            press(200, 0x00),
+            press(300, 0x01),
+            press(400, 0x02),
         ];
 
         let analysis = FrequencyAnalysis::from_events(&events);
@@ -289,11 +521,11 @@ mod tests {
     #[test]
     fn test_top_keys_limit() {
         let events = vec![
-            make_press(100, 0x00),
-            make_press(200, 0x01),
-            make_press(300, 0x02),
-            make_press(400, 0x03),
-            make_press(500, 0x04),
+            press(100, 0x00),
+            press(200, 0x01),
+            press(300, 0x02),
+            press(400, 0x03),
+            press(500, 0x04),
         ];
 
         let analysis = FrequencyAnalysis::from_events(&events);
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Segments with fewer presses than this are never called gaming: there is
+/// too little to tell a short burst of arrows from a game.
+pub const GAMING_MIN_PRESSES: usize = 40;
+
+/// How many of the most pressed keys `GAMING_TOP_KEY_SHARE` looks at. Four
+/// covers WASD and the arrow keys.
+pub const GAMING_TOP_KEYS: usize = 4;
+
+/// A segment is dominated by a tiny key set when its `GAMING_TOP_KEYS` most
+/// pressed keys take at least this share of its presses. Prose rarely gets
+/// above 0.5: Space, E, T and A together are about a third of English.
+pub const GAMING_TOP_KEY_SHARE: f64 = 0.8;
+
+/// Share of presses that repeat the previous key. Movement keys are tapped
+/// and held over and over; prose repeats a key only in double letters, a
+/// few percent of the time.
+pub const GAMING_MIN_REPEAT_RATE: f64 = 0.3;
+
+/// Whether the presses in `segment` look like playing a game rather than
+/// typing: dominated by a few keys that are pressed again and again. Both
+/// conditions must hold, so fast prose or a run of arrow-key navigation
+/// alone isn't flagged.
+pub fn is_gaming_segment(segment: &[KeystrokeEvent]) -> bool {
+    let presses: Vec<u32> = segment
+        .iter()
+        .filter(|e| e.event_type == EventType::Press)
+        .map(|e| e.key_code)
+        .collect();
+    if presses.len() < GAMING_MIN_PRESSES {
+        return false;
+    }
+
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for &key_code in &presses {
+        *counts.entry(key_code).or_insert(0) += 1;
+    }
+    let mut counts: Vec<usize> = counts.into_values().collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    let top: usize = counts.iter().take(GAMING_TOP_KEYS).sum();
+    let top_share = top as f64 / presses.len() as f64;
+
+    let repeats = presses.windows(2).filter(|pair| pair[0] == pair[1]).count();
+    let repeat_rate = repeats as f64 / (presses.len() - 1) as f64;
+
+    top_share >= GAMING_TOP_KEY_SHARE && repeat_rate >= GAMING_MIN_REPEAT_RATE
+}
+
+/// How much of the analyzed data was classified as gaming.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GamingReport {
+    pub gaming_segments: usize,
+    pub gaming_events: usize,
+}
+
+/// Separates typing segments from gaming ones; see `is_gaming_segment`.
+pub fn split_gaming_segments(
+    segments: Vec<&[KeystrokeEvent]>,
+) -> (Vec<&[KeystrokeEvent]>, GamingReport) {
+    let mut report = GamingReport::default();
+    let typing = segments
+        .into_iter()
+        .filter(|segment| {
+            let gaming = is_gaming_segment(segment);
+            if gaming {
+                report.gaming_segments += 1;
+                report.gaming_events += segment.len();
+            }
+            !gaming
+        })
+        .collect();
+    (typing, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::synthetic::synthetic_events;
+    use crate::testutil::{press, release};
+
+    const W: u32 = 0x0D;
+    const A: u32 = 0x00;
+    const S: u32 = 0x01;
+    const D: u32 = 0x02;
+    const SPACE: u32 = 0x31;
+
+    /// Movement in bursts of the same key, with the odd jump.
+    fn wasd(start: i64) -> Vec<KeystrokeEvent> {
+        let keys = [W, W, W, A, A, D, D, D, S, W, W, SPACE];
+        (0..120)
+            .flat_map(|i| {
+                let t = start + i as i64 * 90;
+                let key = keys[i % keys.len()];
+                [press(t, key), release(t + 70, key)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wasd_segment_is_gaming_and_prose_is_not() {
+        let game = wasd(0);
+        let prose = synthetic_events(200);
+        assert!(is_gaming_segment(&game));
+        assert!(!is_gaming_segment(&prose));
+
+        let (typing, report) = split_gaming_segments(vec![&prose, &game]);
+        assert_eq!(typing, vec![prose.as_slice()]);
+        assert_eq!(
+            report,
+            GamingReport {
+                gaming_segments: 1,
+                gaming_events: game.len(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_short_bursts_are_left_alone() {
+        let game = wasd(0);
+        assert!(!is_gaming_segment(&game[..GAMING_MIN_PRESSES]));
+    }
+}
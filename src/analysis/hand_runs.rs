@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use crate::models::keyboard_layout::{Finger, Hand, KeyboardLayout};
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Runs at least this long are reported as strain-prone.
+pub const LONG_RUN_THRESHOLD: usize = 4;
+
+/// Distribution of consecutive same-hand key presses.
+///
+/// Thumb keys end the current run, since the thumb gives both hands a break.
+/// Keys the layout doesn't know about (modifiers, function keys) are skipped
+/// and neither extend nor end a run.
+#[derive(Debug, Default)]
+pub struct HandRunAnalysis {
+    /// Run length -> number of runs of exactly that length.
+    pub histogram: BTreeMap<usize, u64>,
+    pub longest_run: usize,
+    pub longest_run_hand: Option<Hand>,
+    pub total_runs: u64,
+}
+
+impl HandRunAnalysis {
+    pub fn from_events(events: &[KeystrokeEvent], layout: &dyn KeyboardLayout) -> Self {
+        let mut analysis = Self::default();
+        let mut current: Option<(Hand, usize)> = None;
+
+        for event in events {
+            if event.event_type != EventType::Press {
+                continue;
+            }
+
+            let Some(finger) = layout.get_finger(event.key_code) else {
+                continue;
+            };
+
+            if finger == Finger::Thumb {
+                if let Some((hand, len)) = current.take() {
+                    analysis.record(hand, len);
+                }
+                continue;
+            }
+
+            let hand = finger.hand();
+            current = match current {
+                Some((run_hand, len)) if run_hand == hand => Some((hand, len + 1)),
+                Some((run_hand, len)) => {
+                    analysis.record(run_hand, len);
+                    Some((hand, 1))
+                }
+                None => Some((hand, 1)),
+            };
+        }
+
+        if let Some((hand, len)) = current {
+            analysis.record(hand, len);
+        }
+
+        analysis
+    }
+
+    fn record(&mut self, hand: Hand, len: usize) {
+        *self.histogram.entry(len).or_insert(0) += 1;
+        self.total_runs += 1;
+        if len > self.longest_run {
+            self.longest_run = len;
+            self.longest_run_hand = Some(hand);
+        }
+    }
+
+    pub fn runs_at_least(&self, len: usize) -> u64 {
+        self.histogram.range(len..).map(|(_, count)| count).sum()
+    }
+
+    pub fn pct_runs_at_least(&self, len: usize) -> f64 {
+        if self.total_runs == 0 {
+            return 0.0;
+        }
+        self.runs_at_least(len) as f64 / self.total_runs as f64 * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::keyboard_layout::QwertyLayout;
+    use crate::testutil::typed;
+
+    const A: u32 = 0x00;
+    const S: u32 = 0x01;
+    const D: u32 = 0x02;
+    const F: u32 = 0x03;
+    const J: u32 = 0x26;
+    const K: u32 = 0x28;
+    const SPACE: u32 = 0x31;
+    const CMD: u32 = 0x37;
+
+    #[test]
+    fn test_run_histogram() {
+        let layout = QwertyLayout::new();
+        let events = typed(&[A, S, D, F, J, K, A], 100);
+
+        let runs = HandRunAnalysis::from_events(&events, &layout);
+        assert_eq!(runs.histogram.get(&4), Some(&1));
+        assert_eq!(runs.histogram.get(&2), Some(&1));
+        assert_eq!(runs.histogram.get(&1), Some(&1));
+        assert_eq!(runs.total_runs, 3);
+        assert_eq!(runs.longest_run, 4);
+        assert_eq!(runs.longest_run_hand, Some(Hand::Left));
+        assert_eq!(runs.runs_at_least(LONG_RUN_THRESHOLD), 1);
+    }
+
+    #[test]
+    fn test_thumb_breaks_run() {
+        let layout = QwertyLayout::new();
+        let events = typed(&[A, S, SPACE, D, F], 100);
+
+        let runs = HandRunAnalysis::from_events(&events, &layout);
+        assert_eq!(runs.histogram.get(&2), Some(&2));
+        assert_eq!(runs.longest_run, 2);
+    }
+
+    #[test]
+    fn test_unmapped_keys_are_neutral() {
+        let layout = QwertyLayout::new();
+        let events = typed(&[A, S, CMD, D, F], 100);
+
+        let runs = HandRunAnalysis::from_events(&events, &layout);
+        assert_eq!(runs.total_runs, 1);
+        assert_eq!(runs.longest_run, 4);
+    }
+
+    #[test]
+    fn test_empty_events() {
+        let runs = HandRunAnalysis::from_events(&[], &QwertyLayout::new());
+        assert_eq!(runs.total_runs, 0);
+        assert_eq!(runs.pct_runs_at_least(LONG_RUN_THRESHOLD), 0.0);
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+
+use crate::analysis::filters::{sorted_by_time, FilterConfig};
+use crate::analysis::timing::press_intervals;
+use crate::models::KeystrokeEvent;
+
+/// Bucket edges used when none are configured: 50ms buckets up to 250ms.
+pub const DEFAULT_HISTOGRAM_EDGES: [i64; 6] = [0, 50, 100, 150, 200, 250];
+
+/// `--bins N` splits this range into N equal buckets.
+pub const DEFAULT_HISTOGRAM_MAX_MS: i64 = 300;
+
+/// Lower edges, in ms, of the buckets of an inter-key histogram. Each
+/// bucket runs up to the next edge; the last one takes everything slower.
+/// Intervals below the first edge aren't counted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramEdges(Vec<i64>);
+
+impl Default for HistogramEdges {
+    fn default() -> Self {
+        Self(DEFAULT_HISTOGRAM_EDGES.to_vec())
+    }
+}
+
+impl HistogramEdges {
+    /// Edges must be non-negative and strictly ascending.
+    pub fn new(edges: Vec<i64>) -> Result<Self> {
+        if edges.is_empty() {
+            return Err(anyhow!("Histogram needs at least one bucket edge"));
+        }
+        if edges[0] < 0 {
+            return Err(anyhow!("Histogram edges must not be negative"));
+        }
+        if edges.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(anyhow!("Histogram edges must be strictly ascending: {:?}", edges));
+        }
+        Ok(Self(edges))
+    }
+
+    /// `bins` equal buckets from 0 to `max_ms`, plus one for anything slower.
+    pub fn uniform(bins: usize, max_ms: i64) -> Result<Self> {
+        if bins == 0 || bins as i64 > max_ms {
+            return Err(anyhow!("Number of bins must be between 1 and {}", max_ms));
+        }
+        Self::new((0..=bins as i64).map(|i| i * max_ms / bins as i64).collect())
+    }
+
+    pub fn edges(&self) -> &[i64] {
+        &self.0
+    }
+
+    /// "0-50", "50-100", ..., "250+".
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .0
+            .windows(2)
+            .map(|pair| format!("{}-{}", pair[0], pair[1]))
+            .collect();
+        labels.push(format!("{}+", self.0[self.0.len() - 1]));
+        labels
+    }
+
+    /// Index of the bucket holding `interval_ms`, if it isn't below the first edge.
+    fn bucket(&self, interval_ms: i64) -> Option<usize> {
+        self.0.partition_point(|&edge| edge <= interval_ms).checked_sub(1)
+    }
+}
+
+/// Comma-separated edges, e.g. `0,10,20,50`.
+impl std::str::FromStr for HistogramEdges {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let edges = s
+            .split(',')
+            .map(|edge| {
+                edge.trim()
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid bucket edge '{}'. Use ms, e.g. 0,50,100", edge))
+            })
+            .collect::<Result<Vec<i64>>>()?;
+        Self::new(edges)
+    }
+}
+
+/// Counts the inter-key intervals `TimingAnalysis` uses into `edges`'
+/// buckets, labelled as `HistogramEdges::labels`.
+pub fn timing_histogram(
+    events: &[KeystrokeEvent],
+    config: &FilterConfig,
+    edges: &HistogramEdges,
+) -> Vec<(String, u64)> {
+    let events = sorted_by_time(events);
+    let mut counts = vec![0; edges.edges().len()];
+    for interval in press_intervals(&events, config) {
+        if let Some(bucket) = edges.bucket(interval) {
+            counts[bucket] += 1;
+        }
+    }
+    edges.labels().into_iter().zip(counts).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::press;
+
+    #[test]
+    fn test_custom_bins_over_known_intervals() {
+        // Intervals 5, 15, 15, 25, 40 and 400ms; 6000ms is over the max gap.
+        let timestamps = [0, 5, 20, 35, 60, 100, 500, 6500];
+        let events: Vec<_> = timestamps.iter().map(|&ts| press(ts, 0x00)).collect();
+
+        let edges = HistogramEdges::uniform(3, 30).unwrap();
+        assert_eq!(edges.edges(), [0, 10, 20, 30]);
+        let histogram = timing_histogram(&events, &FilterConfig::default(), &edges);
+        let expected = [("0-10", 1), ("10-20", 2), ("20-30", 1), ("30+", 2)];
+        assert_eq!(
+            histogram,
+            expected.map(|(label, count)| (label.to_string(), count)).to_vec()
+        );
+
+        let edges: HistogramEdges = "10, 20".parse().unwrap();
+        let histogram = timing_histogram(&events, &FilterConfig::default(), &edges);
+        assert_eq!(histogram, vec![("10-20".to_string(), 2), ("20+".to_string(), 3)]);
+    }
+
+    #[test]
+    fn test_edges_must_ascend() {
+        assert!("0,50,50".parse::<HistogramEdges>().is_err());
+        assert!("100,50".parse::<HistogramEdges>().is_err());
+        assert!("-10,0".parse::<HistogramEdges>().is_err());
+        assert!("".parse::<HistogramEdges>().is_err());
+        assert!(HistogramEdges::uniform(0, 300).is_err());
+        assert_eq!(HistogramEdges::default().labels()[5], "250+");
+    }
+}
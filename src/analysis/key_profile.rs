@@ -0,0 +1,143 @@
+use crate::analysis::filters::median;
+use crate::analysis::frequency::{FrequencyAnalysis, KeyCount};
+use crate::analysis::timing::{HoldDuration, TimingAnalysis};
+use crate::models::keycode::KeyCode;
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Everything the reports know about one key, for judging its placement.
+#[derive(Debug, Clone)]
+pub struct KeyProfile {
+    pub key_code: u32,
+    pub key_name: String,
+    pub presses: u64,
+    pub percentage: f64,
+    /// 1-based frequency rank among the keys pressed; `None` if never pressed.
+    pub rank: Option<usize>,
+    pub keys_ranked: usize,
+    /// Keys typed right before this one, most common first. `percentage` is
+    /// the share of this key's incoming bigrams.
+    pub preceded_by: Vec<KeyCount>,
+    /// Keys typed right after this one, most common first.
+    pub followed_by: Vec<KeyCount>,
+    pub hold: Option<HoldDuration>,
+    /// Median ms from the previous press to this key, and the sample count.
+    pub incoming_median_ms: Option<i64>,
+    pub incoming_samples: usize,
+    /// Median ms from this key to the next press, and the sample count.
+    pub outgoing_median_ms: Option<i64>,
+    pub outgoing_samples: usize,
+}
+
+impl KeyProfile {
+    /// `events` must be the ones `freq` and `timing` were built from.
+    pub fn new(
+        key_code: u32,
+        events: &[KeystrokeEvent],
+        freq: &FrequencyAnalysis,
+        timing: &TimingAnalysis,
+    ) -> Self {
+        let position = freq.key_frequencies.iter().position(|k| k.key_code == key_code);
+        let (presses, percentage) = position
+            .map(|i| &freq.key_frequencies[i])
+            .map_or((0, 0.0), |k| (k.count, k.percentage));
+
+        let preceded_by = neighbors(
+            freq.bigram_frequencies
+                .iter()
+                .filter(|b| b.second_key == key_code)
+                .map(|b| (b.first_key, b.count)),
+        );
+        let followed_by = neighbors(
+            freq.bigram_frequencies
+                .iter()
+                .filter(|b| b.first_key == key_code)
+                .map(|b| (b.second_key, b.count)),
+        );
+
+        let hold = timing.hold_durations.iter().find(|h| h.key_code == key_code).cloned();
+
+        let presses_only: Vec<_> = events
+            .iter()
+            .filter(|e| e.event_type == EventType::Press)
+            .collect();
+        let mut incoming = Vec::new();
+        let mut outgoing = Vec::new();
+        for pair in presses_only.windows(2) {
+            let interval = pair[1].timestamp - pair[0].timestamp;
+            if !timing.filter_config.is_valid_interval(interval) {
+                continue;
+            }
+            if pair[1].key_code == key_code {
+                incoming.push(interval);
+            }
+            if pair[0].key_code == key_code {
+                outgoing.push(interval);
+            }
+        }
+
+        Self {
+            key_code,
+            key_name: KeyCode(key_code).to_name(),
+            presses,
+            percentage,
+            rank: position.map(|i| i + 1),
+            keys_ranked: freq.key_frequencies.len(),
+            preceded_by,
+            followed_by,
+            hold,
+            incoming_samples: incoming.len(),
+            incoming_median_ms: median(&mut incoming),
+            outgoing_samples: outgoing.len(),
+            outgoing_median_ms: median(&mut outgoing),
+        }
+    }
+}
+
+/// `bigram_frequencies` is already sorted by count, so order is kept.
+fn neighbors(counts: impl Iterator<Item = (u32, u64)>) -> Vec<KeyCount> {
+    let counts: Vec<_> = counts.collect();
+    let total: u64 = counts.iter().map(|(_, count)| count).sum();
+    counts
+        .into_iter()
+        .map(|(key_code, count)| KeyCount {
+            key_code,
+            key_name: KeyCode(key_code).to_name(),
+            count,
+            percentage: count as f64 / total as f64 * 100.0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::FilterConfig;
+    use crate::testutil::typed_held;
+
+    const T: u32 = 0x11;
+    const H: u32 = 0x04;
+    const E: u32 = 0x0E;
+    const R: u32 = 0x0F;
+
+    #[test]
+    fn test_profile_counts_neighbors() {
+        // "thethehertee" typed as one run.
+        let events = typed_held(&[T, H, E, T, H, E, H, E, R, T, E, E], 100, 60);
+        let freq = FrequencyAnalysis::from_events(&events);
+        let timing = TimingAnalysis::from_events(&events, FilterConfig::default());
+        let profile = KeyProfile::new(E, &events, &freq, &timing);
+
+        assert_eq!(profile.presses, 5);
+        assert_eq!(profile.rank, Some(1));
+
+        let counts = |keys: &[KeyCount]| -> Vec<(u32, u64)> {
+            keys.iter().map(|k| (k.key_code, k.count)).collect()
+        };
+        assert_eq!(counts(&profile.preceded_by), vec![(H, 3), (E, 1), (T, 1)]);
+        assert_eq!(counts(&profile.followed_by), vec![(H, 1), (E, 1), (R, 1), (T, 1)]);
+
+        assert_eq!(profile.incoming_samples, 5);
+        assert_eq!(profile.outgoing_median_ms, Some(100));
+        assert_eq!(profile.hold.map(|h| h.sample_count), Some(5));
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::fingers::{finger_loads, hand_balance, home_row_pct, mean_row_distance};
+use crate::analysis::{FrequencyAnalysis, SameFingerAnalysis};
+use crate::models::keyboard_layout::KeyboardLayout;
+
+/// Same-finger bigram share that scores 0. QWERTY English is around 6%;
+/// optimized layouts get near 1%.
+pub const SFB_WORST_PCT: f64 = 10.0;
+
+/// Roll share (same hand, different fingers) that scores 100.
+pub const ROLL_BEST_PCT: f64 = 50.0;
+
+/// Home-row share that scores 100. QWERTY is around 30%; Colemak and
+/// similar layouts reach 60-70%.
+pub const HOME_ROW_BEST_PCT: f64 = 70.0;
+
+/// Mean rows travelled from the home row per press that scores 0 for effort.
+pub const EFFORT_WORST_ROWS: f64 = 1.5;
+
+/// How much each component counts in `LayoutScore::total`. Only the ratios
+/// matter; a weight of 0 leaves the component out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutScoreWeights {
+    pub same_finger: f64,
+    pub rolls: f64,
+    pub hand_balance: f64,
+    pub home_row: f64,
+    pub effort: f64,
+}
+
+impl Default for LayoutScoreWeights {
+    fn default() -> Self {
+        Self {
+            same_finger: 3.0,
+            rolls: 1.0,
+            hand_balance: 1.0,
+            home_row: 2.0,
+            effort: 1.0,
+        }
+    }
+}
+
+impl LayoutScoreWeights {
+    fn as_array(&self) -> [f64; 5] {
+        [self.same_finger, self.rolls, self.hand_balance, self.home_row, self.effort]
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let weights = self.as_array();
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(anyhow!("Layout score weights must not be negative"));
+        }
+        if weights.iter().sum::<f64>() <= 0.0 {
+            return Err(anyhow!("At least one layout score weight must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// The raw numbers `LayoutScore` grades, all in percent except
+/// `mean_row_distance`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LayoutMetrics {
+    pub same_finger_pct: f64,
+    pub roll_pct: f64,
+    pub left_hand_pct: f64,
+    pub home_row_pct: f64,
+    /// Rows away from the home row per finger press, on average.
+    pub mean_row_distance: f64,
+}
+
+impl LayoutMetrics {
+    /// `include_repeats` is `FilterConfig::same_finger_repeats`.
+    pub fn from_frequencies(
+        freq: &FrequencyAnalysis,
+        layout: &dyn KeyboardLayout,
+        include_repeats: bool,
+    ) -> Self {
+        let key_percentages = freq.key_percentages();
+        let bigrams =
+            SameFingerAnalysis::from_bigrams(&freq.bigram_frequencies, layout, include_repeats);
+        let (left_hand_pct, _) = hand_balance(&finger_loads(&key_percentages, layout));
+        Self {
+            same_finger_pct: bigrams.same_finger_pct(),
+            roll_pct: bigrams.roll_pct(),
+            left_hand_pct,
+            home_row_pct: home_row_pct(&key_percentages, layout),
+            mean_row_distance: mean_row_distance(&key_percentages, layout),
+        }
+    }
+}
+
+/// How well a layout fits the recorded typing, 0 to 100, higher is better.
+///
+/// Each component is graded from 0 to 100:
+/// - same finger: `100 * (1 - sfb% / SFB_WORST_PCT)`
+/// - rolls: `100 * roll% / ROLL_BEST_PCT`
+/// - hand balance: `100 - 2 * |left% - 50|`
+/// - home row: `100 * home% / HOME_ROW_BEST_PCT`
+/// - effort: `100 * (1 - rows / EFFORT_WORST_ROWS)`
+///
+/// each clamped to 0-100, and `total` is their mean weighted by `LayoutScoreWeights`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutScore {
+    pub same_finger: f64,
+    pub rolls: f64,
+    pub hand_balance: f64,
+    pub home_row: f64,
+    pub effort: f64,
+    pub total: f64,
+}
+
+impl LayoutScore {
+    pub fn new(metrics: &LayoutMetrics, weights: &LayoutScoreWeights) -> Self {
+        let grade = |fraction: f64| (fraction * 100.0).clamp(0.0, 100.0);
+        let same_finger = grade(1.0 - metrics.same_finger_pct / SFB_WORST_PCT);
+        let rolls = grade(metrics.roll_pct / ROLL_BEST_PCT);
+        let hand_balance = grade(1.0 - (metrics.left_hand_pct - 50.0).abs() / 50.0);
+        let home_row = grade(metrics.home_row_pct / HOME_ROW_BEST_PCT);
+        let effort = grade(1.0 - metrics.mean_row_distance / EFFORT_WORST_ROWS);
+
+        let grades = [same_finger, rolls, hand_balance, home_row, effort];
+        let weights = weights.as_array();
+        let weight_total: f64 = weights.iter().sum();
+        let total = if weight_total > 0.0 {
+            grades.iter().zip(weights).map(|(g, w)| g * w).sum::<f64>() / weight_total
+        } else {
+            0.0
+        };
+
+        Self {
+            same_finger,
+            rolls,
+            hand_balance,
+            home_row,
+            effort,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_metrics_give_known_score() {
+        let metrics = LayoutMetrics {
+            same_finger_pct: 2.5,    // 75
+            roll_pct: 25.0,          // 50
+            left_hand_pct: 60.0,     // 80
+            home_row_pct: 35.0,      // 50
+            mean_row_distance: 0.75, // 50
+        };
+        let score = LayoutScore::new(&metrics, &LayoutScoreWeights::default());
+        assert_eq!(
+            [score.same_finger, score.rolls, score.hand_balance, score.home_row, score.effort],
+            [75.0, 50.0, 80.0, 50.0, 50.0]
+        );
+        // (3 * 75 + 50 + 80 + 2 * 50 + 50) / 8
+        assert_eq!(score.total, 63.125);
+
+        let only_rolls = LayoutScoreWeights {
+            same_finger: 0.0,
+            hand_balance: 0.0,
+            home_row: 0.0,
+            effort: 0.0,
+            ..LayoutScoreWeights::default()
+        };
+        assert_eq!(LayoutScore::new(&metrics, &only_rolls).total, 50.0);
+    }
+
+    #[test]
+    fn test_grades_are_clamped_and_weights_checked() {
+        let metrics = LayoutMetrics {
+            same_finger_pct: 20.0,
+            roll_pct: 80.0,
+            left_hand_pct: 100.0,
+            home_row_pct: 90.0,
+            mean_row_distance: 0.0,
+        };
+        let score = LayoutScore::new(&metrics, &LayoutScoreWeights::default());
+        assert_eq!(score.same_finger, 0.0);
+        assert_eq!(score.rolls, 100.0);
+        assert_eq!(score.hand_balance, 0.0);
+        assert_eq!(score.home_row, 100.0);
+        assert_eq!(score.effort, 100.0);
+
+        let negative = LayoutScoreWeights {
+            rolls: -1.0,
+            ..LayoutScoreWeights::default()
+        };
+        assert!(negative.validate().is_err());
+        let zero = LayoutScoreWeights {
+            same_finger: 0.0,
+            rolls: 0.0,
+            hand_balance: 0.0,
+            home_row: 0.0,
+            effort: 0.0,
+        };
+        assert!(zero.validate().is_err());
+        assert!(LayoutScoreWeights::default().validate().is_ok());
+    }
+}
@@ -1,7 +1,49 @@
+pub mod baseline;
+pub mod bigram_compare;
+pub mod chords;
+pub mod corrections;
+pub mod coverage;
 pub mod filters;
+pub mod finger_balance;
+pub mod fingers;
+pub mod flow;
 pub mod frequency;
+pub mod gaming;
+pub mod hand_runs;
+pub mod histogram;
+pub mod key_profile;
+pub mod layout_score;
+pub mod parallel;
+pub mod quantile;
+pub mod rhythm;
+pub mod rolling_wpm;
+pub mod scissors;
+pub mod sessions;
+pub mod shift;
+pub mod smoothing;
+pub mod stuck_modifiers;
+pub mod synthetic;
 pub mod timing;
+pub mod travel;
+pub mod trigram_timing;
 
+pub use baseline::BaselineComparison;
+pub use bigram_compare::BigramTimingComparison;
+pub use chords::ChordAnalysis;
+pub use corrections::EffectiveKeystrokes;
+pub use coverage::{CoverageReport, CoverageScan};
 pub use filters::FilterConfig;
+pub use finger_balance::{FingerBalance, FingerTargets};
+pub use fingers::SameFingerAnalysis;
 pub use frequency::FrequencyAnalysis;
+pub use hand_runs::HandRunAnalysis;
+pub use key_profile::KeyProfile;
+pub use layout_score::{LayoutScore, LayoutScoreWeights};
+pub use rhythm::HandRhythm;
+pub use rolling_wpm::RollingWpm;
+pub use scissors::ScissorAnalysis;
+pub use sessions::TypingSession;
+pub use shift::ShiftAnalysis;
 pub use timing::TimingAnalysis;
+pub use travel::FingerTravel;
+pub use trigram_timing::TrigramTiming;
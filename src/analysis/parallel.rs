@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Inputs shorter than this are scanned on one thread even with the
+/// `parallel` feature, since splitting them costs more than it saves.
+#[cfg(feature = "parallel")]
+pub const PARALLEL_THRESHOLD: usize = 100_000;
+
+/// Counts `key(window)` over every `size`-wide window of `items`, skipping
+/// windows where `key` returns `None`.
+///
+/// With the `parallel` feature, large inputs are split across threads.
+/// Windows are always taken over the whole slice, so the ones straddling a
+/// chunk boundary are counted exactly once and results match the serial scan.
+pub fn count_windows<T, K, F>(items: &[T], size: usize, key: F) -> HashMap<K, u64>
+where
+    T: Sync,
+    K: Eq + Hash + Send,
+    F: Fn(&[T]) -> Option<K> + Sync,
+{
+    #[cfg(feature = "parallel")]
+    if items.len() >= PARALLEL_THRESHOLD {
+        return count_windows_parallel(items, size, key);
+    }
+
+    count_windows_serial(items, size, key)
+}
+
+/// Like `count_windows`, but collects a value per window under its key. The
+/// order of values within a key is unspecified once work is split, so
+/// callers sort them before taking medians.
+pub fn group_windows<T, K, V, F>(items: &[T], size: usize, entry: F) -> HashMap<K, Vec<V>>
+where
+    T: Sync,
+    K: Eq + Hash + Send,
+    V: Send,
+    F: Fn(&[T]) -> Option<(K, V)> + Sync,
+{
+    #[cfg(feature = "parallel")]
+    if items.len() >= PARALLEL_THRESHOLD {
+        return group_windows_parallel(items, size, entry);
+    }
+
+    group_windows_serial(items, size, entry)
+}
+
+fn count_windows_serial<T, K, F>(items: &[T], size: usize, key: F) -> HashMap<K, u64>
+where
+    K: Eq + Hash,
+    F: Fn(&[T]) -> Option<K>,
+{
+    let mut counts = HashMap::new();
+    for window in items.windows(size) {
+        if let Some(k) = key(window) {
+            *counts.entry(k).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+fn group_windows_serial<T, K, V, F>(items: &[T], size: usize, entry: F) -> HashMap<K, Vec<V>>
+where
+    K: Eq + Hash,
+    F: Fn(&[T]) -> Option<(K, V)>,
+{
+    let mut groups: HashMap<K, Vec<V>> = HashMap::new();
+    for window in items.windows(size) {
+        if let Some((k, v)) = entry(window) {
+            groups.entry(k).or_default().push(v);
+        }
+    }
+    groups
+}
+
+#[cfg(feature = "parallel")]
+fn count_windows_parallel<T, K, F>(items: &[T], size: usize, key: F) -> HashMap<K, u64>
+where
+    T: Sync,
+    K: Eq + Hash + Send,
+    F: Fn(&[T]) -> Option<K> + Sync,
+{
+    use rayon::prelude::*;
+
+    items
+        .par_windows(size)
+        .fold(HashMap::new, |mut counts, window| {
+            if let Some(k) = key(window) {
+                *counts.entry(k).or_insert(0) += 1;
+            }
+            counts
+        })
+        .reduce(HashMap::new, |mut merged, partial| {
+            for (k, count) in partial {
+                *merged.entry(k).or_insert(0) += count;
+            }
+            merged
+        })
+}
+
+#[cfg(feature = "parallel")]
+fn group_windows_parallel<T, K, V, F>(items: &[T], size: usize, entry: F) -> HashMap<K, Vec<V>>
+where
+    T: Sync,
+    K: Eq + Hash + Send,
+    V: Send,
+    F: Fn(&[T]) -> Option<(K, V)> + Sync,
+{
+    use rayon::prelude::*;
+
+    items
+        .par_windows(size)
+        .fold(HashMap::new, |mut groups: HashMap<K, Vec<V>>, window| {
+            if let Some((k, v)) = entry(window) {
+                groups.entry(k).or_default().push(v);
+            }
+            groups
+        })
+        .reduce(HashMap::new, |mut merged, partial| {
+            for (k, mut values) in partial {
+                merged.entry(k).or_default().append(&mut values);
+            }
+            merged
+        })
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use super::*;
+    use crate::analysis::synthetic::synthetic_events;
+    use crate::models::KeystrokeEvent;
+
+    fn bigram(window: &[KeystrokeEvent]) -> Option<(u32, u32)> {
+        (window[1].timestamp - window[0].timestamp < 5000)
+            .then(|| (window[0].key_code, window[1].key_code))
+    }
+
+    #[test]
+    fn test_parallel_counts_match_serial() {
+        let events = synthetic_events(PARALLEL_THRESHOLD);
+
+        assert_eq!(
+            count_windows_parallel(&events, 2, bigram),
+            count_windows_serial(&events, 2, bigram)
+        );
+    }
+
+    #[test]
+    fn test_parallel_groups_match_serial() {
+        let events = synthetic_events(PARALLEL_THRESHOLD);
+        let interval = |w: &[KeystrokeEvent]| Some((w[0].key_code, w[1].timestamp - w[0].timestamp));
+
+        let mut parallel = group_windows_parallel(&events, 2, interval);
+        let mut serial = group_windows_serial(&events, 2, interval);
+        for values in parallel.values_mut().chain(serial.values_mut()) {
+            values.sort_unstable();
+        }
+        assert_eq!(parallel, serial);
+    }
+}
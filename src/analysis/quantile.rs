@@ -0,0 +1,193 @@
+/// Streaming estimate of one quantile in constant memory, using the P²
+/// algorithm (Jain & Chlamtac, 1985): five markers track the minimum, the
+/// maximum, the quantile and the points halfway to it on either side, and
+/// are nudged along a parabola as values arrive.
+///
+/// P² has no worst-case error bound. On smooth, single-peaked data such as
+/// inter-key intervals it lands within about 1% of the exact value for the
+/// median and p90 and within a few percent for p99, where fewer samples
+/// inform the tail; see `test_estimates_close_to_exact`. Spiky or
+/// multi-modal data can do worse. The first five values are kept exactly,
+/// so tiny inputs match `calculate_percentiles`.
+#[derive(Debug, Clone)]
+pub struct P2Quantile {
+    p: f64,
+    count: usize,
+    /// Marker heights.
+    heights: [f64; 5],
+    /// Actual marker positions, 0-based.
+    positions: [f64; 5],
+    /// Where the markers should be.
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    /// `p` in `0.0..=1.0`, e.g. `0.95` for p95.
+    pub fn new(p: f64) -> Self {
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [0.0, 1.0, 2.0, 3.0, 4.0],
+            desired: [0.0, 2.0 * p, 4.0 * p, 2.0 + 2.0 * p, 4.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+
+        let h = &mut self.heights;
+        let cell = if value < h[0] {
+            h[0] = value;
+            0
+        } else if value >= h[4] {
+            h[4] = value;
+            3
+        } else {
+            // h[0] <= value < h[4], so some marker 1..=4 is above it.
+            (1..5).find(|&i| value < h[i]).unwrap_or(4) - 1
+        };
+
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+
+        for i in 1..4 {
+            let drift = self.desired[i] - self.positions[i];
+            let room_above = self.positions[i + 1] - self.positions[i] > 1.0;
+            let room_below = self.positions[i - 1] - self.positions[i] < -1.0;
+            if (drift >= 1.0 && room_above) || (drift <= -1.0 && room_below) {
+                let step = drift.signum();
+                let candidate = self.parabolic(i, step);
+                self.heights[i] = if self.heights[i - 1] < candidate && candidate < self.heights[i + 1] {
+                    candidate
+                } else {
+                    self.linear(i, step)
+                };
+                self.positions[i] += step;
+            }
+        }
+    }
+
+    /// `None` until a value has been pushed.
+    pub fn estimate(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            1..=4 => {
+                let mut seen = self.heights[..self.count].to_vec();
+                seen.sort_by(f64::total_cmp);
+                // Same index rule as `calculate_percentile_sorted`.
+                Some(seen[((seen.len() - 1) as f64 * self.p) as usize])
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+
+    fn parabolic(&self, i: usize, step: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + step / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + step) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - step) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, step: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        let j = if step > 0.0 { i + 1 } else { i - 1 };
+        q[i] + step * (q[j] - q[i]) / (n[j] - n[i])
+    }
+}
+
+/// p50, p90, p95 and p99 of a stream, in the shape `calculate_percentiles`
+/// returns, without keeping the values.
+#[derive(Debug, Clone)]
+pub struct StreamingPercentiles {
+    markers: [P2Quantile; 4],
+}
+
+impl Default for StreamingPercentiles {
+    fn default() -> Self {
+        Self {
+            markers: [0.50, 0.90, 0.95, 0.99].map(P2Quantile::new),
+        }
+    }
+}
+
+impl StreamingPercentiles {
+    pub fn push(&mut self, value: i64) {
+        for marker in &mut self.markers {
+            marker.push(value as f64);
+        }
+    }
+
+    pub fn percentiles(&self) -> Option<(i64, i64, i64, i64)> {
+        let [p50, p90, p95, p99] = &self.markers;
+        let ms = |marker: &P2Quantile| marker.estimate().map(|v| v.round() as i64);
+        Some((ms(p50)?, ms(p90)?, ms(p95)?, ms(p99)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::filters::calculate_percentiles;
+
+    /// Skewed, typing-like intervals from a fixed LCG so the test is repeatable.
+    fn intervals(n: usize) -> Vec<i64> {
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut uniform = move || {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        (0..n)
+            .map(|_| {
+                // Sum of uniforms is near-normal; exp of it is right-skewed like real gaps.
+                let normal: f64 = (0..12).map(|_| uniform()).sum::<f64>() - 6.0;
+                (150.0 * (0.5 * normal).exp()) as i64
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_estimates_close_to_exact() {
+        let mut values = intervals(200_000);
+        let mut streaming = StreamingPercentiles::default();
+        for &value in &values {
+            streaming.push(value);
+        }
+
+        let (e50, e90, e95, e99) = calculate_percentiles(&mut values).unwrap();
+        let (a50, a90, a95, a99) = streaming.percentiles().unwrap();
+        let within = |approx: i64, exact: i64, tolerance: f64| {
+            let error = (approx - exact).abs() as f64 / exact as f64;
+            assert!(error <= tolerance, "{} vs exact {}", approx, exact);
+        };
+        within(a50, e50, 0.01);
+        within(a90, e90, 0.01);
+        within(a95, e95, 0.02);
+        within(a99, e99, 0.05);
+    }
+
+    #[test]
+    fn test_small_inputs_are_exact() {
+        let mut streaming = StreamingPercentiles::default();
+        assert_eq!(streaming.percentiles(), None);
+        for value in [40, 10, 30, 20] {
+            streaming.push(value);
+        }
+        let mut values = vec![40, 10, 30, 20];
+        assert_eq!(streaming.percentiles(), calculate_percentiles(&mut values));
+    }
+}
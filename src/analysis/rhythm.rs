@@ -0,0 +1,119 @@
+use crate::analysis::filters::{median, FilterConfig};
+use crate::analysis::fingers::is_alternating;
+use crate::models::keyboard_layout::{Finger, KeyboardLayout};
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Median inter-key time of bigrams that switch hands vs. bigrams typed
+/// with one hand. While one hand presses, the other can already reach for
+/// its key, so a comfortable layout shows noticeably faster alternation.
+///
+/// As in `HandRunAnalysis`, thumb keys and keys the layout doesn't know are
+/// left out; a bigram counts only when both keys belong to a hand.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HandRhythm {
+    pub alternating_median_ms: Option<i64>,
+    pub alternating_samples: usize,
+    pub same_hand_median_ms: Option<i64>,
+    pub same_hand_samples: usize,
+}
+
+impl HandRhythm {
+    pub fn from_events(
+        events: &[KeystrokeEvent],
+        layout: &dyn KeyboardLayout,
+        config: &FilterConfig,
+    ) -> Self {
+        let presses: Vec<_> = events
+            .iter()
+            .filter(|e| e.event_type == EventType::Press)
+            .collect();
+
+        let mut alternating = Vec::new();
+        let mut same_hand = Vec::new();
+        for pair in presses.windows(2) {
+            let interval = pair[1].timestamp - pair[0].timestamp;
+            if !config.is_valid_interval(interval) {
+                continue;
+            }
+            let (from, to) = (pair[0].key_code, pair[1].key_code);
+            let on_a_hand = |key_code| {
+                layout.get_finger(key_code).is_some_and(|finger| finger != Finger::Thumb)
+            };
+            if !on_a_hand(from) || !on_a_hand(to) {
+                continue;
+            }
+            if is_alternating(layout, from, to) {
+                alternating.push(interval);
+            } else {
+                same_hand.push(interval);
+            }
+        }
+
+        Self {
+            alternating_samples: alternating.len(),
+            alternating_median_ms: median(&mut alternating),
+            same_hand_samples: same_hand.len(),
+            same_hand_median_ms: median(&mut same_hand),
+        }
+    }
+
+    /// Same-hand median over alternating median; above 1.0 means alternation
+    /// is faster. `None` until both kinds of bigram have been seen.
+    pub fn ratio(&self) -> Option<f64> {
+        match (self.same_hand_median_ms, self.alternating_median_ms) {
+            (Some(same), Some(alternating)) if alternating > 0 => {
+                Some(same as f64 / alternating as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::keyboard_layout::QwertyLayout;
+    use crate::testutil::press;
+
+    const A: u32 = 0x00;
+    const S: u32 = 0x01;
+    const J: u32 = 0x26;
+    const K: u32 = 0x28;
+    const SPACE: u32 = 0x31;
+
+    #[test]
+    fn test_alternation_faster_than_same_hand() {
+        // a->j, j->s, s->k alternate at 80ms; k->j, j->k stay on the right at 160ms.
+        let events = [
+            press(0, A),
+            press(80, J),
+            press(160, S),
+            press(240, K),
+            press(400, J),
+            press(560, K),
+        ];
+        let rhythm = HandRhythm::from_events(&events, &QwertyLayout::new(), &FilterConfig::default());
+
+        assert_eq!(rhythm.alternating_samples, 3);
+        assert_eq!(rhythm.alternating_median_ms, Some(80));
+        assert_eq!(rhythm.same_hand_samples, 2);
+        assert_eq!(rhythm.same_hand_median_ms, Some(160));
+        assert_eq!(rhythm.ratio(), Some(2.0));
+    }
+
+    #[test]
+    fn test_thumb_and_long_gaps_are_skipped() {
+        let events = [
+            press(0, A),
+            press(100, SPACE),
+            press(200, J),
+            press(20_000, A),
+            press(20_100, S),
+        ];
+        let rhythm = HandRhythm::from_events(&events, &QwertyLayout::new(), &FilterConfig::default());
+
+        assert_eq!(rhythm.alternating_samples, 0);
+        assert_eq!(rhythm.same_hand_samples, 1);
+        assert_eq!(rhythm.ratio(), None);
+    }
+}
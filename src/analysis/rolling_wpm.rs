@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+pub const DEFAULT_WPM_WINDOW_MS: i64 = 60_000;
+
+/// Below this much observed time a rate is mostly noise, so none is reported.
+const MIN_WARMUP_MS: i64 = 10_000;
+
+/// Characters per word in the usual WPM definition.
+const CHARS_PER_WORD: f64 = 5.0;
+
+/// Words per minute over the last `window_ms`, from presses pushed as they
+/// arrive. Only the presses inside the window are kept.
+///
+/// Until a full window has passed since `started_ms` the rate is taken over
+/// the time observed so far, so it doesn't read low for the first minute.
+#[derive(Debug, Clone)]
+pub struct RollingWpm {
+    window_ms: i64,
+    started_ms: i64,
+    presses: VecDeque<i64>,
+}
+
+impl RollingWpm {
+    pub fn new(window_ms: i64, started_ms: i64) -> Self {
+        Self {
+            window_ms,
+            started_ms,
+            presses: VecDeque::new(),
+        }
+    }
+
+    pub fn window_ms(&self) -> i64 {
+        self.window_ms
+    }
+
+    pub fn push(&mut self, timestamp: i64) {
+        self.presses.push_back(timestamp);
+    }
+
+    /// `None` while warming up (less than `MIN_WARMUP_MS` observed).
+    pub fn wpm(&mut self, now_ms: i64) -> Option<f64> {
+        let window_start = now_ms - self.window_ms;
+        while self.presses.front().is_some_and(|&ts| ts <= window_start) {
+            self.presses.pop_front();
+        }
+
+        let observed_ms = (now_ms - self.started_ms).min(self.window_ms);
+        if observed_ms < MIN_WARMUP_MS {
+            return None;
+        }
+
+        let minutes = observed_ms as f64 / 60_000.0;
+        Some(self.presses.len() as f64 / CHARS_PER_WORD / minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_window_counts_only_recent_presses() {
+        let mut rolling = RollingWpm::new(DEFAULT_WPM_WINDOW_MS, 0);
+        // 100 presses spread over the first minute, then 300 in the second.
+        for i in 0..100 {
+            rolling.push(i * 600);
+        }
+        for i in 0..300 {
+            rolling.push(60_000 + (i + 1) * 200);
+        }
+        assert_eq!(rolling.wpm(120_000), Some(60.0));
+    }
+
+    #[test]
+    fn test_cold_start_scales_by_observed_time() {
+        let mut rolling = RollingWpm::new(DEFAULT_WPM_WINDOW_MS, 0);
+        for i in 0..50 {
+            rolling.push(i * 100);
+        }
+        assert_eq!(rolling.wpm(5_000), None);
+        // 50 presses in the 15s observed so far: 10 words in a quarter minute.
+        assert_eq!(rolling.wpm(15_000), Some(40.0));
+    }
+
+    #[test]
+    fn test_idle_window_reads_zero() {
+        let mut rolling = RollingWpm::new(DEFAULT_WPM_WINDOW_MS, 0);
+        rolling.push(1_000);
+        assert_eq!(rolling.wpm(90_000), Some(0.0));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::analysis::frequency::BigramCount;
+use crate::models::keyboard_layout::KeyboardLayout;
+
+/// A bigram is a scissor when:
+/// - both keys are typed by the same hand,
+/// - by adjacent fingers (pinky/ring, ring/middle or middle/index), and
+/// - the keys are two or more rows apart (e.g. top row to bottom row).
+///
+/// Same-finger bigrams and thumb keys are never scissors. Row distance comes
+/// from the layout, so the number row counts as a row on layouts that have one.
+pub fn is_scissor(layout: &dyn KeyboardLayout, first: u32, second: u32) -> bool {
+    let (Some(first_finger), Some(second_finger)) =
+        (layout.get_finger(first), layout.get_finger(second))
+    else {
+        return false;
+    };
+    let (Some(first_rank), Some(second_rank)) =
+        (first_finger.column_rank(), second_finger.column_rank())
+    else {
+        return false;
+    };
+    let (Some(first_row), Some(second_row)) = (layout.key_row(first), layout.key_row(second))
+    else {
+        return false;
+    };
+
+    first_finger.hand() == second_finger.hand()
+        && first_rank.abs_diff(second_rank) == 1
+        && first_row.abs_diff(second_row) >= 2
+}
+
+#[derive(Debug, Default)]
+pub struct ScissorAnalysis {
+    pub total_bigrams: u64,
+    pub scissor_count: u64,
+    /// Scissor bigrams by count, most frequent first.
+    pub worst: Vec<BigramCount>,
+}
+
+impl ScissorAnalysis {
+    pub fn from_bigrams(bigrams: &[BigramCount], layout: &dyn KeyboardLayout) -> Self {
+        let total_bigrams = bigrams.iter().map(|b| b.count).sum();
+
+        // Input is already sorted by count, so the filtered list is too.
+        let worst: Vec<BigramCount> = bigrams
+            .iter()
+            .filter(|b| is_scissor(layout, b.first_key, b.second_key))
+            .cloned()
+            .collect();
+        let scissor_count = worst.iter().map(|b| b.count).sum();
+
+        Self {
+            total_bigrams,
+            scissor_count,
+            worst,
+        }
+    }
+
+    pub fn scissor_pct(&self) -> f64 {
+        if self.total_bigrams == 0 {
+            return 0.0;
+        }
+        self.scissor_count as f64 / self.total_bigrams as f64 * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::keyboard_layout::QwertyLayout;
+
+    const Q: u32 = 0x0C;
+    const X: u32 = 0x07;
+    const C: u32 = 0x08;
+    const W: u32 = 0x0D;
+    const E: u32 = 0x0E;
+    const S: u32 = 0x01;
+
+    fn bigram(first_key: u32, second_key: u32, count: u64) -> BigramCount {
+        BigramCount {
+            first_key,
+            second_key,
+            display: format!("{:02X}->{:02X}", first_key, second_key),
+            count,
+            percentage: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_known_scissor() {
+        let layout = QwertyLayout::new();
+        // Top-row pinky to bottom-row ring.
+        assert!(is_scissor(&layout, Q, X));
+        // Top-row ring to bottom-row middle.
+        assert!(is_scissor(&layout, W, C));
+    }
+
+    #[test]
+    fn test_clean_bigrams() {
+        let layout = QwertyLayout::new();
+        // Adjacent fingers on neighbouring rows.
+        assert!(!is_scissor(&layout, W, S));
+        // Same row.
+        assert!(!is_scissor(&layout, W, E));
+        // Two rows apart but same finger.
+        assert!(!is_scissor(&layout, E, C));
+    }
+
+    #[test]
+    fn test_scissor_percentage() {
+        let layout = QwertyLayout::new();
+        let bigrams = vec![bigram(W, E, 6), bigram(Q, X, 3), bigram(W, C, 1)];
+
+        let analysis = ScissorAnalysis::from_bigrams(&bigrams, &layout);
+        assert_eq!(analysis.total_bigrams, 10);
+        assert_eq!(analysis.scissor_count, 4);
+        assert!((analysis.scissor_pct() - 40.0).abs() < 0.01);
+        assert_eq!(analysis.worst[0].first_key, Q);
+    }
+}
@@ -0,0 +1,126 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::analysis::filters::{sorted_by_time, FilterConfig};
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Presses further apart than this start a new session. Much longer than
+/// `DEFAULT_MAX_GAP_MS`, which splits typing into bursts rather than sittings.
+pub const DEFAULT_SESSION_GAP_MS: i64 = 5 * 60 * 1000;
+
+/// Characters per word in the usual WPM definition.
+const CHARS_PER_WORD: f64 = 5.0;
+
+/// One sitting of typing: presses with no pause longer than the session gap.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypingSession {
+    /// First and last press, in ms since the epoch.
+    pub start: i64,
+    pub end: i64,
+    pub keystrokes: usize,
+    /// The app that received the most presses.
+    pub application: String,
+    /// `None` for a single press, which has no duration.
+    pub wpm: Option<f64>,
+}
+
+impl TypingSession {
+    pub fn duration_ms(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+/// Splits the presses in `events` into sessions wherever they pause longer
+/// than `gap_ms`. Releases are ignored, so a held key doesn't stretch a session.
+pub fn typing_sessions(events: &[KeystrokeEvent], gap_ms: i64) -> Vec<TypingSession> {
+    let presses: Vec<KeystrokeEvent> = sorted_by_time(events)
+        .iter()
+        .filter(|e| e.event_type == EventType::Press)
+        .cloned()
+        .collect();
+
+    let config = FilterConfig {
+        max_gap_ms: gap_ms,
+        ..FilterConfig::default()
+    };
+    config
+        .filter_events_by_gap(&presses)
+        .into_iter()
+        .map(session_from)
+        .collect()
+}
+
+/// Words per minute over the time actually spent typing: all presses, over
+/// the summed length of the sessions `gap_ms` splits them into. `None` if
+/// that is no time at all.
+pub fn typing_wpm(events: &[KeystrokeEvent], gap_ms: i64) -> Option<f64> {
+    let sessions = typing_sessions(events, gap_ms);
+    let presses: usize = sessions.iter().map(|s| s.keystrokes).sum();
+    let minutes = sessions.iter().map(|s| s.duration_ms()).sum::<i64>() as f64 / 60_000.0;
+    (minutes > 0.0).then(|| presses as f64 / CHARS_PER_WORD / minutes)
+}
+
+/// `segment` is never empty: `filter_events_by_gap` doesn't produce empty slices.
+fn session_from(segment: &[KeystrokeEvent]) -> TypingSession {
+    let start = segment[0].timestamp;
+    let end = segment[segment.len() - 1].timestamp;
+
+    let mut per_app: HashMap<&str, usize> = HashMap::new();
+    for event in segment {
+        *per_app.entry(event.application.as_str()).or_insert(0) += 1;
+    }
+    // Ties go to the alphabetically first app, so output is stable.
+    let application = per_app
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+        .map(|(app, _)| app.to_string())
+        .unwrap_or_default();
+
+    let minutes = (end - start) as f64 / 60_000.0;
+    let wpm = (end > start).then(|| segment.len() as f64 / CHARS_PER_WORD / minutes);
+
+    TypingSession {
+        start,
+        end,
+        keystrokes: segment.len(),
+        application,
+        wpm,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{self, app_press, in_app};
+
+    #[test]
+    fn test_gap_splits_into_two_sessions() {
+        let mut events: Vec<_> = (0..60)
+            .map(|i| app_press(i * 1000, if i < 40 { "com.test.editor" } else { "com.test.chat" }))
+            .collect();
+        // Ten minutes later, a short burst in the chat app.
+        events.extend((0..10).map(|i| app_press(660_000 + i * 500, "com.test.chat")));
+        events.push(in_app(testutil::release(2_000_000, 0x00), "com.test.chat"));
+
+        let sessions = typing_sessions(&events, DEFAULT_SESSION_GAP_MS);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!((sessions[0].start, sessions[0].end), (0, 59_000));
+        assert_eq!(sessions[0].keystrokes, 60);
+        assert_eq!(sessions[0].application, "com.test.editor");
+        // 60 presses over 59s: 12 words in just under a minute.
+        assert!((sessions[0].wpm.unwrap() - 12.0 * 60.0 / 59.0).abs() < 1e-9);
+
+        assert_eq!((sessions[1].start, sessions[1].end), (660_000, 664_500));
+        assert_eq!(sessions[1].keystrokes, 10);
+        assert_eq!(sessions[1].application, "com.test.chat");
+    }
+
+    #[test]
+    fn test_single_press_has_no_wpm() {
+        let sessions = typing_sessions(&[app_press(1000, "com.test.app")], DEFAULT_SESSION_GAP_MS);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].duration_ms(), 0);
+        assert_eq!(sessions[0].wpm, None);
+    }
+}
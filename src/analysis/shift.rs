@@ -0,0 +1,201 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::models::keycode::{KeyCategory, KeyCode};
+use crate::models::{EventType, KeystrokeEvent};
+
+const LEFT_SHIFT: u32 = 0x38;
+const RIGHT_SHIFT: u32 = 0x3C;
+
+/// A Shift with no release event (e.g. releases aren't recorded) counts as
+/// let go once this long passes without another key press.
+const SHIFT_IDLE_LIMIT_MS: i64 = 5000;
+
+fn is_shift(key_code: u32) -> bool {
+    key_code == LEFT_SHIFT || key_code == RIGHT_SHIFT
+}
+
+/// Capital letters reconstructed from Shift overlapping letter presses.
+///
+/// A letter is a capital when it is pressed while a Shift key is held: Shift
+/// went down before the letter and had not been released yet. A Shift press
+/// that capitalized at least one letter is "paired"; the rest are standalone
+/// (selection, shortcuts, symbols).
+#[derive(Debug, Default)]
+pub struct ShiftAnalysis {
+    /// Letter key code -> presses typed as a capital.
+    pub capitals: HashMap<u32, u64>,
+    pub capital_count: u64,
+    pub paired_shifts: u64,
+    pub standalone_shifts: u64,
+}
+
+impl ShiftAnalysis {
+    pub fn from_events(events: &[KeystrokeEvent]) -> Self {
+        let mut analysis = Self::default();
+        for (_, capitalized) in shift_presses(events) {
+            if capitalized.is_empty() {
+                analysis.standalone_shifts += 1;
+                continue;
+            }
+            analysis.paired_shifts += 1;
+            for letter in capitalized {
+                *analysis.capitals.entry(events[letter].key_code).or_insert(0) += 1;
+                analysis.capital_count += 1;
+            }
+        }
+        analysis
+    }
+
+    /// Capital letters, most frequent first, with their name.
+    pub fn top_capitals(&self, n: usize) -> Vec<(String, u64)> {
+        let mut capitals: Vec<_> = self.capitals.iter().map(|(&code, &count)| (code, count)).collect();
+        capitals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        capitals
+            .into_iter()
+            .take(n)
+            .map(|(code, count)| (KeyCode(code).to_name(), count))
+            .collect()
+    }
+}
+
+/// Drops the Shift presses (and their releases) that only served to type
+/// capitals, so frequency counts don't charge Shift for every capital letter.
+/// Standalone Shift presses are kept.
+pub fn without_paired_shifts(events: &[KeystrokeEvent]) -> Vec<KeystrokeEvent> {
+    let mut dropped = vec![false; events.len()];
+    for (index, capitalized) in shift_presses(events) {
+        if capitalized.is_empty() {
+            continue;
+        }
+        dropped[index] = true;
+        let release = events[index + 1..].iter().position(|e| {
+            e.key_code == events[index].key_code && e.event_type == EventType::Release
+        });
+        if let Some(offset) = release {
+            dropped[index + 1 + offset] = true;
+        }
+    }
+
+    events
+        .iter()
+        .zip(dropped)
+        .filter(|(_, dropped)| !dropped)
+        .map(|(event, _)| event.clone())
+        .collect()
+}
+
+/// Every Shift press (by index) with the indices of the letter presses typed
+/// while it was held.
+fn shift_presses(events: &[KeystrokeEvent]) -> Vec<(usize, Vec<usize>)> {
+    let mut presses: Vec<(usize, Vec<usize>)> = Vec::new();
+    // Shift key code -> index into `presses` while that Shift is held.
+    let mut held: HashMap<u32, usize> = HashMap::new();
+    let mut last_press: Option<i64> = None;
+
+    for (i, event) in events.iter().enumerate() {
+        if event.event_type == EventType::Press {
+            if last_press.is_some_and(|t| event.timestamp - t > SHIFT_IDLE_LIMIT_MS) {
+                held.clear();
+            }
+            last_press = Some(event.timestamp);
+        }
+
+        match (event.event_type, is_shift(event.key_code)) {
+            (EventType::Press, true) => {
+                // Key repeat sends more presses while held; keep the first.
+                if let Entry::Vacant(slot) = held.entry(event.key_code) {
+                    slot.insert(presses.len());
+                    presses.push((i, Vec::new()));
+                }
+            }
+            (EventType::Release, true) => {
+                held.remove(&event.key_code);
+            }
+            (EventType::Press, false) => {
+                let is_letter = KeyCode(event.key_code).category() == KeyCategory::Letter;
+                // With both Shifts down, credit the one pressed most recently.
+                if let (true, Some(&slot)) = (is_letter, held.values().max()) {
+                    presses[slot].1.push(i);
+                }
+            }
+            (EventType::Release, false) => {}
+        }
+    }
+
+    presses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{press as down, release as up};
+
+    const A: u32 = 0x00;
+    const S: u32 = 0x01;
+    const ONE: u32 = 0x12;
+
+    #[test]
+    fn test_letter_inside_shift_hold_is_capital() {
+        let events = vec![
+            down(0, LEFT_SHIFT),
+            down(50, A),
+            up(90, A),
+            up(120, LEFT_SHIFT),
+            down(200, S),
+            up(260, S),
+        ];
+
+        let shift = ShiftAnalysis::from_events(&events);
+        assert_eq!(shift.capital_count, 1);
+        assert_eq!(shift.capitals.get(&A), Some(&1));
+        assert_eq!(shift.capitals.get(&S), None);
+        assert_eq!(shift.paired_shifts, 1);
+        assert_eq!(shift.standalone_shifts, 0);
+    }
+
+    #[test]
+    fn test_letter_after_shift_release_is_lowercase() {
+        let events = vec![down(0, RIGHT_SHIFT), up(40, RIGHT_SHIFT), down(60, A), up(100, A)];
+
+        let shift = ShiftAnalysis::from_events(&events);
+        assert_eq!(shift.capital_count, 0);
+        assert_eq!(shift.standalone_shifts, 1);
+    }
+
+    #[test]
+    fn test_shifted_symbol_is_not_a_capital() {
+        let events = vec![down(0, LEFT_SHIFT), down(40, ONE), up(80, ONE), up(100, LEFT_SHIFT)];
+
+        let shift = ShiftAnalysis::from_events(&events);
+        assert_eq!(shift.capital_count, 0);
+        assert_eq!(shift.standalone_shifts, 1);
+    }
+
+    #[test]
+    fn test_missing_release_expires() {
+        let events = vec![down(0, LEFT_SHIFT), down(50, A), down(10_000, S)];
+
+        let shift = ShiftAnalysis::from_events(&events);
+        assert_eq!(shift.capitals.get(&A), Some(&1));
+        assert_eq!(shift.capitals.get(&S), None);
+    }
+
+    #[test]
+    fn test_without_paired_shifts_keeps_standalone() {
+        let events = vec![
+            down(0, LEFT_SHIFT),
+            down(50, A),
+            up(90, A),
+            up(120, LEFT_SHIFT),
+            down(500, LEFT_SHIFT),
+            up(540, LEFT_SHIFT),
+        ];
+
+        let kept = without_paired_shifts(&events);
+        let shift_events: Vec<_> = kept.iter().filter(|e| is_shift(e.key_code)).collect();
+        assert_eq!(kept.len(), 4);
+        assert_eq!(shift_events.len(), 2);
+        assert_eq!(shift_events[0].timestamp, 500);
+    }
+}
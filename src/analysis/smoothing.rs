@@ -0,0 +1,33 @@
+/// Trailing moving average of `values` over `window` entries: each output is
+/// the mean of that entry and up to `window - 1` before it. The first few
+/// entries average over what is there, so the output is as long as the input
+/// and starts at the first value rather than ramping up from zero.
+pub fn moving_average(values: &[u64], window: usize) -> Vec<f64> {
+    let window = window.max(1);
+    let mut sum = 0u64;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            sum += value;
+            if i >= window {
+                sum -= values[i - window];
+            }
+            sum as f64 / (i + 1).min(window) as f64
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moving_average_edges() {
+        assert_eq!(moving_average(&[3, 6, 9, 0, 3], 3), vec![3.0, 4.5, 6.0, 5.0, 4.0]);
+        assert_eq!(moving_average(&[3, 6, 9], 1), vec![3.0, 6.0, 9.0]);
+        assert_eq!(moving_average(&[3, 6, 9], 0), vec![3.0, 6.0, 9.0]);
+        assert_eq!(moving_average(&[2, 4], 7), vec![2.0, 3.0]);
+        assert!(moving_average(&[], 7).is_empty());
+    }
+}
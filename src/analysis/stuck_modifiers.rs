@@ -0,0 +1,180 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::models::keycode::KeyCode;
+use crate::models::{EventType, KeystrokeEvent};
+
+/// A modifier down this long without a release was most likely never let go
+/// as far as the recording is concerned (a missed release event), rather than
+/// deliberately held.
+pub const STUCK_MODIFIER_MS: i64 = 10_000;
+
+/// A modifier press whose release didn't come within the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StuckModifier {
+    /// Index of the press in the events it was found in.
+    pub index: usize,
+    pub key_code: u32,
+    pub pressed_at: i64,
+}
+
+/// Follows which modifier keys (`KeyCode::is_held_modifier`) are down through
+/// a time-ordered stream and reports each one held past the threshold, once,
+/// when the first event after that point arrives. Streams without any release
+/// events (`capture.record_releases` off) would report every modifier; callers
+/// check for that.
+#[derive(Debug)]
+pub struct StuckModifierTracker {
+    threshold_ms: i64,
+    /// Modifier key code -> (index, timestamp) of its press.
+    held: HashMap<u32, (usize, i64)>,
+}
+
+impl StuckModifierTracker {
+    pub fn new(threshold_ms: i64) -> Self {
+        Self {
+            threshold_ms,
+            held: HashMap::new(),
+        }
+    }
+
+    /// Feeds the event at `index`; returns the modifiers it shows to be stuck.
+    pub fn push(&mut self, index: usize, event: &KeystrokeEvent) -> Vec<StuckModifier> {
+        let mut stuck: Vec<StuckModifier> = self
+            .held
+            .iter()
+            .filter(|(_, &(_, pressed_at))| event.timestamp - pressed_at > self.threshold_ms)
+            .map(|(&key_code, &(index, pressed_at))| StuckModifier {
+                index,
+                key_code,
+                pressed_at,
+            })
+            .collect();
+        stuck.sort_by_key(|s| s.index);
+        for modifier in &stuck {
+            self.held.remove(&modifier.key_code);
+        }
+
+        if KeyCode(event.key_code).is_held_modifier() {
+            match event.event_type {
+                EventType::Press => {
+                    if let Entry::Vacant(slot) = self.held.entry(event.key_code) {
+                        slot.insert((index, event.timestamp));
+                    }
+                }
+                EventType::Release => {
+                    self.held.remove(&event.key_code);
+                }
+            }
+        }
+        stuck
+    }
+}
+
+/// Modifier presses in `events` (sorted by time) held longer than
+/// `threshold_ms` without a release. Empty when no releases were recorded.
+pub fn find_stuck_modifiers(events: &[KeystrokeEvent], threshold_ms: i64) -> Vec<StuckModifier> {
+    if !events.iter().any(|e| e.event_type == EventType::Release) {
+        return Vec::new();
+    }
+    let mut tracker = StuckModifierTracker::new(threshold_ms);
+    events
+        .iter()
+        .enumerate()
+        .flat_map(|(i, event)| tracker.push(i, event))
+        .collect()
+}
+
+/// `events` with a release added `threshold_ms` after each stuck modifier's
+/// press, so later keys aren't attributed to it (e.g. by `with_modifiers`).
+/// The late real release, if any, is left in place; analyses that follow held
+/// keys ignore a release for a key that isn't down.
+pub fn release_stuck_modifiers(
+    events: &[KeystrokeEvent],
+    threshold_ms: i64,
+) -> Vec<KeystrokeEvent> {
+    let mut releases: Vec<KeystrokeEvent> = find_stuck_modifiers(events, threshold_ms)
+        .into_iter()
+        .map(|stuck| KeystrokeEvent {
+            timestamp: stuck.pressed_at + threshold_ms,
+            event_type: EventType::Release,
+            ..events[stuck.index].clone()
+        })
+        .collect();
+    if releases.is_empty() {
+        return events.to_vec();
+    }
+    releases.sort_by_key(|e| e.timestamp);
+
+    let mut out = Vec::with_capacity(events.len() + releases.len());
+    let mut pending = releases.into_iter().peekable();
+    for event in events {
+        while let Some(release) = pending.next_if(|r| r.timestamp <= event.timestamp) {
+            out.push(release);
+        }
+        out.push(event.clone());
+    }
+    out.extend(pending);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::filters::with_modifiers;
+    use crate::models::event::Modifier;
+    use crate::testutil::{press, release};
+
+    const SHIFT: u32 = 0x38;
+    const A: u32 = 0x00;
+    const S: u32 = 0x01;
+
+    #[test]
+    fn test_long_held_shift_is_released_before_later_keys() {
+        let shift_down = KeystrokeEvent {
+            modifiers: vec![Modifier::Shift],
+            ..press(0, SHIFT)
+        };
+        let events = vec![
+            shift_down,
+            press(100, A),
+            release(150, A),
+            // The Shift release was lost; these were typed without it.
+            press(20_000, S),
+            release(20_050, S),
+            press(20_100, A),
+        ];
+
+        let stuck = find_stuck_modifiers(&events, STUCK_MODIFIER_MS);
+        assert_eq!(
+            stuck,
+            vec![StuckModifier {
+                index: 0,
+                key_code: SHIFT,
+                pressed_at: 0
+            }]
+        );
+        // Without the fix, Shift poisons everything after it.
+        assert_eq!(with_modifiers(&events, &[Modifier::Shift], i64::MAX).len(), 5);
+
+        let fixed = release_stuck_modifiers(&events, STUCK_MODIFIER_MS);
+        assert_eq!(fixed.len(), 7);
+        assert_eq!(fixed[3].timestamp, STUCK_MODIFIER_MS);
+        assert_eq!(fixed[3].event_type, EventType::Release);
+        assert_eq!(fixed[3].key_code, SHIFT);
+        let shifted = with_modifiers(&fixed, &[Modifier::Shift], i64::MAX);
+        assert_eq!(shifted, vec![press(100, A), release(150, A)]);
+
+        // A Shift released in time is left alone.
+        let held_briefly = [press(0, SHIFT), press(100, A), release(200, SHIFT), press(30_000, S)];
+        assert!(find_stuck_modifiers(&held_briefly, STUCK_MODIFIER_MS).is_empty());
+    }
+
+    #[test]
+    fn test_caps_lock_left_on_is_not_stuck() {
+        const CAPS_LOCK: u32 = 0x39;
+        let events = [press(0, CAPS_LOCK), press(100, A), release(150, A), press(60_000, S)];
+        assert!(find_stuck_modifiers(&events, STUCK_MODIFIER_MS).is_empty());
+        assert_eq!(release_stuck_modifiers(&events, STUCK_MODIFIER_MS), events);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Letter and space key codes, weighted roughly by English frequency
+/// (E, T, A, O, I, N, S, H, R, ... plus Space).
+const WEIGHTED_KEYS: &[(u32, u32)] = &[
+    (0x31, 18),
+    (0x0E, 12),
+    (0x11, 9),
+    (0x00, 8),
+    (0x1F, 8),
+    (0x22, 7),
+    (0x2D, 7),
+    (0x01, 6),
+    (0x04, 6),
+    (0x0F, 6),
+    (0x02, 4),
+    (0x25, 4),
+    (0x20, 3),
+    (0x08, 3),
+    (0x2E, 2),
+    (0x0D, 2),
+    (0x03, 2),
+    (0x10, 2),
+    (0x23, 2),
+    (0x0B, 1),
+    (0x09, 1),
+    (0x28, 1),
+    (0x26, 1),
+    (0x07, 1),
+    (0x0C, 1),
+    (0x06, 1),
+];
+
+/// Deterministic typing-like events for benches and tests: `n` presses,
+/// each followed by its release, with 40-250ms between presses, 60-140ms
+/// holds and an occasional multi-second pause between bursts.
+pub fn synthetic_events(n: usize) -> Vec<KeystrokeEvent> {
+    let total_weight: u32 = WEIGHTED_KEYS.iter().map(|(_, w)| w).sum();
+    let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+    let mut events = Vec::with_capacity(n * 2);
+    let mut timestamp: i64 = 1_700_000_000_000;
+
+    for _ in 0..n {
+        let mut pick = (rng.next() % u64::from(total_weight)) as u32;
+        let key_code = WEIGHTED_KEYS
+            .iter()
+            .find(|(_, weight)| {
+                if pick < *weight {
+                    true
+                } else {
+                    pick -= weight;
+                    false
+                }
+            })
+            .map_or(0x31, |(key_code, _)| *key_code);
+
+        let hold = 60 + (rng.next() % 80) as i64;
+        events.push(synthetic_event(timestamp, key_code, EventType::Press));
+        events.push(synthetic_event(timestamp + hold, key_code, EventType::Release));
+
+        let gap = if rng.next().is_multiple_of(50) {
+            3_000 + (rng.next() % 10_000) as i64
+        } else {
+            40 + (rng.next() % 210) as i64
+        };
+        timestamp += gap;
+    }
+
+    // Holds can outlast the gap to the next press, so restore time order.
+    events.sort_by_key(|e| e.timestamp);
+    events
+}
+
+fn synthetic_event(timestamp: i64, key_code: u32, event_type: EventType) -> KeystrokeEvent {
+    KeystrokeEvent {
+        timestamp,
+        key_code,
+        event_type,
+        modifiers: vec![],
+        application: "com.example.editor".to_string(),
+        window_title: None,
+        character: None,
+        input_source: None,
+    }
+}
+
+/// Small fixed-seed PRNG so generated data is identical across runs.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_events_are_deterministic_and_ordered() {
+        let events = synthetic_events(1_000);
+        assert_eq!(events.len(), 2_000);
+        assert!(events.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+        let again = synthetic_events(1_000);
+        assert!(events
+            .iter()
+            .zip(&again)
+            .all(|(a, b)| a.timestamp == b.timestamp && a.key_code == b.key_code));
+    }
+}
@@ -1,23 +1,30 @@
 use std::collections::HashMap;
 
-use crate::analysis::filters::{calculate_percentiles, FilterConfig};
+use serde::Serialize;
+
+use crate::analysis::filters::{calculate_percentiles, sorted_by_time, FilterConfig};
+use crate::analysis::quantile::StreamingPercentiles;
+use crate::analysis::parallel::group_windows;
+use crate::models::keyboard_layout::{Finger, KeyboardLayout};
 use crate::models::keycode::KeyCode;
 use crate::models::{EventType, KeystrokeEvent};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct InterKeyInterval {
     pub from_key: u32,
     pub to_key: u32,
+    #[serde(skip)]
     pub intervals_ms: Vec<i64>,
     pub mean_ms: f64,
     pub median_ms: i64,
     pub p95_ms: i64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HoldDuration {
     pub key_code: u32,
     pub key_name: String,
+    #[serde(skip)]
     pub durations_ms: Vec<i64>,
     pub mean_ms: f64,
     pub median_ms: i64,
@@ -25,11 +32,29 @@ pub struct HoldDuration {
     pub sample_count: usize,
 }
 
+/// Hold durations pooled over every key a finger types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FingerHold {
+    pub finger: Finger,
+    pub mean_ms: f64,
+    pub median_ms: i64,
+    pub p95_ms: i64,
+    pub sample_count: usize,
+}
+
 #[derive(Debug)]
 pub struct TimingAnalysis {
     pub overall_inter_key: InterKeyStats,
     pub per_key_inter_key: Vec<InterKeyInterval>,
     pub hold_durations: Vec<HoldDuration>,
+    /// Median interval from pressing a key to the next press, pooled over
+    /// every following key. Unlike `per_key_inter_key`, rare pairs count.
+    pub outgoing_medians: HashMap<u32, i64>,
+    /// Consecutive presses with the same timestamp, which give no interval.
+    /// Events are timed after sorting, so out-of-order events (from a
+    /// wall-clock adjustment) don't show up here; `lurk check` counts those
+    /// in storage order.
+    pub zero_intervals: usize,
     pub filter_config: FilterConfig,
 }
 
@@ -44,15 +69,21 @@ pub struct InterKeyStats {
 }
 
 impl TimingAnalysis {
+    /// Events are sorted by timestamp first if they aren't already.
     pub fn from_events(events: &[KeystrokeEvent], config: FilterConfig) -> Self {
+        let events = &*sorted_by_time(events);
+        let zero_intervals = Self::count_zero_intervals(events);
         let overall_inter_key = Self::calculate_overall_inter_key(events, &config);
         let per_key_inter_key = Self::calculate_per_key_inter_key(events, &config);
         let hold_durations = Self::calculate_hold_durations(events, &config);
+        let outgoing_medians = Self::calculate_outgoing_medians(events, &config);
 
         Self {
             overall_inter_key,
             per_key_inter_key,
             hold_durations,
+            outgoing_medians,
+            zero_intervals,
             filter_config: config,
         }
     }
@@ -66,19 +97,16 @@ impl TimingAnalysis {
             .filter(|e| matches!(e.event_type, EventType::Press))
             .collect();
 
-        let mut pair_intervals: HashMap<(u32, u32), Vec<i64>> = HashMap::new();
-
-        for pair in press_events.windows(2) {
+        let pair_intervals = group_windows(&press_events, 2, |pair| {
             let interval = pair[1].timestamp - pair[0].timestamp;
-            if config.is_valid_interval(interval) {
-                let key_pair = (pair[0].key_code, pair[1].key_code);
-                pair_intervals.entry(key_pair).or_default().push(interval);
-            }
-        }
+            config
+                .is_valid_interval(interval)
+                .then(|| ((pair[0].key_code, pair[1].key_code), interval))
+        });
 
         let mut results: Vec<_> = pair_intervals
             .into_iter()
-            .filter(|(_, intervals)| intervals.len() >= 3)
+            .filter(|(_, intervals)| intervals.len() >= config.min_pair_samples.max(1))
             .map(|((from_key, to_key), mut intervals)| {
                 let count = intervals.len();
                 let sum: i64 = intervals.iter().sum();
@@ -100,28 +128,79 @@ impl TimingAnalysis {
             })
             .collect();
 
-        results.sort_by(|a, b| b.intervals_ms.len().cmp(&a.intervals_ms.len()));
+        results.sort_by(|a, b| {
+            b.intervals_ms
+                .len()
+                .cmp(&a.intervals_ms.len())
+                .then((a.from_key, a.to_key).cmp(&(b.from_key, b.to_key)))
+        });
         results
     }
 
-    fn calculate_overall_inter_key(events: &[KeystrokeEvent], config: &FilterConfig) -> InterKeyStats {
+    fn calculate_outgoing_medians(
+        events: &[KeystrokeEvent],
+        config: &FilterConfig,
+    ) -> HashMap<u32, i64> {
         let press_events: Vec<_> = events
             .iter()
             .filter(|e| matches!(e.event_type, EventType::Press))
             .collect();
 
-        let mut intervals: Vec<i64> = press_events
-            .windows(2)
-            .filter_map(|pair| {
-                let interval = pair[1].timestamp - pair[0].timestamp;
-                if config.is_valid_interval(interval) {
-                    Some(interval)
-                } else {
-                    None
-                }
+        let outgoing = group_windows(&press_events, 2, |pair| {
+            let interval = pair[1].timestamp - pair[0].timestamp;
+            config
+                .is_valid_interval(interval)
+                .then(|| (pair[0].key_code, interval))
+        });
+
+        outgoing
+            .into_iter()
+            .map(|(key_code, mut intervals)| {
+                intervals.sort_unstable();
+                (key_code, intervals[intervals.len() / 2])
             })
+            .collect()
+    }
+
+    fn count_zero_intervals(events: &[KeystrokeEvent]) -> usize {
+        let press_events: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e.event_type, EventType::Press))
             .collect();
 
+        press_events
+            .windows(2)
+            .filter(|pair| pair[1].timestamp == pair[0].timestamp)
+            .count()
+    }
+
+    fn calculate_overall_inter_key(events: &[KeystrokeEvent], config: &FilterConfig) -> InterKeyStats {
+        let intervals = || press_intervals(events, config);
+
+        if let Some(threshold) = config.streaming_percentiles_above {
+            let count = intervals().count();
+            if count > threshold {
+                let mut streaming = StreamingPercentiles::default();
+                let mut sum = 0;
+                for interval in intervals() {
+                    streaming.push(interval);
+                    sum += interval;
+                }
+                let (median_ms, p90_ms, p95_ms, p99_ms) =
+                    streaming.percentiles().unwrap_or((0, 0, 0, 0));
+                return InterKeyStats {
+                    count,
+                    mean_ms: sum as f64 / count as f64,
+                    median_ms,
+                    p90_ms,
+                    p95_ms,
+                    p99_ms,
+                };
+            }
+        }
+
+        let mut intervals: Vec<i64> = intervals().collect();
+
         if intervals.is_empty() {
             return InterKeyStats {
                 count: 0,
@@ -160,13 +239,13 @@ impl TimingAnalysis {
         for event in events {
             match event.event_type {
                 EventType::Press => {
-                    press_times
-                        .entry(event.key_code)
-                        .or_default()
-                        .push(event.timestamp);
+                    let times = press_times.entry(event.key_code).or_default();
+                    discard_stale_presses(times, event.timestamp, config.max_hold_ms);
+                    times.push(event.timestamp);
                 }
                 EventType::Release => {
                     if let Some(times) = press_times.get_mut(&event.key_code) {
+                        discard_stale_presses(times, event.timestamp, config.max_hold_ms);
                         if let Some(press_time) = times.pop() {
                             let duration = event.timestamp - press_time;
                             if config.is_valid_hold_duration(duration) {
@@ -214,34 +293,119 @@ impl TimingAnalysis {
         &self.hold_durations[..n.min(self.hold_durations.len())]
     }
 
+    /// Median hold duration per key code.
+    pub fn hold_medians(&self) -> HashMap<u32, i64> {
+        self.hold_durations
+            .iter()
+            .map(|hold| (hold.key_code, hold.median_ms))
+            .collect()
+    }
+
+    /// Hold durations grouped by the finger `layout` assigns each key to,
+    /// with stats recomputed over the pooled samples (not averaged per key),
+    /// so busy keys weigh in proportionally. Fingers with no holds are left
+    /// out; keys the layout doesn't place are ignored.
+    pub fn finger_holds(&self, layout: &dyn KeyboardLayout) -> Vec<FingerHold> {
+        let mut pooled: HashMap<Finger, Vec<i64>> = HashMap::new();
+        for hold in &self.hold_durations {
+            if let Some(finger) = layout.get_finger(hold.key_code) {
+                pooled.entry(finger).or_default().extend(&hold.durations_ms);
+            }
+        }
+
+        let mut results: Vec<_> = pooled
+            .into_iter()
+            .filter(|(_, durations)| !durations.is_empty())
+            .map(|(finger, mut durations)| {
+                durations.sort_unstable();
+                let sample_count = durations.len();
+                let mean_ms = durations.iter().sum::<i64>() as f64 / sample_count as f64;
+                let p95_idx = ((sample_count as f64 * 0.95) as usize).min(sample_count - 1);
+                FingerHold {
+                    finger,
+                    mean_ms,
+                    median_ms: durations[sample_count / 2],
+                    p95_ms: durations[p95_idx],
+                    sample_count,
+                }
+            })
+            .collect();
+
+        results.sort_by_key(|hold| hold.finger as u8);
+        results
+    }
+
     pub fn top_inter_key_pairs(&self, n: usize) -> &[InterKeyInterval] {
         &self.per_key_inter_key[..n.min(self.per_key_inter_key.len())]
     }
+
+    /// Key pairs with enough samples for timing, fastest median first. Ties
+    /// go to the pair with more samples.
+    pub fn pairs_by_median(&self) -> Vec<&InterKeyInterval> {
+        let mut pairs: Vec<_> = self.per_key_inter_key.iter().collect();
+        pairs.sort_by_key(|pair| (pair.median_ms, std::cmp::Reverse(pair.intervals_ms.len())));
+        pairs
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Drops pending presses older than `max_age_ms`. A key pressed and never
+/// released (e.g. focus moved mid-hold) would otherwise sit in the pending
+/// list for the rest of the run. This only bounds memory: releases pair with
+/// the newest press, and a press this old could only give a hold longer than
+/// `max_hold_ms`, which `is_valid_hold_duration` rejects anyway.
+fn discard_stale_presses(pending: &mut Vec<i64>, now: i64, max_age_ms: i64) {
+    pending.retain(|&press_time| now - press_time <= max_age_ms);
+}
 
-    fn make_press(timestamp: i64, key_code: u32) -> KeystrokeEvent {
-        KeystrokeEvent {
-            timestamp,
-            key_code,
-            event_type: EventType::Press,
-            modifiers: vec![],
-            application: "test".to_string(),
+/// Mean interval and sample count per application, over consecutive presses
+/// that both went to it; intervals across an app switch are skipped.
+/// `events` must be sorted by time.
+pub fn per_app_inter_key(
+    events: &[KeystrokeEvent],
+    config: &FilterConfig,
+) -> HashMap<String, (f64, usize)> {
+    let presses: Vec<_> = events
+        .iter()
+        .filter(|e| matches!(e.event_type, EventType::Press))
+        .collect();
+
+    let mut totals: HashMap<&str, (i64, usize)> = HashMap::new();
+    for pair in presses.windows(2) {
+        let interval = pair[1].timestamp - pair[0].timestamp;
+        if pair[0].application == pair[1].application && config.is_valid_interval(interval) {
+            let total = totals.entry(pair[1].application.as_str()).or_insert((0, 0));
+            total.0 += interval;
+            total.1 += 1;
         }
     }
 
-    fn make_release(timestamp: i64, key_code: u32) -> KeystrokeEvent {
-        KeystrokeEvent {
-            timestamp,
-            key_code,
-            event_type: EventType::Release,
-            modifiers: vec![],
-            application: "test".to_string(),
-        }
-    }
+    totals
+        .into_iter()
+        .map(|(app, (sum, count))| (app.to_string(), (sum as f64 / count as f64, count)))
+        .collect()
+}
+
+/// Valid intervals between consecutive presses in `events`, which must be
+/// sorted by time. These are what `overall_inter_key` summarizes.
+pub fn press_intervals<'a>(
+    events: &'a [KeystrokeEvent],
+    config: &'a FilterConfig,
+) -> impl Iterator<Item = i64> + 'a {
+    let mut presses = events
+        .iter()
+        .filter(|e| matches!(e.event_type, EventType::Press))
+        .map(|e| e.timestamp);
+    let mut previous = presses.next();
+    presses.filter_map(move |timestamp| {
+        let interval = timestamp - previous.replace(timestamp)?;
+        config.is_valid_interval(interval).then_some(interval)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{in_app, press, release};
 
     #[test]
     fn test_empty_events() {
@@ -252,9 +416,9 @@ mod tests {
     #[test]
     fn test_inter_key_interval() {
         let events = vec![
-            make_press(100, 0x00),
-            make_press(200, 0x01),
-            make_press(300, 0x02),
+            press(100, 0x00),
+            press(200, 0x01),
+            press(300, 0x02),
         ];
 
         let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
@@ -262,11 +426,48 @@ mod tests {
         assert!((analysis.overall_inter_key.mean_ms - 100.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_shuffled_events_match_sorted() {
+        let sorted = vec![
+            press(100, 0x00),
+            release(150, 0x00),
+            press(220, 0x01),
+            release(300, 0x01),
+            press(400, 0x02),
+        ];
+        let mut shuffled = sorted.clone();
+        shuffled.swap(0, 4);
+        shuffled.swap(1, 2);
+
+        let expected = TimingAnalysis::from_events(&sorted, FilterConfig::default());
+        let analysis = TimingAnalysis::from_events(&shuffled, FilterConfig::default());
+        assert_eq!(analysis.overall_inter_key.count, 2);
+        assert_eq!(analysis.overall_inter_key.median_ms, expected.overall_inter_key.median_ms);
+        assert_eq!(analysis.hold_medians(), expected.hold_medians());
+        assert_eq!(expected.zero_intervals, 0);
+        assert_eq!(analysis.zero_intervals, 0);
+    }
+
+    #[test]
+    fn test_simultaneous_presses_are_counted() {
+        let events = vec![
+            press(1000, 0x00),
+            press(900, 0x01),
+            press(900, 0x02),
+            press(1000, 0x03),
+        ];
+
+        let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
+        assert_eq!(analysis.zero_intervals, 2);
+        assert_eq!(analysis.overall_inter_key.count, 1);
+        assert_eq!(analysis.overall_inter_key.median_ms, 100);
+    }
+
     #[test]
     fn test_inter_key_filters_large_gaps() {
         let events = vec![
-            make_press(100, 0x00),
-            make_press(10000, 0x01),
+            press(100, 0x00),
+            press(10000, 0x01),
         ];
 
         let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
@@ -276,10 +477,10 @@ mod tests {
     #[test]
     fn test_hold_duration_calculation() {
         let events = vec![
-            make_press(100, 0x00),
-            make_release(200, 0x00),
-            make_press(300, 0x00),
-            make_release(400, 0x00),
+            press(100, 0x00),
+            release(200, 0x00),
+            press(300, 0x00),
+            release(400, 0x00),
         ];
 
         let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
@@ -290,31 +491,138 @@ mod tests {
         assert!((hold.mean_ms - 100.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_dangling_press_does_not_pair_with_later_releases() {
+        let events = vec![
+            press(100, 0x00),
+            press(200, 0x01),
+            release(260, 0x01),
+            press(5000, 0x00),
+            release(5080, 0x00),
+            release(5150, 0x00),
+        ];
+
+        let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
+        let hold = analysis
+            .hold_durations
+            .iter()
+            .find(|hold| hold.key_code == 0x00)
+            .unwrap();
+        assert_eq!(hold.sample_count, 1);
+        assert!((hold.mean_ms - 80.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_min_pair_samples_threshold() {
+        let events = vec![
+            press(100, 0x00),
+            press(200, 0x01),
+            press(300, 0x00),
+            press(400, 0x01),
+        ];
+
+        let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
+        assert!(!analysis
+            .per_key_inter_key
+            .iter()
+            .any(|p| (p.from_key, p.to_key) == (0x00, 0x01)));
+
+        let lowered = FilterConfig {
+            min_pair_samples: 2,
+            ..FilterConfig::default()
+        };
+        let analysis = TimingAnalysis::from_events(&events, lowered);
+        let pair = analysis
+            .per_key_inter_key
+            .iter()
+            .find(|p| (p.from_key, p.to_key) == (0x00, 0x01))
+            .unwrap();
+        assert_eq!(pair.intervals_ms.len(), 2);
+    }
+
+    #[test]
+    fn test_discard_stale_presses() {
+        let mut pending = vec![100, 3000, 4900];
+        discard_stale_presses(&mut pending, 5000, 2000);
+        assert_eq!(pending, vec![3000, 4900]);
+    }
+
+    #[test]
+    fn test_finger_holds_pool_keys_per_finger() {
+        use crate::models::keyboard_layout::QwertyLayout;
+
+        // A (left pinky) held 100ms; S (left ring) held 60 and 80ms;
+        // X (also left ring) held 200ms.
+        let (a, s, x) = (0x00, 0x01, 0x07);
+        let events = vec![
+            press(0, a),
+            release(100, a),
+            press(200, s),
+            release(260, s),
+            press(300, s),
+            release(380, s),
+            press(400, x),
+            release(600, x),
+        ];
+
+        let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
+        let holds = analysis.finger_holds(&QwertyLayout::new());
+
+        assert_eq!(holds.len(), 2);
+        assert_eq!(holds[0].finger, Finger::LeftPinky);
+        assert_eq!((holds[0].median_ms, holds[0].sample_count), (100, 1));
+
+        let ring = &holds[1];
+        assert_eq!(ring.finger, Finger::LeftRing);
+        assert_eq!(ring.sample_count, 3);
+        assert_eq!(ring.median_ms, 80);
+        assert!((ring.mean_ms - 340.0 / 3.0).abs() < 0.01);
+        assert_eq!(ring.p95_ms, 200);
+    }
+
     #[test]
     fn test_hold_duration_filters_invalid() {
         let config = FilterConfig {
             max_gap_ms: 5000,
             min_hold_ms: 50,
             max_hold_ms: 500,
+            ..FilterConfig::default()
         };
 
         let events = vec![
-            make_press(100, 0x00),
-            make_release(110, 0x00),
-            make_press(200, 0x01),
-            make_release(1000, 0x01),
+            press(100, 0x00),
+            release(110, 0x00),
+            press(200, 0x01),
+            release(1000, 0x01),
         ];
 
         let analysis = TimingAnalysis::from_events(&events, config);
         assert!(analysis.hold_durations.is_empty());
     }
 
+    #[test]
+    fn test_outgoing_medians_pool_following_keys() {
+        let events = vec![
+            press(0, 0x00),
+            press(100, 0x01),
+            press(200, 0x00),
+            press(260, 0x02),
+            press(300, 0x00),
+            press(450, 0x03),
+        ];
+
+        let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
+        assert_eq!(analysis.outgoing_medians.get(&0x00), Some(&100));
+        assert_eq!(analysis.outgoing_medians.get(&0x01), Some(&100));
+        assert!(!analysis.outgoing_medians.contains_key(&0x03));
+    }
+
     #[test]
     fn test_percentiles() {
         let events: Vec<KeystrokeEvent> = (0..100)
             .flat_map(|i| {
                 vec![
-                    make_press(i * 100, 0x00),
+                    press(i * 100, 0x00),
                 ]
             })
             .collect();
@@ -324,15 +632,39 @@ mod tests {
         assert!(analysis.overall_inter_key.p95_ms >= analysis.overall_inter_key.median_ms);
     }
 
+    #[test]
+    fn test_streaming_percentiles_above_threshold() {
+        // Gaps of 100..=199ms, one of each.
+        let mut timestamp = 0;
+        let events: Vec<KeystrokeEvent> = (0..=100)
+            .map(|i| {
+                timestamp += 100 + i - 1;
+                press(timestamp, 0x00)
+            })
+            .collect();
+        let exact = TimingAnalysis::from_events(&events, FilterConfig::default()).overall_inter_key;
+
+        let config = FilterConfig {
+            streaming_percentiles_above: Some(50),
+            ..FilterConfig::default()
+        };
+        let streamed = TimingAnalysis::from_events(&events, config).overall_inter_key;
+
+        assert_eq!(streamed.count, exact.count);
+        assert_eq!(streamed.mean_ms, exact.mean_ms);
+        assert!((streamed.median_ms - exact.median_ms).abs() <= 2);
+        assert!((streamed.p95_ms - exact.p95_ms).abs() <= 2);
+    }
+
     #[test]
     fn test_multiple_keys_hold_duration() {
         let events = vec![
-            make_press(100, 0x00),
-            make_release(200, 0x00),
-            make_press(100, 0x01),
-            make_release(250, 0x01),
-            make_press(100, 0x01),
-            make_release(250, 0x01),
+            press(100, 0x00),
+            release(200, 0x00),
+            press(100, 0x01),
+            release(250, 0x01),
+            press(100, 0x01),
+            release(250, 0x01),
         ];
 
         let analysis = TimingAnalysis::from_events(&events, FilterConfig::default());
@@ -341,4 +673,19 @@ mod tests {
         let key_01 = analysis.hold_durations.iter().find(|h| h.key_code == 0x01).unwrap();
         assert_eq!(key_01.sample_count, 2);
     }
+
+    #[test]
+    fn test_per_app_inter_key_skips_app_switches() {
+        let events = vec![
+            in_app(press(0, 0x00), "editor"),
+            in_app(press(100, 0x01), "editor"),
+            in_app(press(300, 0x02), "editor"),
+            in_app(press(350, 0x03), "chat"),
+            in_app(press(750, 0x04), "chat"),
+        ];
+
+        let per_app = per_app_inter_key(&events, &FilterConfig::default());
+        assert_eq!(per_app["editor"], (150.0, 2));
+        assert_eq!(per_app["chat"], (400.0, 1));
+    }
 }
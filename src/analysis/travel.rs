@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use crate::analysis::fingers::HAND_FINGERS;
+use crate::models::keyboard_layout::{Finger, KeyboardLayout};
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Layout width units in one key unit: a standard key (width 2) plus the gap
+/// after it. Rows are one key unit apart.
+const WIDTH_UNITS_PER_KEY: f64 = 3.0;
+
+/// Key centres in key units, x from the left edge and y from the top row.
+fn key_centres(layout: &dyn KeyboardLayout) -> HashMap<u32, (f64, f64)> {
+    layout
+        .key_positions()
+        .into_iter()
+        .map(|(row, x, key)| {
+            let centre = (f64::from(x) + f64::from(key.width) / 2.0) / WIDTH_UNITS_PER_KEY;
+            (key.keycode, (centre, row as f64))
+        })
+        .collect()
+}
+
+/// Estimated distance each finger travels, in key units: for every finger,
+/// the straight-line distance between consecutive keys it presses, summed.
+/// A finger's first press starts from wherever it was, so it adds nothing.
+/// Thumb keys and keys the layout doesn't have are left out.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FingerTravel {
+    /// Travel for each of `HAND_FINGERS`, in that order.
+    pub per_finger: Vec<(Finger, f64)>,
+    pub total: f64,
+    /// Presses counted, i.e. on a finger key of the layout.
+    pub presses: u64,
+}
+
+impl FingerTravel {
+    pub fn from_events(events: &[KeystrokeEvent], layout: &dyn KeyboardLayout) -> Self {
+        let centres = key_centres(layout);
+        let mut last_key: HashMap<Finger, (f64, f64)> = HashMap::new();
+        let mut travel: HashMap<Finger, f64> = HashMap::new();
+        let mut presses = 0;
+
+        for event in events.iter().filter(|e| e.event_type == EventType::Press) {
+            let Some(finger) = layout.get_finger(event.key_code) else {
+                continue;
+            };
+            let Some(&(x, y)) = centres.get(&event.key_code) else {
+                continue;
+            };
+            if finger == Finger::Thumb {
+                continue;
+            }
+            presses += 1;
+            if let Some((last_x, last_y)) = last_key.insert(finger, (x, y)) {
+                *travel.entry(finger).or_insert(0.0) += (x - last_x).hypot(y - last_y);
+            }
+        }
+
+        let per_finger: Vec<(Finger, f64)> = HAND_FINGERS
+            .into_iter()
+            .map(|finger| (finger, travel.get(&finger).copied().unwrap_or(0.0)))
+            .collect();
+        let total = per_finger.iter().map(|(_, distance)| distance).sum();
+        Self {
+            per_finger,
+            total,
+            presses,
+        }
+    }
+
+    /// Key units travelled per 100 counted presses, for comparing corpora of
+    /// different sizes. 0 without presses.
+    pub fn per_hundred_presses(&self) -> f64 {
+        if self.presses == 0 {
+            return 0.0;
+        }
+        self.total / self.presses as f64 * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::keyboard_layout::OrthoLayout;
+    use crate::testutil::typed;
+
+    const F: u32 = 0x03;
+    const R: u32 = 0x0F;
+    const J: u32 = 0x26;
+    const N: u32 = 0x2D;
+    const SPACE: u32 = 0x31;
+
+    #[test]
+    fn test_travel_follows_each_finger_separately() {
+        // Left index F -> R -> F: one row up and back. Right index J -> N:
+        // one row down and one column in. Space is a thumb key.
+        let events = typed(&[F, J, R, SPACE, N, F], 100);
+        let travel = FingerTravel::from_events(&events, &OrthoLayout::new());
+        let distances: HashMap<Finger, f64> = travel.per_finger.iter().copied().collect();
+
+        assert_eq!(distances[&Finger::LeftIndex], 2.0);
+        assert!((distances[&Finger::RightIndex] - 2f64.sqrt()).abs() < 1e-9);
+        assert_eq!(distances[&Finger::LeftPinky], 0.0);
+        assert!((travel.total - (2.0 + 2f64.sqrt())).abs() < 1e-9);
+        assert_eq!(travel.presses, 5);
+        assert_eq!(FingerTravel::default().per_hundred_presses(), 0.0);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::analysis::filters::{sorted_by_time, FilterConfig};
+use crate::analysis::frequency::TrigramCount;
+use crate::analysis::parallel::group_windows;
+use crate::models::{EventType, KeystrokeEvent};
+
+/// How long a common trigram takes to type: the two inter-key intervals
+/// summed per occurrence.
+#[derive(Debug, Clone)]
+pub struct TrigramTiming {
+    pub keys: (u32, u32, u32),
+    pub display: String,
+    pub count: u64,
+    pub mean_ms: f64,
+    pub median_ms: i64,
+    /// Occurrences with both intervals valid; can be fewer than `count`.
+    pub samples: usize,
+}
+
+impl TrigramTiming {
+    /// Total time spent on this trigram, roughly: count times median. Slow
+    /// but rare and fast but common sequences both rank below ones that are
+    /// common and slow.
+    pub fn cost_ms(&self) -> u64 {
+        self.count * self.median_ms.max(0) as u64
+    }
+}
+
+/// Times each trigram in `trigrams` over the presses in `events` and returns
+/// the `n` most costly. Trigrams with fewer than `min_pair_samples` timed
+/// occurrences are left out, as for key-pair timing.
+pub fn slow_trigrams(
+    events: &[KeystrokeEvent],
+    trigrams: &[TrigramCount],
+    config: &FilterConfig,
+    n: usize,
+) -> Vec<TrigramTiming> {
+    let events = sorted_by_time(events);
+    let presses: Vec<_> = events
+        .iter()
+        .filter(|e| e.event_type == EventType::Press)
+        .collect();
+
+    let mut durations = group_windows(&presses, 3, |window| {
+        let first = window[1].timestamp - window[0].timestamp;
+        let second = window[2].timestamp - window[1].timestamp;
+        (config.is_valid_interval(first) && config.is_valid_interval(second)).then(|| {
+            let keys = (window[0].key_code, window[1].key_code, window[2].key_code);
+            (keys, first + second)
+        })
+    });
+
+    let mut timings: Vec<TrigramTiming> = trigrams
+        .iter()
+        .filter_map(|trigram| {
+            let mut samples = durations.remove(&trigram.keys)?;
+            if samples.len() < config.min_pair_samples.max(1) {
+                return None;
+            }
+            samples.sort_unstable();
+            Some(TrigramTiming {
+                keys: trigram.keys,
+                display: trigram.display.clone(),
+                count: trigram.count,
+                mean_ms: samples.iter().sum::<i64>() as f64 / samples.len() as f64,
+                median_ms: samples[samples.len() / 2],
+                samples: samples.len(),
+            })
+        })
+        .collect();
+
+    timings.sort_by(|a, b| b.cost_ms().cmp(&a.cost_ms()).then(a.keys.cmp(&b.keys)));
+    timings.truncate(n);
+    timings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::FrequencyAnalysis;
+    use crate::testutil::press;
+
+    const T: u32 = 0x11;
+    const H: u32 = 0x04;
+    const E: u32 = 0x0E;
+    const A: u32 = 0x00;
+    const N: u32 = 0x2D;
+    const D: u32 = 0x02;
+
+    /// Types `keys` `times` times, `interval` ms apart, with a pause longer
+    /// than the gap limit between repetitions.
+    fn repeated(keys: [u32; 3], interval: i64, times: usize, start: i64) -> Vec<KeystrokeEvent> {
+        (0..times)
+            .flat_map(|i| {
+                let t = start + i as i64 * 10_000;
+                keys.iter()
+                    .enumerate()
+                    .map(move |(j, &key)| press(t + j as i64 * interval, key))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_common_slow_trigram_ranks_first() {
+        // THE four times at 100ms steps (200ms per trigram, cost 800ms);
+        // AND twice at 300ms steps (600ms, cost 1200ms).
+        let mut events = repeated([T, H, E], 100, 4, 0);
+        events.extend(repeated([A, N, D], 300, 2, 100_000));
+        let config = FilterConfig {
+            min_pair_samples: 1,
+            ..FilterConfig::default()
+        };
+        let freq = FrequencyAnalysis::from_events(&events);
+
+        let timings = slow_trigrams(&events, &freq.trigram_frequencies, &config, 10);
+
+        let ranked: Vec<_> = timings.iter().map(|t| (t.keys, t.count, t.median_ms)).collect();
+        assert_eq!(ranked, vec![((A, N, D), 2, 600), ((T, H, E), 4, 200)]);
+        assert_eq!(timings[1].cost_ms(), 800);
+        assert_eq!(timings[1].samples, 4);
+        assert!((timings[1].mean_ms - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rarely_timed_trigrams_are_skipped() {
+        let events = repeated([T, H, E], 100, 2, 0);
+        let freq = FrequencyAnalysis::from_events(&events);
+        let config = FilterConfig {
+            min_pair_samples: 3,
+            ..FilterConfig::default()
+        };
+        assert!(slow_trigrams(&events, &freq.trigram_frequencies, &config, 10).is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::models::KeystrokeEvent;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Rewrites events for sharing. Key codes, event types, modifiers and the
+/// intervals between events are kept; everything that says what was typed
+/// where is removed:
+///
+/// - application bundle IDs become `app-<hash>`, the same for every event of
+///   one app within an export. The hash is salted per export, so it can't be
+///   reversed by hashing a list of known bundle IDs, and two exports don't
+///   share IDs.
+/// - window titles, recorded characters and input sources are dropped.
+/// - with `shift_timestamps`, every timestamp moves back by the same random
+///   offset of 30 to 395 days, hiding when the data was recorded.
+#[derive(Debug, Clone, Copy)]
+pub struct Anonymizer {
+    salt: u64,
+    offset_ms: i64,
+}
+
+impl Anonymizer {
+    pub fn new(shift_timestamps: bool) -> Self {
+        let salt = random_u64();
+        let offset_ms = if shift_timestamps {
+            30 * DAY_MS + (random_u64() % (365 * DAY_MS) as u64) as i64
+        } else {
+            0
+        };
+        Self { salt, offset_ms }
+    }
+
+    pub fn timestamp(&self, timestamp: i64) -> i64 {
+        timestamp - self.offset_ms
+    }
+
+    pub fn application(&self, application: &str) -> String {
+        // FNV-1a over the salt and name: stable for the lifetime of this value.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in self.salt.to_le_bytes().iter().chain(application.as_bytes()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("app-{:016x}", hash)
+    }
+
+    pub fn apply(&self, event: KeystrokeEvent) -> KeystrokeEvent {
+        KeystrokeEvent {
+            timestamp: self.timestamp(event.timestamp),
+            application: self.application(&event.application),
+            window_title: None,
+            character: None,
+            input_source: None,
+            ..event
+        }
+    }
+}
+
+/// Seeded from the OS through `RandomState`, which is random per instance.
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{in_app, press};
+
+    fn event(timestamp: i64, application: &str) -> KeystrokeEvent {
+        KeystrokeEvent {
+            window_title: Some("secret.txt".to_string()),
+            character: Some("a".to_string()),
+            input_source: Some("com.apple.keylayout.French".to_string()),
+            ..in_app(press(timestamp, 0x00), application)
+        }
+    }
+
+    #[test]
+    fn test_apps_hash_consistently_and_intervals_survive() {
+        let anonymizer = Anonymizer::new(true);
+        let events = [
+            event(1_700_000_000_000, "com.apple.Safari"),
+            event(1_700_000_000_120, "com.apple.Mail"),
+            event(1_700_000_000_370, "com.apple.Safari"),
+        ];
+        let out: Vec<_> = events.iter().cloned().map(|e| anonymizer.apply(e)).collect();
+
+        assert_eq!(out[0].application, out[2].application);
+        assert_ne!(out[0].application, out[1].application);
+        assert!(!out[0].application.contains("Safari"));
+        assert!(out[0].timestamp < events[0].timestamp);
+        assert_eq!(out[1].timestamp - out[0].timestamp, 120);
+        assert_eq!(out[2].timestamp - out[1].timestamp, 250);
+        assert!(out
+            .iter()
+            .all(|e| e.window_title.is_none() && e.character.is_none() && e.input_source.is_none()));
+        assert!(out.iter().all(|e| e.key_code == 0x00));
+    }
+
+    #[test]
+    fn test_timestamps_kept_without_shift() {
+        let anonymizer = Anonymizer::new(false);
+        assert_eq!(anonymizer.apply(event(1_000, "app")).timestamp, 1_000);
+    }
+}
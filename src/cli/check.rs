@@ -0,0 +1,170 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+use crate::analysis::stuck_modifiers::{StuckModifierTracker, STUCK_MODIFIER_MS};
+use crate::storage::{Database, IntegrityReport, IntegrityScan};
+
+/// Problems `storage_problems` lists before giving up on the rest.
+const MAX_STORAGE_PROBLEMS_SHOWN: usize = 10;
+
+/// What to do when SQLite finds the file at `db_path` damaged, e.g. after
+/// losing power mid-write.
+pub fn storage_recovery_hint(db_path: &Path) -> String {
+    let damaged = format!("{}.damaged", db_path.display());
+    format!(
+        "Stop the daemon, move {} aside (e.g. to {}), let the daemon create a fresh one, then \
+         copy over whatever is still readable with `lurk merge {}`.",
+        db_path.display(),
+        damaged,
+        damaged
+    )
+}
+
+/// Scans every event in insertion order without loading them all, counting
+/// stuck modifiers alongside `IntegrityScan`.
+pub fn scan_database(db: &Database) -> Result<IntegrityReport> {
+    let mut scan = IntegrityScan::new();
+    let mut modifiers = StuckModifierTracker::new(STUCK_MODIFIER_MS);
+    let mut stuck_modifiers = 0;
+    db.for_each_event_with_id(|id, event| {
+        scan.push(id, &event);
+        // Row ids only need to be distinct here; stuck presses aren't removed.
+        stuck_modifiers += modifiers.push(id as usize, &event).len() as u64;
+        Ok(())
+    })?;
+    let mut report = scan.finish();
+    // Without releases every modifier would look stuck.
+    if report.releases > 0 {
+        report.stuck_modifiers = stuck_modifiers;
+    }
+    Ok(report)
+}
+
+/// Runs SQLite's full `integrity_check` on `db`, opened from `db_path`, and
+/// fails if the file itself is damaged.
+pub fn check_storage(db: &Database, db_path: &Path) -> Result<()> {
+    let problems = db.storage_problems(true)?;
+    if problems.is_empty() {
+        println!("SQLite integrity:      ok");
+        return Ok(());
+    }
+    println!("SQLite integrity:      {} problems", problems.len());
+    for problem in problems.iter().take(MAX_STORAGE_PROBLEMS_SHOWN) {
+        println!("    {}", problem);
+    }
+    Err(anyhow!(
+        "The database file is damaged; nothing was changed. {}",
+        storage_recovery_hint(db_path)
+    ))
+}
+
+/// Prints anomaly counts; with `fix`, deletes the rows that are clearly
+/// corrupt (events without an application). Orphan releases and the other
+/// anomalies have ordinary causes and are only reported.
+pub fn run_check(db: &mut Database, fix: bool) -> Result<()> {
+    let report = scan_database(db)?;
+    println!("Events scanned:        {}", report.events);
+    println!(
+        "Orphan releases:       {} (kept: usually held at startup or pressed in a skipped app)",
+        report.orphan_releases
+    );
+    if report.releases == 0 {
+        println!("Unreleased presses:    - (releases not recorded)");
+    } else {
+        println!("Unreleased presses:    {}", report.unreleased_presses);
+        println!(
+            "Stuck modifiers:       {} (held over {}s without a release)",
+            report.stuck_modifiers,
+            STUCK_MODIFIER_MS / 1000
+        );
+    }
+    println!("Out-of-order events:   {}", report.out_of_order);
+    println!("Empty application:     {}", report.empty_application);
+
+    if report.is_clean() {
+        println!("\nNo problems found.");
+        return Ok(());
+    }
+
+    if report.corrupt_ids.is_empty() {
+        println!("\nNothing to remove; the remaining anomalies are kept as recorded.");
+    } else if fix {
+        let deleted = db.delete_events(&report.corrupt_ids)?;
+        println!("\nRemoved {} corrupt rows.", deleted);
+    } else {
+        println!(
+            "\n{} rows are clearly corrupt. Run `lurk check --fix` to remove them.",
+            report.corrupt_ids.len()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{in_app, press, release};
+
+    #[test]
+    fn test_fix_drops_only_corrupt_rows() {
+        let mut db = Database::new(":memory:").unwrap();
+        db.insert_events_batch(&[
+            release(1000, 0x00),
+            press(1100, 0x00),
+            release(1050, 0x00),
+            in_app(press(1200, 0x00), ""),
+        ])
+        .unwrap();
+
+        let report = scan_database(&db).unwrap();
+        assert_eq!(report.orphan_releases, 1);
+        assert_eq!(report.out_of_order, 1);
+        assert_eq!(report.empty_application, 1);
+
+        check_storage(&db, Path::new(":memory:")).unwrap();
+        run_check(&mut db, true).unwrap();
+        assert_eq!(db.get_total_count().unwrap(), 3);
+
+        let report = scan_database(&db).unwrap();
+        assert_eq!(report.orphan_releases, 1);
+        assert_eq!(report.empty_application, 0);
+        assert!(report.corrupt_ids.is_empty());
+    }
+
+    #[test]
+    fn test_damaged_file_fails_the_storage_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lurk.db");
+        let mut db = Database::new(&path).unwrap();
+        let events: Vec<_> = (0..2000).map(|i| press(i * 10, 0x00)).collect();
+        db.insert_events_batch(&events).unwrap();
+        drop(db);
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[3 * 4096..3 * 4096 + 512].fill(0xAA);
+        std::fs::write(&path, bytes).unwrap();
+
+        let db = Database::open_read_only(&path).unwrap();
+        let err = check_storage(&db, &path).unwrap_err().to_string();
+        assert!(err.contains(&format!("lurk merge {}.damaged", path.display())));
+    }
+
+    #[test]
+    fn test_stuck_shift_counted_but_not_caps_lock() {
+        let mut db = Database::new(":memory:").unwrap();
+        db.insert_events_batch(&[
+            press(0, 0x38),
+            press(0, 0x39),
+            press(100, 0x00),
+            release(150, 0x00),
+            press(20_000, 0x01),
+            release(20_050, 0x01),
+        ])
+        .unwrap();
+
+        let report = scan_database(&db).unwrap();
+        assert_eq!(report.stuck_modifiers, 1);
+        assert!(!report.is_clean());
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate};
+
+use crate::analysis::bigram_compare::BigramTimingDelta;
+use crate::analysis::{BigramTimingComparison, FilterConfig};
+use crate::storage::Database;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Parses a `YYYY-MM-DD` date into the timestamp (ms) of local midnight.
+pub fn parse_split_date(date: &str) -> Result<i64> {
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid date '{}'. Use YYYY-MM-DD", date))?;
+    let midnight = date
+        .and_hms_opt(0, 0, 0)
+        .and_then(|dt| dt.and_local_timezone(Local).earliest())
+        .ok_or_else(|| anyhow!("Date '{}' has no local midnight", date))?;
+    Ok(midnight.timestamp_millis())
+}
+
+/// Compares bigram timing in the `days` before `split` against the `days`
+/// from `split` on.
+pub fn show_comparison(
+    db: &Database,
+    split: i64,
+    days: u32,
+    top: usize,
+    min_samples: usize,
+    config: &FilterConfig,
+) -> Result<()> {
+    let span = i64::from(days).saturating_mul(DAY_MS);
+    let before = db.get_events_in_range(split.saturating_sub(span).max(0), split - 1)?;
+    let after = db.get_events_in_range(split, split.saturating_add(span))?;
+
+    println!("=== Lurk Comparison ===\n");
+    println!("Before: {} events in the {} days before the split", before.len(), days);
+    println!("After:  {} events in the {} days from the split", after.len(), days);
+
+    if before.is_empty() || after.is_empty() {
+        println!("\nNeed keystroke data on both sides of the split to compare.");
+        return Ok(());
+    }
+
+    let comparison = BigramTimingComparison::from_events(&before, &after, config, min_samples);
+    println!(
+        "Bigrams compared: {} (at least {} samples in each window)",
+        comparison.compared, min_samples
+    );
+
+    println!("\n--- Most Improved Bigrams ---");
+    print_deltas(&comparison.improved, top);
+
+    println!("\n--- Most Regressed Bigrams ---");
+    print_deltas(&comparison.regressed, top);
+
+    Ok(())
+}
+
+fn print_deltas(deltas: &[BigramTimingDelta], top: usize) {
+    if deltas.is_empty() {
+        println!("  (none)");
+        return;
+    }
+
+    for (i, delta) in deltas.iter().take(top).enumerate() {
+        let pct = match delta.change_pct() {
+            Some(pct) => format!("{:+.0}%", pct),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:2}. {:20} {:>5}ms -> {:>5}ms  {:>+6}ms {:>6}  (n={}/{})",
+            i + 1,
+            delta.display,
+            delta.before_median_ms,
+            delta.after_median_ms,
+            delta.delta_ms(),
+            pct,
+            delta.before_samples,
+            delta.after_samples
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_split_date() {
+        let ts = parse_split_date("2024-03-01").unwrap();
+        let next = parse_split_date("2024-03-02").unwrap();
+        assert!(next - ts >= 23 * 60 * 60 * 1000);
+        assert!(parse_split_date("03/01/2024").is_err());
+    }
+}
@@ -1,13 +1,44 @@
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use chrono::{DateTime, Local};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions, Permissions};
+use std::io::{self, BufWriter, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
 
-use crate::models::keycode::KeyCode;
+use crate::analysis::filters::{
+    is_excluded_app, ModifierFilter, DEFAULT_MAX_GAP_MS, MISSING_RELEASES_WARNING,
+};
+use crate::analysis::sessions::{typing_sessions, TypingSession};
+use crate::cli::anonymize::Anonymizer;
+use crate::cli::progress::Progress;
+use crate::models::event::Modifier;
+use crate::models::KeystrokeEvent;
 use crate::storage::Database;
 
-fn validate_export_path<P: AsRef<Path>>(output_path: P) -> Result<std::path::PathBuf> {
-    let path = output_path.as_ref();
+/// Resolves an export destination to an absolute path whose parent exists.
+///
+/// Exports contain raw keystrokes, so by default they may only be written
+/// inside the home directory: that keeps them out of shared, often
+/// world-readable places like `/tmp` and other users' folders, and stops a
+/// mistyped or scripted path from dropping them somewhere unexpected.
+/// `allow_outside_home` lifts only that restriction, for a destination the
+/// user chose on purpose (an external drive, a project checkout).
+pub(super) fn validate_export_path<P: AsRef<Path>>(
+    output_path: P,
+    allow_outside_home: bool,
+) -> Result<PathBuf> {
+    let home_dir = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+    validate_export_path_within(output_path.as_ref(), allow_outside_home, &home_dir)
+}
+
+fn validate_export_path_within(
+    path: &Path,
+    allow_outside_home: bool,
+    home_dir: &Path,
+) -> Result<PathBuf> {
     
     let absolute_path = if path.is_absolute() {
         path.to_path_buf()
@@ -28,12 +59,10 @@ fn validate_export_path<P: AsRef<Path>>(output_path: P) -> Result<std::path::Pat
     
     let canonical_parent = canonical_parent.canonicalize()?;
     
-    let home_dir = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
-    
-    if !canonical_parent.starts_with(&home_dir) {
+    if !allow_outside_home && !canonical_parent.starts_with(home_dir) {
         return Err(anyhow!(
-            "Security: export path must be within user's home directory. \
-             Attempted path: {}",
+            "Security: export path must be within user's home directory \
+             (pass --allow-outside-home to override). Attempted path: {}",
             canonical_parent.display()
         ));
     }
@@ -45,15 +74,346 @@ fn validate_export_path<P: AsRef<Path>>(output_path: P) -> Result<std::path::Pat
     Ok(canonical_parent.join(filename))
 }
 
-pub fn export_csv<P: AsRef<Path>>(db: &Database, output_path: P) -> Result<()> {
-    let safe_path = validate_export_path(&output_path)?;
-    let events = db.get_all_events()?;
-    let mut file = File::create(&safe_path)?;
+/// Creates (or truncates) an export file only its owner can read: exports
+/// hold raw keystrokes.
+fn create_private_file(path: &Path) -> Result<File> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // `mode` only applies to new files; an export written over an older one
+    // would otherwise keep that file's permissions.
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+/// Appends `.gz` unless the path already ends with it.
+fn with_gz_suffix(path: PathBuf) -> PathBuf {
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        return path;
+    }
+    let mut name = path.into_os_string();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// Output path meaning "write to standard output" instead of a file.
+pub const STDOUT_PATH: &str = "-";
+
+/// File name for an export made at `time`, e.g. `lurk-export-20240301-091502.csv`,
+/// so repeated exports into one directory (say, from cron) sort by date and
+/// never overwrite each other.
+pub fn timestamped_export_name(time: DateTime<Local>, extension: &str) -> String {
+    format!("lurk-export-{}.{}", time.format("%Y%m%d-%H%M%S"), extension)
+}
+
+/// A path in `dir` named by `timestamped_export_name` for now. It goes
+/// through `validate_export_path` like any other destination.
+pub fn timestamped_export_path(dir: &Path, extension: &str) -> PathBuf {
+    dir.join(timestamped_export_name(Local::now(), extension))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Compress with gzip, adding `.gz` to file names.
+    pub gzip: bool,
+    /// Skip the home-directory check; see `validate_export_path`.
+    pub allow_outside_home: bool,
+    /// Hash applications and drop titles and characters; see `Anonymizer`.
+    pub anonymize: bool,
+    /// With `anonymize`, also move all timestamps by one random offset.
+    pub shift_timestamps: bool,
+    /// Leave out events whose application contains any of these; see `is_excluded_app`.
+    pub exclude_apps: Vec<String>,
+    /// Keep only keys typed while all of these are held; see `ModifierFilter`.
+    pub with_modifiers: Vec<Modifier>,
+    /// How long a modifier without a recorded release counts as held
+    /// (`FilterConfig::max_gap_ms`); `None` uses `DEFAULT_MAX_GAP_MS`.
+    pub max_gap_ms: Option<i64>,
+    /// Count rows on stderr during large exports; see `Progress`. Never
+    /// shown when the export itself goes to stdout.
+    pub progress: bool,
+}
+
+impl ExportOptions {
+    fn anonymizer(&self) -> Option<Anonymizer> {
+        self.anonymize.then(|| Anonymizer::new(self.shift_timestamps))
+    }
+
+    fn for_output(self, output_path: &Path) -> Self {
+        Self {
+            progress: self.progress && output_path != Path::new(STDOUT_PATH),
+            ..self
+        }
+    }
+
+    /// Whether any events are left out, so counts differ from the database's.
+    fn filters_events(&self) -> bool {
+        !self.exclude_apps.is_empty() || !self.with_modifiers.is_empty()
+    }
+}
+
+/// Creates the output file (gzip-compressed if requested), runs `write`
+/// against it and finishes the stream. Returns the final path (`None` for
+/// stdout) and event count.
+fn write_export<P, F>(
+    output_path: P,
+    options: &ExportOptions,
+    write: F,
+) -> Result<(Option<PathBuf>, usize)>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut dyn Write) -> Result<usize>,
+{
+    write_export_to(output_path, options, &mut io::stdout().lock(), write)
+}
+
+/// Like `write_export`, with `-` sending output to `stdout`. Path validation
+/// only applies to real files.
+fn write_export_to<P, F>(
+    output_path: P,
+    options: &ExportOptions,
+    stdout: &mut dyn Write,
+    write: F,
+) -> Result<(Option<PathBuf>, usize)>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut dyn Write) -> Result<usize>,
+{
+    if output_path.as_ref() == Path::new(STDOUT_PATH) {
+        let count = write_stream(BufWriter::new(stdout), options.gzip, write)?;
+        return Ok((None, count));
+    }
+
+    let mut safe_path = validate_export_path(&output_path, options.allow_outside_home)?;
+    if options.gzip {
+        safe_path = with_gz_suffix(safe_path);
+    }
+
+    let file = create_private_file(&safe_path)?;
+    let count = write_stream(BufWriter::new(file), options.gzip, write)?;
+    Ok((Some(safe_path), count))
+}
+
+fn write_stream<W, F>(mut out: W, gzip: bool, write: F) -> Result<usize>
+where
+    W: Write,
+    F: FnOnce(&mut dyn Write) -> Result<usize>,
+{
+    if gzip {
+        let mut encoder = GzEncoder::new(out, Compression::default());
+        let count = write(&mut encoder)?;
+        encoder.finish()?.flush()?;
+        Ok(count)
+    } else {
+        let count = write(&mut out)?;
+        out.flush()?;
+        Ok(count)
+    }
+}
+
+/// Reports where the export went. For stdout the summary goes to stderr so
+/// it doesn't end up in the piped data. `what` names the rows, e.g. "events".
+fn report_export(path: Option<PathBuf>, count: usize, what: &str) {
+    match path {
+        Some(path) => println!("Exported {} {} to {}", count, what, path.display()),
+        None => eprintln!("Exported {} {} to stdout", count, what),
+    }
+}
+
+pub fn export_csv<P: AsRef<Path>>(
+    db: &Database,
+    output_path: P,
+    options: ExportOptions,
+) -> Result<()> {
+    let options = options.for_output(output_path.as_ref());
+    let anonymizer = options.anonymizer();
+    let (path, count) = write_export(output_path, &options, |out| {
+        write_csv(db, out, anonymizer.as_ref(), &options)
+    })?;
+    report_export(path, count, "events");
+    Ok(())
+}
+
+pub fn export_json<P: AsRef<Path>>(
+    db: &Database,
+    output_path: P,
+    options: ExportOptions,
+) -> Result<()> {
+    let options = options.for_output(output_path.as_ref());
+    let anonymizer = options.anonymizer();
+    let (path, count) = write_export(output_path, &options, |out| {
+        write_json(db, out, anonymizer.as_ref(), &options)
+    })?;
+    report_export(path, count, "events");
+    Ok(())
+}
+
+/// One event object per line, without the metadata wrapper, so the file can
+/// be streamed back in; see `read_events_jsonl`.
+pub fn export_jsonl<P: AsRef<Path>>(
+    db: &Database,
+    output_path: P,
+    options: ExportOptions,
+) -> Result<()> {
+    let options = options.for_output(output_path.as_ref());
+    let anonymizer = options.anonymizer();
+    let (path, count) = write_export(output_path, &options, |out| {
+        write_jsonl(db, out, anonymizer.as_ref(), &options)
+    })?;
+    report_export(path, count, "events");
+    Ok(())
+}
+
+/// One row per typing session instead of per event; see `typing_sessions`.
+pub fn export_sessions_csv<P: AsRef<Path>>(
+    db: &Database,
+    output_path: P,
+    options: ExportOptions,
+    gap_ms: i64,
+) -> Result<()> {
+    let options = options.for_output(output_path.as_ref());
+    let sessions = load_sessions(db, &options, gap_ms)?;
+    let (path, count) = write_export(output_path, &options, |out| {
+        write_sessions_csv(&sessions, out)
+    })?;
+    report_export(path, count, "sessions");
+    Ok(())
+}
+
+pub fn export_sessions_json<P: AsRef<Path>>(
+    db: &Database,
+    output_path: P,
+    options: ExportOptions,
+    gap_ms: i64,
+) -> Result<()> {
+    let options = options.for_output(output_path.as_ref());
+    let sessions = load_sessions(db, &options, gap_ms)?;
+    let (path, count) = write_export(output_path, &options, |out| {
+        write_sessions_json(&sessions, gap_ms, options.anonymize, out)
+    })?;
+    report_export(path, count, "sessions");
+    Ok(())
+}
+
+/// Anonymizes before segmenting, so sessions name hashed apps and shifted times.
+fn load_sessions(
+    db: &Database,
+    options: &ExportOptions,
+    gap_ms: i64,
+) -> Result<Vec<TypingSession>> {
+    let anonymizer = options.anonymizer();
+    let mut events = Vec::new();
+    for_each_included_event(db, options, |event| {
+        events.push(prepare(event, anonymizer.as_ref()));
+        Ok(())
+    })?;
+    Ok(typing_sessions(&events, gap_ms))
+}
+
+fn write_sessions_csv(sessions: &[TypingSession], out: &mut dyn Write) -> Result<usize> {
+    writeln!(out, "start,end,duration_ms,keystrokes,application,wpm")?;
+    for session in sessions {
+        writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            session.start,
+            session.end,
+            session.duration_ms(),
+            session.keystrokes,
+            csv_field(&session.application),
+            session.wpm.map_or(String::new(), |wpm| format!("{:.1}", wpm))
+        )?;
+    }
+    Ok(sessions.len())
+}
+
+fn write_sessions_json(
+    sessions: &[TypingSession],
+    gap_ms: i64,
+    anonymized: bool,
+    out: &mut dyn Write,
+) -> Result<usize> {
+    let mut metadata = serde_json::json!({
+        "export_date": chrono::Utc::now().to_rfc3339(),
+        "total_sessions": sessions.len(),
+        "session_gap_ms": gap_ms,
+    });
+    if anonymized {
+        metadata["anonymized"] = serde_json::Value::Bool(true);
+    }
+
+    let doc = serde_json::json!({
+        "metadata": metadata,
+        "sessions": sessions,
+    });
+    serde_json::to_writer_pretty(&mut *out, &doc)?;
+    writeln!(out)?;
+    Ok(sessions.len())
+}
+
+/// `Database::for_each_event` minus the events `options` filters out.
+/// Returns the number of events passed to `f`.
+fn for_each_included_event<F>(db: &Database, options: &ExportOptions, mut f: F) -> Result<usize>
+where
+    F: FnMut(KeystrokeEvent) -> Result<()>,
+{
+    let total = if options.progress { db.get_total_count()? as u64 } else { 0 };
+    let mut progress = Progress::new("Reading events", total, options.progress);
+    let max_gap_ms = options.max_gap_ms.unwrap_or(DEFAULT_MAX_GAP_MS);
+    let mut modifier_filter = ModifierFilter::new(&options.with_modifiers, max_gap_ms);
+    let mut included = 0;
+    db.for_each_event(|event| {
+        progress.tick();
+        if is_excluded_app(&event.application, &options.exclude_apps)
+            || !modifier_filter.keep(&event)
+        {
+            return Ok(());
+        }
+        included += 1;
+        f(event)
+    })?;
+    if modifier_filter.missed_releases() {
+        eprintln!("{}", MISSING_RELEASES_WARNING);
+    }
+    Ok(included)
+}
+
+/// Passes events through `anonymizer` when exporting anonymized.
+fn prepare(event: KeystrokeEvent, anonymizer: Option<&Anonymizer>) -> KeystrokeEvent {
+    match anonymizer {
+        Some(anonymizer) => anonymizer.apply(event),
+        None => event,
+    }
+}
+
+/// `text` as one CSV field: quoted, with quotes doubled, when it holds a
+/// comma, quote or line break, and as is otherwise.
+fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
 
-    writeln!(file, "timestamp,key_code,key_name,event_type,modifiers,application")?;
+fn write_csv(
+    db: &Database,
+    out: &mut dyn Write,
+    anonymizer: Option<&Anonymizer>,
+    options: &ExportOptions,
+) -> Result<usize> {
+    writeln!(
+        out,
+        "timestamp,key_code,key_name,event_type,modifiers,application,window_title,character,\
+         input_source"
+    )?;
 
-    for event in &events {
-        let key_name = KeyCode(event.key_code).to_name();
+    for_each_included_event(db, options, |event| {
+        let event = prepare(event, anonymizer);
+        let key_name = event.key_name();
         let modifiers_str = event
             .modifiers
             .iter()
@@ -62,62 +422,364 @@ pub fn export_csv<P: AsRef<Path>>(db: &Database, output_path: P) -> Result<()> {
             .join(";");
 
         writeln!(
-            file,
-            "{},{},{},{},{},{}",
+            out,
+            "{},{},{},{},{},{},{},{},{}",
             event.timestamp,
             event.key_code,
-            key_name,
+            csv_field(&key_name),
             event.event_type,
-            modifiers_str,
-            event.application.replace(',', ";")
+            csv_field(&modifiers_str),
+            csv_field(&event.application),
+            csv_field(event.window_title.as_deref().unwrap_or("")),
+            csv_field(event.character.as_deref().unwrap_or("")),
+            csv_field(event.input_source.as_deref().unwrap_or(""))
         )?;
-    }
-
-    println!(
-        "Exported {} events to {}",
-        events.len(),
-        safe_path.display()
-    );
-
-    Ok(())
+        Ok(())
+    })
 }
 
-pub fn export_json<P: AsRef<Path>>(db: &Database, output_path: P) -> Result<()> {
-    let safe_path = validate_export_path(&output_path)?;
-    let events = db.get_all_events()?;
+/// Writes the same document shape as before, but event by event rather than
+/// building the whole array in memory.
+fn write_json(
+    db: &Database,
+    out: &mut dyn Write,
+    anonymizer: Option<&Anonymizer>,
+    options: &ExportOptions,
+) -> Result<usize> {
+    let shift = |ts: i64| anonymizer.map_or(ts, |a| a.timestamp(ts));
     let date_range = db.get_date_range()?;
+    // The header comes before the events, so filters need a counting pass.
+    let total_events = if options.filters_events() {
+        for_each_included_event(db, options, |_| Ok(()))?
+    } else {
+        db.get_total_count()? as usize
+    };
 
-    let export_data = serde_json::json!({
-        "metadata": {
-            "export_date": chrono::Utc::now().to_rfc3339(),
-            "total_events": events.len(),
-            "date_range": date_range.map(|(start, end)| {
-                serde_json::json!({
-                    "start": start,
-                    "end": end
-                })
-            })
-        },
-        "events": events.iter().map(|e| {
+    let mut metadata = serde_json::json!({
+        "export_date": chrono::Utc::now().to_rfc3339(),
+        "total_events": total_events,
+        "date_range": date_range.map(|(start, end)| {
             serde_json::json!({
-                "timestamp": e.timestamp,
-                "key_code": e.key_code,
-                "key_name": KeyCode(e.key_code).to_name(),
-                "event_type": e.event_type,
-                "modifiers": e.modifiers,
-                "application": e.application
+                "start": shift(start),
+                "end": shift(end)
             })
-        }).collect::<Vec<_>>()
+        })
     });
+    if anonymizer.is_some() {
+        metadata["anonymized"] = serde_json::Value::Bool(true);
+    }
+    if !options.exclude_apps.is_empty() && anonymizer.is_none() {
+        metadata["excluded_apps"] = serde_json::json!(options.exclude_apps);
+    }
+    if !options.with_modifiers.is_empty() {
+        metadata["with_modifiers"] = serde_json::json!(options.with_modifiers);
+    }
 
-    let file = File::create(&safe_path)?;
-    serde_json::to_writer_pretty(file, &export_data)?;
+    write!(out, "{{\n  \"metadata\": ")?;
+    serde_json::to_writer(&mut *out, &metadata)?;
+    write!(out, ",\n  \"events\": [")?;
 
-    println!(
-        "Exported {} events to {}",
-        events.len(),
-        safe_path.display()
-    );
+    let mut first = true;
+    let count = for_each_included_event(db, options, |e| {
+        let event = event_json(&prepare(e, anonymizer));
+        out.write_all(if first { b"\n    " } else { b",\n    " })?;
+        first = false;
+        serde_json::to_writer(&mut *out, &event)?;
+        Ok(())
+    })?;
 
-    Ok(())
+    write!(out, "\n  ]\n}}\n")?;
+
+    Ok(count)
+}
+
+fn write_jsonl(
+    db: &Database,
+    out: &mut dyn Write,
+    anonymizer: Option<&Anonymizer>,
+    options: &ExportOptions,
+) -> Result<usize> {
+    for_each_included_event(db, options, |e| {
+        serde_json::to_writer(&mut *out, &event_json(&prepare(e, anonymizer)))?;
+        out.write_all(b"\n")?;
+        Ok(())
+    })
+}
+
+/// An event as the JSON exports write it: the stored fields plus `key_name`,
+/// named after the event's own input source.
+fn event_json(e: &KeystrokeEvent) -> serde_json::Value {
+    serde_json::json!({
+        "timestamp": e.timestamp,
+        "key_code": e.key_code,
+        "key_name": e.key_name(),
+        "event_type": e.event_type,
+        "modifiers": e.modifiers,
+        "application": e.application,
+        "window_title": e.window_title,
+        "character": e.character,
+        "input_source": e.input_source
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::input::read_events_jsonl;
+    use crate::testutil::{in_app, press};
+    use chrono::TimeZone;
+    use flate2::read::GzDecoder;
+    use flate2::write::GzEncoder;
+    use std::io::Read;
+
+    fn test_db() -> Database {
+        let db = Database::new(":memory:").unwrap();
+        for (ts, key_code) in [(1000, 0x00), (1100, 0x01)] {
+            db.insert_event(&in_app(press(ts, key_code), "com.test.app")).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn test_csv_gzip_round_trip() {
+        let db = test_db();
+
+        let mut plain = Vec::new();
+        write_csv(&db, &mut plain, None, &ExportOptions::default()).unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let count = write_csv(&db, &mut encoder, None, &ExportOptions::default()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(decoded, plain);
+        assert!(String::from_utf8(decoded).unwrap().contains("1100,1,S,press"));
+    }
+
+    #[test]
+    fn test_streamed_json_is_valid() {
+        let db = test_db();
+
+        let mut out = Vec::new();
+        write_json(&db, &mut out, None, &ExportOptions::default()).unwrap();
+
+        let doc: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(doc["metadata"]["total_events"], 2);
+        assert_eq!(doc["events"].as_array().unwrap().len(), 2);
+        assert_eq!(doc["events"][1]["key_name"], "S");
+    }
+
+    #[test]
+    fn test_jsonl_reads_back() {
+        let db = test_db();
+
+        let mut out = Vec::new();
+        let count = write_jsonl(&db, &mut out, None, &ExportOptions::default()).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(String::from_utf8_lossy(&out).lines().count(), 2);
+        let events = read_events_jsonl(out.as_slice()).unwrap();
+        assert_eq!(events, db.get_all_events().unwrap());
+    }
+
+    #[test]
+    fn test_csv_quotes_text_fields() {
+        let db = Database::new(":memory:").unwrap();
+        for (ts, character) in [(1000, Some(",")), (1100, Some("ß")), (1200, None)] {
+            let event = in_app(press(ts, 0x2B), "com.test.app")
+                .with_character(character.map(str::to_string));
+            db.insert_event(&event).unwrap();
+        }
+        let mut titled = in_app(press(1300, 0x00), "Acme, Inc.");
+        titled.window_title = Some("Say \"hi\"".to_string());
+        db.insert_event(&titled).unwrap();
+
+        let mut out = Vec::new();
+        write_csv(&db, &mut out, None, &ExportOptions::default()).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines[1..],
+            [
+                "1000,43,\",\",press,,com.test.app,,\",\",",
+                "1100,43,\",\",press,,com.test.app,,ß,",
+                "1200,43,\",\",press,,com.test.app,,,",
+                "1300,0,A,press,,\"Acme, Inc.\",\"Say \"\"hi\"\"\",,",
+            ]
+        );
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn test_keys_named_after_their_input_source() {
+        let db = Database::new(":memory:").unwrap();
+        let french = Some("com.apple.keylayout.French".to_string());
+        db.insert_event(&press(1000, 0x0C).with_input_source(french)).unwrap();
+        db.insert_event(&press(1001, 0x0C)).unwrap();
+
+        let mut out = Vec::new();
+        write_csv(&db, &mut out, None, &ExportOptions::default()).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert!(lines[1].contains(",12,A,press,"), "{}", lines[1]);
+        assert!(lines[1].ends_with(",com.apple.keylayout.French"));
+        assert!(lines[2].contains(",12,Q,press,"), "{}", lines[2]);
+    }
+
+    #[test]
+    fn test_dash_writes_to_stdout_writer() {
+        let db = test_db();
+
+        let mut stdout = Vec::new();
+        let (path, count) =
+            write_export_to(STDOUT_PATH, &ExportOptions::default(), &mut stdout, |out| {
+                write_csv(&db, out, None, &ExportOptions::default())
+            })
+            .unwrap();
+
+        assert_eq!(path, None);
+        assert_eq!(count, 2);
+        let csv = String::from_utf8(stdout).unwrap();
+        assert!(csv.starts_with("timestamp,key_code,key_name,event_type,"));
+        assert_eq!(csv.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_outside_home_rejected_by_default() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path().canonicalize().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("data.csv");
+
+        let err = validate_export_path_within(&target, false, &home).unwrap_err();
+        assert!(err.to_string().contains("--allow-outside-home"));
+
+        let allowed = validate_export_path_within(&target, true, &home).unwrap();
+        assert_eq!(allowed, dir.path().canonicalize().unwrap().join("data.csv"));
+
+        let inside = validate_export_path_within(&home.join("data.csv"), false, &home).unwrap();
+        assert_eq!(inside, home.join("data.csv"));
+    }
+
+    #[test]
+    fn test_export_file_is_private() {
+        let db = test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("data.csv");
+        std::fs::write(&target, "old").unwrap();
+        std::fs::set_permissions(&target, Permissions::from_mode(0o644)).unwrap();
+
+        let options = ExportOptions {
+            allow_outside_home: true,
+            ..ExportOptions::default()
+        };
+        write_export_to(&target, &options, &mut Vec::new(), |out| {
+            write_csv(&db, out, None, &options)
+        })
+        .unwrap();
+
+        let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_override_keeps_parent_and_filename_checks() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let missing_parent = dir.path().join("missing").join("data.csv");
+        let err = validate_export_path(&missing_parent, true).unwrap_err();
+        assert!(err.to_string().contains("Parent directory does not exist"));
+
+        let no_filename = dir.path().join("..");
+        let err = validate_export_path(&no_filename, true).unwrap_err();
+        assert!(err.to_string().contains("no filename"));
+    }
+
+    #[test]
+    fn test_timestamped_export_name() {
+        let time = Local.with_ymd_and_hms(2024, 3, 1, 9, 15, 2).unwrap();
+        assert_eq!(timestamped_export_name(time, "csv"), "lurk-export-20240301-091502.csv");
+        assert_eq!(
+            timestamped_export_path(Path::new("/exports"), "jsonl").parent(),
+            Some(Path::new("/exports"))
+        );
+    }
+
+    #[test]
+    fn test_inside_home_allowed() {
+        let home = dirs::home_dir().unwrap();
+        let path = validate_export_path(home.join("lurk-export.csv"), false).unwrap();
+        assert_eq!(path, home.canonicalize().unwrap().join("lurk-export.csv"));
+    }
+
+    #[test]
+    fn test_anonymized_json_hides_apps() {
+        let db = test_db();
+        let anonymizer = Anonymizer::new(true);
+
+        let mut out = Vec::new();
+        write_json(&db, &mut out, Some(&anonymizer), &ExportOptions::default()).unwrap();
+
+        let doc: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let events = doc["events"].as_array().unwrap();
+        assert_eq!(doc["metadata"]["anonymized"], true);
+        assert_eq!(events[0]["application"], events[1]["application"]);
+        assert!(!String::from_utf8(out.clone()).unwrap().contains("com.test.app"));
+        let ts = |i: usize| events[i]["timestamp"].as_i64().unwrap();
+        assert_eq!(ts(1) - ts(0), 100);
+    }
+
+    #[test]
+    fn test_session_export_rows() {
+        let db = test_db();
+        for ts in [900_000, 900_200, 900_400] {
+            db.insert_event(&in_app(press(ts, 0x02), "com.other.app")).unwrap();
+        }
+        let sessions = load_sessions(&db, &ExportOptions::default(), 60_000).unwrap();
+
+        let mut csv = Vec::new();
+        assert_eq!(write_sessions_csv(&sessions, &mut csv).unwrap(), 2);
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "start,end,duration_ms,keystrokes,application,wpm");
+        assert_eq!(lines[1], "1000,1100,100,2,com.test.app,240.0");
+        assert_eq!(lines[2], "900000,900400,400,3,com.other.app,90.0");
+
+        let mut json = Vec::new();
+        write_sessions_json(&sessions, 60_000, false, &mut json).unwrap();
+        let doc: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(doc["metadata"]["total_sessions"], 2);
+        assert_eq!(doc["sessions"][1]["keystrokes"], 3);
+    }
+
+    #[test]
+    fn test_excluded_apps_left_out_of_csv_and_json() {
+        let db = test_db();
+        db.insert_event(&in_app(press(1200, 0x02), "com.apple.Terminal")).unwrap();
+        let options = ExportOptions {
+            exclude_apps: vec!["terminal".to_string()],
+            ..ExportOptions::default()
+        };
+
+        let mut csv = Vec::new();
+        assert_eq!(write_csv(&db, &mut csv, None, &options).unwrap(), 2);
+        assert!(!String::from_utf8(csv).unwrap().contains("Terminal"));
+
+        let mut json = Vec::new();
+        assert_eq!(write_json(&db, &mut json, None, &options).unwrap(), 2);
+        let doc: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(doc["metadata"]["total_events"], 2);
+        assert_eq!(doc["metadata"]["excluded_apps"][0], "terminal");
+    }
+
+    #[test]
+    fn test_with_gz_suffix() {
+        assert_eq!(with_gz_suffix(PathBuf::from("a/data.csv")), PathBuf::from("a/data.csv.gz"));
+        assert_eq!(with_gz_suffix(PathBuf::from("data.json.gz")), PathBuf::from("data.json.gz"));
+    }
 }
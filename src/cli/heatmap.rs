@@ -0,0 +1,188 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use super::export::validate_export_path;
+use crate::analysis::frequency::{count_percentages, heat_reference};
+use crate::models::keyboard_layout::KeyboardLayout;
+use crate::storage::{timestamp_days_ago, Database};
+
+/// Pixels per layout width unit. Layout widths are terminal columns, where a
+/// standard key is 2 units wide with a 1 unit gap.
+const UNIT_PX: u32 = 16;
+const ROW_HEIGHT_PX: u32 = 44;
+const KEY_HEIGHT_PX: u32 = 38;
+const MARGIN_PX: u32 = 12;
+
+const COLD: (u8, u8, u8) = (240, 240, 240);
+const HOT: (u8, u8, u8) = (215, 48, 31);
+
+/// Parses `--range` values: `7d`, `30d`, a plain number of days, or `all`.
+pub fn parse_range_days(range: &str) -> Result<Option<u32>> {
+    let range = range.trim();
+    if range.eq_ignore_ascii_case("all") {
+        return Ok(None);
+    }
+
+    range
+        .trim_end_matches(['d', 'D'])
+        .parse::<u32>()
+        .map(Some)
+        .map_err(|_| anyhow!("Invalid range '{}'. Use e.g. 7d, 30d or all", range))
+}
+
+pub fn export_heatmap<P: AsRef<Path>>(
+    db: &Database,
+    output_path: P,
+    layout: &dyn KeyboardLayout,
+    days: Option<u32>,
+    cap_percentile: Option<f64>,
+    allow_outside_home: bool,
+) -> Result<()> {
+    let safe_path = validate_export_path(&output_path, allow_outside_home)?;
+
+    let start = days.map_or(0, timestamp_days_ago);
+    let counts = db.get_top_keys_since(start, None, usize::MAX)?;
+    let total_presses: i64 = counts.iter().map(|(_, count)| count).sum();
+    let svg = render_heatmap_svg(layout, &count_percentages(&counts, false), cap_percentile);
+
+    fs::write(&safe_path, svg)?;
+
+    println!(
+        "Rendered heatmap of {} key presses to {}",
+        total_presses,
+        safe_path.display()
+    );
+
+    Ok(())
+}
+
+/// `cap_percentile` sets full intensity as in `heat_reference`.
+pub fn render_heatmap_svg(
+    layout: &dyn KeyboardLayout,
+    frequencies: &HashMap<u32, f64>,
+    cap_percentile: Option<f64>,
+) -> String {
+    let max_freq = heat_reference(frequencies.values().copied(), cap_percentile);
+
+    let width = (u32::from(layout.total_width()) + 1) * UNIT_PX + 2 * MARGIN_PX;
+    let height = layout.rows().len() as u32 * ROW_HEIGHT_PX + 2 * MARGIN_PX;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="Helvetica, Arial, sans-serif">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect x="0" y="0" width="{}" height="{}" fill="#ffffff"/>"##,
+        width, height
+    );
+
+    for (row, x_units, key) in layout.key_positions() {
+        let y = MARGIN_PX + row as u32 * ROW_HEIGHT_PX;
+        let freq = frequencies.get(&key.keycode).copied().unwrap_or(0.0);
+        let intensity = if max_freq > 0.0 { (freq / max_freq).min(1.0) } else { 0.0 };
+        let (r, g, b) = heat_color(intensity);
+
+        let x = MARGIN_PX + u32::from(x_units) * UNIT_PX;
+        let key_width = u32::from(key.width) * UNIT_PX + (UNIT_PX - 4);
+        let text_fill = if intensity > 0.6 { "#ffffff" } else { "#222222" };
+
+        let _ = writeln!(
+            svg,
+            r##"<rect x="{}" y="{}" width="{}" height="{}" rx="4" fill="#{:02x}{:02x}{:02x}" stroke="#999999"><title>{} {:.2}%</title></rect>"##,
+            x,
+            y,
+            key_width,
+            KEY_HEIGHT_PX,
+            r,
+            g,
+            b,
+            escape_xml(key.label),
+            freq
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-size="14" text-anchor="middle" fill="{}">{}</text>"#,
+            x + key_width / 2,
+            y + KEY_HEIGHT_PX / 2 + 5,
+            text_fill,
+            escape_xml(key.label)
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn heat_color(intensity: f64) -> (u8, u8, u8) {
+    let t = intensity.clamp(0.0, 1.0);
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    (lerp(COLD.0, HOT.0), lerp(COLD.1, HOT.1), lerp(COLD.2, HOT.2))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::keyboard_layout::{OrthoLayout, QwertyLayout, SplitLayout};
+
+    #[test]
+    fn test_svg_has_one_rect_per_key() {
+        let layout = QwertyLayout::new();
+        let mut frequencies = HashMap::new();
+        frequencies.insert(0x0E, 12.5);
+        frequencies.insert(0x31, 18.0);
+
+        let svg = render_heatmap_svg(&layout, &frequencies, None);
+        assert_eq!(count_key_rects(&svg), layout.key_positions().len());
+    }
+
+    #[test]
+    fn test_svg_renders_grid_layouts() {
+        let frequencies = HashMap::new();
+        let layouts: [Box<dyn KeyboardLayout>; 2] =
+            [Box::new(OrthoLayout::new()), Box::new(SplitLayout::new())];
+
+        for layout in &layouts {
+            let svg = render_heatmap_svg(layout.as_ref(), &frequencies, None);
+            assert_eq!(count_key_rects(&svg), layout.key_positions().len());
+        }
+    }
+
+    fn count_key_rects(svg: &str) -> usize {
+        let doc = roxmltree::Document::parse(svg).expect("heatmap should be valid XML");
+        doc.descendants()
+            .filter(|n| n.has_tag_name("rect") && n.attribute("rx").is_some())
+            .count()
+    }
+
+    #[test]
+    fn test_hottest_key_uses_hot_color() {
+        let layout = QwertyLayout::new();
+        let mut frequencies = HashMap::new();
+        frequencies.insert(0x0E, 12.5);
+
+        let svg = render_heatmap_svg(&layout, &frequencies, None);
+        assert!(svg.contains(r##"fill="#d7301f""##));
+    }
+
+    #[test]
+    fn test_parse_range_days() {
+        assert_eq!(parse_range_days("7d").unwrap(), Some(7));
+        assert_eq!(parse_range_days("30").unwrap(), Some(30));
+        assert_eq!(parse_range_days("all").unwrap(), None);
+        assert!(parse_range_days("week").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::models::KeystrokeEvent;
+
+/// Reads events in the format `lurk export --format jsonl` writes: one JSON
+/// object per line. Extra fields such as `key_name` are ignored and blank
+/// lines skipped; a line that isn't an event is an error naming the line.
+pub fn read_events_jsonl<R: BufRead>(reader: R) -> Result<Vec<KeystrokeEvent>> {
+    let mut events = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line)
+            .with_context(|| format!("Line {} is not a keystroke event", index + 1))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Like `read_events_jsonl`, from a file or, for `-`, stdin. Files ending
+/// in `.gz` are decompressed, as written by `export --gzip`.
+pub fn read_events_jsonl_from(path: &str) -> Result<Vec<KeystrokeEvent>> {
+    if path == "-" {
+        return read_events_jsonl(io::stdin().lock());
+    }
+
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path))?;
+    let events = if Path::new(path).extension().is_some_and(|ext| ext == "gz") {
+        read_events_jsonl(BufReader::new(GzDecoder::new(file)))
+    } else {
+        read_events_jsonl(BufReader::new(file))
+    };
+    events.with_context(|| format!("Failed to read events from {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::FrequencyAnalysis;
+    use crate::storage::Database;
+
+    #[test]
+    fn test_reads_export_lines_into_analysis() {
+        let jsonl = r#"{"timestamp":1000,"key_code":17,"key_name":"T","event_type":"press","modifiers":[],"application":"com.test.app","window_title":null,"character":"t"}
+{"timestamp":1060,"key_code":17,"event_type":"release","modifiers":[],"application":"com.test.app"}
+
+{"timestamp":1100,"key_code":4,"event_type":"press","modifiers":[],"application":"com.test.app"}
+{"timestamp":1200,"key_code":17,"event_type":"press","modifiers":[],"application":"com.test.app"}
+"#;
+        let events = read_events_jsonl(jsonl.as_bytes()).unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].character.as_deref(), Some("t"));
+
+        let freq = FrequencyAnalysis::from_events(&events);
+        assert_eq!(freq.total_presses, 3);
+        assert_eq!(freq.top_keys(1)[0].key_name, "T");
+        assert_eq!(freq.top_keys(1)[0].count, 2);
+    }
+
+    #[test]
+    fn test_input_analyzed_in_memory_database() {
+        let jsonl = r#"{"timestamp":1000,"key_code":17,"event_type":"press","modifiers":[],"application":"com.test.app"}
+{"timestamp":1060,"key_code":17,"event_type":"release","modifiers":[],"application":"com.test.app"}
+{"timestamp":1100,"key_code":4,"event_type":"press","modifiers":[],"application":"com.other.app"}
+{"timestamp":1200,"key_code":17,"event_type":"press","modifiers":[],"application":"com.test.app"}
+"#;
+        let events = read_events_jsonl(jsonl.as_bytes()).unwrap();
+        let db = Database::from_events(&events).unwrap();
+
+        assert_eq!(db.get_total_count().unwrap(), 4);
+        assert_eq!(db.get_press_count().unwrap(), 3);
+        assert_eq!(db.get_top_keys_since(0, None, 1).unwrap(), vec![(17, 2)]);
+        assert_eq!(
+            db.get_top_applications_since(0, None, 1).unwrap(),
+            vec![("com.test.app".to_string(), 2)]
+        );
+        assert_eq!(db.get_all_events().unwrap(), events);
+    }
+
+    #[test]
+    fn test_bad_line_is_reported_by_number() {
+        let jsonl = r#"{"timestamp":1,"key_code":0,"event_type":"press","modifiers":[],"application":"a"}
+not json
+"#;
+        let err = read_events_jsonl(jsonl.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("Line 2"));
+    }
+}
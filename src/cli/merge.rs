@@ -0,0 +1,80 @@
+use anyhow::Result;
+
+use crate::storage::Database;
+
+/// Events inserted into the target per transaction.
+const MERGE_BATCH: usize = 10_000;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MergeReport {
+    pub inserted: usize,
+    /// Events the target already had.
+    pub skipped: usize,
+}
+
+/// Copies every event from `source` into `target`, skipping ones `target`
+/// already has (see `Database::insert_missing_events`), so merging the same
+/// database twice is harmless.
+pub fn merge_databases(source: &Database, target: &mut Database) -> Result<MergeReport> {
+    let mut report = MergeReport::default();
+    let mut batch = Vec::with_capacity(MERGE_BATCH);
+
+    source.for_each_event(|event| {
+        batch.push(event);
+        if batch.len() >= MERGE_BATCH {
+            let (inserted, skipped) = target.insert_missing_events(&batch)?;
+            report.inserted += inserted;
+            report.skipped += skipped;
+            batch.clear();
+        }
+        Ok(())
+    })?;
+
+    let (inserted, skipped) = target.insert_missing_events(&batch)?;
+    report.inserted += inserted;
+    report.skipped += skipped;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::app_press;
+
+    #[test]
+    fn test_merge_skips_events_the_target_has() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source_path = source_dir.path().join("events.db");
+        let target_path = target_dir.path().join("events.db");
+
+        let source = Database::new(&source_path).unwrap();
+        for event in [app_press(1, "laptop"), app_press(2, "laptop"), app_press(3, "shared")] {
+            source.insert_event(&event).unwrap();
+        }
+        drop(source);
+        let mut target = Database::new(&target_path).unwrap();
+        target.insert_event(&app_press(3, "shared")).unwrap();
+        target.insert_event(&app_press(4, "desktop")).unwrap();
+
+        // Each database has its own key file; the source is read with its own.
+        let source = Database::open_read_only(&source_path).unwrap();
+        let report = merge_databases(&source, &mut target).unwrap();
+        assert_eq!(report, MergeReport { inserted: 2, skipped: 1 });
+
+        let timestamps: Vec<_> = target.get_all_events().unwrap().iter().map(|e| e.timestamp).collect();
+        assert_eq!(timestamps, vec![1, 2, 3, 4]);
+
+        let again = merge_databases(&source, &mut target).unwrap();
+        assert_eq!(again, MergeReport { inserted: 0, skipped: 3 });
+    }
+
+    #[test]
+    fn test_wrong_source_key_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.db");
+        Database::new(&path).unwrap().insert_event(&app_press(1, "laptop")).unwrap();
+
+        assert!(Database::open_read_only_with_key(&path, "not-the-key").is_err());
+    }
+}
@@ -1,5 +1,26 @@
+pub mod anonymize;
+pub mod check;
+pub mod compare;
 pub mod export;
+pub mod heatmap;
+pub mod input;
+pub mod merge;
+pub mod ndjson;
+pub mod progress;
+pub mod replay;
 pub mod stats;
+pub mod status;
+pub mod summary;
 
-pub use export::{export_csv, export_json};
-pub use stats::show_stats;
+pub use check::run_check;
+pub use compare::show_comparison;
+pub use export::{
+    export_csv, export_json, export_jsonl, export_sessions_csv, export_sessions_json,
+    ExportOptions,
+};
+pub use heatmap::export_heatmap;
+pub use merge::merge_databases;
+pub use ndjson::write_analysis_ndjson;
+pub use stats::{show_stats, StatsReport};
+pub use status::StatusReport;
+pub use summary::AnalysisSummary;
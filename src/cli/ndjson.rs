@@ -0,0 +1,102 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+
+use crate::analysis::frequency::{BigramCount, KeyCount, TrigramCount};
+use crate::analysis::timing::{HoldDuration, InterKeyInterval};
+use crate::analysis::{FrequencyAnalysis, TimingAnalysis};
+
+/// One line of `analyze --format ndjson`, tagged with `"type"`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Row<'a> {
+    Key(&'a KeyCount),
+    Bigram(&'a BigramCount),
+    Trigram(&'a TrigramCount),
+    TimingPair {
+        samples: usize,
+        #[serde(flatten)]
+        pair: &'a InterKeyInterval,
+    },
+    Hold(&'a HoldDuration),
+}
+
+/// Writes the top `top` keys, bigrams, trigrams, key-pair timings and hold
+/// durations as one JSON object per line, in that order. N-grams seen fewer
+/// than `min_count` times are left out, as in the text report. Returns the
+/// number of lines written.
+pub fn write_analysis_ndjson(
+    out: &mut dyn Write,
+    freq: &FrequencyAnalysis,
+    timing: &TimingAnalysis,
+    top: usize,
+    min_count: u64,
+) -> Result<usize> {
+    let rows = freq
+        .top_keys(top)
+        .iter()
+        .map(Row::Key)
+        .chain(freq.top_bigrams_min(top, min_count).iter().map(Row::Bigram))
+        .chain(freq.top_trigrams_min(top, min_count).iter().map(Row::Trigram))
+        .chain(timing.top_inter_key_pairs(top).iter().map(|pair| Row::TimingPair {
+            samples: pair.intervals_ms.len(),
+            pair,
+        }))
+        .chain(timing.top_hold_durations(top).iter().map(Row::Hold));
+
+    let mut count = 0;
+    for row in rows {
+        serde_json::to_writer(&mut *out, &row)?;
+        writeln!(out)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::FilterConfig;
+    use crate::models::EventType;
+    use crate::testutil::event;
+
+    #[test]
+    fn test_one_line_per_row() {
+        // A S A S A S: 2 keys, 2 bigrams (AS x3, SA x2), 2 trigrams (ASA x2, SAS x2),
+        // 2 timing pairs with 3 and 2 samples, and 2 held keys.
+        let mut events = Vec::new();
+        for i in 0..6 {
+            let key_code = if i % 2 == 0 { 0x00 } else { 0x01 };
+            events.push(event(i * 100, key_code, EventType::Press));
+            events.push(event(i * 100 + 50, key_code, EventType::Release));
+        }
+        let config = FilterConfig {
+            min_pair_samples: 2,
+            ..FilterConfig::default()
+        };
+        let freq = FrequencyAnalysis::from_events(&events);
+        let timing = TimingAnalysis::from_events(&events, config);
+
+        let mut out = Vec::new();
+        let count = write_analysis_ndjson(&mut out, &freq, &timing, 10, 2).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let rows: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(count, 10);
+        assert_eq!(rows.len(), 10);
+
+        let of_type = |t: &str| rows.iter().filter(|r| r["type"] == t).count();
+        assert_eq!(of_type("key"), 2);
+        assert_eq!(of_type("bigram"), 2);
+        assert_eq!(of_type("trigram"), 2);
+        assert_eq!(of_type("timing_pair"), 2);
+        assert_eq!(of_type("hold"), 2);
+
+        let pair = rows.iter().find(|r| r["type"] == "timing_pair").unwrap();
+        assert_eq!(pair["samples"], 3);
+        assert!(pair.get("intervals_ms").is_none());
+    }
+}
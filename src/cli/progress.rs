@@ -0,0 +1,89 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Smaller jobs finish quickly enough that a counter would only flicker.
+pub const PROGRESS_THRESHOLD: u64 = 500_000;
+
+const REDRAW_EVERY: Duration = Duration::from_millis(200);
+
+/// A one-line row counter on stderr for long exports and analyses, redrawn
+/// in place a few times per second. It stays silent unless asked for, the
+/// job is at least `PROGRESS_THRESHOLD` rows and stderr is a terminal, so
+/// piped and scripted runs never see it.
+pub struct Progress {
+    label: &'static str,
+    total: u64,
+    done: u64,
+    enabled: bool,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: u64, wanted: bool) -> Self {
+        Self {
+            label,
+            total,
+            done: 0,
+            enabled: wanted && total >= PROGRESS_THRESHOLD && io::stderr().is_terminal(),
+            last_draw: None,
+        }
+    }
+
+    /// Counts one row, redrawing if enough time has passed.
+    pub fn tick(&mut self) {
+        self.done += 1;
+        if !self.enabled {
+            return;
+        }
+        if self.last_draw.is_some_and(|at| at.elapsed() < REDRAW_EVERY) {
+            return;
+        }
+        self.last_draw = Some(Instant::now());
+        eprint!("\r{}", status_line(self.label, self.done, self.total));
+        let _ = io::stderr().flush();
+    }
+
+    /// Clears the line so the summary that follows starts clean.
+    pub fn finish(&mut self) {
+        if self.enabled && self.last_draw.is_some() {
+            eprint!("\r\x1b[2K");
+            let _ = io::stderr().flush();
+        }
+        self.enabled = false;
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+fn status_line(label: &str, done: u64, total: u64) -> String {
+    // Rows can be added while we read, so never claim more than 100%.
+    let pct = if total > 0 {
+        (done as f64 / total as f64 * 100.0).min(100.0)
+    } else {
+        100.0
+    };
+    format!("{}: {}/{} ({:.0}%)", label, done, total, pct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_line() {
+        assert_eq!(status_line("Exporting", 250, 1000), "Exporting: 250/1000 (25%)");
+        assert_eq!(status_line("Exporting", 1005, 1000), "Exporting: 1005/1000 (100%)");
+    }
+
+    #[test]
+    fn test_small_jobs_stay_silent() {
+        let mut progress = Progress::new("Exporting", PROGRESS_THRESHOLD - 1, true);
+        progress.tick();
+        assert!(!progress.enabled);
+        assert_eq!(progress.done, 1);
+    }
+}
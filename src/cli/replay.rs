@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use crate::analysis::sessions::typing_sessions;
+use crate::cli::compare::parse_split_date;
+use crate::models::{EventType, KeystrokeEvent};
+
+/// With `--realtime`, pauses longer than this are cut short: a session can
+/// hold minutes of silence that nobody wants to sit through.
+pub const REPLAY_MAX_PAUSE_MS: i64 = 2_000;
+
+/// Parses `YYYY-MM-DD HH:MM` in local time, or `YYYY-MM-DD` for local midnight,
+/// into a timestamp in ms.
+pub fn parse_local_time(time: &str) -> Result<i64> {
+    let time = time.trim();
+    if !time.contains(' ') {
+        return parse_split_date(time);
+    }
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M")
+        .ok()
+        .and_then(|dt| dt.and_local_timezone(Local).earliest())
+        .map(|dt| dt.timestamp_millis())
+        .ok_or_else(|| anyhow!("Invalid time '{}'. Use YYYY-MM-DD or \"YYYY-MM-DD HH:MM\"", time))
+}
+
+/// Like `parse_local_time`, but a bare `YYYY-MM-DD` means the last ms of that
+/// day, so an inclusive range ending there covers the whole day.
+pub fn parse_local_end_time(time: &str) -> Result<i64> {
+    let time = time.trim();
+    if time.contains(' ') {
+        return parse_local_time(time);
+    }
+    let next_day = NaiveDate::parse_from_str(time, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.succ_opt())
+        .ok_or_else(|| anyhow!("Invalid date '{}'. Use YYYY-MM-DD", time))?;
+    Ok(parse_split_date(&next_day.to_string())? - 1)
+}
+
+/// The events of the `n`th most recent session in `events`, 1 being the
+/// latest, split as `typing_sessions` does. Releases after the session's last
+/// press are left out.
+pub fn session_events(
+    events: &[KeystrokeEvent],
+    gap_ms: i64,
+    n: usize,
+) -> Result<Vec<KeystrokeEvent>> {
+    let sessions = typing_sessions(events, gap_ms);
+    let session = n
+        .checked_sub(1)
+        .and_then(|i| sessions.iter().rev().nth(i))
+        .ok_or_else(|| anyhow!("No session {}: found {} in this range", n, sessions.len()))?;
+    Ok(events
+        .iter()
+        .filter(|e| e.timestamp >= session.start && e.timestamp <= session.end)
+        .cloned()
+        .collect())
+}
+
+/// "120 events from 2024-03-01 09:15:02", in local time.
+pub fn replay_header(events: &[KeystrokeEvent]) -> String {
+    let start = events
+        .first()
+        .and_then(|e| DateTime::from_timestamp_millis(e.timestamp))
+        .map(|dt| dt.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    format!("{} events from {}", events.len(), start)
+}
+
+/// Time since the previous event: "+42ms", or "+1.25s" from a second up.
+pub fn format_offset(delta_ms: i64) -> String {
+    if delta_ms < 1_000 {
+        format!("+{}ms", delta_ms)
+    } else {
+        format!("+{:.2}s", delta_ms as f64 / 1_000.0)
+    }
+}
+
+/// One line per event in `events` (sorted by time) with the time since the
+/// previous one, e.g. "+42ms E", and a `[bundle.id]` line whenever the app
+/// changes. Each line comes with the ms to wait before it in real time.
+pub fn replay_lines(events: &[KeystrokeEvent]) -> Vec<(i64, String)> {
+    let mut lines = Vec::new();
+    let mut previous: Option<&KeystrokeEvent> = None;
+    for event in events {
+        if previous.is_none_or(|p| p.application != event.application) {
+            lines.push((0, format!("[{}]", event.application)));
+        }
+        let delta_ms = previous.map_or(0, |p| event.timestamp - p.timestamp);
+        let key = event.key_name();
+        let line = match event.event_type {
+            EventType::Press => format!("{:>8} {}", format_offset(delta_ms), key),
+            EventType::Release => format!("{:>8} {} (release)", format_offset(delta_ms), key),
+        };
+        lines.push((delta_ms, line));
+        previous = Some(event);
+    }
+    lines
+}
+
+/// Writes `replay_lines` to `out`, waiting out the gaps between events when
+/// `realtime` is set (at most `REPLAY_MAX_PAUSE_MS` each).
+pub fn replay<W: Write>(events: &[KeystrokeEvent], realtime: bool, out: &mut W) -> Result<()> {
+    for (delay_ms, line) in replay_lines(events) {
+        if realtime && delay_ms > 0 {
+            out.flush()?;
+            thread::sleep(Duration::from_millis(delay_ms.min(REPLAY_MAX_PAUSE_MS) as u64));
+        }
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{in_app, press, release};
+
+    #[test]
+    fn test_date_only_end_covers_the_whole_day() {
+        let end = parse_local_end_time("2024-03-01").unwrap();
+        assert_eq!(end, parse_local_time("2024-03-02").unwrap() - 1);
+        assert!(end > parse_local_time("2024-03-01 23:59").unwrap());
+        assert_eq!(
+            parse_local_end_time("2024-03-01 09:30").unwrap(),
+            parse_local_time("2024-03-01 09:30").unwrap()
+        );
+        assert!(parse_local_end_time("2024-13-01").is_err());
+    }
+
+    #[test]
+    fn test_lines_show_relative_timing_and_app_changes() {
+        let events = vec![
+            press(1_000, 0x0E),
+            release(1_042, 0x0E),
+            press(1_100, 0x31),
+            in_app(press(3_350, 0xFFFF), "com.apple.Terminal"),
+        ];
+        let lines: Vec<_> = replay_lines(&events).into_iter().map(|(_, line)| line).collect();
+        assert_eq!(
+            lines,
+            [
+                "[test]",
+                "    +0ms E",
+                "   +42ms E (release)",
+                "   +58ms Space",
+                "[com.apple.Terminal]",
+                "  +2.25s Redacted",
+            ]
+        );
+        assert_eq!(format_offset(999), "+999ms");
+        assert_eq!(format_offset(12_000), "+12.00s");
+    }
+
+    #[test]
+    fn test_session_one_is_the_latest() {
+        let events = vec![press(0, 0x00), press(100, 0x01), press(600_000, 0x02)];
+        let latest = session_events(&events, 60_000, 1).unwrap();
+        assert_eq!(latest, vec![press(600_000, 0x02)]);
+        assert_eq!(session_events(&events, 60_000, 2).unwrap().len(), 2);
+        assert!(session_events(&events, 60_000, 3).is_err());
+    }
+}
@@ -1,27 +1,171 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+
+use crate::analysis::coverage::{ACTIVE_HOURS, MIN_ACTIVE_GAP_MS};
+use crate::analysis::corrections::DELETE_KEYS;
+use crate::models::hours::HourWindow;
+use crate::analysis::{CoverageScan, EffectiveKeystrokes};
 
 use crate::models::keycode::KeyCode;
-use crate::storage::Database;
+use crate::storage::{timestamp_days_ago, Database};
+
+/// Longest capture gaps listed under the coverage summary.
+const MAX_GAPS_SHOWN: usize = 5;
+
+/// How many keys and applications `show_stats` lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsLimits {
+    pub keys: usize,
+    pub apps: usize,
+}
+
+impl Default for StatsLimits {
+    fn default() -> Self {
+        Self { keys: 10, apps: 5 }
+    }
+}
+
+/// A key and how often it was pressed, in `StatsReport::top_keys`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyCount {
+    pub key_code: u32,
+    pub key: String,
+    pub presses: i64,
+}
+
+/// An application and the presses made in it, in `StatsReport::top_apps`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppCount {
+    pub application: String,
+    pub presses: i64,
+}
+
+/// The numbers `show_stats` prints, apart from coverage.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsReport {
+    pub total_events: i64,
+    pub presses: i64,
+    /// Presses of the delete keys, see `EffectiveKeystrokes`.
+    pub deletions: i64,
+    /// Unix milliseconds of the oldest and newest event in range.
+    pub first_event: Option<i64>,
+    pub last_event: Option<i64>,
+    /// Most pressed first.
+    pub top_keys: Vec<KeyCount>,
+    /// Busiest first.
+    pub top_apps: Vec<AppCount>,
+}
+
+impl StatsReport {
+    /// Gathers the report for the last `days` days when given and for
+    /// everything recorded otherwise, within `hours` if given.
+    pub fn collect(
+        db: &Database,
+        days: Option<u32>,
+        hours: Option<HourWindow>,
+        limits: StatsLimits,
+    ) -> Result<Self> {
+        let start = days.map_or(0, timestamp_days_ago);
+        let date_range = db.get_date_range_since(start, hours)?;
+        let top_keys = db
+            .get_top_keys_since(start, hours, limits.keys)?
+            .into_iter()
+            .map(|(key_code, presses)| KeyCount {
+                key_code,
+                key: KeyCode(key_code).to_name(),
+                presses,
+            })
+            .collect();
+        let top_apps = db
+            .get_top_applications_since(start, hours, limits.apps)?
+            .into_iter()
+            .map(|(application, presses)| AppCount { application, presses })
+            .collect();
+        Ok(Self {
+            total_events: db.get_total_count_since(start, hours)?,
+            presses: db.get_press_count_since(start, hours)?,
+            deletions: db.get_key_press_count_since(start, hours, &DELETE_KEYS)?,
+            first_event: date_range.map(|(first, _)| first),
+            last_event: date_range.map(|(_, last)| last),
+            top_keys,
+            top_apps,
+        })
+    }
+
+    pub fn effective(&self) -> EffectiveKeystrokes {
+        EffectiveKeystrokes {
+            presses: self.presses as u64,
+            deletions: self.deletions as u64,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Prints totals, date range, top keys and top apps, over the last `days`
+/// days when given and over everything recorded otherwise. `hours` further
+/// limits everything to a local time-of-day window.
+pub fn show_stats(
+    db: &Database,
+    days: Option<u32>,
+    hours: Option<HourWindow>,
+    limits: StatsLimits,
+) -> Result<()> {
+    let report = StatsReport::collect(db, days, hours, limits)?;
+    if !print_totals(&report, days, hours) {
+        return Ok(());
+    }
+
+    // Gaps outside the window would all look like missed capture.
+    if hours.is_none() {
+        print_coverage(db, days.map_or(0, timestamp_days_ago))?;
+    }
+
+    print_top_lists(&report, limits);
+    Ok(())
+}
 
-pub fn show_stats(db: &Database, _days: Option<u32>) -> Result<()> {
-    let total = db.get_total_count()?;
-    let presses = db.get_press_count()?;
+/// The heading, totals and date range of `report`. Returns false when there
+/// is nothing recorded, after saying so.
+fn print_totals(report: &StatsReport, days: Option<u32>, hours: Option<HourWindow>) -> bool {
+    match (days, hours) {
+        (Some(days), Some(hours)) => {
+            println!("=== Lurk Statistics (last {} days, {}) ===\n", days, hours)
+        }
+        (Some(days), None) => println!("=== Lurk Statistics (last {} days) ===\n", days),
+        (None, Some(hours)) => println!("=== Lurk Statistics ({}) ===\n", hours),
+        (None, None) => println!("=== Lurk Statistics ===\n"),
+    }
 
-    println!("=== Lurk Statistics ===\n");
+    let (total, presses) = (report.total_events, report.presses);
+    if total == 0 && (days.is_some() || hours.is_some()) {
+        println!("No keystrokes recorded in this period.");
+        return false;
+    }
 
     if total == 0 {
         println!("No keystroke data recorded yet.");
         println!("\nMake sure the daemon is running:");
         println!("  launchctl list | grep lurk");
-        return Ok(());
+        return false;
     }
 
     println!("Total Events:     {}", total);
     println!("Key Presses:      {}", presses);
     println!("Key Releases:     {}", total - presses);
 
-    if let Some((start, end)) = db.get_date_range()? {
+    let effective = report.effective();
+    println!(
+        "Effective:        {} ({:.1}% of presses; {} deletes, each assumed to undo one character)",
+        effective.effective(),
+        effective.effective_pct(),
+        effective.deletions
+    );
+
+    if let (Some(start), Some(end)) = (report.first_event, report.last_event) {
         let start_dt = DateTime::from_timestamp_millis(start)
             .unwrap_or_else(|| Utc::now());
         let end_dt = DateTime::from_timestamp_millis(end)
@@ -38,22 +182,134 @@ pub fn show_stats(db: &Database, _days: Option<u32>) -> Result<()> {
         let avg_per_day = presses / days_recorded;
         println!("\nAverage: {} presses/day", avg_per_day);
     }
+    true
+}
 
-    println!("\n--- Top 10 Keys ---");
-    let top_keys = db.get_top_keys(10)?;
-    for (i, (key_code, count)) in top_keys.iter().enumerate() {
-        let key_name = KeyCode(*key_code).to_name();
-        let pct = (*count as f64 / presses as f64) * 100.0;
-        println!("{:2}. {:15} {:>8} ({:.1}%)", i + 1, key_name, count, pct);
+fn print_top_lists(report: &StatsReport, limits: StatsLimits) {
+    let presses = report.presses;
+    println!("\n--- Top {} Keys ---", limits.keys);
+    for (i, key) in report.top_keys.iter().enumerate() {
+        let pct = (key.presses as f64 / presses as f64) * 100.0;
+        println!("{:2}. {:15} {:>8} ({:.1}%)", i + 1, key.key, key.presses, pct);
     }
 
-    println!("\n--- Top 5 Applications ---");
-    let top_apps = db.get_top_applications(5)?;
-    for (i, (app, count)) in top_apps.iter().enumerate() {
-        let app_short = app.split('.').last().unwrap_or(app);
-        let pct = (*count as f64 / presses as f64) * 100.0;
-        println!("{:2}. {:25} {:>8} ({:.1}%)", i + 1, app_short, count, pct);
+    println!("\n--- Top {} Applications ---", limits.apps);
+    for (i, app) in report.top_apps.iter().enumerate() {
+        let app_short = app.application.rsplit('.').next().unwrap_or(&app.application);
+        let pct = (app.presses as f64 / presses as f64) * 100.0;
+        println!("{:2}. {:25} {:>8} ({:.1}%)", i + 1, app_short, app.presses, pct);
     }
+}
+
+/// Days with data versus days elapsed, and the longest stretches of active
+/// hours with nothing recorded, which usually mean the daemon wasn't running.
+fn print_coverage(db: &Database, start: i64) -> Result<()> {
+    let mut scan = CoverageScan::new(Local);
+    db.for_each_timestamp_since(start, |ts| scan.push(ts))?;
+    let report = scan.finish();
 
+    println!("\nCoverage:");
+    println!(
+        "  Captured: {} of {} days ({:.1}%)",
+        report.captured_days,
+        report.elapsed_days,
+        report.coverage_pct()
+    );
+    println!(
+        "  Gaps over {}h in active hours ({:02}:00-{:02}:00): {}",
+        MIN_ACTIVE_GAP_MS / 3_600_000,
+        ACTIVE_HOURS.0,
+        ACTIVE_HOURS.1,
+        report.gaps.len()
+    );
+    for gap in report.gaps.iter().take(MAX_GAPS_SHOWN) {
+        println!(
+            "    {} -> {} ({}h active)",
+            format_local(gap.start),
+            format_local(gap.end),
+            gap.active_ms / 3_600_000
+        );
+    }
     Ok(())
 }
+
+fn format_local(timestamp: i64) -> String {
+    DateTime::from_timestamp_millis(timestamp)
+        .map(|dt| dt.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{in_app, press, release};
+
+    fn seeded_db() -> Database {
+        let db = Database::new(":memory:").unwrap();
+        let seeded = [
+            (0x00, "editor"),
+            (0x33, "editor"),
+            (0x00, "com.app.browser"),
+            (0x02, "chat"),
+        ];
+        for (i, (key_code, app)) in seeded.into_iter().enumerate() {
+            db.insert_event(&in_app(press(1_000 + i as i64, key_code), app)).unwrap();
+        }
+        db.insert_event(&release(1_010, 0x02)).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_report_counts_seeded_events() {
+        let db = seeded_db();
+        let report = StatsReport::collect(&db, None, None, StatsLimits::default()).unwrap();
+        assert_eq!((report.total_events, report.presses, report.deletions), (5, 4, 1));
+        assert_eq!((report.first_event, report.last_event), (Some(1_000), Some(1_010)));
+        assert_eq!(report.effective().effective(), 2);
+        assert_eq!(
+            report.top_keys[0],
+            KeyCount {
+                key_code: 0x00,
+                key: "A".to_string(),
+                presses: 2
+            }
+        );
+        assert_eq!(report.top_apps.len(), 3);
+    }
+
+    #[test]
+    fn test_limits_cap_listed_keys_and_apps() {
+        let db = seeded_db();
+        let limits = StatsLimits { keys: 2, apps: 1 };
+        let report = StatsReport::collect(&db, None, None, limits).unwrap();
+        assert_eq!(report.top_keys.len(), 2);
+        assert_eq!(
+            report.top_apps,
+            vec![AppCount {
+                application: "editor".to_string(),
+                presses: 2
+            }]
+        );
+        show_stats(&db, None, None, limits).unwrap();
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let db = Database::new(":memory:").unwrap();
+        db.insert_event(&press(1_000, 0x0E)).unwrap();
+        let report = StatsReport::collect(&db, None, None, StatsLimits::default()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "total_events": 1,
+                "presses": 1,
+                "deletions": 0,
+                "first_event": 1000,
+                "last_event": 1000,
+                "top_keys": [{"key_code": 14, "key": "E", "presses": 1}],
+                "top_apps": [{"application": "test", "presses": 1}],
+            })
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::storage::Database;
+
+/// Quick health probe for scripts and menu-bar items; `lurk check` is the
+/// thorough one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatusReport {
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub total_events: i64,
+    /// Unix milliseconds of the newest event.
+    pub last_event: Option<i64>,
+    pub db_bytes: u64,
+}
+
+impl StatusReport {
+    /// `db` is `None` when no database has been created yet.
+    pub fn collect(db: Option<&Database>, pid: Option<u32>, db_bytes: u64) -> Result<Self> {
+        let (total_events, last_event) = match db {
+            Some(db) => (db.get_total_count()?, db.get_date_range()?.map(|(_, end)| end)),
+            None => (0, None),
+        };
+        Ok(Self {
+            running: pid.is_some(),
+            pid,
+            total_events,
+            last_event,
+            db_bytes,
+        })
+    }
+
+    /// One line of space-separated `key=value` pairs; missing values print as `-`.
+    pub fn to_line(&self) -> String {
+        format!(
+            "running={} pid={} events={} last_event={} db_bytes={}",
+            self.running,
+            self.pid.map_or_else(|| "-".to_string(), |pid| pid.to_string()),
+            self.total_events,
+            self.last_event
+                .and_then(DateTime::<Utc>::from_timestamp_millis)
+                .map_or_else(|| "-".to_string(), |dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            self.db_bytes,
+        )
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::press;
+
+    #[test]
+    fn test_json_shape() {
+        let db = Database::new(":memory:").unwrap();
+        db.insert_event(&press(1_700_000_000_000, 0x00)).unwrap();
+
+        let report = StatusReport::collect(Some(&db), Some(4242), 8192).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "running": true,
+                "pid": 4242,
+                "total_events": 1,
+                "last_event": 1_700_000_000_000_i64,
+                "db_bytes": 8192,
+            })
+        );
+        assert_eq!(
+            report.to_line(),
+            "running=true pid=4242 events=1 last_event=2023-11-14T22:13:20Z db_bytes=8192"
+        );
+    }
+
+    #[test]
+    fn test_json_without_database_or_daemon() {
+        let report = StatusReport::collect(None, None, 0).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["running"], false);
+        assert!(json["pid"].is_null());
+        assert!(json["last_event"].is_null());
+        assert_eq!(json["total_events"], 0);
+        assert_eq!(report.to_line(), "running=false pid=- events=0 last_event=- db_bytes=0");
+    }
+}
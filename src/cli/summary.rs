@@ -0,0 +1,108 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::analysis::fingers::{finger_loads, hand_balance, home_row_pct};
+use crate::analysis::sessions::typing_wpm;
+use crate::analysis::{FrequencyAnalysis, SameFingerAnalysis, TimingAnalysis};
+use crate::models::keyboard_layout::KeyboardLayout;
+use crate::models::KeystrokeEvent;
+
+/// Headline numbers of `lurk analyze`, printed as one compact JSON object so
+/// a cron job can append a line a day to a metrics log.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnalysisSummary {
+    pub total_presses: u64,
+    /// Over the time spent typing, with pauses longer than the max gap left
+    /// out. `None` if there was no such time.
+    pub wpm: Option<f64>,
+    pub median_inter_key_ms: i64,
+    pub p95_inter_key_ms: i64,
+    pub same_finger_pct: f64,
+    pub left_hand_pct: f64,
+    pub right_hand_pct: f64,
+    pub home_row_pct: f64,
+}
+
+impl AnalysisSummary {
+    /// `events` are the ones `freq` and `timing` were computed from.
+    pub fn new(
+        events: &[KeystrokeEvent],
+        freq: &FrequencyAnalysis,
+        timing: &TimingAnalysis,
+        layout: &dyn KeyboardLayout,
+    ) -> Self {
+        let key_percentages = freq.key_percentages();
+        let (left, right) = hand_balance(&finger_loads(&key_percentages, layout));
+        let same_finger = SameFingerAnalysis::from_bigrams(
+            &freq.bigram_frequencies,
+            layout,
+            timing.filter_config.same_finger_repeats,
+        );
+
+        Self {
+            total_presses: freq.total_presses,
+            wpm: typing_wpm(events, timing.filter_config.max_gap_ms).map(round2),
+            median_inter_key_ms: timing.overall_inter_key.median_ms,
+            p95_inter_key_ms: timing.overall_inter_key.p95_ms,
+            same_finger_pct: round2(same_finger.same_finger_pct()),
+            left_hand_pct: round2(left),
+            right_hand_pct: round2(right),
+            home_row_pct: round2(home_row_pct(&key_percentages, layout)),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Two decimals are plenty for a log and keep the line short.
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::FilterConfig;
+    use crate::models::keyboard_layout::QwertyLayout;
+    use crate::testutil::typed;
+
+    #[test]
+    fn test_json_keys_and_types() {
+        // "asdfjkl;" and "erui" at 100ms a key: home row 8 of 12 presses.
+        let keys = [0x00, 0x01, 0x02, 0x03, 0x26, 0x28, 0x25, 0x29, 0x0E, 0x0F, 0x20, 0x22];
+        let events = typed(&keys, 100);
+        let freq = FrequencyAnalysis::from_events(&events);
+        let timing = TimingAnalysis::from_events(&events, FilterConfig::default());
+
+        let summary = AnalysisSummary::new(&events, &freq, &timing, &QwertyLayout::new());
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json().unwrap()).unwrap();
+        let object = json.as_object().unwrap();
+
+        let mut names: Vec<_> = object.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            [
+                "home_row_pct",
+                "left_hand_pct",
+                "median_inter_key_ms",
+                "p95_inter_key_ms",
+                "right_hand_pct",
+                "same_finger_pct",
+                "total_presses",
+                "wpm",
+            ]
+        );
+        assert_eq!(object["total_presses"].as_u64(), Some(12));
+        assert_eq!(object["median_inter_key_ms"].as_i64(), Some(100));
+        assert_eq!(object["p95_inter_key_ms"].as_i64(), Some(100));
+        // 12 presses over 1.1s.
+        assert_eq!(object["wpm"].as_f64(), Some(round2(12.0 / 5.0 / (1_100.0 / 60_000.0))));
+        assert_eq!(object["home_row_pct"].as_f64(), Some(round2(800.0 / 12.0)));
+        assert_eq!(object["left_hand_pct"].as_f64(), Some(50.0));
+        assert_eq!(object["right_hand_pct"].as_f64(), Some(50.0));
+        assert!(object["same_finger_pct"].is_f64());
+    }
+}
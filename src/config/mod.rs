@@ -0,0 +1,3 @@
+pub mod settings;
+
+pub use settings::{read_bundle_list, AppMode, Config, DashboardConfig, OVERRIDE_KEYS};
@@ -0,0 +1,484 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::analysis::histogram::HistogramEdges;
+use crate::analysis::{FilterConfig, FingerTargets, LayoutScoreWeights};
+use crate::models::keyboard_layout::LAYOUT_NAMES;
+use crate::models::keycode::KeyNaming;
+
+/// Keys accepted by `lurk config set`. Values stored in the database take
+/// precedence over the config file.
+pub const OVERRIDE_KEYS: &[&str] = &[
+    "retention_days",
+    "filter.max_gap_ms",
+    "filter.min_hold_ms",
+    "filter.max_hold_ms",
+    "filter.min_pair_samples",
+    "filter.min_ngram_count",
+    "filter.low_confidence_samples",
+    "filter.same_finger_repeats",
+    "filter.letters_only_ngrams",
+];
+
+/// Written on first run: every setting, commented out at its default.
+pub const DEFAULT_CONFIG: &str = include_str!("default.toml");
+
+/// Settings loaded from `~/.lurk/config.toml`. Missing fields take their defaults,
+/// so a config file only needs the values it changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub filter: FilterConfig,
+    /// Delete events older than this many days when the daemon starts. `None` keeps everything.
+    pub retention_days: Option<u32>,
+    pub layout: String,
+    /// Legends used for key names: `us`, `azerty` or `qwertz`. Unset, keys
+    /// are named after the input source they were typed with, falling back
+    /// to US legends.
+    pub key_names: Option<KeyNaming>,
+    pub capture: CaptureConfig,
+    pub dashboard: DashboardConfig,
+    /// Weights of the components of the layout fit score.
+    pub layout_score: LayoutScoreWeights,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DashboardConfig {
+    /// Only count character-producing keys in the heatmap and finger stats. Toggled with `c`.
+    pub character_keys_only: bool,
+    /// Ideal per-finger load the Fingers view compares against.
+    pub finger_targets: FingerTargets,
+    /// Presses per day shown as a progress gauge in the Overview. Unset hides it.
+    pub daily_goal: Option<u64>,
+    /// Frequency heatmap reaches full color at this percentile of key
+    /// frequencies rather than at the busiest key. Unset uses the busiest key.
+    pub heatmap_cap_percentile: Option<f64>,
+    /// Lower bucket edges in ms for the Timing view's inter-key histogram,
+    /// strictly ascending. Unset uses `DEFAULT_HISTOGRAM_EDGES`.
+    pub timing_histogram_edges: Option<Vec<i64>>,
+    /// Most events the dashboard loads into memory for a time range. Larger
+    /// ranges load only the latest this many and take counts from SQL.
+    /// Unset uses `DEFAULT_MAX_CACHED_EVENTS`.
+    pub max_cached_events: Option<usize>,
+}
+
+/// What the daemon stores for keystrokes typed in a given app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppMode {
+    /// Nothing is stored.
+    Skip,
+    /// Events are stored with the key replaced by `KeyCode::REDACTED`, so
+    /// counts and timing survive but not what was typed.
+    Redact,
+    Record,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    /// Release events are needed for hold durations; disabling roughly halves storage.
+    pub record_releases: bool,
+    pub record_window_titles: bool,
+    /// Store the text each press produced, as resolved by the active input source.
+    pub record_characters: bool,
+    pub block_sensitive_titles: bool,
+    pub title_blocklist: Vec<String>,
+    /// File of extra bundle IDs to skip, one per line (`#` starts a comment).
+    pub app_blocklist_path: Option<PathBuf>,
+    /// File of bundle IDs to record even though they're on the built-in blocklist.
+    pub app_allowlist_path: Option<PathBuf>,
+    /// Per-app mode by bundle ID (`skip`, `redact` or `record`), overriding the
+    /// blocklists and allowlist, e.g. `"com.apple.Terminal" = "redact"`.
+    pub app_modes: BTreeMap<String, AppMode>,
+    /// Store only one press in this many (with its release). Key frequencies
+    /// stay roughly proportional, but inter-key timing and n-grams are lost.
+    pub sample_every: Option<u32>,
+    /// Store at most this many presses per second. Trims bursts only, which
+    /// skews frequencies and timing toward slower typing.
+    pub max_events_per_second: Option<u32>,
+    /// Drop a press arriving less than this many ms after a press of the same
+    /// key, for keyboards that report one keystroke twice. Off when unset or 0.
+    pub debounce_ms: Option<u64>,
+    /// After this many minutes without any input, check that Input Monitoring
+    /// is still granted and stop with an error if not. 0 disables the check.
+    pub watchdog_idle_minutes: Option<u64>,
+    /// Show a macOS notification when the press total passes 1M, 5M, 10M, ...
+    /// Milestones are logged either way.
+    pub milestone_notifications: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            filter: FilterConfig::default(),
+            retention_days: None,
+            layout: "qwerty".to_string(),
+            key_names: None,
+            capture: CaptureConfig::default(),
+            dashboard: DashboardConfig::default(),
+            layout_score: LayoutScoreWeights::default(),
+        }
+    }
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            record_releases: true,
+            record_window_titles: false,
+            record_characters: false,
+            block_sensitive_titles: false,
+            title_blocklist: Vec::new(),
+            app_blocklist_path: None,
+            app_allowlist_path: None,
+            app_modes: BTreeMap::new(),
+            sample_every: None,
+            max_events_per_second: None,
+            debounce_ms: None,
+            watchdog_idle_minutes: Some(15),
+            milestone_notifications: false,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config at `path`, writing the defaults there first if it doesn't exist.
+    pub fn load_or_init(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            write_private(path, DEFAULT_CONFIG)?;
+            return Ok(Self::default());
+        }
+
+        Self::load(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let config: Self = toml::from_str(&contents)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Writes the config to `path`, keeping the comments and layout of the
+    /// file already there (or of `DEFAULT_CONFIG`) and changing only values.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let existing = fs::read_to_string(path).ok();
+        let mut document = existing
+            .and_then(|contents| contents.parse::<DocumentMut>().ok())
+            .unwrap_or_else(|| DEFAULT_CONFIG.parse().expect("default config is valid TOML"));
+        let values: DocumentMut = toml::to_string(self)?.parse()?;
+        merge_values(document.as_table_mut(), values.as_table());
+        write_private(path, &document.to_string())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !LAYOUT_NAMES.contains(&self.layout.as_str()) {
+            return Err(anyhow!(
+                "Unknown layout '{}'. Supported: {}",
+                self.layout,
+                LAYOUT_NAMES.join(", ")
+            ));
+        }
+        if self.filter.max_gap_ms <= 0 {
+            return Err(anyhow!("filter.max_gap_ms must be positive"));
+        }
+        if self.filter.min_hold_ms > self.filter.max_hold_ms {
+            return Err(anyhow!("filter.min_hold_ms must not exceed filter.max_hold_ms"));
+        }
+        if self.capture.max_events_per_second == Some(0) {
+            return Err(anyhow!(
+                "capture.max_events_per_second must be positive; leave it unset for no cap"
+            ));
+        }
+        self.layout_score.validate().context("Invalid layout_score")?;
+        if let Some(edges) = &self.dashboard.timing_histogram_edges {
+            HistogramEdges::new(edges.clone()).context("Invalid dashboard.timing_histogram_edges")?;
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Applies a single key/value override, as stored by `lurk config set`.
+    /// `retention_days` accepts `none` to keep everything.
+    pub fn apply_override(&mut self, key: &str, value: &str) -> Result<()> {
+        self.set_override(key, value)?;
+        self.validate()
+    }
+
+    /// Applies stored overrides, validating once at the end so the order
+    /// rows come back in doesn't matter.
+    pub fn with_overrides(mut self, overrides: &[(String, String)]) -> Result<Self> {
+        for (key, value) in overrides {
+            self.set_override(key, value)
+                .with_context(|| format!("Invalid stored config value for '{}'", key))?;
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    fn set_override(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        match key {
+            "retention_days" => {
+                self.retention_days = if value.eq_ignore_ascii_case("none") {
+                    None
+                } else {
+                    Some(parse_value(key, value)?)
+                };
+            }
+            "filter.max_gap_ms" => self.filter.max_gap_ms = parse_value(key, value)?,
+            "filter.min_hold_ms" => self.filter.min_hold_ms = parse_value(key, value)?,
+            "filter.max_hold_ms" => self.filter.max_hold_ms = parse_value(key, value)?,
+            "filter.min_pair_samples" => self.filter.min_pair_samples = parse_value(key, value)?,
+            "filter.min_ngram_count" => self.filter.min_ngram_count = parse_value(key, value)?,
+            "filter.low_confidence_samples" => {
+                self.filter.low_confidence_samples = parse_value(key, value)?
+            }
+            "filter.same_finger_repeats" => {
+                self.filter.same_finger_repeats = parse_value(key, value)?
+            }
+            "filter.letters_only_ngrams" => {
+                self.filter.letters_only_ngrams = parse_value(key, value)?
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unknown config key '{}'. Known keys: {}",
+                    key,
+                    OVERRIDE_KEYS.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Current value of an override key, formatted the way `apply_override` accepts it.
+    pub fn override_value(&self, key: &str) -> Option<String> {
+        match key {
+            "retention_days" => Some(
+                self.retention_days
+                    .map_or_else(|| "none".to_string(), |days| days.to_string()),
+            ),
+            "filter.max_gap_ms" => Some(self.filter.max_gap_ms.to_string()),
+            "filter.min_hold_ms" => Some(self.filter.min_hold_ms.to_string()),
+            "filter.max_hold_ms" => Some(self.filter.max_hold_ms.to_string()),
+            "filter.min_pair_samples" => Some(self.filter.min_pair_samples.to_string()),
+            "filter.min_ngram_count" => Some(self.filter.min_ngram_count.to_string()),
+            "filter.low_confidence_samples" => Some(self.filter.low_confidence_samples.to_string()),
+            "filter.same_finger_repeats" => Some(self.filter.same_finger_repeats.to_string()),
+            "filter.letters_only_ngrams" => Some(self.filter.letters_only_ngrams.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Copies `source` into `target` table by table, so comments attached to
+/// keys and sections in `target` survive. Keys missing from `source` (unset
+/// options) are removed.
+fn merge_values(target: &mut Table, source: &Table) {
+    target.retain(|key, _| source.contains_key(key));
+    for (key, item) in source.iter() {
+        match (target.get_mut(key), item) {
+            (Some(Item::Table(existing)), Item::Table(table)) => merge_values(existing, table),
+            (Some(Item::Value(existing)), Item::Value(value)) => {
+                let decor = existing.decor().clone();
+                *existing = value.clone();
+                *existing.decor_mut() = decor;
+            }
+            _ => {
+                target.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+/// Writes a file only the owner can read; the config may list the apps and
+/// window titles someone wants kept out of the log.
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to write config file {}", path.display()))?;
+    // `mode` only applies to new files.
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+fn parse_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid value '{}' for {}", value, key))
+}
+
+/// Reads a bundle-ID list file: one ID per line, blank lines and `#` comments ignored.
+pub fn read_bundle_list(path: &Path) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read bundle list {}", path.display()))?;
+
+    Ok(contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+        assert_eq!(config.filter.max_gap_ms, 5000);
+        assert_eq!(config.retention_days, None);
+        assert_eq!(config.layout, "qwerty");
+        assert_eq!(config.key_names, None);
+        assert!(config.capture.record_releases);
+        assert!(!config.capture.record_window_titles);
+        assert!(!config.capture.record_characters);
+        assert!(!config.dashboard.character_keys_only);
+        assert_eq!(config.dashboard.finger_targets, FingerTargets::default());
+    }
+
+    #[test]
+    fn test_partial_config_uses_defaults() {
+        let config: Config =
+            toml::from_str("retention_days = 30\n[filter]\nmax_gap_ms = 2000\n").unwrap();
+
+        assert_eq!(config.retention_days, Some(30));
+        assert_eq!(config.filter.max_gap_ms, 2000);
+        assert_eq!(config.filter.max_hold_ms, 2000);
+        assert_eq!(config.layout, "qwerty");
+        assert!(config.capture.record_releases);
+    }
+
+    #[test]
+    fn test_load_or_init_writes_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let config = Config::load_or_init(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), DEFAULT_CONFIG);
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(config.layout, "qwerty");
+
+        let reloaded = Config::load(&path).unwrap();
+        assert_eq!(reloaded.filter.max_gap_ms, config.filter.max_gap_ms);
+    }
+
+    #[test]
+    fn test_default_config_file_documents_the_defaults() {
+        let json = |config: &Config| serde_json::to_value(config).unwrap();
+        let commented: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(json(&commented), json(&Config::default()));
+
+        // Uncommenting every `# key = value` line must not change anything either.
+        let uncommented: String = DEFAULT_CONFIG
+            .lines()
+            .map(|line| match line.strip_prefix("# ") {
+                Some(setting) if setting.split_once(" = ").is_some_and(|(key, _)| {
+                    key.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+                }) => setting,
+                _ => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let uncommented: Config = toml::from_str(&uncommented).unwrap();
+        assert_eq!(json(&uncommented), json(&Config::default()));
+    }
+
+    #[test]
+    fn test_save_keeps_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "# mine\nlayout = \"ortho\" # split soon\n[filter]\nmax_gap_ms = 3000\n")
+            .unwrap();
+
+        let mut config = Config::load(&path).unwrap();
+        config.filter.max_gap_ms = 4000;
+        config.dashboard.character_keys_only = true;
+        config.save(&path).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.starts_with("# mine\nlayout = \"ortho\" # split soon\n"));
+        assert!(saved.contains("max_gap_ms = 4000"));
+        let reloaded = Config::load(&path).unwrap();
+        assert_eq!(reloaded.layout, "ortho");
+        assert!(reloaded.dashboard.character_keys_only);
+    }
+
+    #[test]
+    fn test_key_names_parsed() {
+        let config: Config = toml::from_str(r#"key_names = "azerty""#).unwrap();
+        assert_eq!(config.key_names, Some(KeyNaming::Azerty));
+    }
+
+    #[test]
+    fn test_invalid_layout_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, r#"layout = "klingon""#).unwrap();
+
+        assert!(Config::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_zero_rate_cap_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[capture]\nmax_events_per_second = 0\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        fs::write(&path, "[capture]\nmax_events_per_second = 50\n").unwrap();
+        assert_eq!(Config::load(&path).unwrap().capture.max_events_per_second, Some(50));
+    }
+
+    #[test]
+    fn test_apply_override() {
+        let mut config = Config::default();
+        config.apply_override("retention_days", "30").unwrap();
+        config.apply_override("filter.max_gap_ms", "2500").unwrap();
+        assert_eq!(config.retention_days, Some(30));
+        assert_eq!(config.filter.max_gap_ms, 2500);
+        assert_eq!(config.override_value("filter.max_gap_ms").as_deref(), Some("2500"));
+
+        config.apply_override("retention_days", "none").unwrap();
+        assert_eq!(config.retention_days, None);
+    }
+
+    #[test]
+    fn test_apply_override_rejects_bad_input() {
+        let mut config = Config::default();
+        assert!(config.apply_override("layout", "qwerty").is_err());
+        assert!(config.apply_override("filter.max_gap_ms", "soon").is_err());
+        assert!(config.apply_override("filter.min_hold_ms", "9999").is_err());
+    }
+
+    #[test]
+    fn test_read_bundle_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allow.txt");
+        fs::write(&path, "# editors\ncom.microsoft.VSCode\n\ncom.apple.Terminal  # shell\n").unwrap();
+
+        let list = read_bundle_list(&path).unwrap();
+        assert_eq!(list, vec!["com.microsoft.VSCode", "com.apple.Terminal"]);
+    }
+}
@@ -1,65 +1,275 @@
 #![allow(deprecated)]
 
 use cocoa::base::{id, nil};
+use core_foundation::base::{CFGetTypeID, CFRelease, CFTypeRef, TCFType};
+use core_foundation::string::{CFString, CFStringRef};
 use objc::{class, msg_send, sel, sel_impl};
-use std::sync::{Arc, RwLock};
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+type AXUIElementRef = *const c_void;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+unsafe extern "C" {
+    fn AXUIElementCreateApplication(pid: libc::pid_t) -> AXUIElementRef;
+    fn AXUIElementCopyAttributeValue(
+        element: AXUIElementRef,
+        attribute: CFStringRef,
+        value: *mut CFTypeRef,
+    ) -> i32;
+}
+
+#[link(name = "Carbon", kind = "framework")]
+unsafe extern "C" {
+    fn TISCopyCurrentKeyboardInputSource() -> CFTypeRef;
+    fn TISGetInputSourceProperty(source: CFTypeRef, key: CFStringRef) -> CFTypeRef;
+    static kTISPropertyInputSourceID: CFStringRef;
+}
+
+const K_AX_ERROR_SUCCESS: i32 = 0;
+
+/// Poll interval while keystrokes are arriving.
+const ACTIVE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// With no keystrokes for this long, polling backs off exponentially.
+const IDLE_AFTER: Duration = Duration::from_secs(10);
+
+const MAX_IDLE_POLL_INTERVAL: Duration = Duration::from_secs(8);
+
+/// A snapshot older than this is re-queried on the event path. During typing
+/// the poller keeps it fresher than this, so only the first keystrokes after
+/// an idle period pay for a synchronous lookup.
+const STALE_AFTER: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct FrontmostApp {
+    pub bundle_id: String,
+    pub window_title: Option<String>,
+    /// Input source ID such as `com.apple.keylayout.US`; only filled in by
+    /// `AppTracker::snapshot`.
+    pub input_source: Option<String>,
+    captured_at: Instant,
+}
+
+impl FrontmostApp {
+    pub fn new(bundle_id: &str, window_title: Option<String>) -> Self {
+        Self {
+            bundle_id: bundle_id.to_string(),
+            window_title,
+            input_source: None,
+            captured_at: Instant::now(),
+        }
+    }
+}
+
+struct TrackerState {
+    current: RwLock<FrontmostApp>,
+    started: Instant,
+    last_activity_ms: AtomicU64,
+}
+
+impl TrackerState {
+    fn mark_activity(&self) {
+        let now_ms = self.started.elapsed().as_millis() as u64;
+        self.last_activity_ms.store(now_ms, Ordering::Relaxed);
+    }
+
+    fn idle_for(&self) -> Duration {
+        let now_ms = self.started.elapsed().as_millis() as u64;
+        let last_ms = self.last_activity_ms.load(Ordering::Relaxed);
+        Duration::from_millis(now_ms.saturating_sub(last_ms))
+    }
+}
 
 pub struct AppTracker {
-    current_app: Arc<RwLock<String>>,
+    state: Arc<TrackerState>,
+    track_titles: bool,
+    /// Last input source looked up and when. Text Input Sources calls must
+    /// run on the main thread, where keystrokes arrive, so unlike the app
+    /// this isn't polled in the background.
+    input_source: Mutex<Option<(Instant, Option<String>)>>,
 }
 
 impl AppTracker {
     pub fn new() -> Self {
-        let initial_app = Self::get_frontmost_app_internal();
-        let current_app = Arc::new(RwLock::new(initial_app));
-
-        let current_app_clone = Arc::clone(&current_app);
-        thread::spawn(move || loop {
-            let app = Self::get_frontmost_app_internal();
-            if let Ok(mut current) = current_app_clone.write() {
-                *current = app;
+        Self::spawn(false)
+    }
+
+    /// Like `new`, but also polls the focused window title through the
+    /// Accessibility API. Titles are `None` when access hasn't been granted.
+    pub fn with_window_titles() -> Self {
+        Self::spawn(true)
+    }
+
+    fn spawn(track_titles: bool) -> Self {
+        let state = Arc::new(TrackerState {
+            current: RwLock::new(Self::get_frontmost_app_internal(track_titles)),
+            started: Instant::now(),
+            last_activity_ms: AtomicU64::new(0),
+        });
+
+        let poller_state = Arc::clone(&state);
+        thread::spawn(move || {
+            let mut interval = ACTIVE_POLL_INTERVAL;
+            loop {
+                let app = Self::get_frontmost_app_internal(track_titles);
+                if let Ok(mut current) = poller_state.current.write() {
+                    *current = app;
+                }
+                interval = next_poll_interval(interval, poller_state.idle_for());
+                thread::sleep(interval);
             }
-            thread::sleep(Duration::from_millis(500));
         });
 
-        Self { current_app }
+        Self {
+            state,
+            track_titles,
+            input_source: Mutex::new(None),
+        }
+    }
+
+    /// Returns the frontmost app and window title as one consistent pair,
+    /// refreshing inline if the polled value is stale, plus the current input
+    /// source. Each call counts as keyboard activity and keeps the poller at
+    /// its active rate. Call it from the thread receiving keystrokes.
+    pub fn snapshot(&self) -> FrontmostApp {
+        self.state.mark_activity();
+        FrontmostApp {
+            input_source: self.input_source(),
+            ..self.frontmost()
+        }
     }
 
-    pub fn get_current_app(&self) -> String {
-        self.current_app
-            .read()
-            .map(|app| app.clone())
-            .unwrap_or_else(|_| "Unknown".to_string())
+    fn frontmost(&self) -> FrontmostApp {
+        if let Ok(current) = self.state.current.read() {
+            if current.captured_at.elapsed() < STALE_AFTER {
+                return current.clone();
+            }
+        }
+
+        let fresh = Self::get_frontmost_app_internal(self.track_titles);
+        if let Ok(mut current) = self.state.current.write() {
+            *current = fresh.clone();
+        }
+        fresh
     }
 
-    fn get_frontmost_app_internal() -> String {
+    /// The current input source, looked up again once the cached one is
+    /// older than `STALE_AFTER`.
+    fn input_source(&self) -> Option<String> {
+        let Ok(mut cached) = self.input_source.lock() else {
+            return current_input_source();
+        };
+        match &*cached {
+            Some((at, source)) if at.elapsed() < STALE_AFTER => source.clone(),
+            _ => {
+                let source = current_input_source();
+                *cached = Some((Instant::now(), source.clone()));
+                source
+            }
+        }
+    }
+
+    fn get_frontmost_app_internal(track_titles: bool) -> FrontmostApp {
         unsafe {
+            let unknown = FrontmostApp::new("Unknown", None);
+
             let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
             if workspace == nil {
-                return "Unknown".to_string();
+                return unknown;
             }
 
             let frontmost_app: id = msg_send![workspace, frontmostApplication];
             if frontmost_app == nil {
-                return "Unknown".to_string();
+                return unknown;
             }
 
+            let window_title = if track_titles {
+                let pid: libc::pid_t = msg_send![frontmost_app, processIdentifier];
+                Self::get_focused_window_title(pid)
+            } else {
+                None
+            };
+
             let bundle_id: id = msg_send![frontmost_app, bundleIdentifier];
             if bundle_id == nil {
-                return "Unknown".to_string();
+                return FrontmostApp {
+                    window_title,
+                    ..unknown
+                };
             }
 
             let utf8: *const libc::c_char = msg_send![bundle_id, UTF8String];
             if utf8.is_null() {
-                return "Unknown".to_string();
+                return FrontmostApp {
+                    window_title,
+                    ..unknown
+                };
+            }
+
+            FrontmostApp {
+                bundle_id: std::ffi::CStr::from_ptr(utf8)
+                    .to_string_lossy()
+                    .into_owned(),
+                window_title,
+                ..unknown
+            }
+        }
+    }
+
+    fn get_focused_window_title(pid: libc::pid_t) -> Option<String> {
+        unsafe {
+            let app = AXUIElementCreateApplication(pid);
+            if app.is_null() {
+                return None;
             }
 
-            std::ffi::CStr::from_ptr(utf8)
-                .to_string_lossy()
-                .into_owned()
+            let window = Self::copy_attribute(app, "AXFocusedWindow");
+            CFRelease(app as CFTypeRef);
+            let window = window?;
+
+            let title = Self::copy_attribute(window as AXUIElementRef, "AXTitle");
+            CFRelease(window);
+            let title = title?;
+
+            if CFGetTypeID(title) != CFString::type_id() {
+                CFRelease(title);
+                return None;
+            }
+
+            Some(CFString::wrap_under_create_rule(title as CFStringRef).to_string())
+        }
+    }
+
+    unsafe fn copy_attribute(element: AXUIElementRef, name: &str) -> Option<CFTypeRef> {
+        let attribute = CFString::new(name);
+        let mut value: CFTypeRef = std::ptr::null();
+        let result =
+            AXUIElementCopyAttributeValue(element, attribute.as_concrete_TypeRef(), &mut value);
+
+        if result != K_AX_ERROR_SUCCESS || value.is_null() {
+            return None;
         }
+        Some(value)
+    }
+}
+
+/// ID of the keyboard input source in use, e.g. `com.apple.keylayout.German`.
+fn current_input_source() -> Option<String> {
+    unsafe {
+        let source = TISCopyCurrentKeyboardInputSource();
+        if source.is_null() {
+            return None;
+        }
+        // Owned by `source`, so not released separately.
+        let id = TISGetInputSourceProperty(source, kTISPropertyInputSourceID);
+        let id = (!id.is_null())
+            .then(|| CFString::wrap_under_get_rule(id as CFStringRef).to_string());
+        CFRelease(source);
+        id
     }
 }
 
@@ -68,3 +278,35 @@ impl Default for AppTracker {
         Self::new()
     }
 }
+
+fn next_poll_interval(current: Duration, idle_for: Duration) -> Duration {
+    if idle_for < IDLE_AFTER {
+        ACTIVE_POLL_INTERVAL
+    } else {
+        (current * 2).min(MAX_IDLE_POLL_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_interval_stays_active_while_typing() {
+        let interval = next_poll_interval(MAX_IDLE_POLL_INTERVAL, Duration::from_secs(1));
+        assert_eq!(interval, ACTIVE_POLL_INTERVAL);
+    }
+
+    #[test]
+    fn test_poll_interval_backs_off_when_idle() {
+        let idle = IDLE_AFTER + Duration::from_secs(1);
+        let first = next_poll_interval(ACTIVE_POLL_INTERVAL, idle);
+        assert_eq!(first, ACTIVE_POLL_INTERVAL * 2);
+
+        let mut interval = first;
+        for _ in 0..10 {
+            interval = next_poll_interval(interval, idle);
+        }
+        assert_eq!(interval, MAX_IDLE_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::keycode::KeyCode;
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Drops phantom double presses: some keyboards and drivers report one
+/// keystroke as two presses of the same key a millisecond or so apart.
+///
+/// A press arriving within `window_ms` of the previous press of the same key
+/// code is dropped. OS key repeat comes at 30ms and slower, after a longer
+/// initial delay, so a window of a few ms leaves it alone. If the phantom came
+/// with its own release, that release is dropped too, so the stored key still
+/// has one press and one release.
+///
+/// Redacted events all share one key code, so they're never debounced.
+#[derive(Debug, Default)]
+pub struct Debouncer {
+    window_ms: Option<u64>,
+    last_press: HashMap<u32, i64>,
+    /// Keys currently held down, as far as kept events tell.
+    down: HashSet<u32>,
+    /// Keys whose next release belongs to a dropped press. Cleared by the
+    /// next kept press of the key, in case that release never came.
+    dropped_down: HashSet<u32>,
+    suppressed: u64,
+}
+
+impl Debouncer {
+    /// `None` or 0 lets every event through.
+    pub fn new(window_ms: Option<u64>) -> Self {
+        Self {
+            window_ms: window_ms.filter(|&ms| ms > 0),
+            ..Self::default()
+        }
+    }
+
+    pub fn keep(&mut self, event: &KeystrokeEvent) -> bool {
+        let Some(window_ms) = self.window_ms else {
+            return true;
+        };
+        if event.key_code == KeyCode::REDACTED.0 {
+            return true;
+        }
+        match event.event_type {
+            EventType::Release => {
+                if self.dropped_down.remove(&event.key_code) {
+                    return false;
+                }
+                self.down.remove(&event.key_code);
+                true
+            }
+            EventType::Press => {
+                let previous = self.last_press.get(&event.key_code);
+                if previous.is_some_and(|&at| event.timestamp.abs_diff(at) < window_ms) {
+                    self.suppressed += 1;
+                    if !self.down.contains(&event.key_code) {
+                        self.dropped_down.insert(event.key_code);
+                    }
+                    return false;
+                }
+                self.last_press.insert(event.key_code, event.timestamp);
+                self.down.insert(event.key_code);
+                self.dropped_down.remove(&event.key_code);
+                true
+            }
+        }
+    }
+
+    /// Presses dropped so far.
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{press, release};
+
+    fn kept(debouncer: &mut Debouncer, events: &[KeystrokeEvent]) -> Vec<KeystrokeEvent> {
+        events.iter().filter(|e| debouncer.keep(e)).cloned().collect()
+    }
+
+    #[test]
+    fn test_press_one_ms_after_the_last_is_dropped() {
+        let events = [press(1_000, 0x0E), press(1_001, 0x0E), release(1_080, 0x0E)];
+
+        let mut debouncer = Debouncer::new(Some(5));
+        assert_eq!(kept(&mut debouncer, &events), [press(1_000, 0x0E), release(1_080, 0x0E)]);
+        assert_eq!(debouncer.suppressed(), 1);
+
+        let mut off = Debouncer::new(None);
+        assert_eq!(kept(&mut off, &events).len(), 3);
+        assert_eq!(off.suppressed(), 0);
+    }
+
+    #[test]
+    fn test_phantom_release_and_other_keys_are_handled() {
+        let events = [
+            press(1_000, 0x0E),
+            release(1_001, 0x0E),
+            press(1_002, 0x0E),
+            press(1_002, 0x0F),
+            release(1_090, 0x0E),
+            press(1_200, 0x0E),
+            release(1_280, 0x0E),
+        ];
+        let mut debouncer = Debouncer::new(Some(5));
+        assert_eq!(
+            kept(&mut debouncer, &events),
+            [
+                press(1_000, 0x0E),
+                release(1_001, 0x0E),
+                press(1_002, 0x0F),
+                press(1_200, 0x0E),
+                release(1_280, 0x0E),
+            ]
+        );
+        assert_eq!(debouncer.suppressed(), 1);
+    }
+
+    #[test]
+    fn test_phantom_without_release_keeps_the_next_release() {
+        let events = [
+            press(1_000, 0x0E),
+            release(1_001, 0x0E),
+            press(1_002, 0x0E),
+            press(1_200, 0x0E),
+            release(1_280, 0x0E),
+        ];
+        let mut debouncer = Debouncer::new(Some(5));
+        assert_eq!(
+            kept(&mut debouncer, &events),
+            [press(1_000, 0x0E), release(1_001, 0x0E), press(1_200, 0x0E), release(1_280, 0x0E)]
+        );
+    }
+}
@@ -1,9 +1,14 @@
 use anyhow::Result;
 use rdev::{listen, Event, EventType, Key};
-use std::sync::mpsc::Sender;
-use tracing::{debug, error, trace};
+use std::collections::{BTreeMap, HashSet};
+use tracing::{debug, error, info, trace, warn};
 
-use crate::daemon::app_tracker::AppTracker;
+use crate::config::AppMode;
+use crate::daemon::app_tracker::{AppTracker, FrontmostApp};
+use crate::daemon::debounce::Debouncer;
+use crate::daemon::sampler::{Sampler, SamplingConfig};
+use crate::daemon::watchdog::Heartbeat;
+use crate::daemon::writer::EventSender;
 use crate::models::event::{EventType as KEventType, Modifier};
 use crate::models::keycode::KeyCode;
 use crate::models::KeystrokeEvent;
@@ -45,25 +50,143 @@ const SENSITIVE_APP_BLOCKLIST: &[&str] = &[
     "com.expressvpn.ExpressVPN",
 ];
 
+/// After the first, suppressed duplicate presses are logged every this many.
+const DEBOUNCE_REPORT_EVERY: u64 = 100;
+
+/// Window-title substrings that suggest sensitive input inside an otherwise allowed app,
+/// e.g. a password manager browser popup or a bank login page.
+pub const DEFAULT_SENSITIVE_TITLE_KEYWORDS: &[&str] = &[
+    "password",
+    "passcode",
+    "login",
+    "log in",
+    "sign in",
+    "sign-in",
+    "verification code",
+    "two-factor",
+    "2fa",
+    "credit card",
+    "bank",
+];
+
+#[derive(Debug, Clone)]
+pub struct MonitorConfig {
+    /// Skip events while the focused window title contains any of these (case-insensitive).
+    /// Empty disables title checks entirely, so titles are never read.
+    pub title_blocklist: Vec<String>,
+    /// Store the focused window title with each event.
+    pub record_window_titles: bool,
+    /// Store the text each press produced (`rdev`'s `Event::name`), which
+    /// follows the user's input source rather than the US key names.
+    pub record_characters: bool,
+    pub record_releases: bool,
+    /// Bundle IDs skipped in addition to `SENSITIVE_APP_BLOCKLIST`.
+    pub app_blocklist: Vec<String>,
+    /// Bundle IDs recorded even if they appear on a blocklist.
+    pub app_allowlist: Vec<String>,
+    /// Per-app modes by bundle ID; these win over both lists.
+    pub app_modes: BTreeMap<String, AppMode>,
+    /// Thins events before they are sent on; see `SamplingConfig`.
+    pub sampling: SamplingConfig,
+    /// Drop a press less than this many ms after one of the same key; see `Debouncer`.
+    pub debounce_ms: Option<u64>,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            title_blocklist: Vec::new(),
+            record_window_titles: false,
+            record_characters: false,
+            record_releases: true,
+            app_blocklist: Vec::new(),
+            app_allowlist: Vec::new(),
+            app_modes: BTreeMap::new(),
+            sampling: SamplingConfig::default(),
+            debounce_ms: None,
+        }
+    }
+}
+
 pub struct EventMonitor {
     app_tracker: AppTracker,
-    event_sender: Sender<KeystrokeEvent>,
+    event_sender: EventSender,
+    config: MonitorConfig,
+    heartbeat: Heartbeat,
 }
 
 impl EventMonitor {
-    pub fn new(event_sender: Sender<KeystrokeEvent>) -> Self {
+    pub fn new(event_sender: EventSender, mut config: MonitorConfig) -> Self {
+        config.title_blocklist = config
+            .title_blocklist
+            .iter()
+            .map(|keyword| keyword.to_lowercase())
+            .collect();
+
+        let app_tracker = if config.title_blocklist.is_empty() && !config.record_window_titles {
+            AppTracker::new()
+        } else {
+            AppTracker::with_window_titles()
+        };
+
         Self {
-            app_tracker: AppTracker::new(),
+            app_tracker,
             event_sender,
+            config,
+            heartbeat: Heartbeat::new(),
         }
     }
 
+    /// Beats on every event the listener delivers, blocked or not, so a
+    /// `Watchdog` can tell a stalled listener from one whose events are filtered.
+    pub fn heartbeat(&self) -> Heartbeat {
+        self.heartbeat.clone()
+    }
+
     pub fn start(self) -> Result<()> {
         let app_tracker = self.app_tracker;
         let event_sender = self.event_sender;
+        let config = self.config;
+        let heartbeat = self.heartbeat;
+
+        // Key codes already reported as unnamed, so each is logged once.
+        let mut unnamed_seen = HashSet::new();
+        // Command/Control keys currently down; presses under them are shortcuts, not text.
+        let mut shortcut_keys: HashSet<Key> = HashSet::new();
+        let mut sampler = Sampler::new(config.sampling);
+        let mut debouncer = Debouncer::new(config.debounce_ms);
 
         listen(move |event: Event| {
-            if let Some(keystroke) = Self::process_event(&event, &app_tracker) {
+            heartbeat.beat();
+
+            match event.event_type {
+                EventType::KeyPress(key) if Self::is_shortcut_modifier(&key) => {
+                    shortcut_keys.insert(key);
+                }
+                EventType::KeyRelease(key) => {
+                    shortcut_keys.remove(&key);
+                }
+                _ => {}
+            }
+
+            let shortcut_active = !shortcut_keys.is_empty();
+            let keystroke =
+                Self::process_event(&event, || app_tracker.snapshot(), &config, shortcut_active);
+            let keystroke = keystroke.filter(|k| {
+                let keep = debouncer.keep(k);
+                let suppressed = debouncer.suppressed();
+                if !keep && (suppressed == 1 || suppressed.is_multiple_of(DEBOUNCE_REPORT_EVERY)) {
+                    info!("Suppressed {} duplicate presses so far", suppressed);
+                }
+                keep
+            });
+            if let Some(keystroke) = keystroke.filter(|k| sampler.keep(k)) {
+                if !KeyCode(keystroke.key_code).is_named() && unnamed_seen.insert(keystroke.key_code) {
+                    warn!(
+                        "Unrecognized key code 0x{:02X}; it will show as Unknown in stats",
+                        keystroke.key_code
+                    );
+                }
                 if let Err(e) = event_sender.send(keystroke) {
                     error!("Failed to send event: {}", e);
                 }
@@ -72,39 +195,140 @@ impl EventMonitor {
         .map_err(|e| anyhow::anyhow!("Failed to start event listener: {:?}", e))
     }
 
-    fn process_event(event: &Event, app_tracker: &AppTracker) -> Option<KeystrokeEvent> {
+    /// `frontmost` is only called for events that could be recorded.
+    fn process_event(
+        event: &Event,
+        frontmost: impl FnOnce() -> FrontmostApp,
+        config: &MonitorConfig,
+        shortcut_active: bool,
+    ) -> Option<KeystrokeEvent> {
         let (key, event_type) = match &event.event_type {
             EventType::KeyPress(key) => (key, KEventType::Press),
             EventType::KeyRelease(key) => (key, KEventType::Release),
             _ => return None,
         };
 
-        let application = app_tracker.get_current_app();
+        if event_type == KEventType::Release && !config.record_releases {
+            return None;
+        }
+
+        let frontmost = frontmost();
+        let application = frontmost.bundle_id;
 
-        if Self::is_sensitive_app(&application) {
+        let mode = Self::app_mode(&application, config);
+        if mode == AppMode::Skip {
             trace!("Skipping event from sensitive app");
             return None;
         }
 
+        if !config.title_blocklist.is_empty() {
+            // An unreadable title (no Accessibility access) falls back to bundle-ID blocking only.
+            if let Some(title) = &frontmost.window_title {
+                if Self::is_sensitive_title(title, &config.title_blocklist) {
+                    trace!("Skipping event from sensitive window");
+                    return None;
+                }
+            }
+        }
+
+        let window_title = if config.record_window_titles {
+            frontmost.window_title
+        } else {
+            None
+        };
+
+        let character = if config.record_characters {
+            Self::typed_character(event.name.as_deref(), event_type, shortcut_active)
+        } else {
+            None
+        };
+
+        if mode == AppMode::Redact {
+            // Titles often name the command or document, so they go too.
+            trace!("Redacting event from {}", application);
+            return Some(KeystrokeEvent::new(
+                KeyCode::REDACTED.0,
+                event_type,
+                Vec::new(),
+                application,
+            ));
+        }
+
         let key_code = KeyCode::from_rdev_key(key);
         let modifiers = Self::extract_modifiers(key);
 
         debug!("Event: {:?} app={}", event_type, application);
 
-        Some(KeystrokeEvent::new(
-            key_code.0,
-            event_type,
-            modifiers,
-            application,
-        ))
+        Some(
+            KeystrokeEvent::new(key_code.0, event_type, modifiers, application)
+                .with_window_title(window_title)
+                .with_character(character)
+                .with_input_source(frontmost.input_source),
+        )
+    }
+
+    /// The text a press typed, from `rdev`'s `Event::name`, which already
+    /// applies the active layout, Shift and Option. Releases, presses under
+    /// Command/Control, and keys that produce control characters (Return,
+    /// Backspace, arrows) or nothing at all get `None`.
+    fn typed_character(
+        name: Option<&str>,
+        event_type: KEventType,
+        shortcut_active: bool,
+    ) -> Option<String> {
+        if event_type != KEventType::Press || shortcut_active {
+            return None;
+        }
+        name.filter(|text| !text.is_empty() && !text.chars().any(char::is_control))
+            .map(str::to_string)
+    }
+
+    fn is_shortcut_modifier(key: &Key) -> bool {
+        matches!(
+            key,
+            Key::MetaLeft | Key::MetaRight | Key::ControlLeft | Key::ControlRight
+        )
+    }
+
+    /// An explicit `app_modes` entry wins; otherwise blocked apps are skipped
+    /// and everything else is recorded.
+    fn app_mode(bundle_id: &str, config: &MonitorConfig) -> AppMode {
+        let configured = config
+            .app_modes
+            .iter()
+            .find(|(app, _)| bundle_id.eq_ignore_ascii_case(app))
+            .map(|(_, &mode)| mode);
+        configured.unwrap_or_else(|| {
+            if Self::is_blocked_app(bundle_id, config) {
+                AppMode::Skip
+            } else {
+                AppMode::Record
+            }
+        })
     }
 
-    fn is_sensitive_app(bundle_id: &str) -> bool {
+    fn is_blocked_app(bundle_id: &str, config: &MonitorConfig) -> bool {
+        if config
+            .app_allowlist
+            .iter()
+            .any(|allowed| bundle_id.eq_ignore_ascii_case(allowed))
+        {
+            return false;
+        }
+
         SENSITIVE_APP_BLOCKLIST
             .iter()
+            .copied()
+            .chain(config.app_blocklist.iter().map(String::as_str))
             .any(|blocked| bundle_id.eq_ignore_ascii_case(blocked))
     }
 
+    /// `keywords` are expected to be lowercase already.
+    fn is_sensitive_title(title: &str, keywords: &[String]) -> bool {
+        let title = title.to_lowercase();
+        keywords.iter().any(|keyword| title.contains(keyword.as_str()))
+    }
+
     fn extract_modifiers(key: &Key) -> Vec<Modifier> {
         let mut modifiers = Vec::new();
 
@@ -121,3 +345,126 @@ impl EventMonitor {
         modifiers
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keywords(list: &[&str]) -> Vec<String> {
+        list.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn test_sensitive_title_case_insensitive() {
+        let blocklist = keywords(&["password", "login"]);
+        assert!(EventMonitor::is_sensitive_title("Enter Password", &blocklist));
+        assert!(EventMonitor::is_sensitive_title("GitHub LOGIN - Safari", &blocklist));
+    }
+
+    #[test]
+    fn test_non_sensitive_title() {
+        let blocklist = keywords(&["password", "login"]);
+        assert!(!EventMonitor::is_sensitive_title("main.rs - lurk", &blocklist));
+        assert!(!EventMonitor::is_sensitive_title("", &blocklist));
+    }
+
+    #[test]
+    fn test_blocked_app_builtin_and_extra() {
+        let config = MonitorConfig {
+            app_blocklist: keywords(&["com.example.secret"]),
+            ..Default::default()
+        };
+        assert!(EventMonitor::is_blocked_app("com.bitwarden.desktop", &config));
+        assert!(EventMonitor::is_blocked_app("com.example.Secret", &config));
+        assert!(!EventMonitor::is_blocked_app("com.apple.TextEdit", &config));
+    }
+
+    #[test]
+    fn test_allowlist_overrides_blocklist() {
+        let config = MonitorConfig {
+            app_allowlist: keywords(&["com.microsoft.VSCode"]),
+            ..Default::default()
+        };
+        assert!(!EventMonitor::is_blocked_app("com.microsoft.VSCode", &config));
+        assert!(EventMonitor::is_blocked_app("com.apple.Terminal", &config));
+    }
+
+    #[test]
+    fn test_typed_character_keeps_layout_text() {
+        let typed = |name| EventMonitor::typed_character(Some(name), KEventType::Press, false);
+        assert_eq!(typed("a").as_deref(), Some("a"));
+        assert_eq!(typed("A").as_deref(), Some("A"));
+        assert_eq!(typed("ü").as_deref(), Some("ü"));
+        assert_eq!(typed(" ").as_deref(), Some(" "));
+    }
+
+    #[test]
+    fn test_typed_character_skips_non_text() {
+        assert_eq!(EventMonitor::typed_character(Some("\r"), KEventType::Press, false), None);
+        assert_eq!(EventMonitor::typed_character(Some("\u{8}"), KEventType::Press, false), None);
+        assert_eq!(EventMonitor::typed_character(Some(""), KEventType::Press, false), None);
+        assert_eq!(EventMonitor::typed_character(None, KEventType::Press, false), None);
+        assert_eq!(EventMonitor::typed_character(Some("a"), KEventType::Release, false), None);
+        assert_eq!(EventMonitor::typed_character(Some("c"), KEventType::Press, true), None);
+    }
+
+    #[test]
+    fn test_empty_blocklist_matches_nothing() {
+        assert!(!EventMonitor::is_sensitive_title("Password", &[]));
+    }
+
+    fn press_a_in(bundle_id: &str, config: &MonitorConfig) -> Option<KeystrokeEvent> {
+        let event = Event {
+            time: std::time::SystemTime::now(),
+            name: Some("a".to_string()),
+            event_type: EventType::KeyPress(Key::KeyA),
+        };
+        let frontmost = || {
+            let mut app = FrontmostApp::new(bundle_id, Some("vim ~/.ssh/config".to_string()));
+            app.input_source = Some("com.apple.keylayout.French".to_string());
+            app
+        };
+        EventMonitor::process_event(&event, frontmost, config, false)
+    }
+
+    fn modes(list: &[(&str, AppMode)]) -> MonitorConfig {
+        MonitorConfig {
+            record_window_titles: true,
+            record_characters: true,
+            app_modes: list.iter().map(|&(app, mode)| (app.to_string(), mode)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_skip_mode_drops_event() {
+        let config = modes(&[("com.example.Editor", AppMode::Skip)]);
+        assert!(press_a_in("com.example.Editor", &config).is_none());
+        // Built-in blocklist still applies without an entry.
+        assert!(press_a_in("com.bitwarden.desktop", &config).is_none());
+    }
+
+    #[test]
+    fn test_redact_mode_keeps_event_but_not_content() {
+        let config = modes(&[("com.apple.terminal", AppMode::Redact)]);
+        let event = press_a_in("com.apple.Terminal", &config).expect("redacted event");
+
+        assert_eq!(event.key_code, KeyCode::REDACTED.0);
+        assert_eq!(event.event_type, KEventType::Press);
+        assert_eq!(event.application, "com.apple.Terminal");
+        assert_eq!(event.character, None);
+        assert_eq!(event.window_title, None);
+        assert_eq!(event.input_source, None);
+    }
+
+    #[test]
+    fn test_record_mode_overrides_blocklist() {
+        let config = modes(&[("com.apple.Terminal", AppMode::Record)]);
+        let event = press_a_in("com.apple.Terminal", &config).expect("recorded event");
+
+        assert_eq!(event.key_code, KeyCode::from_rdev_key(&Key::KeyA).0);
+        assert_eq!(event.character.as_deref(), Some("a"));
+        assert_eq!(event.window_title.as_deref(), Some("vim ~/.ssh/config"));
+        assert_eq!(event.input_source.as_deref(), Some("com.apple.keylayout.French"));
+    }
+}
@@ -0,0 +1,123 @@
+use chrono::{Duration, NaiveDate, Utc};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+pub const LOG_FILE_NAME: &str = "lurk.log";
+const LOG_FILE_MODE: u32 = 0o600;
+/// Days of daemon logs kept, counting today.
+pub const LOG_RETENTION_DAYS: i64 = 14;
+
+/// The daemon's log, one `lurk.log.<date>` file per UTC day in `dir`. Each
+/// file is created 0600, and starting a new day deletes files older than
+/// `keep_days` days.
+#[derive(Debug)]
+pub struct DailyLogFile {
+    dir: PathBuf,
+    keep_days: i64,
+    current: Option<(NaiveDate, File)>,
+}
+
+impl DailyLogFile {
+    pub fn new(dir: &Path, keep_days: i64) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            keep_days,
+            current: None,
+        }
+    }
+
+    fn file_for(&mut self, today: NaiveDate) -> io::Result<&mut File> {
+        if self.current.as_ref().map(|(date, _)| *date) != Some(today) {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .mode(LOG_FILE_MODE)
+                .open(log_path(&self.dir, today))?;
+            self.current = Some((today, file));
+            // A failed prune only leaves old logs behind.
+            let _ = prune_logs(&self.dir, today, self.keep_days);
+        }
+        Ok(&mut self.current.as_mut().expect("just opened").1)
+    }
+}
+
+impl Write for DailyLogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file_for(Utc::now().date_naive())?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some((_, file)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+fn log_path(dir: &Path, date: NaiveDate) -> PathBuf {
+    dir.join(format!("{}.{}", LOG_FILE_NAME, date.format("%Y-%m-%d")))
+}
+
+/// Deletes the `lurk.log.<date>` files in `dir` dated `keep_days` or more
+/// days before `today`, and returns how many went. Other files are left alone.
+pub fn prune_logs(dir: &Path, today: NaiveDate, keep_days: i64) -> io::Result<usize> {
+    let cutoff = today - Duration::days(keep_days);
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let date = path
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_prefix(LOG_FILE_NAME)?.strip_prefix('.'))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+        if date.is_some_and(|date| date <= cutoff) {
+            fs::remove_file(&path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_log_file_is_created_private() {
+        let dir = TempDir::new().unwrap();
+        let mut log = DailyLogFile::new(dir.path(), LOG_RETENTION_DAYS);
+        writeln!(log, "started").unwrap();
+
+        let path = log_path(dir.path(), Utc::now().date_naive());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "started\n");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, LOG_FILE_MODE);
+    }
+
+    #[test]
+    fn test_new_day_prunes_old_logs() {
+        let dir = TempDir::new().unwrap();
+        for name in ["lurk.log.2024-02-28", "lurk.log.2024-02-29", "lurk.log.2024-03-01"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        fs::write(dir.path().join("launchd.err"), "").unwrap();
+
+        let mut log = DailyLogFile::new(dir.path(), 10);
+        log.file_for(date("2024-03-10")).unwrap();
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["launchd.err", "lurk.log.2024-03-01", "lurk.log.2024-03-10"]);
+        assert_eq!(prune_logs(dir.path(), date("2024-03-10"), 10).unwrap(), 0);
+    }
+}
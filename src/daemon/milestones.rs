@@ -0,0 +1,122 @@
+use anyhow::Result;
+use std::process::Command;
+use std::thread;
+use tracing::{info, warn};
+
+use crate::daemon::writer::EventSink;
+use crate::models::{EventType, KeystrokeEvent};
+use crate::storage::Database;
+
+/// Metadata key holding the last milestone announced, so a restart doesn't
+/// announce it again.
+pub const LAST_MILESTONE_KEY: &str = "last_milestone";
+
+/// Power of ten of the smallest milestone, 1M; after it come 5M, 10M, 50M,
+/// 100M and so on.
+const FIRST_MILESTONE_EXP: u32 = 6;
+
+fn milestones() -> impl Iterator<Item = u64> {
+    (FIRST_MILESTONE_EXP..)
+        .map_while(|exp| 10u64.checked_pow(exp))
+        .flat_map(|power| [Some(power), power.checked_mul(5)])
+        .flatten()
+}
+
+/// The highest milestone passed when the press total goes from `before` to
+/// `after`, if any.
+pub fn milestone_crossed(before: u64, after: u64) -> Option<u64> {
+    milestones()
+        .take_while(|m| *m <= after)
+        .filter(|m| *m > before)
+        .last()
+}
+
+/// "1M", "50M", "1B".
+pub fn milestone_label(milestone: u64) -> String {
+    if milestone >= 1_000_000_000 && milestone.is_multiple_of(1_000_000_000) {
+        format!("{}B", milestone / 1_000_000_000)
+    } else {
+        format!("{}M", milestone / 1_000_000)
+    }
+}
+
+/// Wraps the database in the daemon's writer path and keeps a running press
+/// total, so crossing a milestone costs one comparison per press. Milestones
+/// are logged, and shown as a macOS notification when `notify` is set.
+pub struct MilestoneSink {
+    db: Database,
+    presses: u64,
+    last_milestone: u64,
+    notify: bool,
+}
+
+impl MilestoneSink {
+    pub fn new(db: Database, notify: bool) -> Result<Self> {
+        let presses = db.get_press_count()?.max(0) as u64;
+        let last_milestone = db
+            .get_metadata(LAST_MILESTONE_KEY)?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        Ok(Self {
+            db,
+            presses,
+            last_milestone,
+            notify,
+        })
+    }
+
+    fn celebrate(&mut self, milestone: u64) {
+        self.last_milestone = milestone;
+        let message = format!("{} keystrokes recorded", milestone_label(milestone));
+        info!("Milestone: {}", message);
+        if let Err(e) = self.db.set_metadata(LAST_MILESTONE_KEY, &milestone.to_string()) {
+            warn!("Failed to store milestone: {}", e);
+        }
+        if self.notify {
+            let script = format!("display notification \"{}\" with title \"lurk\"", message);
+            match Command::new("osascript").args(["-e", &script]).spawn() {
+                // Waited for off the writer thread so it doesn't linger as a zombie.
+                Ok(mut child) => {
+                    thread::spawn(move || child.wait());
+                }
+                Err(e) => warn!("Failed to show milestone notification: {}", e),
+            }
+        }
+    }
+}
+
+impl EventSink for MilestoneSink {
+    fn insert_event(&mut self, event: &KeystrokeEvent) -> Result<()> {
+        self.db.insert_event(event)?;
+        if event.event_type == EventType::Press {
+            let before = self.presses;
+            self.presses += 1;
+            if let Some(milestone) = milestone_crossed(before, self.presses)
+                .filter(|m| *m > self.last_milestone)
+            {
+                self.celebrate(milestone);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_milestone_crossed_between_counts() {
+        assert_eq!(milestone_crossed(999_999, 1_000_000), Some(1_000_000));
+        assert_eq!(milestone_crossed(1_000_000, 1_000_001), None);
+        assert_eq!(milestone_crossed(0, 999_999), None);
+        assert_eq!(milestone_crossed(4_999_999, 5_000_000), Some(5_000_000));
+        assert_eq!(milestone_crossed(5_000_000, 9_999_999), None);
+        assert_eq!(milestone_crossed(9_999_999, 10_000_000), Some(10_000_000));
+        // A jump past several reports the highest.
+        assert_eq!(milestone_crossed(0, 60_000_000), Some(50_000_000));
+
+        assert_eq!(milestone_label(5_000_000), "5M");
+        assert_eq!(milestone_label(1_000_000_000), "1B");
+    }
+}
@@ -1,6 +1,21 @@
 pub mod app_tracker;
+pub mod debounce;
 pub mod event_monitor;
+pub mod log_file;
+pub mod milestones;
 pub mod permissions;
+pub mod pidfile;
+pub mod sampler;
+pub mod shutdown;
+pub mod watchdog;
+pub mod writer;
 
-pub use event_monitor::EventMonitor;
+pub use debounce::Debouncer;
+pub use event_monitor::{EventMonitor, MonitorConfig, DEFAULT_SENSITIVE_TITLE_KEYWORDS};
+pub use log_file::DailyLogFile;
+pub use milestones::MilestoneSink;
 pub use permissions::{check_input_monitoring_permission, ensure_permissions};
+pub use pidfile::{running_daemon_pid, PidFile};
+pub use sampler::{Sampler, SamplingConfig};
+pub use watchdog::{Heartbeat, Watchdog, WatchdogStatus};
+pub use writer::{EventSender, RetryConfig, RetryWriter};
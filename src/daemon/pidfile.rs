@@ -0,0 +1,168 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+const PIDFILE_MODE: u32 = 0o600;
+
+/// Holds the daemon pidfile for as long as the daemon runs, so a second
+/// `lurk daemon` doesn't start another listener on the same database.
+/// The file stays `flock`ed while held, which makes two daemons starting at
+/// once unable to both take it, and is removed on drop.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+    /// Keeps the lock; closing it releases the lock.
+    _file: File,
+}
+
+impl PidFile {
+    /// Fails if another daemon holds the pidfile. A pidfile naming a live
+    /// process that doesn't hold the lock fails too, unless `force` is set.
+    /// A pidfile left by a crash is simply replaced.
+    pub fn acquire(path: &Path, force: bool) -> Result<Self> {
+        Self::acquire_with(path, force, is_process_alive)
+    }
+
+    fn acquire_with(path: &Path, force: bool, is_alive: impl Fn(u32) -> bool) -> Result<Self> {
+        let mut file = lock_pidfile(path)?;
+        if let Some(pid) = read_pidfile(path).filter(|&pid| pid != std::process::id()) {
+            if is_alive(pid) && !force {
+                return Err(anyhow!(
+                    "lurk daemon is already running (pid {}). Stop it first, or pass --force \
+                     if {:?} is stale.",
+                    pid,
+                    path
+                ));
+            }
+        }
+        file.set_len(0)?;
+        writeln!(file, "{}", std::process::id())
+            .with_context(|| format!("Failed to write {:?}", path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            _file: file,
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // A forced second instance may have taken the file over; leave theirs alone.
+        if read_pidfile(&self.path) == Some(std::process::id()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Opens the pidfile, creating it if needed, and takes an exclusive `flock`
+/// on it. Fails straight away if another process holds the lock.
+fn lock_pidfile(path: &Path) -> Result<File> {
+    loop {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .mode(PIDFILE_MODE)
+            .open(path)
+            .with_context(|| format!("Failed to open {:?}", path))?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let pid = read_pidfile(path).map_or_else(|| "unknown".to_string(), |p| p.to_string());
+            return Err(anyhow!("lurk daemon is already running (pid {})", pid));
+        }
+        // The previous holder may have removed the file between our open and
+        // the lock; a lock on the removed file would exclude nobody.
+        let current = fs::metadata(path).ok().map(|m| (m.dev(), m.ino()));
+        let locked = file.metadata()?;
+        if current == Some((locked.dev(), locked.ino())) {
+            return Ok(file);
+        }
+    }
+}
+
+/// The PID in the pidfile, if there is one and it parses.
+pub fn read_pidfile(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The daemon's PID, if the pidfile names a process that is still alive.
+/// A stale pidfile left by a crash reads as not running.
+pub fn running_daemon_pid(path: &Path) -> Option<u32> {
+    read_pidfile(path).filter(|&pid| is_process_alive(pid))
+}
+
+/// `kill(pid, 0)`: delivers no signal, only checks that the process exists.
+/// `EPERM` means it exists but belongs to another user.
+pub fn is_process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    let signalled = unsafe { libc::kill(pid, 0) == 0 };
+    signalled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_pidfile(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("lurk-{}-{}.pid", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_live_daemon_blocks_start_unless_forced() {
+        let path = temp_pidfile("live");
+        fs::write(&path, "999999\n").unwrap();
+
+        let err = PidFile::acquire_with(&path, false, |pid| pid == 999999).unwrap_err();
+        assert!(err.to_string().contains("already running (pid 999999)"));
+        assert_eq!(read_pidfile(&path), Some(999999));
+
+        let pidfile = PidFile::acquire_with(&path, true, |pid| pid == 999999).unwrap();
+        assert_eq!(read_pidfile(&path), Some(std::process::id()));
+        drop(pidfile);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_stale_pidfile_is_replaced() {
+        let path = temp_pidfile("stale");
+        fs::write(&path, "999999\n").unwrap();
+
+        let pidfile = PidFile::acquire_with(&path, false, |_| false).unwrap();
+        assert_eq!(read_pidfile(&path), Some(std::process::id()));
+        drop(pidfile);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_held_pidfile_blocks_even_with_force() {
+        let path = temp_pidfile("held");
+        let first = PidFile::acquire_with(&path, false, |_| false).unwrap();
+
+        let err = PidFile::acquire_with(&path, true, |_| false).unwrap_err();
+        assert!(err.to_string().contains("already running"), "{}", err);
+        assert_eq!(read_pidfile(&path), Some(std::process::id()));
+
+        drop(first);
+        assert!(!path.exists());
+        drop(PidFile::acquire_with(&path, false, |_| false).unwrap());
+    }
+
+    #[test]
+    fn test_pidfile_round_trip_reports_self_running() {
+        let path = std::env::temp_dir().join(format!("lurk-pidfile-{}.pid", std::process::id()));
+        fs::write(&path, format!("{}\n", std::process::id())).unwrap();
+
+        assert_eq!(read_pidfile(&path), Some(std::process::id()));
+        assert_eq!(running_daemon_pid(&path), Some(std::process::id()));
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(running_daemon_pid(&path), None);
+    }
+}
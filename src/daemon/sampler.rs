@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use crate::models::{EventType, KeystrokeEvent};
+
+/// Optional limits on how many events the daemon stores.
+///
+/// Both work on presses; a release is kept exactly when its press was, so
+/// stored data never has orphaned halves. The tradeoff:
+///
+/// - `every_nth` keeps key frequencies roughly proportional (every key is
+///   thinned by the same factor), but consecutive stored presses are no
+///   longer consecutive keystrokes, so inter-key timing, bigrams and
+///   trigrams become meaningless. Hold durations are unaffected.
+/// - `max_per_second` stores everything at normal speeds and only trims
+///   bursts, so fast typing is under-represented: frequencies skew toward
+///   slow typing and timing stats lose their fastest intervals.
+///
+/// Stored counts are not scaled back up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SamplingConfig {
+    /// Keep one press in this many. `None` (or 1) keeps all.
+    pub every_nth: Option<u32>,
+    /// Keep at most this many presses per wall-clock second.
+    pub max_per_second: Option<u32>,
+}
+
+impl SamplingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.every_nth.is_some_and(|n| n > 1) || self.max_per_second.is_some()
+    }
+}
+
+/// Decides, event by event, what `SamplingConfig` lets through.
+#[derive(Debug, Default)]
+pub struct Sampler {
+    config: SamplingConfig,
+    presses_seen: u64,
+    current_second: i64,
+    kept_this_second: u32,
+    /// Key codes whose latest press was kept, so their release is kept too.
+    kept_down: HashSet<u32>,
+}
+
+impl Sampler {
+    pub fn new(config: SamplingConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn keep(&mut self, event: &KeystrokeEvent) -> bool {
+        if !self.config.is_enabled() {
+            return true;
+        }
+        match event.event_type {
+            EventType::Release => self.kept_down.remove(&event.key_code),
+            EventType::Press => {
+                let keep = self.keep_press(event.timestamp);
+                if keep {
+                    self.kept_down.insert(event.key_code);
+                } else {
+                    self.kept_down.remove(&event.key_code);
+                }
+                keep
+            }
+        }
+    }
+
+    fn keep_press(&mut self, timestamp: i64) -> bool {
+        let nth = self.presses_seen;
+        self.presses_seen += 1;
+        if let Some(n) = self.config.every_nth.filter(|&n| n > 1) {
+            if !nth.is_multiple_of(u64::from(n)) {
+                return false;
+            }
+        }
+
+        if let Some(max) = self.config.max_per_second {
+            let second = timestamp.div_euclid(1000);
+            if second != self.current_second {
+                self.current_second = second;
+                self.kept_this_second = 0;
+            }
+            if self.kept_this_second >= max {
+                return false;
+            }
+            self.kept_this_second += 1;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::event;
+
+    fn kept_presses(sampler: &mut Sampler, timestamps: &[i64]) -> Vec<i64> {
+        timestamps
+            .iter()
+            .copied()
+            .filter(|&ts| sampler.keep(&event(ts, 0x00, EventType::Press)))
+            .collect()
+    }
+
+    #[test]
+    fn test_disabled_keeps_everything() {
+        let mut sampler = Sampler::new(SamplingConfig {
+            every_nth: Some(1),
+            max_per_second: None,
+        });
+        assert_eq!(kept_presses(&mut sampler, &[0, 1, 2]), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_every_nth_keeps_one_in_n() {
+        let mut sampler = Sampler::new(SamplingConfig {
+            every_nth: Some(3),
+            max_per_second: None,
+        });
+        let timestamps: Vec<i64> = (0..7).collect();
+        assert_eq!(kept_presses(&mut sampler, &timestamps), vec![0, 3, 6]);
+    }
+
+    #[test]
+    fn test_rate_cap_resets_each_second() {
+        let mut sampler = Sampler::new(SamplingConfig {
+            every_nth: None,
+            max_per_second: Some(2),
+        });
+        let kept = kept_presses(&mut sampler, &[0, 100, 200, 999, 1000, 1500, 1600]);
+        assert_eq!(kept, vec![0, 100, 1000, 1500]);
+    }
+
+    #[test]
+    fn test_release_follows_its_press() {
+        let mut sampler = Sampler::new(SamplingConfig {
+            every_nth: Some(2),
+            max_per_second: None,
+        });
+        assert!(sampler.keep(&event(0, 0x00, EventType::Press)));
+        assert!(!sampler.keep(&event(10, 0x01, EventType::Press)));
+        assert!(!sampler.keep(&event(20, 0x01, EventType::Release)));
+        assert!(sampler.keep(&event(30, 0x00, EventType::Release)));
+        assert!(!sampler.keep(&event(40, 0x00, EventType::Release)));
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::thread;
+use tracing::info;
+
+/// Signals that stop the daemon cleanly: `kill`, launchd unloading the
+/// agent, logout, and Ctrl+C.
+const TERMINATION_SIGNALS: [libc::c_int; 3] = [libc::SIGTERM, libc::SIGINT, libc::SIGHUP];
+
+/// Write end of the pipe the signal handler wakes the shutdown thread through.
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// What the pipe carries for `request_shutdown`; signals send their number.
+const REQUESTED: u8 = 0;
+
+/// Exit status after the shutdown: 0 for a signal, or what
+/// `request_shutdown` asked for.
+static EXIT_STATUS: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    // Only async-signal-safe calls here: hand the signal number to the thread.
+    let fd = SIGNAL_PIPE.load(Ordering::Relaxed);
+    if fd >= 0 {
+        let byte = signal as u8;
+        unsafe { libc::write(fd, &byte as *const u8 as *const libc::c_void, 1) };
+    }
+}
+
+/// Runs `shutdown` on a background thread when the process is asked to
+/// terminate, then exits with status 0. `rdev`'s listener never returns, so
+/// this is the only place the daemon gets to flush queued events and remove
+/// its pidfile. A second signal while `shutdown` runs is ignored.
+///
+/// `request_shutdown` goes through the same path from inside the daemon.
+pub fn on_termination<F>(shutdown: F) -> Result<()>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(anyhow!(
+            "Failed to set up signal handling: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    let [read_fd, write_fd] = fds;
+    SIGNAL_PIPE.store(write_fd, Ordering::Relaxed);
+
+    thread::spawn(move || {
+        let mut byte = 0u8;
+        let read = unsafe { libc::read(read_fd, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+        if read == 1 {
+            if byte == REQUESTED {
+                info!("Shutting down");
+            } else {
+                info!("Received signal {}, shutting down", byte);
+            }
+            shutdown();
+            std::process::exit(EXIT_STATUS.load(Ordering::Relaxed));
+        }
+    });
+
+    for signal in TERMINATION_SIGNALS {
+        let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+            return Err(anyhow!(
+                "Failed to install handler for signal {}: {}",
+                signal,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the shutdown `on_termination` installed, as a signal would, and then
+/// exits with `status`. Exits straight away if none is installed.
+pub fn request_shutdown(status: i32) {
+    EXIT_STATUS.store(status, Ordering::Relaxed);
+    let fd = SIGNAL_PIPE.load(Ordering::Relaxed);
+    if fd < 0 {
+        std::process::exit(status);
+    }
+    let byte = REQUESTED;
+    unsafe { libc::write(fd, &byte as *const u8 as *const libc::c_void, 1) };
+}
@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Longest wait between watchdog checks, so a revocation after a short idle
+/// threshold is still noticed promptly.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// When the listener last delivered an event. Cloned into the listener
+/// callback, which beats on every event, and the watchdog, which reads it.
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    started: Instant,
+    last_beat_ms: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            last_beat_ms: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn beat(&self) {
+        let elapsed_ms = self.started.elapsed().as_millis() as u64;
+        self.last_beat_ms.store(elapsed_ms, Ordering::Relaxed);
+    }
+
+    /// Time since the last beat, or since creation if there hasn't been one.
+    pub fn idle(&self) -> Duration {
+        let last_beat = Duration::from_millis(self.last_beat_ms.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(last_beat)
+    }
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogStatus {
+    /// Events arrived within the idle threshold.
+    Active,
+    /// No events for a while, but the permission is still granted, so the
+    /// user is most likely just away.
+    Idle,
+    /// No events and Input Monitoring is no longer granted.
+    Revoked,
+}
+
+/// Notices when `rdev::listen` has gone quiet because Input Monitoring was
+/// revoked. The listener keeps running in that case, so without this the
+/// daemon looks alive while capturing nothing.
+///
+/// Silence alone is not treated as a failure: the permission is only
+/// re-checked once nothing has arrived for `idle_threshold`, and a granted
+/// permission means the user is idle.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    heartbeat: Heartbeat,
+    idle_threshold: Duration,
+}
+
+impl Watchdog {
+    pub fn new(heartbeat: Heartbeat, idle_threshold: Duration) -> Self {
+        Self {
+            heartbeat,
+            idle_threshold,
+        }
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        self.idle_threshold.min(MAX_POLL_INTERVAL)
+    }
+
+    pub fn check(&self, permission_granted: impl FnOnce() -> bool) -> WatchdogStatus {
+        Self::status(self.heartbeat.idle(), self.idle_threshold, permission_granted)
+    }
+
+    fn status(
+        idle: Duration,
+        idle_threshold: Duration,
+        permission_granted: impl FnOnce() -> bool,
+    ) -> WatchdogStatus {
+        if idle < idle_threshold {
+            WatchdogStatus::Active
+        } else if permission_granted() {
+            WatchdogStatus::Idle
+        } else {
+            WatchdogStatus::Revoked
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_secs(600);
+
+    #[test]
+    fn test_recent_events_skip_permission_check() {
+        let status = Watchdog::status(Duration::from_secs(5), THRESHOLD, || {
+            panic!("permission checked while events are arriving")
+        });
+        assert_eq!(status, WatchdogStatus::Active);
+    }
+
+    #[test]
+    fn test_long_silence_rechecks_permission() {
+        let idle = Duration::from_secs(900);
+        assert_eq!(Watchdog::status(idle, THRESHOLD, || true), WatchdogStatus::Idle);
+        assert_eq!(Watchdog::status(idle, THRESHOLD, || false), WatchdogStatus::Revoked);
+    }
+
+    #[test]
+    fn test_heartbeat_resets_idle() {
+        let heartbeat = Heartbeat::new();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(heartbeat.idle() >= Duration::from_millis(20));
+        heartbeat.beat();
+        assert!(heartbeat.idle() < Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,373 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::models::KeystrokeEvent;
+use crate::storage::Database;
+
+const PENDING_FILE_MODE: u32 = 0o600;
+
+/// Where events are ultimately written. Implemented by `Database`; tests use
+/// sinks that fail on demand.
+pub trait EventSink {
+    fn insert_event(&mut self, event: &KeystrokeEvent) -> Result<()>;
+}
+
+impl EventSink for Database {
+    fn insert_event(&mut self, event: &KeystrokeEvent) -> Result<()> {
+        Database::insert_event(self, event)
+    }
+}
+
+/// The sending half of the writer's channel, closable from another thread.
+/// `rdev`'s listener holds its sender forever, so closing it here is how the
+/// writer learns to drain and stop on shutdown.
+#[derive(Debug, Clone)]
+pub struct EventSender(Arc<Mutex<Option<Sender<KeystrokeEvent>>>>);
+
+impl EventSender {
+    pub fn new(sender: Sender<KeystrokeEvent>) -> Self {
+        Self(Arc::new(Mutex::new(Some(sender))))
+    }
+
+    /// Fails once the channel is closed.
+    pub fn send(&self, event: KeystrokeEvent) -> Result<()> {
+        let sender = self.0.lock().map_err(|_| anyhow!("event channel lock poisoned"))?;
+        match sender.as_ref() {
+            Some(sender) => sender.send(event).map_err(|_| anyhow!("event writer has stopped")),
+            None => Err(anyhow!("event channel is closed")),
+        }
+    }
+
+    /// Drops the sender, so `RetryWriter::run` returns once it has taken
+    /// everything already sent.
+    pub fn close(&self) {
+        if let Ok(mut sender) = self.0.lock() {
+            sender.take();
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Events held in memory before the queue is spilled to the pending file.
+    pub queue_cap: usize,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(30),
+            queue_cap: 10_000,
+        }
+    }
+}
+
+/// Writes events to a sink, keeping failed ones instead of dropping them.
+///
+/// Failed inserts wait in a queue and are retried with exponential backoff;
+/// later events queue behind them so order is kept. If the queue outgrows
+/// `queue_cap` (or the writer shuts down with events queued), they are
+/// appended to a JSON-lines file that `replay_pending` loads on next start.
+pub struct RetryWriter<S: EventSink> {
+    sink: S,
+    config: RetryConfig,
+    pending_path: PathBuf,
+    queue: VecDeque<KeystrokeEvent>,
+    backoff: Duration,
+    next_attempt: Option<Instant>,
+}
+
+impl<S: EventSink> RetryWriter<S> {
+    pub fn new(sink: S, pending_path: PathBuf, config: RetryConfig) -> Self {
+        let backoff = config.initial_backoff;
+        Self {
+            sink,
+            config,
+            pending_path,
+            queue: VecDeque::new(),
+            backoff,
+            next_attempt: None,
+        }
+    }
+
+    /// Inserts events spilled by a previous run, keeping any that still fail
+    /// in the pending file. Returns how many were written.
+    pub fn replay_pending(&mut self) -> Result<usize> {
+        if !self.pending_path.exists() {
+            return Ok(0);
+        }
+
+        let file = File::open(&self.pending_path)
+            .with_context(|| format!("Failed to open {:?}", self.pending_path))?;
+        let mut remaining = Vec::new();
+        let mut written = 0;
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: KeystrokeEvent = match serde_json::from_str(&line) {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("Skipping unreadable pending event: {}", e);
+                    continue;
+                }
+            };
+            if !remaining.is_empty() || self.sink.insert_event(&event).is_err() {
+                remaining.push(event);
+            } else {
+                written += 1;
+            }
+        }
+
+        if remaining.is_empty() {
+            fs::remove_file(&self.pending_path)?;
+        } else {
+            self.rewrite_pending(&remaining)?;
+        }
+        Ok(written)
+    }
+
+    /// Replaces the pending file with `events`. They go to a temporary file
+    /// first, renamed over the old one, so a crash midway leaves either the
+    /// old file or the new one, never neither.
+    fn rewrite_pending(&self, events: &[KeystrokeEvent]) -> Result<()> {
+        let temp_path = self.pending_path.with_extension("jsonl.tmp");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .mode(PENDING_FILE_MODE)
+            .open(&temp_path)
+            .with_context(|| format!("Failed to open {:?}", temp_path))?;
+        for event in events {
+            writeln!(file, "{}", serde_json::to_string(event)?)?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp_path, &self.pending_path)
+            .with_context(|| format!("Failed to replace {:?}", self.pending_path))?;
+        Ok(())
+    }
+
+    pub fn write(&mut self, event: KeystrokeEvent) {
+        self.queue.push_back(event);
+        self.retry();
+    }
+
+    /// Attempts queued inserts if the backoff has elapsed.
+    pub fn retry(&mut self) {
+        if self.next_attempt.is_some_and(|at| Instant::now() < at) {
+            return;
+        }
+
+        while let Some(event) = self.queue.front() {
+            match self.sink.insert_event(event) {
+                Ok(()) => {
+                    self.queue.pop_front();
+                }
+                Err(e) => {
+                    if self.next_attempt.is_none() {
+                        warn!("Failed to write event, will retry: {}", e);
+                    }
+                    self.next_attempt = Some(Instant::now() + self.backoff);
+                    self.backoff = (self.backoff * 2).min(self.config.max_backoff);
+                    if self.queue.len() > self.config.queue_cap {
+                        self.spill();
+                    }
+                    return;
+                }
+            }
+        }
+
+        if self.next_attempt.take().is_some() {
+            info!("Event writes recovered");
+        }
+        self.backoff = self.config.initial_backoff;
+    }
+
+    /// How long until the next retry is due, if events are waiting.
+    pub fn retry_delay(&self) -> Option<Duration> {
+        if self.queue.is_empty() {
+            return None;
+        }
+        Some(
+            self.next_attempt
+                .map_or(Duration::ZERO, |at| at.saturating_duration_since(Instant::now())),
+        )
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Moves every queued event to the pending file.
+    pub fn spill(&mut self) {
+        if self.queue.is_empty() {
+            return;
+        }
+        let events: Vec<_> = self.queue.drain(..).collect();
+        match self.append_pending(&events) {
+            Ok(()) => warn!("Spilled {} unwritten events to {:?}", events.len(), self.pending_path),
+            Err(e) => error!("Failed to spill {} events: {}", events.len(), e),
+        }
+    }
+
+    /// Feeds events from `rx` until the channel closes, retrying failed
+    /// inserts in between, then spills whatever is still queued.
+    pub fn run(mut self, rx: Receiver<KeystrokeEvent>) {
+        loop {
+            let received = match self.retry_delay() {
+                Some(delay) => rx.recv_timeout(delay),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(event) => self.write(event),
+                Err(RecvTimeoutError::Timeout) => self.retry(),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        self.spill();
+    }
+
+    fn append_pending(&self, events: &[KeystrokeEvent]) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(PENDING_FILE_MODE)
+            .open(&self.pending_path)
+            .with_context(|| format!("Failed to open {:?}", self.pending_path))?;
+        for event in events {
+            writeln!(file, "{}", serde_json::to_string(event)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil;
+
+    /// Fails the first `failures` inserts, then records everything.
+    struct FlakySink {
+        failures: usize,
+        written: Vec<KeystrokeEvent>,
+    }
+
+    impl EventSink for FlakySink {
+        fn insert_event(&mut self, event: &KeystrokeEvent) -> Result<()> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(anyhow!("database is locked"));
+            }
+            self.written.push(event.clone());
+            Ok(())
+        }
+    }
+
+    fn flaky(failures: usize) -> FlakySink {
+        FlakySink {
+            failures,
+            written: Vec::new(),
+        }
+    }
+
+    fn no_backoff(queue_cap: usize) -> RetryConfig {
+        RetryConfig {
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            queue_cap,
+        }
+    }
+
+    fn timestamps(events: &[KeystrokeEvent]) -> Vec<i64> {
+        events.iter().map(|e| e.timestamp).collect()
+    }
+
+    #[test]
+    fn test_failed_inserts_are_retried_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RetryWriter::new(flaky(3), dir.path().join("pending.jsonl"), no_backoff(100));
+
+        for ts in 1..=3 {
+            writer.write(testutil::press(ts, 0x00));
+        }
+        assert_eq!(writer.queued(), 3);
+        assert!(writer.sink.written.is_empty());
+
+        writer.write(testutil::press(4, 0x00));
+        assert_eq!(writer.queued(), 0);
+        assert_eq!(timestamps(&writer.sink.written), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_overflow_spills_and_replays_on_next_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let pending = dir.path().join("pending.jsonl");
+        let mut writer = RetryWriter::new(flaky(usize::MAX), pending.clone(), no_backoff(2));
+
+        for ts in 1..=5 {
+            writer.write(testutil::press(ts, 0x00));
+        }
+        writer.spill();
+        assert_eq!(writer.queued(), 0);
+        assert!(pending.exists());
+
+        let mut next = RetryWriter::new(flaky(0), pending.clone(), no_backoff(2));
+        assert_eq!(next.replay_pending().unwrap(), 5);
+        assert_eq!(timestamps(&next.sink.written), vec![1, 2, 3, 4, 5]);
+        assert!(!pending.exists());
+    }
+
+    #[test]
+    fn test_replay_keeps_events_that_still_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let pending = dir.path().join("pending.jsonl");
+        let mut writer = RetryWriter::new(flaky(usize::MAX), pending.clone(), no_backoff(10));
+        for ts in 1..=3 {
+            writer.write(testutil::press(ts, 0x00));
+        }
+        writer.spill();
+
+        let mut next = RetryWriter::new(flaky(1), pending.clone(), no_backoff(10));
+        assert_eq!(next.replay_pending().unwrap(), 0);
+        assert!(next.sink.written.is_empty());
+
+        let mut last = RetryWriter::new(flaky(0), pending.clone(), no_backoff(10));
+        assert_eq!(last.replay_pending().unwrap(), 3);
+        assert!(!pending.exists());
+        assert!(!pending.with_extension("jsonl.tmp").exists());
+    }
+
+    #[test]
+    fn test_closing_the_sender_stops_the_writer_and_spills() {
+        let dir = tempfile::tempdir().unwrap();
+        let pending = dir.path().join("pending.jsonl");
+        let writer = RetryWriter::new(flaky(usize::MAX), pending.clone(), no_backoff(10));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let sender = EventSender::new(tx);
+        let handle = std::thread::spawn(move || writer.run(rx));
+        for ts in 1..=3 {
+            sender.send(testutil::press(ts, 0x00)).unwrap();
+        }
+        sender.close();
+        handle.join().unwrap();
+
+        assert!(sender.send(testutil::press(4, 0x00)).is_err());
+        let mut next = RetryWriter::new(flaky(0), pending, no_backoff(10));
+        assert_eq!(next.replay_pending().unwrap(), 3);
+    }
+}
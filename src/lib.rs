@@ -0,0 +1,12 @@
+//! Capture, storage and analysis for lurk. The `lurk` binary is a thin CLI
+//! over these modules; they live in a library so benches can use them too.
+
+pub mod analysis;
+pub mod cli;
+pub mod config;
+pub mod daemon;
+pub mod models;
+pub mod storage;
+#[cfg(test)]
+pub(crate) mod testutil;
+pub mod tui;
@@ -1,18 +1,17 @@
-mod analysis;
-mod cli;
-mod daemon;
-mod models;
-mod storage;
-mod tui;
-
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{anyhow, Result};
+use clap::{ArgAction, Parser, Subcommand};
 use std::fs::{self, Permissions};
 use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::channel;
 use std::thread;
-use tracing::{error, info};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+use lurk::{analysis, cli, config, daemon, models, storage, tui};
+use lurk::daemon::log_file::{LOG_FILE_NAME, LOG_RETENTION_DAYS};
 
 fn get_data_dir() -> PathBuf {
     dirs::home_dir()
@@ -24,8 +23,26 @@ fn get_db_path() -> PathBuf {
     get_data_dir().join("events.db")
 }
 
+fn get_config_path() -> PathBuf {
+    get_data_dir().join("config.toml")
+}
+
+fn get_pending_path() -> PathBuf {
+    get_data_dir().join("pending.jsonl")
+}
+
+fn get_pid_path() -> PathBuf {
+    get_data_dir().join("lurk.pid")
+}
+
+fn get_log_dir() -> PathBuf {
+    get_data_dir().join("logs")
+}
+
 const SECURE_DIR_MODE: u32 = 0o700;
 const SECURE_FILE_MODE: u32 = 0o600;
+/// Characters in the tallest bar of `analyze`'s inter-key histogram.
+const HISTOGRAM_BAR_WIDTH: u64 = 40;
 
 fn create_secure_dir(path: &PathBuf) -> Result<()> {
     if !path.exists() {
@@ -47,6 +64,12 @@ fn set_secure_file_permissions(path: &PathBuf) -> Result<()> {
 #[command(about = "A local-only keystroke logger for custom keyboard design analysis")]
 #[command(version)]
 struct Cli {
+    #[arg(short, long, global = true, action = ArgAction::Count, help = "More log output (-v debug, -vv trace)")]
+    verbose: u8,
+
+    #[arg(short, long, global = true, conflicts_with = "verbose", help = "Only log warnings and errors")]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -54,21 +77,99 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "Run the capture daemon (default)")]
-    Daemon,
+    Daemon {
+        #[arg(long, help = "Skip keystrokes while the window title looks sensitive (password, login, ...)")]
+        block_sensitive_titles: bool,
+
+        #[arg(long = "block-title", value_name = "TEXT", help = "Additional window-title substring to skip (repeatable)")]
+        block_titles: Vec<String>,
+
+        #[arg(long, help = "Store the focused window title with each event")]
+        record_window_titles: bool,
+
+        #[arg(long, help = "Store the character each key press produced under the active input source")]
+        record_characters: bool,
+
+        #[arg(long, help = "Start even if lurk.pid names a live process, as long as no daemon holds it (e.g. a stale PID reused by another process)")]
+        force: bool,
+
+        #[arg(long, help = "Create the database as plain, unencrypted SQLite (kept that way on later runs; no key file). Keystrokes are then readable by anyone with file access")]
+        no_encryption: bool,
+    },
 
     #[command(about = "Export keystroke data")]
     Export {
-        #[arg(short, long, default_value = "csv", help = "Output format: csv or json")]
+        #[arg(short, long, default_value = "csv", help = "Output format: csv, json, or jsonl (one event per line)")]
         format: String,
 
+        #[arg(short, long, required_unless_present = "output_dir", conflicts_with = "output_dir", help = "Output file path, or - for stdout")]
+        output: Option<String>,
+
+        #[arg(long, value_name = "DIR", help = "Write to DIR/lurk-export-YYYYMMDD-HHMMSS.<format> instead of an --output path")]
+        output_dir: Option<PathBuf>,
+
+        #[arg(long, help = "Compress the output with gzip (adds .gz)")]
+        gzip: bool,
+
+        #[arg(long, help = "Allow writing outside the home directory (exports contain raw keystrokes)")]
+        allow_outside_home: bool,
+
+        #[arg(long, help = "Hash application names and drop window titles and characters")]
+        anonymize: bool,
+
+        #[arg(long, requires = "anonymize", help = "With --anonymize, move all timestamps by one random offset")]
+        shift_timestamps: bool,
+
+        #[arg(long, help = "One row per typing session (start, end, keystrokes, main app, WPM) instead of per event")]
+        sessions: bool,
+
+        #[arg(long = "exclude-app", value_name = "BUNDLE", help = "Leave out apps whose bundle ID contains this, ignoring case (repeatable)")]
+        exclude_apps: Vec<String>,
+
+        #[arg(long = "with-modifier", value_name = "MODIFIER", help = "Only keys typed while this modifier is held, e.g. cmd (repeatable: all must be held)")]
+        with_modifiers: Vec<models::event::Modifier>,
+
+        #[arg(long, value_name = "MINUTES", requires = "sessions", help = "With --sessions, a pause longer than this starts a new session [default: 5]")]
+        session_gap: Option<u32>,
+    },
+
+    #[command(about = "Render a keyboard heatmap as SVG")]
+    ExportHeatmap {
         #[arg(short, long, help = "Output file path")]
         output: String,
+
+        #[arg(long, help = "Keyboard layout [default: from config]")]
+        layout: Option<String>,
+
+        #[arg(long, default_value = "all", help = "Time range: 7d, 30d, 90d, ... or all")]
+        range: String,
+
+        #[arg(long, value_name = "PCT", help = "Full color at this percentile of key frequencies instead of the busiest key (e.g. 95)")]
+        cap_percentile: Option<f64>,
+
+        #[arg(long, help = "Allow writing outside the home directory (exports contain raw keystrokes)")]
+        allow_outside_home: bool,
     },
 
     #[command(about = "Show keystroke statistics")]
     Stats {
         #[arg(short, long, help = "Limit to last N days")]
         days: Option<u32>,
+
+        #[arg(long, value_name = "START-END", help = "Only local clock hours START to END, e.g. 9-17 or 22-6 (wraps past midnight)")]
+        hours: Option<models::hours::HourWindow>,
+
+        #[arg(long, value_name = "FILE", help = "Show statistics for events from a JSONL file (- for stdin, .gz is decompressed), held in memory instead of the database")]
+        input: Option<String>,
+
+        #[arg(long, value_name = "N", default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..), help = "Number of top keys to show")]
+        top: u32,
+
+        #[arg(long, value_name = "N", default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..), help = "Number of top applications to show")]
+        top_apps: u32,
+
+        #[arg(long, help = "Print the statistics as a JSON object (without the coverage summary)")]
+        json: bool,
     },
 
     #[command(about = "Analyze typing patterns")]
@@ -76,18 +177,171 @@ enum Commands {
         #[arg(short, long, default_value = "10", help = "Number of top items to show")]
         top: usize,
 
-        #[arg(long, default_value = "5000", help = "Max gap in ms to consider (filters outliers)")]
-        max_gap: i64,
+        #[arg(long, help = "Max gap in ms to consider (filters outliers) [default: from config]")]
+        max_gap: Option<i64>,
 
         #[arg(short, long, help = "Show detailed output including key codes and per-pair timing")]
         detailed: bool,
+
+        #[arg(long, value_name = "N", help = "Only analyze the most recent N events (fast preview)")]
+        limit_events: Option<usize>,
+
+        #[arg(long, help = "Report scissor bigrams (adjacent fingers reaching across 2+ rows)")]
+        scissors: bool,
+
+        #[arg(long, help = "Report estimated finger travel in key units, per finger and overall")]
+        effort: bool,
+
+        #[arg(long, value_name = "CORPUS", help = "Compare letter and letter-pair frequencies against a reference corpus: english")]
+        baseline: Option<analysis::baseline::Baseline>,
+
+        #[arg(long, help = "Don't count Shift presses that only typed capital letters")]
+        merge_shift: bool,
+
+        #[arg(long, help = "Report keys pressed together (rolls, chords) instead of one after another")]
+        chords: bool,
+
+        #[arg(long, value_name = "MS", default_value_t = analysis::chords::DEFAULT_CHORD_WINDOW_MS, help = "Max ms between presses of a chord")]
+        chord_window: i64,
+
+        #[arg(long, value_name = "N", help = "Minimum intervals per key pair for per-pair timing [default: from config]")]
+        min_samples: Option<usize>,
+
+        #[arg(long, value_name = "N", help = "Minimum occurrences for listed bigrams/trigrams [default: from config]")]
+        min_count: Option<u64>,
+
+        #[arg(long, help = "Count the same key twice (e.g. \"ee\") as a same-finger bigram [default: from config, off]")]
+        same_finger_repeats: bool,
+
+        #[arg(long, help = "Only list and analyze bigrams and trigrams made of letters, skipping Space/Shift transitions [default: from config, off]")]
+        letters_only: bool,
+
+        #[arg(short, long, default_value = "text", help = "Output format: text, or ndjson for one JSON object per line")]
+        format: String,
+
+        #[arg(long, value_name = "NAME", conflicts_with = "format", help = "Profile a single key (e.g. E, Space, 0x0E): rank, neighbors, hold and timing")]
+        key: Option<String>,
+
+        #[arg(long, conflicts_with_all = ["format", "key"], help = "Print only the headline numbers as one compact JSON object")]
+        summary_json: bool,
+
+        #[arg(long, value_name = "N", conflicts_with = "bin_edges", help = "Inter-key histogram with N equal buckets from 0 to 300ms, plus one for slower")]
+        bins: Option<usize>,
+
+        #[arg(long, value_name = "MS,MS,...", help = "Inter-key histogram bucket edges in ms, ascending, e.g. 0,25,50,100,200 [default: 0,50,...,250]")]
+        bin_edges: Option<analysis::histogram::HistogramEdges>,
+
+        #[arg(long, value_name = "FILE", conflicts_with = "limit_events", help = "Analyze events from a JSONL file (as written by export --format jsonl; - for stdin, .gz is decompressed) instead of the database")]
+        input: Option<String>,
+
+        #[arg(long = "exclude-app", value_name = "BUNDLE", help = "Leave out apps whose bundle ID contains this, ignoring case (repeatable)")]
+        exclude_apps: Vec<String>,
+
+        #[arg(long = "with-modifier", value_name = "MODIFIER", help = "Only keys typed while this modifier is held, e.g. cmd (repeatable: all must be held)")]
+        with_modifiers: Vec<models::event::Modifier>,
+
+        #[arg(long, value_name = "START-END", help = "Only local clock hours START to END, e.g. 9-17 or 22-6 (wraps past midnight)")]
+        hours: Option<models::hours::HourWindow>,
+
+        #[arg(long, help = "Leave out segments that look like gaming (a few keys, e.g. WASD, pressed over and over)")]
+        exclude_gaming: bool,
+
+        #[arg(long, value_name = "SOURCE", help = "Only keys typed with this input source, e.g. French or com.apple.keylayout.French; keys are named after it")]
+        input_source: Option<String>,
+
+        #[arg(long, help = "Treat modifiers held over 10s without a release as let go, so a lost release doesn't mark later keys as modified")]
+        release_stuck_modifiers: bool,
+
+        #[arg(long, value_name = "FILE", help = "Analyze finger use against a layout defined in a JSON file instead of the configured one")]
+        layout_file: Option<PathBuf>,
+    },
+
+    #[command(about = "Compare bigram timing before and after a date (e.g. a layout change)")]
+    Compare {
+        #[arg(long, value_name = "YYYY-MM-DD", help = "First day of the \"after\" window")]
+        split: String,
+
+        #[arg(long, default_value = "14", help = "Days in each window")]
+        days: u32,
+
+        #[arg(short, long, default_value = "10", help = "Number of bigrams to list per direction")]
+        top: usize,
+
+        #[arg(long, default_value = "5", help = "Minimum samples per bigram in each window")]
+        min_samples: usize,
+    },
+
+    #[command(about = "Print the events of a typing session or time range with relative timing")]
+    Replay {
+        #[arg(long, value_name = "N", help = "Replay the Nth most recent session (1 is the latest) [default: 1 without --from/--to]")]
+        session: Option<usize>,
+
+        #[arg(long, value_name = "TIME", help = "Start of the range: YYYY-MM-DD or \"YYYY-MM-DD HH:MM\" (local time)")]
+        from: Option<String>,
+
+        #[arg(long, value_name = "TIME", help = "End of the range, like --from; a date alone means the end of that day [default: now]")]
+        to: Option<String>,
+
+        #[arg(short, long, conflicts_with_all = ["from", "to"], help = "Look for sessions in the last N days [default: 1]")]
+        days: Option<u32>,
+
+        #[arg(long, value_name = "MINUTES", help = "A pause longer than this starts a new session [default: 5]")]
+        session_gap: Option<u32>,
+
+        #[arg(long, help = "Wait out the gaps between events (pauses capped at 2s)")]
+        realtime: bool,
+    },
+
+    #[command(about = "Print a one-line summary: daemon running, event count, last event, db size")]
+    Status {
+        #[arg(long, help = "Print the summary as a JSON object")]
+        json: bool,
     },
 
     #[command(about = "Check if Input Monitoring permission is granted")]
     CheckPermission,
 
+    #[command(about = "Scan the database for orphaned, unreleased or out-of-order events")]
+    Check {
+        #[arg(long, help = "Delete clearly corrupt rows (orphan releases, missing application)")]
+        fix: bool,
+
+        #[arg(long, help = "First run SQLite's full integrity check on the database file (slow on large databases)")]
+        integrity: bool,
+    },
+
+    #[command(about = "Copy events from another lurk database, skipping duplicates")]
+    Merge {
+        #[arg(help = "Database to read events from")]
+        source: PathBuf,
+
+        #[arg(long, value_name = "DB", help = "Database to merge into [default: ~/.lurk/events.db]")]
+        into: Option<PathBuf>,
+
+        #[arg(long, value_name = "KEY", help = "Encryption key of the source [default: the .key file next to it]")]
+        source_key: Option<String>,
+    },
+
     #[command(about = "Open interactive TUI dashboard")]
-    Dashboard,
+    Dashboard {
+        #[arg(long, help = "Require pressing q twice to quit")]
+        confirm_quit: bool,
+
+        #[arg(long, help = "Disable colors (also honored via the NO_COLOR environment variable)")]
+        no_color: bool,
+
+        #[arg(long, help = "Start with the light theme (toggle with t)")]
+        light: bool,
+
+        #[arg(long, value_name = "PCT", help = "Full color at this percentile of key frequencies instead of the busiest key (e.g. 95)")]
+        cap_percentile: Option<f64>,
+    },
+
+    #[command(about = "Show or change configuration")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
 
     #[command(about = "Delete old keystroke data")]
     Cleanup {
@@ -97,30 +351,389 @@ enum Commands {
         #[arg(short, long, help = "Skip confirmation prompt")]
         force: bool,
     },
+
+    #[command(about = "Re-encrypt the database with a new key and replace ~/.lurk/.key (stop the daemon first)")]
+    RotateKey,
 }
 
-fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("lurk=info".parse().unwrap()),
-        )
+#[derive(Subcommand)]
+enum ConfigAction {
+    #[command(about = "Print the effective configuration")]
+    Show,
+
+    #[command(about = "Print the effective value of a setting")]
+    Get { key: String },
+
+    #[command(about = "Store a setting in the database, overriding config.toml")]
+    Set { key: String, value: String },
+}
+
+/// Filter directive for the `-v`/`--quiet` flags. `RUST_LOG` takes precedence.
+fn log_directive(verbose: u8, quiet: bool) -> &'static str {
+    match (quiet, verbose) {
+        (true, _) => "lurk=warn",
+        (false, 0) => "lurk=info",
+        (false, 1) => "lurk=debug",
+        (false, _) => "lurk=trace",
+    }
+}
+
+/// Logs to the terminal, and for the daemon also to a daily-rotated
+/// `lurk.log.<date>` in `log_dir` so a launchd run leaves a trail. The
+/// returned guard flushes the file writer and must live as long as the process.
+fn init_logging(verbose: u8, quiet: bool, log_dir: Option<PathBuf>) -> Result<Option<WorkerGuard>> {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(log_directive(verbose, quiet)));
+
+    let (file_layer, guard) = match log_dir {
+        Some(dir) => {
+            secure_log_files(&dir)?;
+
+            let log_file = daemon::DailyLogFile::new(&dir, LOG_RETENTION_DAYS);
+            let (writer, guard) = tracing_appender::non_blocking(log_file);
+            (Some(fmt::layer().with_ansi(false).with_writer(writer)), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file_layer)
         .init();
 
+    Ok(guard)
+}
+
+/// Tightens log files written before they were created 0600.
+fn secure_log_files(dir: &PathBuf) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.file_name().is_some_and(|n| n.to_string_lossy().starts_with(LOG_FILE_NAME)) {
+            set_secure_file_permissions(&path)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    create_secure_dir(&get_data_dir())?;
+
+    let is_daemon = matches!(cli.command, None | Some(Commands::Daemon { .. }));
+    let log_dir = if is_daemon {
+        create_secure_dir(&get_log_dir())?;
+        Some(get_log_dir())
+    } else {
+        None
+    };
+    let _log_guard = init_logging(cli.verbose, cli.quiet, log_dir)?;
+
     match cli.command {
-        None | Some(Commands::Daemon) => run_daemon(),
-        Some(Commands::Export { format, output }) => run_export(&format, &output),
-        Some(Commands::Stats { days }) => run_stats(days),
-        Some(Commands::Analyze { top, max_gap, detailed }) => run_analyze(top, max_gap, detailed),
+        None => run_daemon(&load_config()?, false, Vec::new(), false, false, false, false),
+        Some(Commands::Daemon {
+            block_sensitive_titles,
+            block_titles,
+            record_window_titles,
+            record_characters,
+            force,
+            no_encryption,
+        }) => run_daemon(
+            &load_config()?,
+            block_sensitive_titles,
+            block_titles,
+            record_window_titles,
+            record_characters,
+            force,
+            no_encryption,
+        ),
+        Some(Commands::Export {
+            format,
+            output,
+            output_dir,
+            gzip,
+            allow_outside_home,
+            anonymize,
+            shift_timestamps,
+            sessions,
+            exclude_apps,
+            with_modifiers,
+            session_gap,
+        }) => {
+            let config = effective_config(load_config_or_default())?;
+            apply_key_naming(&config);
+            let options = cli::ExportOptions {
+                gzip,
+                allow_outside_home,
+                anonymize,
+                shift_timestamps,
+                exclude_apps,
+                with_modifiers,
+                max_gap_ms: Some(config.filter.max_gap_ms),
+                progress: true,
+            };
+            let output = match (output, output_dir) {
+                (Some(output), _) => PathBuf::from(output),
+                (None, Some(dir)) => cli::export::timestamped_export_path(&dir, &format),
+                (None, None) => return Err(anyhow!("Pass --output or --output-dir")),
+            };
+            if sessions {
+                let gap_ms = session_gap
+                    .map_or(analysis::sessions::DEFAULT_SESSION_GAP_MS, |minutes| {
+                        i64::from(minutes) * 60_000
+                    });
+                run_export_sessions(&format, &output, options, gap_ms)
+            } else {
+                run_export(&format, &output, options)
+            }
+        }
+        Some(Commands::ExportHeatmap {
+            output,
+            layout,
+            range,
+            cap_percentile,
+            allow_outside_home,
+        }) => {
+            let config = load_config()?;
+            apply_key_naming(&config);
+            run_export_heatmap(
+                &config,
+                &output,
+                layout.as_deref(),
+                &range,
+                cap_percentile,
+                allow_outside_home,
+            )
+        }
+        Some(Commands::Stats {
+            days,
+            hours,
+            input,
+            top,
+            top_apps,
+            json,
+        }) => {
+            let limits = cli::stats::StatsLimits {
+                keys: top as usize,
+                apps: top_apps as usize,
+            };
+            apply_key_naming(&load_config_or_default());
+            run_stats(days, hours, input.as_deref(), limits, json)
+        }
+        Some(Commands::Replay {
+            session,
+            from,
+            to,
+            days,
+            session_gap,
+            realtime,
+        }) => {
+            let gap_ms = session_gap.map_or(analysis::sessions::DEFAULT_SESSION_GAP_MS, |minutes| {
+                i64::from(minutes) * 60_000
+            });
+            apply_key_naming(&load_config_or_default());
+            run_replay(session, from.as_deref(), to.as_deref(), days, gap_ms, realtime)
+        }
+        Some(Commands::Analyze {
+            top,
+            max_gap,
+            detailed,
+            limit_events,
+            scissors,
+            effort,
+            baseline,
+            merge_shift,
+            chords,
+            chord_window,
+            min_samples,
+            min_count,
+            same_finger_repeats,
+            letters_only,
+            format,
+            key,
+            summary_json,
+            bins,
+            bin_edges,
+            input,
+            exclude_apps,
+            with_modifiers,
+            hours,
+            exclude_gaming,
+            input_source,
+            release_stuck_modifiers,
+            layout_file,
+        }) => {
+            let config = effective_config(load_config()?)?;
+            apply_key_naming(&config);
+            run_analyze(
+                &config,
+                FilterArgs {
+                    max_gap,
+                    min_samples,
+                    min_count,
+                    same_finger_repeats,
+                    letters_only,
+                },
+                limit_events,
+                EventSelection {
+                    input,
+                    exclude_apps,
+                    with_modifiers,
+                    hours,
+                    exclude_gaming,
+                    input_source,
+                    release_stuck_modifiers,
+                },
+                ReportArgs {
+                    top,
+                    detailed,
+                    scissors,
+                    effort,
+                    baseline,
+                    merge_shift,
+                    chord_window: chords.then_some(chord_window),
+                    format,
+                    key,
+                    summary_json,
+                    bins,
+                    bin_edges,
+                    layout_file,
+                },
+            )
+        }
+        Some(Commands::Compare {
+            split,
+            days,
+            top,
+            min_samples,
+        }) => {
+            let config = effective_config(load_config()?)?;
+            apply_key_naming(&config);
+            run_compare(&config, &split, days, top, min_samples)
+        }
+        Some(Commands::Status { json }) => run_status(json),
         Some(Commands::CheckPermission) => check_permission(),
-        Some(Commands::Dashboard) => run_dashboard(),
+        Some(Commands::Check { fix, integrity }) => run_check(fix, integrity),
+        Some(Commands::Merge {
+            source,
+            into,
+            source_key,
+        }) => run_merge(&source, into, source_key.as_deref()),
+        Some(Commands::Dashboard {
+            confirm_quit,
+            no_color,
+            light,
+            cap_percentile,
+        }) => {
+            let config = effective_config(load_config()?)?;
+            apply_key_naming(&config);
+            run_dashboard(
+                &config,
+                confirm_quit,
+                tui::Theme::from_options(no_color, light),
+                cap_percentile,
+            )
+        }
+        Some(Commands::Config { action }) => run_config(&load_config()?, action),
         Some(Commands::Cleanup { days, force }) => run_cleanup(days, force),
+        Some(Commands::RotateKey) => run_rotate_key(),
+    }
+}
+
+/// Loads `config.toml`, writing the commented defaults on first run. Only
+/// commands whose results depend on the config load it, so a broken file
+/// doesn't lock anyone out of `status` or `check`.
+fn load_config() -> Result<config::Config> {
+    config::Config::load_or_init(&get_config_path())
+}
+
+/// Names keys after `key_names` when it's set, for commands that print them.
+fn apply_key_naming(config: &config::Config) {
+    if let Some(naming) = config.key_names {
+        models::keycode::set_key_naming(naming);
     }
 }
 
-fn run_dashboard() -> Result<()> {
+/// `config` with the overrides stored by `lurk config set` applied, as every
+/// command reading an override key should see it.
+fn effective_config(config: config::Config) -> Result<config::Config> {
+    config.with_overrides(&stored_overrides()?)
+}
+
+/// `load_config` for commands that only use the config for presentation:
+/// a broken file is reported and the defaults are used.
+fn load_config_or_default() -> config::Config {
+    load_config().unwrap_or_else(|e| {
+        warn!("{:#}; using default settings", e);
+        config::Config::default()
+    })
+}
+
+/// Opens the database and layers its stored overrides on top of `config`.
+fn open_with_overrides(config: &config::Config) -> Result<(storage::Database, config::Config)> {
+    let db_path = get_db_path();
+    let db = storage::Database::new(&db_path)?;
+    set_secure_file_permissions(&db_path)?;
+    let config = config.clone().with_overrides(&db.get_all_config()?)?;
+    Ok((db, config))
+}
+
+/// Overrides stored in the database, read without creating a database (or
+/// its key) when none exists yet.
+fn stored_overrides() -> Result<Vec<(String, String)>> {
+    let db_path = get_db_path();
+    if !db_path.exists() {
+        return Ok(vec![]);
+    }
+    storage::Database::open_read_only(&db_path)?.get_all_config()
+}
+
+fn run_config(config: &config::Config, action: ConfigAction) -> Result<()> {
+    match action {
+        ConfigAction::Show => {
+            let overrides = stored_overrides()?;
+            let effective = config.clone().with_overrides(&overrides)?;
+            println!("# {}", get_config_path().display());
+            print!("{}", toml::to_string_pretty(&effective)?);
+
+            if !overrides.is_empty() {
+                println!("\n# Overridden in database:");
+                for (key, value) in overrides {
+                    println!("#   {} = {}", key, value);
+                }
+            }
+        }
+        ConfigAction::Get { key } => {
+            let effective = effective_config(config.clone())?;
+            match effective.override_value(&key) {
+                Some(value) => println!("{}", value),
+                None => {
+                    return Err(anyhow!(
+                        "Unknown config key '{}'. Known keys: {}",
+                        key,
+                        config::OVERRIDE_KEYS.join(", ")
+                    ))
+                }
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            let (db, mut effective) = open_with_overrides(config)?;
+            effective.apply_override(&key, &value)?;
+            db.set_config(&key, value.trim())?;
+            println!("{} = {}", key, value.trim());
+        }
+    }
+
+    Ok(())
+}
+
+fn run_dashboard(
+    config: &config::Config,
+    confirm_quit: bool,
+    theme: tui::Theme,
+    cap_percentile: Option<f64>,
+) -> Result<()> {
     let db_path = get_db_path();
 
     if !db_path.exists() {
@@ -129,10 +742,23 @@ fn run_dashboard() -> Result<()> {
         return Ok(());
     }
 
-    tui::run_dashboard(&db_path)
+    let layout = models::keyboard_layout::layout_by_name(&config.layout)?;
+    let mut config = config.clone();
+    if cap_percentile.is_some() {
+        config.dashboard.heatmap_cap_percentile = cap_percentile;
+    }
+    tui::run_dashboard(&db_path, &get_config_path(), confirm_quit, layout, theme, &config)
 }
 
-fn run_daemon() -> Result<()> {
+fn run_daemon(
+    config: &config::Config,
+    block_sensitive_titles: bool,
+    block_titles: Vec<String>,
+    record_window_titles: bool,
+    record_characters: bool,
+    force: bool,
+    no_encryption: bool,
+) -> Result<()> {
     info!("Starting lurk daemon...");
 
     daemon::ensure_permissions()?;
@@ -140,34 +766,176 @@ fn run_daemon() -> Result<()> {
     let data_dir = get_data_dir();
     create_secure_dir(&data_dir)?;
 
-    let log_dir = data_dir.join("logs");
-    create_secure_dir(&log_dir)?;
+    let pidfile = daemon::PidFile::acquire(&get_pid_path(), force)?;
 
-    let db_path = get_db_path();
-    let db = storage::Database::new(&db_path)?;
-    set_secure_file_permissions(&db_path)?;
-    info!("Database initialized: {:?}", db_path);
+    if no_encryption {
+        // Marks the database plaintext, which later opens keep it.
+        storage::Database::new_unencrypted(get_db_path())?;
+    }
+    let (db, config) = open_with_overrides(config)?;
+    apply_key_naming(&config);
+    if db.is_plaintext()? {
+        let db_path = get_db_path();
+        warn!("Encryption is OFF: keystrokes in {:?} are stored in plaintext", db_path);
+        eprintln!("WARNING: encryption is off; {:?} holds keystrokes in plaintext", db_path);
+    }
+    info!("Database initialized: {:?}", get_db_path());
+
+    // A power loss can leave a damaged file behind; appending to it could
+    // make things worse.
+    let problems = db.storage_problems(false)?;
+    if !problems.is_empty() {
+        for problem in &problems {
+            error!("Database check: {}", problem);
+        }
+        return Err(anyhow!(
+            "{:?} failed SQLite's quick_check ({} problems); not writing to it. {}",
+            get_db_path(),
+            problems.len(),
+            cli::check::storage_recovery_hint(&get_db_path())
+        ));
+    }
+    info!("Database quick_check passed");
 
-    let (tx, rx) = channel();
+    if let Some(days) = config.retention_days {
+        let deleted = db.cleanup_old_events(cutoff_for_days(days))?;
+        info!("Retention: deleted {} events older than {} days", deleted, days);
+    }
 
-    thread::spawn(move || {
-        for event in rx {
-            if let Err(e) = db.insert_event(&event) {
-                error!("Failed to write event: {}", e);
-            }
+    let sink = daemon::MilestoneSink::new(db, config.capture.milestone_notifications)?;
+    let mut writer =
+        daemon::RetryWriter::new(sink, get_pending_path(), daemon::RetryConfig::default());
+    match writer.replay_pending() {
+        Ok(0) => {}
+        Ok(replayed) => info!("Replayed {} events left pending by a previous run", replayed),
+        Err(e) => error!("Failed to replay pending events: {}", e),
+    }
+
+    let (tx, rx) = channel();
+    let tx = daemon::EventSender::new(tx);
+    let writer = thread::spawn(move || writer.run(rx));
+    let closing = tx.clone();
+    daemon::shutdown::on_termination(move || {
+        // Let the writer store what's queued, or spill it to the pending file.
+        closing.close();
+        if writer.join().is_err() {
+            error!("Event writer panicked during shutdown");
         }
-    });
+        // `process::exit` skips destructors, so remove the pidfile here.
+        drop(pidfile);
+    })?;
 
     info!("Starting event monitor...");
     info!("Press Ctrl+C to stop");
 
-    let monitor = daemon::EventMonitor::new(tx);
+    let capture = &config.capture;
+    let block_sensitive_titles = block_sensitive_titles || capture.block_sensitive_titles;
+    let record_window_titles = record_window_titles || capture.record_window_titles;
+    let record_characters = record_characters || capture.record_characters;
+
+    let mut title_blocklist = block_titles;
+    title_blocklist.extend(capture.title_blocklist.iter().cloned());
+    if block_sensitive_titles {
+        title_blocklist.extend(
+            daemon::DEFAULT_SENSITIVE_TITLE_KEYWORDS
+                .iter()
+                .map(|keyword| keyword.to_string()),
+        );
+    }
+    if !title_blocklist.is_empty() {
+        info!("Window-title blocklist enabled ({} keywords)", title_blocklist.len());
+    }
+
+    if record_window_titles {
+        info!("Recording window titles");
+    }
+
+    if record_characters {
+        info!("Recording typed characters");
+    }
+
+    if !capture.record_releases {
+        info!("Not recording key releases; hold durations will be unavailable");
+    }
+
+    let sampling = daemon::SamplingConfig {
+        every_nth: capture.sample_every,
+        max_per_second: capture.max_events_per_second,
+    };
+    if sampling.is_enabled() {
+        info!(
+            "Sampling events (1 in {}, max {}/s); timing stats will be less accurate",
+            sampling.every_nth.unwrap_or(1),
+            sampling
+                .max_per_second
+                .map_or_else(|| "unlimited".to_string(), |max| max.to_string())
+        );
+    }
+
+    if let Some(ms) = capture.debounce_ms.filter(|&ms| ms > 0) {
+        info!("Dropping repeated presses of a key within {}ms", ms);
+    }
+
+    let app_blocklist = match &capture.app_blocklist_path {
+        Some(path) => config::read_bundle_list(path)?,
+        None => Vec::new(),
+    };
+    let app_allowlist = match &capture.app_allowlist_path {
+        Some(path) => config::read_bundle_list(path)?,
+        None => Vec::new(),
+    };
+
+    let monitor = daemon::EventMonitor::new(
+        tx,
+        daemon::MonitorConfig {
+            title_blocklist,
+            record_window_titles,
+            record_characters,
+            record_releases: capture.record_releases,
+            app_blocklist,
+            app_allowlist,
+            app_modes: capture.app_modes.clone(),
+            sampling,
+            debounce_ms: capture.debounce_ms,
+        },
+    );
+
+    if let Some(minutes) = capture.watchdog_idle_minutes.filter(|&minutes| minutes > 0) {
+        let idle_threshold = Duration::from_secs(minutes * 60);
+        let watchdog = daemon::Watchdog::new(monitor.heartbeat(), idle_threshold);
+        thread::spawn(move || run_watchdog(watchdog));
+    }
+
     monitor.start()?;
 
     Ok(())
 }
 
-fn run_export(format: &str, output: &str) -> Result<()> {
+/// Exits the daemon if Input Monitoring is revoked while it runs; `rdev`
+/// keeps listening in that case but never delivers another event.
+fn run_watchdog(watchdog: daemon::Watchdog) {
+    loop {
+        thread::sleep(watchdog.poll_interval());
+        match watchdog.check(daemon::check_input_monitoring_permission) {
+            daemon::WatchdogStatus::Active => {}
+            daemon::WatchdogStatus::Idle => {
+                debug!("No input for a while; Input Monitoring is still granted")
+            }
+            daemon::WatchdogStatus::Revoked => {
+                error!(
+                    "Input Monitoring permission was revoked; no keystrokes are being captured. \
+                     Re-grant it in System Settings -> Privacy & Security -> Input Monitoring \
+                     and restart lurk."
+                );
+                // Flushes queued events and removes the pidfile before exiting.
+                daemon::shutdown::request_shutdown(1);
+                return;
+            }
+        }
+    }
+}
+
+fn run_export(format: &str, output: &Path, options: cli::ExportOptions) -> Result<()> {
     let db_path = get_db_path();
 
     if !db_path.exists() {
@@ -176,20 +944,26 @@ fn run_export(format: &str, output: &str) -> Result<()> {
         return Ok(());
     }
 
-    let db = storage::Database::new(&db_path)?;
+    let db = storage::Database::open_read_only(&db_path)?;
 
     match format {
-        "csv" => cli::export_csv(&db, output)?,
-        "json" => cli::export_json(&db, output)?,
+        "csv" => cli::export_csv(&db, output, options)?,
+        "json" => cli::export_json(&db, output, options)?,
+        "jsonl" => cli::export_jsonl(&db, output, options)?,
         _ => {
-            eprintln!("Unknown format: {}. Use 'csv' or 'json'.", format);
+            eprintln!("Unknown format: {}. Use 'csv', 'json' or 'jsonl'.", format);
         }
     }
 
     Ok(())
 }
 
-fn run_stats(days: Option<u32>) -> Result<()> {
+fn run_export_sessions(
+    format: &str,
+    output: &Path,
+    options: cli::ExportOptions,
+    gap_ms: i64,
+) -> Result<()> {
     let db_path = get_db_path();
 
     if !db_path.exists() {
@@ -198,13 +972,30 @@ fn run_stats(days: Option<u32>) -> Result<()> {
         return Ok(());
     }
 
-    let db = storage::Database::new(&db_path)?;
-    cli::show_stats(&db, days)?;
+    let db = storage::Database::open_read_only(&db_path)?;
+
+    match format {
+        "csv" => cli::export_sessions_csv(&db, output, options, gap_ms)?,
+        "json" => cli::export_sessions_json(&db, output, options, gap_ms)?,
+        _ => {
+            eprintln!("Unknown format: {}. Use 'csv' or 'json'.", format);
+        }
+    }
 
     Ok(())
 }
 
-fn run_analyze(top: usize, max_gap: i64, detailed: bool) -> Result<()> {
+fn run_export_heatmap(
+    config: &config::Config,
+    output: &str,
+    layout: Option<&str>,
+    range: &str,
+    cap_percentile: Option<f64>,
+    allow_outside_home: bool,
+) -> Result<()> {
+    let layout = models::keyboard_layout::layout_by_name(layout.unwrap_or(&config.layout))?;
+    let days = cli::heatmap::parse_range_days(range)?;
+
     let db_path = get_db_path();
 
     if !db_path.exists() {
@@ -213,31 +1004,487 @@ fn run_analyze(top: usize, max_gap: i64, detailed: bool) -> Result<()> {
         return Ok(());
     }
 
-    let db = storage::Database::new(&db_path)?;
-    let events = db.get_all_events()?;
+    let db = storage::Database::open_read_only(&db_path)?;
+    cli::export_heatmap(&db, output, layout.as_ref(), days, cap_percentile, allow_outside_home)
+}
+
+/// With `input`, the events are loaded into an in-memory database and the
+/// one on disk (and its key) is never touched.
+fn run_stats(
+    days: Option<u32>,
+    hours: Option<models::hours::HourWindow>,
+    input: Option<&str>,
+    limits: cli::stats::StatsLimits,
+    json: bool,
+) -> Result<()> {
+    let show = |db: &storage::Database| {
+        models::keycode::use_input_source_naming(&db.get_input_source_namings()?);
+        if json {
+            let report = cli::StatsReport::collect(db, days, hours, limits)?;
+            println!("{}", report.to_json()?);
+            Ok(())
+        } else {
+            cli::show_stats(db, days, hours, limits)
+        }
+    };
+
+    if let Some(path) = input {
+        let db = storage::Database::from_events(&cli::input::read_events_jsonl_from(path)?)?;
+        return show(&db);
+    }
+
+    let db_path = get_db_path();
+
+    if !db_path.exists() {
+        eprintln!("No database found at {:?}", db_path);
+        eprintln!("Make sure the daemon has been run at least once.");
+        return Ok(());
+    }
+
+    let db = storage::Database::open_read_only(&db_path)?;
+    show(&db)
+}
+
+/// Replays session `session` of the range, or of the last `days` without
+/// one; with `from`/`to` and no session, the whole range.
+fn run_replay(
+    session: Option<usize>,
+    from: Option<&str>,
+    to: Option<&str>,
+    days: Option<u32>,
+    gap_ms: i64,
+    realtime: bool,
+) -> Result<()> {
+    let db_path = get_db_path();
+
+    if !db_path.exists() {
+        eprintln!("No database found at {:?}", db_path);
+        eprintln!("Make sure the daemon has been run at least once.");
+        return Ok(());
+    }
+
+    let db = storage::Database::open_read_only(&db_path)?;
+    let ranged = from.is_some() || to.is_some();
+    let events = if ranged {
+        let start = from.map(cli::replay::parse_local_time).transpose()?.unwrap_or(0);
+        let end = to.map(cli::replay::parse_local_end_time).transpose()?.unwrap_or(i64::MAX);
+        db.get_events_in_range(start, end)?
+    } else {
+        db.get_events_since(days.unwrap_or(1))?
+    };
 
     if events.is_empty() {
-        eprintln!("No keystroke data recorded yet.");
+        eprintln!("No keystrokes recorded in this period.");
         return Ok(());
     }
 
-    let filter_config = analysis::FilterConfig {
-        max_gap_ms: max_gap,
-        ..Default::default()
+    let events = match session {
+        Some(n) => cli::replay::session_events(&events, gap_ms, n)?,
+        None if !ranged => cli::replay::session_events(&events, gap_ms, 1)?,
+        None => events,
+    };
+
+    println!("{}\n", cli::replay::replay_header(&events));
+    cli::replay::replay(&events, realtime, &mut std::io::stdout().lock())
+}
+
+fn run_status(json: bool) -> Result<()> {
+    let db_path = get_db_path();
+    let db = if db_path.exists() {
+        Some(storage::Database::open_read_only(&db_path)?)
+    } else {
+        None
+    };
+    let db_bytes = fs::metadata(&db_path).map_or(0, |metadata| metadata.len());
+    let pid = daemon::running_daemon_pid(&get_pid_path());
+
+    let report = cli::StatusReport::collect(db.as_ref(), pid, db_bytes)?;
+    if json {
+        println!("{}", report.to_json()?);
+    } else {
+        println!("{}", report.to_line());
+    }
+
+    Ok(())
+}
+
+fn print_key_profile(profile: &analysis::KeyProfile, top: usize) {
+    println!("=== Key Profile: {} (0x{:02X}) ===\n", profile.key_name, profile.key_code);
+
+    let Some(rank) = profile.rank else {
+        println!("Never pressed in the analyzed events.");
+        return;
+    };
+    println!(
+        "Presses:    {} ({:.2}%), rank {} of {}",
+        profile.presses, profile.percentage, rank, profile.keys_ranked
+    );
+    match &profile.hold {
+        Some(hold) => println!(
+            "Hold:       median={}ms mean={:.1}ms p95={}ms (n={})",
+            hold.median_ms, hold.mean_ms, hold.p95_ms, hold.sample_count
+        ),
+        None => println!("Hold:       - (no releases recorded)"),
+    }
+    let median_or_dash =
+        |median: Option<i64>| median.map_or_else(|| "-".to_string(), |ms| format!("{}ms", ms));
+    println!(
+        "Incoming:   median={} (n={})",
+        median_or_dash(profile.incoming_median_ms),
+        profile.incoming_samples
+    );
+    println!(
+        "Outgoing:   median={} (n={})",
+        median_or_dash(profile.outgoing_median_ms),
+        profile.outgoing_samples
+    );
+
+    for (title, neighbors) in [
+        ("Preceded By", &profile.preceded_by),
+        ("Followed By", &profile.followed_by),
+    ] {
+        println!("\n--- {} ---", title);
+        for (i, neighbor) in neighbors.iter().take(top).enumerate() {
+            println!(
+                "{:2}. {:15} {:>8} ({:.2}%)",
+                i + 1,
+                neighbor.key_name,
+                neighbor.count,
+                neighbor.percentage
+            );
+        }
+    }
+}
+
+fn run_compare(
+    config: &config::Config,
+    split: &str,
+    days: u32,
+    top: usize,
+    min_samples: usize,
+) -> Result<()> {
+    let db_path = get_db_path();
+
+    if !db_path.exists() {
+        eprintln!("No database found at {:?}", db_path);
+        eprintln!("Make sure the daemon has been run at least once.");
+        return Ok(());
+    }
+
+    let split = cli::compare::parse_split_date(split)?;
+    let db = storage::Database::open_read_only(&db_path)?;
+    cli::show_comparison(&db, split, days, top, min_samples, &config.filter)
+}
+
+/// Command-line overrides for the configured `FilterConfig`.
+struct FilterArgs {
+    max_gap: Option<i64>,
+    min_samples: Option<usize>,
+    min_count: Option<u64>,
+    /// Count repeated keys as same-finger bigrams, whatever the config says.
+    same_finger_repeats: bool,
+    /// Letter-only n-grams, whatever the config says.
+    letters_only: bool,
+}
+
+impl FilterArgs {
+    fn apply(&self, base: &analysis::FilterConfig) -> analysis::FilterConfig {
+        analysis::FilterConfig {
+            max_gap_ms: self.max_gap.unwrap_or(base.max_gap_ms),
+            min_pair_samples: self.min_samples.unwrap_or(base.min_pair_samples),
+            min_ngram_count: self.min_count.unwrap_or(base.min_ngram_count),
+            same_finger_repeats: self.same_finger_repeats || base.same_finger_repeats,
+            letters_only_ngrams: self.letters_only || base.letters_only_ngrams,
+            ..base.clone()
+        }
+    }
+}
+
+/// Which recorded events `analyze` looks at.
+struct EventSelection {
+    /// Read events from this JSONL file (`-` for stdin) instead of the database.
+    input: Option<String>,
+    exclude_apps: Vec<String>,
+    with_modifiers: Vec<models::event::Modifier>,
+    hours: Option<models::hours::HourWindow>,
+    /// Drop typing segments `is_gaming_segment` flags.
+    exclude_gaming: bool,
+    /// Keep only events typed with this input source; see `is_input_source`.
+    input_source: Option<String>,
+    /// Add releases for modifiers `find_stuck_modifiers` flags.
+    release_stuck_modifiers: bool,
+}
+
+/// What `analyze` reports and in which format.
+struct ReportArgs {
+    top: usize,
+    detailed: bool,
+    scissors: bool,
+    /// Report `FingerTravel`.
+    effort: bool,
+    /// Report a `BaselineComparison` against this corpus.
+    baseline: Option<analysis::baseline::Baseline>,
+    merge_shift: bool,
+    /// Report chords, counting presses this many ms apart as simultaneous.
+    chord_window: Option<i64>,
+    format: String,
+    /// Print only this key's profile.
+    key: Option<String>,
+    /// Print only `AnalysisSummary` as JSON.
+    summary_json: bool,
+    /// Equal inter-key histogram buckets up to `DEFAULT_HISTOGRAM_MAX_MS`.
+    bins: Option<usize>,
+    /// Explicit histogram bucket edges; takes precedence over `bins`.
+    bin_edges: Option<analysis::histogram::HistogramEdges>,
+    /// Custom layout to use instead of `config.layout`.
+    layout_file: Option<PathBuf>,
+}
+
+/// Events `analyze` starts from, before any `EventSelection` filter.
+struct LoadedEvents {
+    events: Vec<models::KeystrokeEvent>,
+    /// Events in the database, when only the most recent were loaded.
+    sampled_from: Option<i64>,
+}
+
+/// Reads `input` (JSONL, `-` for stdin) if given, without touching the
+/// database; otherwise loads from the database. `None` if there is no
+/// database yet, after saying so.
+fn load_analysis_events(
+    input: Option<&str>,
+    limit_events: Option<usize>,
+    quiet: bool,
+) -> Result<Option<LoadedEvents>> {
+    if let Some(path) = input {
+        return Ok(Some(LoadedEvents {
+            events: cli::input::read_events_jsonl_from(path)?,
+            sampled_from: None,
+        }));
+    }
+
+    let db_path = get_db_path();
+
+    if !db_path.exists() {
+        eprintln!("No database found at {:?}", db_path);
+        eprintln!("Make sure the daemon has been run at least once.");
+        return Ok(None);
+    }
+
+    let db = storage::Database::open_read_only(&db_path)?;
+    let (events, sampled_from) = match limit_events {
+        Some(limit) => (db.get_recent_events(limit)?, Some(db.get_total_count()?)),
+        None => {
+            let total = db.get_total_count()? as u64;
+            let mut progress = cli::progress::Progress::new("Loading events", total, !quiet);
+            let mut events = Vec::with_capacity(total as usize);
+            db.for_each_event(|event| {
+                progress.tick();
+                events.push(event);
+                Ok(())
+            })?;
+            progress.finish();
+            (events, None)
+        }
     };
+    Ok(Some(LoadedEvents {
+        events,
+        sampled_from,
+    }))
+}
+
+/// The layout in `layout_file` if given, else the configured built-in one.
+fn load_layout(
+    config: &config::Config,
+    layout_file: Option<&Path>,
+) -> Result<Box<dyn models::keyboard_layout::KeyboardLayout>> {
+    match layout_file {
+        Some(path) => Ok(Box::new(models::layout_file::FileLayout::load(path)?)),
+        None => models::keyboard_layout::layout_by_name(&config.layout),
+    }
+}
 
-    let segments = filter_config.filter_events_by_gap(&events);
+fn run_analyze(
+    config: &config::Config,
+    filter_args: FilterArgs,
+    limit_events: Option<usize>,
+    selection: EventSelection,
+    report: ReportArgs,
+) -> Result<()> {
+    let ReportArgs {
+        top,
+        detailed,
+        scissors,
+        effort,
+        baseline,
+        merge_shift,
+        chord_window,
+        format,
+        key,
+        summary_json,
+        bins,
+        bin_edges,
+        layout_file,
+    } = report;
+    let layout = load_layout(config, layout_file.as_deref())?;
+    let histogram_edges = match (bin_edges, bins) {
+        (Some(edges), _) => edges,
+        (None, Some(bins)) => analysis::histogram::HistogramEdges::uniform(
+            bins,
+            analysis::histogram::DEFAULT_HISTOGRAM_MAX_MS,
+        )?,
+        (None, None) => analysis::histogram::HistogramEdges::default(),
+    };
+    let ndjson = match format.as_str() {
+        "text" => false,
+        "ndjson" => true,
+        _ => return Err(anyhow!("Unknown format: {}. Use 'text' or 'ndjson'.", format)),
+    };
+    let key_code = key
+        .map(|name| {
+            models::keycode::KeyCode::from_name(&name).ok_or_else(|| {
+                anyhow!("Unknown key: {}. Use a name like E or Space, or a code like 0x0E.", name)
+            })
+        })
+        .transpose()?;
+
+    // Loading dominates on big databases; machine-readable output stays quiet.
+    let quiet = ndjson || summary_json;
+    let input = selection.input.as_deref();
+    let Some(loaded) = load_analysis_events(input, limit_events, quiet)? else {
+        return Ok(());
+    };
+    let LoadedEvents {
+        mut events,
+        sampled_from,
+    } = loaded;
+    // Typing on either side of an excluded app is joined up; gaps longer than
+    // max_gap_ms still split segments below.
+    events.retain(|e| !analysis::filters::is_excluded_app(&e.application, &selection.exclude_apps));
+    if let Some(wanted) = &selection.input_source {
+        events.retain(|e| analysis::filters::is_input_source(e.input_source.as_deref(), wanted));
+    }
+    if selection.release_stuck_modifiers {
+        events = analysis::stuck_modifiers::release_stuck_modifiers(
+            &events,
+            analysis::stuck_modifiers::STUCK_MODIFIER_MS,
+        );
+    }
+    let filter_config = filter_args.apply(&config.filter);
+    if !selection.with_modifiers.is_empty() {
+        if !quiet && events.iter().all(|e| e.event_type == models::EventType::Press) {
+            eprintln!("{}", analysis::filters::MISSING_RELEASES_WARNING);
+        }
+        events = analysis::filters::with_modifiers(
+            &events,
+            &selection.with_modifiers,
+            filter_config.max_gap_ms,
+        );
+    }
+    if let Some(hours) = selection.hours {
+        events.retain(|e| hours.contains_timestamp(e.timestamp));
+    }
+
+    if events.is_empty() {
+        eprintln!("No keystroke data recorded yet.");
+        return Ok(());
+    }
+    // Keys are counted by position, so one set of names has to do for the
+    // whole report: the most common layout's, unless `key_names` fixes it.
+    let presses = events.iter().filter(|e| e.event_type == models::EventType::Press);
+    let sources = presses.map(|e| e.input_source.as_deref());
+    let namings = models::keycode::InputSourceNamings::from_presses(sources);
+    let naming = models::keycode::use_input_source_naming(&namings);
+    if namings.is_mixed() && !quiet {
+        eprintln!(
+            "Note: keys were typed on several layouts ({}); names follow {}. \
+             Use --input-source to analyze one at a time.",
+            namings.summary(),
+            naming.label()
+        );
+    }
+
+    let mut segments = filter_config.filter_events_by_gap(&events);
     let segment_count = segments.len();
+    let (typing_segments, gaming) = analysis::gaming::split_gaming_segments(segments.clone());
+    if selection.exclude_gaming {
+        segments = typing_segments;
+    }
     let filtered_events: Vec<_> = segments.into_iter().flatten().cloned().collect();
 
+    let freq_analysis = if merge_shift {
+        analysis::FrequencyAnalysis::with_filter(
+            &analysis::shift::without_paired_shifts(&filtered_events),
+            &filter_config,
+        )
+    } else {
+        analysis::FrequencyAnalysis::with_filter(&filtered_events, &filter_config)
+    };
+
+    if let Some(key_code) = key_code {
+        let timing = analysis::TimingAnalysis::from_events(&filtered_events, filter_config.clone());
+        let profile =
+            analysis::KeyProfile::new(key_code.0, &filtered_events, &freq_analysis, &timing);
+        print_key_profile(&profile, top);
+        return Ok(());
+    }
+
+    if summary_json {
+        let timing = analysis::TimingAnalysis::from_events(&filtered_events, filter_config.clone());
+        let summary =
+            cli::AnalysisSummary::new(&filtered_events, &freq_analysis, &timing, layout.as_ref());
+        println!("{}", summary.to_json()?);
+        return Ok(());
+    }
+
+    if ndjson {
+        use std::io::{self, Write};
+
+        let timing = analysis::TimingAnalysis::from_events(&filtered_events, filter_config.clone());
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        let min_count = filter_config.min_ngram_count;
+        cli::write_analysis_ndjson(&mut out, &freq_analysis, &timing, top, min_count)?;
+        out.flush()?;
+        return Ok(());
+    }
+
     println!("=== Lurk Analysis ===\n");
+    if let Some(total) = sampled_from {
+        println!("Sampled:          most recent {} of {} events", events.len(), total);
+    }
     println!("Total events:     {}", events.len());
-    println!("Typing segments:  {} (gaps > {}ms filtered)", segment_count, max_gap);
+    println!(
+        "Typing segments:  {} (gaps > {}ms filtered)",
+        segment_count, filter_config.max_gap_ms
+    );
+    if gaming.gaming_segments > 0 || selection.exclude_gaming {
+        println!(
+            "Gaming segments:  {} ({} events){}",
+            gaming.gaming_segments,
+            gaming.gaming_events,
+            if selection.exclude_gaming { ", excluded" } else { ", kept (see --exclude-gaming)" }
+        );
+    }
     println!("Analyzed events:  {}\n", filtered_events.len());
 
-    let freq_analysis = analysis::FrequencyAnalysis::from_events(&filtered_events);
+    let shift_analysis = analysis::ShiftAnalysis::from_events(&filtered_events);
 
-    println!("Total key presses: {}\n", freq_analysis.total_presses);
+    println!("Total key presses: {}", freq_analysis.total_presses);
+    println!(
+        "Unnamed keys:      {} ({:.2}%){}\n",
+        freq_analysis.unnamed_presses(),
+        freq_analysis.unnamed_pct(),
+        if freq_analysis.unnamed_presses() > 0 {
+            " - shown as Unknown(0x..); the key table may need extending"
+        } else {
+            ""
+        }
+    );
+
+    println!("--- Key Categories ---");
+    for (category, count) in freq_analysis.category_counts() {
+        let pct = count as f64 / freq_analysis.total_presses.max(1) as f64 * 100.0;
+        println!("    {:15} {:>8} ({:.2}%)", category.label(), count, pct);
+    }
 
     println!("--- Top {} Keys ---", top);
     for (i, key) in freq_analysis.top_keys(top).iter().enumerate() {
@@ -261,8 +1508,10 @@ fn run_analyze(top: usize, max_gap: i64, detailed: bool) -> Result<()> {
         }
     }
 
-    println!("\n--- Top {} Bigrams ---", top);
-    for (i, bigram) in freq_analysis.top_bigrams(top).iter().enumerate() {
+    let letters_note = if filter_config.letters_only_ngrams { " (letters only)" } else { "" };
+    println!("\n--- Top {} Bigrams{} ---", top, letters_note);
+    let min_count = filter_config.min_ngram_count;
+    for (i, bigram) in freq_analysis.top_bigrams_min(top, min_count).iter().enumerate() {
         if detailed {
             println!(
                 "{:2}. {:25} (0x{:02X}->0x{:02X}) {:>6} ({:.2}%)",
@@ -284,8 +1533,8 @@ fn run_analyze(top: usize, max_gap: i64, detailed: bool) -> Result<()> {
         }
     }
 
-    println!("\n--- Top {} Trigrams ---", top);
-    for (i, trigram) in freq_analysis.top_trigrams(top).iter().enumerate() {
+    println!("\n--- Top {} Trigrams{} ---", top, letters_note);
+    for (i, trigram) in freq_analysis.top_trigrams_min(top, min_count).iter().enumerate() {
         if detailed {
             println!(
                 "{:2}. {:35} (0x{:02X}->0x{:02X}->0x{:02X}) {:>5} ({:.2}%)",
@@ -308,6 +1557,152 @@ fn run_analyze(top: usize, max_gap: i64, detailed: bool) -> Result<()> {
         }
     }
 
+    println!("\n--- Capitalization ---");
+    println!(
+        "Capital letters:  {} (Shift presses: {} for capitals, {} other{})",
+        shift_analysis.capital_count,
+        shift_analysis.paired_shifts,
+        shift_analysis.standalone_shifts,
+        if merge_shift { ", capital-only Shifts not counted above" } else { "" }
+    );
+    for (i, (name, count)) in shift_analysis.top_capitals(top.min(5)).iter().enumerate() {
+        println!("{:2}. {:15} {:>8}", i + 1, name, count);
+    }
+
+    if let Some(baseline) = baseline {
+        let comparison = analysis::BaselineComparison::new(&freq_analysis, baseline);
+
+        println!("\n--- Versus {} (share of letters, percentage points) ---", baseline.label());
+        for (i, delta) in comparison.letters.iter().take(top).enumerate() {
+            println!(
+                "{:2}. {:15} {:>+7.2} ({:.2}% vs {:.2}%)",
+                i + 1,
+                delta.display,
+                delta.delta(),
+                delta.observed_pct,
+                delta.expected_pct
+            );
+        }
+        println!("Letter pairs:");
+        for (i, delta) in comparison.bigrams.iter().take(top).enumerate() {
+            println!(
+                "{:2}. {:15} {:>+7.2} ({:.2}% vs {:.2}%)",
+                i + 1,
+                delta.display,
+                delta.delta(),
+                delta.observed_pct,
+                delta.expected_pct
+            );
+        }
+    }
+
+    let finger_loads =
+        analysis::fingers::finger_loads(&freq_analysis.key_percentages(), layout.as_ref());
+    let (left_pct, right_pct) = analysis::fingers::hand_balance(&finger_loads);
+    let same_finger = analysis::SameFingerAnalysis::from_bigrams(
+        &freq_analysis.bigram_frequencies,
+        layout.as_ref(),
+        filter_config.same_finger_repeats,
+    );
+
+    println!("\n--- Fingers ({}) ---", layout.display_name());
+    let loads: Vec<String> = finger_loads
+        .iter()
+        .map(|(finger, pct)| format!("{} {:.1}%", finger.short_name(), pct))
+        .collect();
+    println!("Load:         {}", loads.join("  "));
+    println!("Hands:        left {:.1}%, right {:.1}%", left_pct, right_pct);
+    println!(
+        "Same finger:  {} of {} bigrams ({:.2}%{})",
+        same_finger.same_finger_count,
+        same_finger.total_bigrams,
+        same_finger.same_finger_pct(),
+        if filter_config.same_finger_repeats { ", repeated keys included" } else { "" }
+    );
+    println!("Rolls:        {:.2}%", same_finger.roll_pct());
+    println!("Alternating:  {:.2}%", same_finger.alternation_pct());
+    for (i, bigram) in same_finger.worst.iter().take(top.min(5)).enumerate() {
+        println!(
+            "{:2}. {:20} {:>8} ({:.2}%)",
+            i + 1,
+            bigram.display,
+            bigram.count,
+            bigram.percentage
+        );
+    }
+
+    let metrics = analysis::layout_score::LayoutMetrics::from_frequencies(
+        &freq_analysis,
+        layout.as_ref(),
+        filter_config.same_finger_repeats,
+    );
+    let score = analysis::LayoutScore::new(&metrics, &config.layout_score);
+    println!("\n--- Layout Fit ({}) ---", layout.display_name());
+    println!("Score:        {:.0}/100", score.total);
+    println!(
+        "Components:   same finger {:.0}, rolls {:.0}, hands {:.0}, home row {:.0}, effort {:.0}",
+        score.same_finger, score.rolls, score.hand_balance, score.home_row, score.effort
+    );
+
+    if scissors {
+        let scissor_analysis = analysis::ScissorAnalysis::from_bigrams(
+            &freq_analysis.bigram_frequencies,
+            layout.as_ref(),
+        );
+
+        println!("\n--- Scissors ({}) ---", layout.display_name());
+        println!(
+            "Scissor bigrams: {} of {} ({:.2}%)",
+            scissor_analysis.scissor_count,
+            scissor_analysis.total_bigrams,
+            scissor_analysis.scissor_pct()
+        );
+        for (i, bigram) in scissor_analysis.worst.iter().take(top).enumerate() {
+            println!(
+                "{:2}. {:20} {:>8} ({:.2}%)",
+                i + 1,
+                bigram.display,
+                bigram.count,
+                bigram.percentage
+            );
+        }
+    }
+
+    if effort {
+        let travel = analysis::FingerTravel::from_events(&filtered_events, layout.as_ref());
+
+        println!("\n--- Finger Travel ({}) ---", layout.display_name());
+        println!(
+            "Total:        {:.0} key units ({:.1} per 100 presses)",
+            travel.total,
+            travel.per_hundred_presses()
+        );
+        for (finger, distance) in &travel.per_finger {
+            let pct = if travel.total > 0.0 { distance / travel.total * 100.0 } else { 0.0 };
+            let label = format!("{}:", finger.short_name());
+            println!("{:14}{:.0} key units ({:.1}%)", label, distance, pct);
+        }
+    }
+
+    if let Some(window_ms) = chord_window {
+        let chord_analysis = analysis::ChordAnalysis::from_events(&filtered_events, window_ms);
+
+        println!("\n--- Chords (presses within {}ms, key still held) ---", window_ms);
+        if chord_analysis.total_presses == 0 {
+            println!("No release events recorded; chords can't be detected.");
+        } else {
+            println!(
+                "Chorded presses: {} of {} ({:.2}%)",
+                chord_analysis.chord_presses,
+                chord_analysis.total_presses,
+                chord_analysis.chord_pct()
+            );
+        }
+        for (i, chord) in chord_analysis.top_chords(top).iter().enumerate() {
+            println!("{:2}. {:20} {:>8}", i + 1, chord.display, chord.count);
+        }
+    }
+
     let timing = analysis::TimingAnalysis::from_events(&filtered_events, filter_config.clone());
 
     println!("\n--- Inter-Key Timing ---");
@@ -317,19 +1712,94 @@ fn run_analyze(top: usize, max_gap: i64, detailed: bool) -> Result<()> {
     println!("P90:        {}ms", timing.overall_inter_key.p90_ms);
     println!("P95:        {}ms", timing.overall_inter_key.p95_ms);
     println!("P99:        {}ms", timing.overall_inter_key.p99_ms);
+    if detailed {
+        println!(
+            "Same-ms:    {} (skipped; `lurk check` counts out-of-order events)",
+            timing.zero_intervals
+        );
+    }
+
+    println!("\n--- Inter-Key Histogram ---");
+    let histogram =
+        analysis::histogram::timing_histogram(&filtered_events, &filter_config, &histogram_edges);
+    let tallest = histogram.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    for (label, count) in &histogram {
+        let bar = "#".repeat((count * HISTOGRAM_BAR_WIDTH / tallest) as usize);
+        println!("{:>11}ms {:>8}  {}", label, count, bar);
+    }
+
+    let rhythm =
+        analysis::HandRhythm::from_events(&filtered_events, layout.as_ref(), &filter_config);
+    let median_or_dash =
+        |median: Option<i64>| median.map_or_else(|| "-".to_string(), |ms| format!("{}ms", ms));
+
+    println!("\n--- Hand Alternation ({}) ---", layout.display_name());
+    println!(
+        "Alternating:  {} (n={})",
+        median_or_dash(rhythm.alternating_median_ms),
+        rhythm.alternating_samples
+    );
+    println!(
+        "Same hand:    {} (n={})",
+        median_or_dash(rhythm.same_hand_median_ms),
+        rhythm.same_hand_samples
+    );
+    if let Some(ratio) = rhythm.ratio() {
+        println!(
+            "Ratio:        {:.2}x (same hand / alternating; above 1 means alternation is faster)",
+            ratio
+        );
+    }
 
     if detailed && !timing.per_key_inter_key.is_empty() {
         println!("\n--- Top {} Key-Pair Timings ---", top);
-        for (i, pair) in timing.top_inter_key_pairs(top).iter().enumerate() {
+        let pairs = timing.top_inter_key_pairs(top);
+        let mut flagged = false;
+        for (i, pair) in pairs.iter().enumerate() {
+            let samples = pair.intervals_ms.len();
+            let low = timing.filter_config.is_low_confidence(samples);
+            flagged |= low;
             println!(
-                "{:2}. 0x{:02X}->0x{:02X}  mean={:.1}ms median={}ms p95={}ms (n={})",
+                "{:2}. 0x{:02X}->0x{:02X}  mean={:.1}ms median={}ms p95={}ms (n={}{})",
                 i + 1,
                 pair.from_key,
                 pair.to_key,
                 pair.mean_ms,
                 pair.median_ms,
                 pair.p95_ms,
-                pair.intervals_ms.len()
+                samples,
+                if low { ", low confidence" } else { "" }
+            );
+        }
+        if flagged {
+            println!(
+                "Pairs with fewer than {} samples are low confidence.",
+                timing.filter_config.low_confidence_samples
+            );
+        }
+    }
+
+    if detailed {
+        let trigrams = freq_analysis.top_trigrams_min(usize::MAX, min_count);
+        let slow = analysis::trigram_timing::slow_trigrams(
+            &filtered_events,
+            trigrams,
+            &filter_config,
+            top,
+        );
+        if !slow.is_empty() {
+            println!("\n--- Top {} Slow Trigrams (count x median) ---", top);
+        }
+        for (i, trigram) in slow.iter().enumerate() {
+            println!(
+                "{:2}. {:30} median={}ms mean={:.1}ms count={} cost={:.1}s (n={})",
+                i + 1,
+                trigram.display,
+                trigram.median_ms,
+                trigram.mean_ms,
+                trigram.count,
+                trigram.cost_ms() as f64 / 1000.0,
+                trigram.samples
             );
         }
     }
@@ -366,6 +1836,9 @@ fn run_analyze(top: usize, max_gap: i64, detailed: bool) -> Result<()> {
         println!("Max gap:    {}ms", timing.filter_config.max_gap_ms);
         println!("Min hold:   {}ms", timing.filter_config.min_hold_ms);
         println!("Max hold:   {}ms", timing.filter_config.max_hold_ms);
+        println!("Min pair samples: {}", timing.filter_config.min_pair_samples);
+        println!("Low confidence below: {} samples", timing.filter_config.low_confidence_samples);
+        println!("Min n-gram count: {}", min_count);
     }
 
     Ok(())
@@ -389,9 +1862,92 @@ fn check_permission() -> Result<()> {
     Ok(())
 }
 
+fn cutoff_for_days(days: u32) -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let now = i64::try_from(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_millis(),
+    )
+    .unwrap_or(i64::MAX);
+
+    now.saturating_sub(i64::from(days).saturating_mul(24 * 60 * 60 * 1000))
+        .max(0)
+}
+
+fn run_check(fix: bool, integrity: bool) -> Result<()> {
+    let db_path = get_db_path();
+
+    if !db_path.exists() {
+        eprintln!("No database found at {:?}", db_path);
+        eprintln!("Make sure the daemon has been run at least once.");
+        return Ok(());
+    }
+
+    println!("=== Lurk Data Check ===\n");
+    // Checked before anything that could write to the file, such as a
+    // migration.
+    if integrity {
+        cli::check::check_storage(&storage::Database::open_read_only(&db_path)?, &db_path)?;
+    }
+    let mut db = if fix {
+        storage::Database::new(&db_path)?
+    } else {
+        storage::Database::open_read_only(&db_path)?
+    };
+    cli::run_check(&mut db, fix)
+}
+
+fn run_merge(source: &Path, into: Option<PathBuf>, source_key: Option<&str>) -> Result<()> {
+    if !source.exists() {
+        return Err(anyhow!("No database found at {:?}", source));
+    }
+    if into.is_none() {
+        create_secure_dir(&get_data_dir())?;
+    }
+    let target_path = into.unwrap_or_else(get_db_path);
+    if target_path.exists() && fs::canonicalize(source)? == fs::canonicalize(&target_path)? {
+        return Err(anyhow!("Source and target are the same database"));
+    }
+
+    let source_db = match source_key {
+        Some(key) => storage::Database::open_read_only_with_key(source, key)?,
+        None => storage::Database::open_read_only(source)?,
+    };
+    let mut target_db = storage::Database::new(&target_path)?;
+    set_secure_file_permissions(&target_path)?;
+
+    let report = cli::merge_databases(&source_db, &mut target_db)?;
+    println!(
+        "Merged {:?} into {:?}: {} events inserted, {} already present",
+        source, target_path, report.inserted, report.skipped
+    );
+    Ok(())
+}
+
+fn run_rotate_key() -> Result<()> {
+    let db_path = get_db_path();
+    if !db_path.exists() {
+        return Err(anyhow!("No database found at {:?}", db_path));
+    }
+    if let Some(pid) = daemon::running_daemon_pid(&get_pid_path()) {
+        return Err(anyhow!(
+            "The daemon is running (PID {}); stop it first so nothing is written mid-rotation",
+            pid
+        ));
+    }
+
+    // Migrates a legacy unencrypted file and checks the current key.
+    drop(storage::Database::new(&db_path)?);
+    storage::Database::rotate_key(&db_path)?;
+    println!("Re-encrypted {:?} with a new key", db_path);
+    Ok(())
+}
+
 fn run_cleanup(days: u32, force: bool) -> Result<()> {
     use std::io::{self, Write};
-    use std::time::{SystemTime, UNIX_EPOCH};
 
     let db_path = get_db_path();
 
@@ -408,11 +1964,7 @@ fn run_cleanup(days: u32, force: bool) -> Result<()> {
         return Ok(());
     }
 
-    let cutoff_ms = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_millis() as i64
-        - (days as i64 * 24 * 60 * 60 * 1000);
+    let cutoff_ms = cutoff_for_days(days);
 
     if !force {
         print!(
@@ -1,13 +1,24 @@
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::models::keycode::{event_naming, KeyCode};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct KeystrokeEvent {
     pub timestamp: i64,
     pub key_code: u32,
     pub event_type: EventType,
     pub modifiers: Vec<Modifier>,
     pub application: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_title: Option<String>,
+    /// Text the key produced under the layout active at capture time, when recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub character: Option<String>,
+    /// macOS input source active at capture time, e.g.
+    /// `com.apple.keylayout.French`. `None` for events recorded before it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_source: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -17,7 +28,7 @@ pub enum EventType {
     Release,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Modifier {
     Shift,
@@ -35,10 +46,11 @@ impl KeystrokeEvent {
         modifiers: Vec<Modifier>,
         application: String,
     ) -> Self {
-        let timestamp = SystemTime::now()
+        let millis = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards")
-            .as_millis() as i64;
+            .as_millis();
+        let timestamp = i64::try_from(millis).unwrap_or(i64::MAX);
 
         Self {
             timestamp,
@@ -46,8 +58,31 @@ impl KeystrokeEvent {
             event_type,
             modifiers,
             application,
+            window_title: None,
+            character: None,
+            input_source: None,
         }
     }
+
+    pub fn with_window_title(mut self, window_title: Option<String>) -> Self {
+        self.window_title = window_title;
+        self
+    }
+
+    pub fn with_character(mut self, character: Option<String>) -> Self {
+        self.character = character;
+        self
+    }
+
+    pub fn with_input_source(mut self, input_source: Option<String>) -> Self {
+        self.input_source = input_source;
+        self
+    }
+
+    /// The key's name on the layout it was typed with; see `event_naming`.
+    pub fn key_name(&self) -> String {
+        KeyCode(self.key_code).to_name_for(event_naming(self.input_source.as_deref()))
+    }
 }
 
 impl EventType {
@@ -79,6 +114,27 @@ impl std::fmt::Display for Modifier {
     }
 }
 
+impl std::str::FromStr for Modifier {
+    type Err = anyhow::Error;
+
+    /// Accepts the `Display` names plus the usual short forms (`cmd`, `ctrl`,
+    /// `opt`/`option`, `caps`, `fn`), ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "shift" => Ok(Modifier::Shift),
+            "control" | "ctrl" => Ok(Modifier::Control),
+            "alt" | "option" | "opt" => Ok(Modifier::Alt),
+            "command" | "cmd" => Ok(Modifier::Command),
+            "capslock" | "caps" => Ok(Modifier::CapsLock),
+            "function" | "fn" => Ok(Modifier::Function),
+            _ => Err(anyhow::anyhow!(
+                "Unknown modifier '{}'. Use shift, control, alt, command, capslock or function",
+                s
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,6 +175,18 @@ mod tests {
         assert_eq!(format!("{}", Modifier::Command), "command");
     }
 
+    #[test]
+    fn test_modifier_from_str() {
+        assert_eq!("cmd".parse::<Modifier>().unwrap(), Modifier::Command);
+        assert_eq!("Command".parse::<Modifier>().unwrap(), Modifier::Command);
+        assert_eq!("opt".parse::<Modifier>().unwrap(), Modifier::Alt);
+        assert_eq!("fn".parse::<Modifier>().unwrap(), Modifier::Function);
+        for modifier in [Modifier::Shift, Modifier::Control, Modifier::CapsLock] {
+            assert_eq!(modifier.to_string().parse::<Modifier>().unwrap(), modifier);
+        }
+        assert!("hyper".parse::<Modifier>().is_err());
+    }
+
     #[test]
     fn test_event_serialization() {
         let event = KeystrokeEvent {
@@ -127,14 +195,27 @@ mod tests {
             event_type: EventType::Press,
             modifiers: vec![Modifier::Shift, Modifier::Command],
             application: "com.test.app".to_string(),
+            window_title: None,
+            character: None,
+            input_source: None,
         };
 
         let json = serde_json::to_string(&event).unwrap();
         assert!(json.contains("\"event_type\":\"press\""));
         assert!(json.contains("\"modifiers\":[\"shift\",\"command\"]"));
+        assert!(!json.contains("window_title"));
 
         let deserialized: KeystrokeEvent = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.key_code, event.key_code);
         assert_eq!(deserialized.event_type, event.event_type);
+        assert_eq!(deserialized.window_title, None);
+    }
+
+    #[test]
+    fn test_with_window_title() {
+        let event = KeystrokeEvent::new(0x00, EventType::Press, vec![], "com.test.app".to_string())
+            .with_window_title(Some("notes.md".to_string()));
+
+        assert_eq!(event.window_title.as_deref(), Some("notes.md"));
     }
 }
@@ -0,0 +1,83 @@
+/// A range of local clock hours, start inclusive and end exclusive, e.g.
+/// `9-17` for 09:00-16:59. A start after the end wraps past midnight, so
+/// `22-6` is 22:00-05:59.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HourWindow {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl std::str::FromStr for HourWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            anyhow::anyhow!("Invalid hours '{}'. Use START-END in 0-24, e.g. 9-17 or 22-6", s)
+        };
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let start: u32 = start.trim().parse().map_err(|_| invalid())?;
+        let end: u32 = end.trim().parse().map_err(|_| invalid())?;
+        if start > 23 || end > 24 || start == end {
+            return Err(invalid());
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl std::fmt::Display for HourWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:00-{:02}:00", self.start, self.end)
+    }
+}
+
+impl HourWindow {
+    pub fn contains_hour(&self, hour: u32) -> bool {
+        if self.start < self.end {
+            hour >= self.start && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+
+    /// Whether `timestamp` (ms) falls in the window in the local time zone.
+    pub fn contains_timestamp(&self, timestamp: i64) -> bool {
+        use chrono::Timelike;
+        chrono::DateTime::from_timestamp_millis(timestamp)
+            .is_some_and(|dt| self.contains_hour(dt.with_timezone(&chrono::Local).hour()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hour_window_normal_range() {
+        let window: HourWindow = "9-17".parse().unwrap();
+        assert!(!window.contains_hour(8));
+        assert!(window.contains_hour(9));
+        assert!(window.contains_hour(16));
+        assert!(!window.contains_hour(17));
+        assert!(!window.contains_hour(23));
+    }
+
+    #[test]
+    fn test_hour_window_wraps_past_midnight() {
+        let window: HourWindow = "22-6".parse().unwrap();
+        assert!(window.contains_hour(22));
+        assert!(window.contains_hour(23));
+        assert!(window.contains_hour(0));
+        assert!(window.contains_hour(5));
+        assert!(!window.contains_hour(6));
+        assert!(!window.contains_hour(12));
+        assert!(!window.contains_hour(21));
+    }
+
+    #[test]
+    fn test_hour_window_rejects_bad_input() {
+        for bad in ["9", "9-9", "25-3", "9-25", "a-b", "-5"] {
+            assert!(bad.parse::<HourWindow>().is_err(), "{}", bad);
+        }
+        assert_eq!("0-24".parse::<HourWindow>().unwrap(), HourWindow { start: 0, end: 24 });
+    }
+}
@@ -0,0 +1,520 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Finger {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+    Thumb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+impl Finger {
+    pub fn hand(&self) -> Hand {
+        match self {
+            Finger::LeftPinky | Finger::LeftRing | Finger::LeftMiddle | Finger::LeftIndex => {
+                Hand::Left
+            }
+            Finger::RightIndex | Finger::RightMiddle | Finger::RightRing | Finger::RightPinky => {
+                Hand::Right
+            }
+            Finger::Thumb => Hand::Right,
+        }
+    }
+
+    /// Position from the outside of the hand inward: pinky 0 .. index 3.
+    /// `None` for the thumb, which sits outside the finger columns.
+    pub fn column_rank(&self) -> Option<u8> {
+        match self {
+            Finger::LeftPinky | Finger::RightPinky => Some(0),
+            Finger::LeftRing | Finger::RightRing => Some(1),
+            Finger::LeftMiddle | Finger::RightMiddle => Some(2),
+            Finger::LeftIndex | Finger::RightIndex => Some(3),
+            Finger::Thumb => None,
+        }
+    }
+
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Finger::LeftPinky => "L4",
+            Finger::LeftRing => "L3",
+            Finger::LeftMiddle => "L2",
+            Finger::LeftIndex => "L1",
+            Finger::RightIndex => "R1",
+            Finger::RightMiddle => "R2",
+            Finger::RightRing => "R3",
+            Finger::RightPinky => "R4",
+            Finger::Thumb => "Th",
+        }
+    }
+}
+
+/// `left_pinky` ... `right_pinky` and `thumb`, or the short names (`L4`, `Th`).
+impl std::str::FromStr for Finger {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let finger = match s.to_ascii_lowercase().as_str() {
+            "left_pinky" | "l4" => Finger::LeftPinky,
+            "left_ring" | "l3" => Finger::LeftRing,
+            "left_middle" | "l2" => Finger::LeftMiddle,
+            "left_index" | "l1" => Finger::LeftIndex,
+            "right_index" | "r1" => Finger::RightIndex,
+            "right_middle" | "r2" => Finger::RightMiddle,
+            "right_ring" | "r3" => Finger::RightRing,
+            "right_pinky" | "r4" => Finger::RightPinky,
+            "thumb" | "th" => Finger::Thumb,
+            _ => {
+                return Err(anyhow!(
+                    "Unknown finger '{}'. Use e.g. left_index, right_pinky, thumb or L1..R4",
+                    s
+                ))
+            }
+        };
+        Ok(finger)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyInfo {
+    pub keycode: u32,
+    pub label: &'static str,
+    pub width: u16,
+    pub finger: Finger,
+}
+
+/// The key under the left index finger at rest (F on QWERTY).
+const HOME_ROW_KEYCODE: u32 = 0x03;
+
+/// Names accepted by `layout_by_name`, and by the `layout` config setting.
+pub const LAYOUT_NAMES: &[&str] = &["qwerty", "ortho", "split36"];
+
+/// Physical key geometry and finger assignments. Positions are in the same
+/// width units as `KeyInfo::width`, with a one unit gap between keys.
+pub trait KeyboardLayout {
+    fn display_name(&self) -> &'static str;
+
+    fn rows(&self) -> &[Vec<KeyInfo>];
+
+    fn get_finger(&self, keycode: u32) -> Option<Finger>;
+
+    /// Extra horizontal space before the key at `col` of `row`, used for
+    /// centering short rows and the gap between halves of a split board.
+    fn indent(&self, _row: usize, _col: usize) -> u16 {
+        0
+    }
+
+    /// Every key with its row index and x offset.
+    fn key_positions(&self) -> Vec<(usize, u16, &KeyInfo)> {
+        let mut positions = Vec::new();
+        for (row_index, row) in self.rows().iter().enumerate() {
+            let mut x = 0;
+            for (col, key) in row.iter().enumerate() {
+                x += self.indent(row_index, col);
+                positions.push((row_index, x, key));
+                x += key.width + 1;
+            }
+        }
+        positions
+    }
+
+    fn key_row(&self, keycode: u32) -> Option<usize> {
+        self.rows()
+            .iter()
+            .position(|row| row.iter().any(|key| key.keycode == keycode))
+    }
+
+    /// The row the fingers rest on: the one holding the F position. Key
+    /// codes are positional, so this holds whatever the legends say.
+    fn home_row(&self) -> Option<usize> {
+        self.key_row(HOME_ROW_KEYCODE)
+    }
+
+    fn total_width(&self) -> u16 {
+        self.key_positions()
+            .iter()
+            .map(|(_, x, key)| x + key.width)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+pub fn layout_by_name(name: &str) -> Result<Box<dyn KeyboardLayout>> {
+    match name {
+        "qwerty" => Ok(Box::new(QwertyLayout::new())),
+        "ortho" => Ok(Box::new(OrthoLayout::new())),
+        "split36" => Ok(Box::new(SplitLayout::new())),
+        _ => Err(anyhow!(
+            "Unknown layout '{}'. Supported: {}",
+            name,
+            LAYOUT_NAMES.join(", ")
+        )),
+    }
+}
+
+pub(crate) fn build_finger_map(rows: &[Vec<KeyInfo>]) -> HashMap<u32, Finger> {
+    let mut finger_map = HashMap::new();
+    for row in rows {
+        for key in row {
+            finger_map.insert(key.keycode, key.finger);
+        }
+    }
+    finger_map
+}
+
+/// Builds a row of standard-width keys from `(keycode, label)` pairs, with
+/// fingers assigned by column.
+fn grid_row(keys: &[(u32, &'static str)], fingers: &[Finger]) -> Vec<KeyInfo> {
+    keys.iter()
+        .zip(fingers)
+        .map(|(&(keycode, label), &finger)| KeyInfo {
+            keycode,
+            label,
+            width: 2,
+            finger,
+        })
+        .collect()
+}
+
+pub struct QwertyLayout {
+    pub rows: Vec<Vec<KeyInfo>>,
+    finger_map: HashMap<u32, Finger>,
+}
+
+impl QwertyLayout {
+    pub fn new() -> Self {
+        let rows = vec![
+            vec![
+                KeyInfo { keycode: 0x32, label: "`", width: 2, finger: Finger::LeftPinky },
+                KeyInfo { keycode: 0x12, label: "1", width: 2, finger: Finger::LeftPinky },
+                KeyInfo { keycode: 0x13, label: "2", width: 2, finger: Finger::LeftRing },
+                KeyInfo { keycode: 0x14, label: "3", width: 2, finger: Finger::LeftMiddle },
+                KeyInfo { keycode: 0x15, label: "4", width: 2, finger: Finger::LeftIndex },
+                KeyInfo { keycode: 0x17, label: "5", width: 2, finger: Finger::LeftIndex },
+                KeyInfo { keycode: 0x16, label: "6", width: 2, finger: Finger::RightIndex },
+                KeyInfo { keycode: 0x1A, label: "7", width: 2, finger: Finger::RightIndex },
+                KeyInfo { keycode: 0x1C, label: "8", width: 2, finger: Finger::RightMiddle },
+                KeyInfo { keycode: 0x19, label: "9", width: 2, finger: Finger::RightRing },
+                KeyInfo { keycode: 0x1D, label: "0", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x1B, label: "-", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x18, label: "=", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x33, label: "⌫", width: 3, finger: Finger::RightPinky },
+            ],
+            vec![
+                KeyInfo { keycode: 0x30, label: "⇥", width: 3, finger: Finger::LeftPinky },
+                KeyInfo { keycode: 0x0C, label: "Q", width: 2, finger: Finger::LeftPinky },
+                KeyInfo { keycode: 0x0D, label: "W", width: 2, finger: Finger::LeftRing },
+                KeyInfo { keycode: 0x0E, label: "E", width: 2, finger: Finger::LeftMiddle },
+                KeyInfo { keycode: 0x0F, label: "R", width: 2, finger: Finger::LeftIndex },
+                KeyInfo { keycode: 0x11, label: "T", width: 2, finger: Finger::LeftIndex },
+                KeyInfo { keycode: 0x10, label: "Y", width: 2, finger: Finger::RightIndex },
+                KeyInfo { keycode: 0x20, label: "U", width: 2, finger: Finger::RightIndex },
+                KeyInfo { keycode: 0x22, label: "I", width: 2, finger: Finger::RightMiddle },
+                KeyInfo { keycode: 0x1F, label: "O", width: 2, finger: Finger::RightRing },
+                KeyInfo { keycode: 0x23, label: "P", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x21, label: "[", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x1E, label: "]", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x2A, label: "\\", width: 2, finger: Finger::RightPinky },
+            ],
+            vec![
+                KeyInfo { keycode: 0x39, label: "⇪", width: 4, finger: Finger::LeftPinky },
+                KeyInfo { keycode: 0x00, label: "A", width: 2, finger: Finger::LeftPinky },
+                KeyInfo { keycode: 0x01, label: "S", width: 2, finger: Finger::LeftRing },
+                KeyInfo { keycode: 0x02, label: "D", width: 2, finger: Finger::LeftMiddle },
+                KeyInfo { keycode: 0x03, label: "F", width: 2, finger: Finger::LeftIndex },
+                KeyInfo { keycode: 0x05, label: "G", width: 2, finger: Finger::LeftIndex },
+                KeyInfo { keycode: 0x04, label: "H", width: 2, finger: Finger::RightIndex },
+                KeyInfo { keycode: 0x26, label: "J", width: 2, finger: Finger::RightIndex },
+                KeyInfo { keycode: 0x28, label: "K", width: 2, finger: Finger::RightMiddle },
+                KeyInfo { keycode: 0x25, label: "L", width: 2, finger: Finger::RightRing },
+                KeyInfo { keycode: 0x29, label: ";", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x27, label: "'", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x24, label: "⏎", width: 4, finger: Finger::RightPinky },
+            ],
+            vec![
+                KeyInfo { keycode: 0x38, label: "⇧", width: 5, finger: Finger::LeftPinky },
+                KeyInfo { keycode: 0x06, label: "Z", width: 2, finger: Finger::LeftPinky },
+                KeyInfo { keycode: 0x07, label: "X", width: 2, finger: Finger::LeftRing },
+                KeyInfo { keycode: 0x08, label: "C", width: 2, finger: Finger::LeftMiddle },
+                KeyInfo { keycode: 0x09, label: "V", width: 2, finger: Finger::LeftIndex },
+                KeyInfo { keycode: 0x0B, label: "B", width: 2, finger: Finger::LeftIndex },
+                KeyInfo { keycode: 0x2D, label: "N", width: 2, finger: Finger::RightIndex },
+                KeyInfo { keycode: 0x2E, label: "M", width: 2, finger: Finger::RightMiddle },
+                KeyInfo { keycode: 0x2B, label: ",", width: 2, finger: Finger::RightMiddle },
+                KeyInfo { keycode: 0x2F, label: ".", width: 2, finger: Finger::RightRing },
+                KeyInfo { keycode: 0x2C, label: "/", width: 2, finger: Finger::RightPinky },
+                KeyInfo { keycode: 0x3C, label: "⇧", width: 5, finger: Finger::RightPinky },
+            ],
+            vec![
+                KeyInfo { keycode: 0x31, label: "␣", width: 20, finger: Finger::Thumb },
+            ],
+        ];
+
+        let finger_map = build_finger_map(&rows);
+
+        Self { rows, finger_map }
+    }
+}
+
+impl KeyboardLayout for QwertyLayout {
+    fn display_name(&self) -> &'static str {
+        "QWERTY"
+    }
+
+    fn rows(&self) -> &[Vec<KeyInfo>] {
+        &self.rows
+    }
+
+    fn get_finger(&self, keycode: u32) -> Option<Finger> {
+        self.finger_map.get(&keycode).copied()
+    }
+
+    fn indent(&self, row: usize, col: usize) -> u16 {
+        let keys = &self.rows[row];
+        if col == 0 && keys.len() == 1 && keys[0].label == "␣" {
+            8
+        } else {
+            0
+        }
+    }
+}
+
+impl Default for QwertyLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const ORTHO_FINGERS: [Finger; 12] = [
+    Finger::LeftPinky,
+    Finger::LeftPinky,
+    Finger::LeftRing,
+    Finger::LeftMiddle,
+    Finger::LeftIndex,
+    Finger::LeftIndex,
+    Finger::RightIndex,
+    Finger::RightIndex,
+    Finger::RightMiddle,
+    Finger::RightRing,
+    Finger::RightPinky,
+    Finger::RightPinky,
+];
+
+/// 4x12 ortholinear grid (Planck style): no stagger, columns map straight to fingers.
+pub struct OrthoLayout {
+    pub rows: Vec<Vec<KeyInfo>>,
+    finger_map: HashMap<u32, Finger>,
+}
+
+impl OrthoLayout {
+    pub fn new() -> Self {
+        let thumbs = [Finger::Thumb; 5];
+        let rows = vec![
+            grid_row(
+                &[
+                    (0x30, "⇥"), (0x0C, "Q"), (0x0D, "W"), (0x0E, "E"), (0x0F, "R"), (0x11, "T"),
+                    (0x10, "Y"), (0x20, "U"), (0x22, "I"), (0x1F, "O"), (0x23, "P"), (0x33, "⌫"),
+                ],
+                &ORTHO_FINGERS,
+            ),
+            grid_row(
+                &[
+                    (0x35, "⎋"), (0x00, "A"), (0x01, "S"), (0x02, "D"), (0x03, "F"), (0x05, "G"),
+                    (0x04, "H"), (0x26, "J"), (0x28, "K"), (0x25, "L"), (0x29, ";"), (0x27, "'"),
+                ],
+                &ORTHO_FINGERS,
+            ),
+            grid_row(
+                &[
+                    (0x38, "⇧"), (0x06, "Z"), (0x07, "X"), (0x08, "C"), (0x09, "V"), (0x0B, "B"),
+                    (0x2D, "N"), (0x2E, "M"), (0x2B, ","), (0x2F, "."), (0x2C, "/"), (0x24, "⏎"),
+                ],
+                &ORTHO_FINGERS,
+            ),
+            grid_row(
+                &[(0x3B, "⌃"), (0x3A, "⌥"), (0x37, "⌘"), (0x31, "␣"), (0x3C, "⇧")],
+                &thumbs,
+            ),
+        ];
+
+        let finger_map = build_finger_map(&rows);
+
+        Self { rows, finger_map }
+    }
+}
+
+impl KeyboardLayout for OrthoLayout {
+    fn display_name(&self) -> &'static str {
+        "Ortho 4x12"
+    }
+
+    fn rows(&self) -> &[Vec<KeyInfo>] {
+        &self.rows
+    }
+
+    fn get_finger(&self, keycode: u32) -> Option<Finger> {
+        self.finger_map.get(&keycode).copied()
+    }
+
+    fn indent(&self, row: usize, col: usize) -> u16 {
+        // Thumb row sits under the middle columns of the grid.
+        if row == 3 && col == 0 {
+            9
+        } else {
+            0
+        }
+    }
+}
+
+impl Default for OrthoLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const SPLIT_FINGERS: [Finger; 10] = [
+    Finger::LeftPinky,
+    Finger::LeftRing,
+    Finger::LeftMiddle,
+    Finger::LeftIndex,
+    Finger::LeftIndex,
+    Finger::RightIndex,
+    Finger::RightIndex,
+    Finger::RightMiddle,
+    Finger::RightRing,
+    Finger::RightPinky,
+];
+
+/// Gap between the two halves, in width units.
+const SPLIT_GAP: u16 = 6;
+
+/// 36-key split ortholinear board: 3x5 per half plus three thumb keys each.
+pub struct SplitLayout {
+    pub rows: Vec<Vec<KeyInfo>>,
+    finger_map: HashMap<u32, Finger>,
+}
+
+impl SplitLayout {
+    pub fn new() -> Self {
+        let rows = vec![
+            grid_row(
+                &[
+                    (0x0C, "Q"), (0x0D, "W"), (0x0E, "E"), (0x0F, "R"), (0x11, "T"),
+                    (0x10, "Y"), (0x20, "U"), (0x22, "I"), (0x1F, "O"), (0x23, "P"),
+                ],
+                &SPLIT_FINGERS,
+            ),
+            grid_row(
+                &[
+                    (0x00, "A"), (0x01, "S"), (0x02, "D"), (0x03, "F"), (0x05, "G"),
+                    (0x04, "H"), (0x26, "J"), (0x28, "K"), (0x25, "L"), (0x29, ";"),
+                ],
+                &SPLIT_FINGERS,
+            ),
+            grid_row(
+                &[
+                    (0x06, "Z"), (0x07, "X"), (0x08, "C"), (0x09, "V"), (0x0B, "B"),
+                    (0x2D, "N"), (0x2E, "M"), (0x2B, ","), (0x2F, "."), (0x2C, "/"),
+                ],
+                &SPLIT_FINGERS,
+            ),
+            grid_row(
+                &[
+                    (0x37, "⌘"), (0x38, "⇧"), (0x31, "␣"),
+                    (0x24, "⏎"), (0x33, "⌫"), (0x30, "⇥"),
+                ],
+                &[Finger::Thumb; 6],
+            ),
+        ];
+
+        let finger_map = build_finger_map(&rows);
+
+        Self { rows, finger_map }
+    }
+}
+
+impl KeyboardLayout for SplitLayout {
+    fn display_name(&self) -> &'static str {
+        "Split 3x5+3"
+    }
+
+    fn rows(&self) -> &[Vec<KeyInfo>] {
+        &self.rows
+    }
+
+    fn get_finger(&self, keycode: u32) -> Option<Finger> {
+        self.finger_map.get(&keycode).copied()
+    }
+
+    fn indent(&self, row: usize, col: usize) -> u16 {
+        let is_thumb_row = row == 3;
+        match (is_thumb_row, col) {
+            // Thumb clusters sit under the inner three columns of each half.
+            (true, 0) => 6,
+            (true, 3) => SPLIT_GAP + 6,
+            (false, 5) => SPLIT_GAP,
+            _ => 0,
+        }
+    }
+}
+
+impl Default for SplitLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_by_name() {
+        for name in LAYOUT_NAMES {
+            assert!(layout_by_name(name).is_ok());
+        }
+        assert!(layout_by_name("dvorak-ish").is_err());
+    }
+
+    #[test]
+    fn test_split_layout_has_36_keys_and_a_gap() {
+        let layout = SplitLayout::new();
+        let positions = layout.key_positions();
+        assert_eq!(positions.len(), 36);
+
+        let home_row: Vec<u16> = positions
+            .iter()
+            .filter(|(row, _, _)| *row == 1)
+            .map(|(_, x, _)| *x)
+            .collect();
+        assert_eq!(home_row[4] + 3, home_row[5] - SPLIT_GAP);
+        assert_eq!(layout.get_finger(0x03), Some(Finger::LeftIndex));
+        assert_eq!(layout.get_finger(0x26), Some(Finger::RightIndex));
+    }
+
+    #[test]
+    fn test_ortho_rows_have_no_stagger() {
+        let layout = OrthoLayout::new();
+        let positions = layout.key_positions();
+        let column_x = |row: usize| -> Vec<u16> {
+            positions
+                .iter()
+                .filter(|(r, _, _)| *r == row)
+                .map(|(_, x, _)| *x)
+                .collect()
+        };
+        assert_eq!(column_x(0), column_x(1));
+        assert_eq!(column_x(1), column_x(2));
+    }
+}
@@ -1,9 +1,391 @@
 use rdev::Key;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// Which input layout's legends to use when naming key codes. Key codes are
+/// physical positions, so e.g. 0x0C is "Q" on US but "A" on AZERTY.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyNaming {
+    #[default]
+    Us,
+    Azerty,
+    Qwertz,
+}
+
+static KEY_NAMING: AtomicU8 = AtomicU8::new(KeyNaming::Us as u8);
+/// Cleared by `set_key_naming`: an explicit naming beats recorded input sources.
+static FOLLOW_INPUT_SOURCES: AtomicBool = AtomicBool::new(true);
+
+/// Fixes the naming used by `KeyCode::to_name` and `event_naming` for the
+/// rest of the process, whatever input sources events were recorded with.
+/// This is the `key_names` setting.
+pub fn set_key_naming(naming: KeyNaming) {
+    KEY_NAMING.store(naming as u8, Ordering::Relaxed);
+    FOLLOW_INPUT_SOURCES.store(false, Ordering::Relaxed);
+}
+
+/// Without a `key_names` setting, makes the predominant naming of `namings`
+/// the one `KeyCode::to_name` uses, for output that aggregates events typed
+/// on different layouts. Returns the naming now in use.
+pub fn use_input_source_naming(namings: &InputSourceNamings) -> KeyNaming {
+    if FOLLOW_INPUT_SOURCES.load(Ordering::Relaxed) {
+        if let Some(naming) = namings.predominant() {
+            KEY_NAMING.store(naming as u8, Ordering::Relaxed);
+        }
+    }
+    key_naming()
+}
+
+/// The naming for one event typed with `input_source`: the `key_names`
+/// setting if there is one, otherwise the source's own, falling back to the
+/// process-wide naming for unrecorded or unrecognized sources.
+pub fn event_naming(input_source: Option<&str>) -> KeyNaming {
+    if !FOLLOW_INPUT_SOURCES.load(Ordering::Relaxed) {
+        return key_naming();
+    }
+    input_source
+        .and_then(KeyNaming::from_input_source)
+        .unwrap_or_else(key_naming)
+}
+
+impl KeyNaming {
+    const ALL: [KeyNaming; 3] = [KeyNaming::Us, KeyNaming::Azerty, KeyNaming::Qwertz];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyNaming::Us => "US",
+            KeyNaming::Azerty => "AZERTY",
+            KeyNaming::Qwertz => "QWERTZ",
+        }
+    }
+
+    /// The naming for a macOS input source ID such as
+    /// `com.apple.keylayout.German`, if it's one lurk has legends for.
+    /// Layouts that move letters around (Dvorak, Colemak) have none: naming
+    /// them after US legends would be wrong.
+    pub fn from_input_source(id: &str) -> Option<KeyNaming> {
+        let layout = id.strip_prefix("com.apple.keylayout.")?;
+        match layout {
+            "US" | "ABC" | "USExtended" | "USInternational-PC" | "British" | "British-PC"
+            | "Australian" | "Canadian" | "Irish" => Some(KeyNaming::Us),
+            "French" | "French-PC" | "French-numerical" | "Belgian" => Some(KeyNaming::Azerty),
+            "German" | "Austrian" | "Czech" | "Czech-QWERTY" | "Hungarian" | "Slovak" => {
+                Some(KeyNaming::Qwertz)
+            }
+            _ if layout.starts_with("Swiss") => Some(KeyNaming::Qwertz),
+            _ => None,
+        }
+    }
+}
+
+/// Presses per naming among recorded input sources. Sources without a
+/// naming, and events recorded before input sources were, aren't counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputSourceNamings {
+    presses: [u64; 3],
+}
+
+impl InputSourceNamings {
+    pub fn add(&mut self, input_source: Option<&str>, presses: u64) {
+        if let Some(naming) = input_source.and_then(KeyNaming::from_input_source) {
+            self.presses[naming as usize] += presses;
+        }
+    }
+
+    /// Counts one press per item.
+    pub fn from_presses<'a, I>(input_sources: I) -> Self
+    where
+        I: IntoIterator<Item = Option<&'a str>>,
+    {
+        let mut namings = Self::default();
+        for input_source in input_sources {
+            namings.add(input_source, 1);
+        }
+        namings
+    }
+
+    /// The most common naming, if any was recognized.
+    pub fn predominant(&self) -> Option<KeyNaming> {
+        KeyNaming::ALL
+            .into_iter()
+            .filter(|naming| self.presses[*naming as usize] > 0)
+            .max_by_key(|naming| self.presses[*naming as usize])
+    }
+
+    /// Whether keys were typed under more than one naming, so one set of key
+    /// names can't be right for all of them.
+    pub fn is_mixed(&self) -> bool {
+        self.presses.iter().filter(|&&presses| presses > 0).count() > 1
+    }
+
+    /// "AZERTY 60%, US 40%", most common first.
+    pub fn summary(&self) -> String {
+        let total: u64 = self.presses.iter().sum();
+        let mut namings: Vec<KeyNaming> = KeyNaming::ALL
+            .into_iter()
+            .filter(|naming| self.presses[*naming as usize] > 0)
+            .collect();
+        namings.sort_by_key(|naming| std::cmp::Reverse(self.presses[*naming as usize]));
+        namings
+            .iter()
+            .map(|naming| {
+                let pct = self.presses[*naming as usize] as f64 / total as f64 * 100.0;
+                format!("{} {:.0}%", naming.label(), pct)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn key_naming() -> KeyNaming {
+    match KEY_NAMING.load(Ordering::Relaxed) {
+        x if x == KeyNaming::Azerty as u8 => KeyNaming::Azerty,
+        x if x == KeyNaming::Qwertz as u8 => KeyNaming::Qwertz,
+        _ => KeyNaming::Us,
+    }
+}
+
+/// French (macOS) legends that differ from US.
+fn azerty_name(code: u32) -> Option<&'static str> {
+    Some(match code {
+        0x0C => "A",
+        0x0D => "Z",
+        0x00 => "Q",
+        0x06 => "W",
+        0x29 => "M",
+        0x2E => ",",
+        0x2B => ";",
+        0x2F => ":",
+        0x2C => "=",
+        0x12 => "&",
+        0x13 => "é",
+        0x14 => "\"",
+        0x15 => "'",
+        0x17 => "(",
+        0x16 => "§",
+        0x1A => "è",
+        0x1C => "!",
+        0x19 => "ç",
+        0x1D => "à",
+        0x1B => ")",
+        0x18 => "-",
+        0x21 => "^",
+        0x1E => "$",
+        0x27 => "ù",
+        0x2A => "`",
+        0x32 => "@",
+        _ => return None,
+    })
+}
+
+/// German (macOS) legends that differ from US.
+fn qwertz_name(code: u32) -> Option<&'static str> {
+    Some(match code {
+        0x10 => "Z",
+        0x06 => "Y",
+        0x21 => "Ü",
+        0x1E => "+",
+        0x29 => "Ö",
+        0x27 => "Ä",
+        0x2A => "#",
+        0x1B => "ß",
+        0x18 => "´",
+        0x2C => "-",
+        0x32 => "<",
+        0x0A => "^",
+        _ => return None,
+    })
+}
+
+/// US legends and names for non-character keys.
+fn us_name(code: u32) -> Option<&'static str> {
+    Some(match code {
+        0x00 => "A",
+        0x01 => "S",
+        0x02 => "D",
+        0x03 => "F",
+        0x04 => "H",
+        0x05 => "G",
+        0x06 => "Z",
+        0x07 => "X",
+        0x08 => "C",
+        0x09 => "V",
+        0x0B => "B",
+        0x0C => "Q",
+        0x0D => "W",
+        0x0E => "E",
+        0x0F => "R",
+        0x10 => "Y",
+        0x11 => "T",
+        0x12 => "1",
+        0x13 => "2",
+        0x14 => "3",
+        0x15 => "4",
+        0x16 => "6",
+        0x17 => "5",
+        0x18 => "=",
+        0x19 => "9",
+        0x1A => "7",
+        0x1B => "-",
+        0x1C => "8",
+        0x1D => "0",
+        0x1E => "]",
+        0x1F => "O",
+        0x20 => "U",
+        0x21 => "[",
+        0x22 => "I",
+        0x23 => "P",
+        0x24 => "Return",
+        0x25 => "L",
+        0x26 => "J",
+        0x27 => "'",
+        0x28 => "K",
+        0x29 => ";",
+        0x2A => "\\",
+        0x2B => ",",
+        0x2C => "/",
+        0x2D => "N",
+        0x2E => "M",
+        0x2F => ".",
+        0x30 => "Tab",
+        0x31 => "Space",
+        0x32 => "`",
+        0x33 => "Backspace",
+        0x35 => "Escape",
+        0x36 => "RightCommand",
+        0x37 => "LeftCommand",
+        0x38 => "LeftShift",
+        0x39 => "CapsLock",
+        0x3A => "LeftAlt",
+        0x3B => "LeftControl",
+        0x3C => "RightShift",
+        0x3D => "RightAlt",
+        0x3E => "RightControl",
+        0x3F => "Function",
+        0x7A => "F1",
+        0x78 => "F2",
+        0x63 => "F3",
+        0x76 => "F4",
+        0x60 => "F5",
+        0x61 => "F6",
+        0x62 => "F7",
+        0x64 => "F8",
+        0x65 => "F9",
+        0x6D => "F10",
+        0x67 => "F11",
+        0x6F => "F12",
+        0x73 => "Home",
+        0x74 => "PageUp",
+        0x75 => "Delete",
+        0x77 => "End",
+        0x79 => "PageDown",
+        0x7B => "LeftArrow",
+        0x7C => "RightArrow",
+        0x7D => "DownArrow",
+        0x7E => "UpArrow",
+        // Keypad
+        0x41 => "Keypad.",
+        0x43 => "Keypad*",
+        0x45 => "Keypad+",
+        0x47 => "Clear",
+        0x4B => "Keypad/",
+        0x4C => "Enter",
+        0x4E => "Keypad-",
+        0x51 => "Keypad=",
+        0x52 => "Keypad0",
+        0x53 => "Keypad1",
+        0x54 => "Keypad2",
+        0x55 => "Keypad3",
+        0x56 => "Keypad4",
+        0x57 => "Keypad5",
+        0x58 => "Keypad6",
+        0x59 => "Keypad7",
+        0x5B => "Keypad8",
+        0x5C => "Keypad9",
+        // Extended function row and media keys
+        0x69 => "F13",
+        0x6B => "F14",
+        0x71 => "F15",
+        0x6A => "F16",
+        0x40 => "F17",
+        0x4F => "F18",
+        0x50 => "F19",
+        0x5A => "F20",
+        0x48 => "VolumeUp",
+        0x49 => "VolumeDown",
+        0x4A => "Mute",
+        0x72 => "Help",
+        0x6E => "ContextMenu",
+        0x83 => "Launchpad",
+        0xA0 => "MissionControl",
+        // ISO and JIS extras
+        0x0A => "§",
+        0x5D => "Yen",
+        0x5E => "JISUnderscore",
+        0x5F => "JISKeypadComma",
+        0x66 => "Eisu",
+        0x68 => "Kana",
+        // Not a real key: stands in for keys typed in a redacted app
+        0xFFFF => "Redacted",
+        _ => return None,
+    })
+}
+
+/// Broad role of a key, for excluding non-typing keys from stats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCategory {
+    Letter,
+    Digit,
+    Symbol,
+    /// Space, Tab and Return.
+    Whitespace,
+    /// Backspace and forward delete.
+    Editing,
+    Modifier,
+    Navigation,
+    Keypad,
+    /// F-keys, volume, Mission Control and similar.
+    MediaFunction,
+    /// Escape, JIS input-mode keys and unnamed codes.
+    Other,
+}
+
+impl KeyCategory {
+    /// Keys that type a character: letters, digits, symbols and whitespace.
+    pub fn produces_character(&self) -> bool {
+        matches!(
+            self,
+            KeyCategory::Letter | KeyCategory::Digit | KeyCategory::Symbol | KeyCategory::Whitespace
+        )
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyCategory::Letter => "Letters",
+            KeyCategory::Digit => "Digits",
+            KeyCategory::Symbol => "Symbols",
+            KeyCategory::Whitespace => "Whitespace",
+            KeyCategory::Editing => "Editing",
+            KeyCategory::Modifier => "Modifiers",
+            KeyCategory::Navigation => "Navigation",
+            KeyCategory::Keypad => "Keypad",
+            KeyCategory::MediaFunction => "Media/Function",
+            KeyCategory::Other => "Other",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct KeyCode(pub u32);
 
 impl KeyCode {
+    /// Recorded in place of the real key for apps in `AppMode::Redact`.
+    pub const REDACTED: KeyCode = KeyCode(0xFFFF);
+
+    /// On macOS rdev only names the common keys; everything else (keypad,
+    /// media and extended F-keys) arrives as `Key::Unknown` carrying the
+    /// virtual keycode, which is kept as is and named by `to_name`.
     pub fn from_rdev_key(key: &Key) -> Self {
         let code = match key {
             Key::Alt => 0x3A,
@@ -116,92 +498,73 @@ impl KeyCode {
         KeyCode(code)
     }
 
-    pub fn to_name(&self) -> String {
+    /// Classification by physical position, independent of `KeyNaming`.
+    pub fn category(&self) -> KeyCategory {
         match self.0 {
-            0x00 => "A".to_string(),
-            0x01 => "S".to_string(),
-            0x02 => "D".to_string(),
-            0x03 => "F".to_string(),
-            0x04 => "H".to_string(),
-            0x05 => "G".to_string(),
-            0x06 => "Z".to_string(),
-            0x07 => "X".to_string(),
-            0x08 => "C".to_string(),
-            0x09 => "V".to_string(),
-            0x0B => "B".to_string(),
-            0x0C => "Q".to_string(),
-            0x0D => "W".to_string(),
-            0x0E => "E".to_string(),
-            0x0F => "R".to_string(),
-            0x10 => "Y".to_string(),
-            0x11 => "T".to_string(),
-            0x12 => "1".to_string(),
-            0x13 => "2".to_string(),
-            0x14 => "3".to_string(),
-            0x15 => "4".to_string(),
-            0x16 => "6".to_string(),
-            0x17 => "5".to_string(),
-            0x18 => "=".to_string(),
-            0x19 => "9".to_string(),
-            0x1A => "7".to_string(),
-            0x1B => "-".to_string(),
-            0x1C => "8".to_string(),
-            0x1D => "0".to_string(),
-            0x1E => "]".to_string(),
-            0x1F => "O".to_string(),
-            0x20 => "U".to_string(),
-            0x21 => "[".to_string(),
-            0x22 => "I".to_string(),
-            0x23 => "P".to_string(),
-            0x24 => "Return".to_string(),
-            0x25 => "L".to_string(),
-            0x26 => "J".to_string(),
-            0x27 => "'".to_string(),
-            0x28 => "K".to_string(),
-            0x29 => ";".to_string(),
-            0x2A => "\\".to_string(),
-            0x2B => ",".to_string(),
-            0x2C => "/".to_string(),
-            0x2D => "N".to_string(),
-            0x2E => "M".to_string(),
-            0x2F => ".".to_string(),
-            0x30 => "Tab".to_string(),
-            0x31 => "Space".to_string(),
-            0x32 => "`".to_string(),
-            0x33 => "Backspace".to_string(),
-            0x35 => "Escape".to_string(),
-            0x36 => "RightCommand".to_string(),
-            0x37 => "LeftCommand".to_string(),
-            0x38 => "LeftShift".to_string(),
-            0x39 => "CapsLock".to_string(),
-            0x3A => "LeftAlt".to_string(),
-            0x3B => "LeftControl".to_string(),
-            0x3C => "RightShift".to_string(),
-            0x3D => "RightAlt".to_string(),
-            0x3E => "RightControl".to_string(),
-            0x3F => "Function".to_string(),
-            0x7A => "F1".to_string(),
-            0x78 => "F2".to_string(),
-            0x63 => "F3".to_string(),
-            0x76 => "F4".to_string(),
-            0x60 => "F5".to_string(),
-            0x61 => "F6".to_string(),
-            0x62 => "F7".to_string(),
-            0x64 => "F8".to_string(),
-            0x65 => "F9".to_string(),
-            0x6D => "F10".to_string(),
-            0x67 => "F11".to_string(),
-            0x6F => "F12".to_string(),
-            0x73 => "Home".to_string(),
-            0x74 => "PageUp".to_string(),
-            0x75 => "Delete".to_string(),
-            0x77 => "End".to_string(),
-            0x79 => "PageDown".to_string(),
-            0x7B => "LeftArrow".to_string(),
-            0x7C => "RightArrow".to_string(),
-            0x7D => "DownArrow".to_string(),
-            0x7E => "UpArrow".to_string(),
-            code => format!("Unknown(0x{:02X})", code),
+            0x00..=0x09 | 0x0B..=0x11 | 0x1F | 0x20 | 0x22 | 0x23 | 0x25 | 0x26 | 0x28
+            | 0x2D | 0x2E => KeyCategory::Letter,
+            0x12..=0x17 | 0x19 | 0x1A | 0x1C | 0x1D => KeyCategory::Digit,
+            0x0A | 0x18 | 0x1B | 0x1E | 0x21 | 0x27 | 0x29..=0x2C | 0x2F | 0x32 => {
+                KeyCategory::Symbol
+            }
+            0x24 | 0x30 | 0x31 => KeyCategory::Whitespace,
+            0x33 | 0x75 => KeyCategory::Editing,
+            0x36..=0x3F => KeyCategory::Modifier,
+            0x73 | 0x74 | 0x77 | 0x79 | 0x7B..=0x7E => KeyCategory::Navigation,
+            0x41 | 0x43 | 0x45 | 0x47 | 0x4B | 0x4C | 0x4E | 0x51..=0x59 | 0x5B | 0x5C
+            | 0x5F => KeyCategory::Keypad,
+            0x40 | 0x48..=0x4A | 0x4F | 0x50 | 0x5A | 0x60..=0x65 | 0x67 | 0x69..=0x6B
+            | 0x6D..=0x6F | 0x71 | 0x72 | 0x76 | 0x78 | 0x7A | 0x83 | 0xA0 => {
+                KeyCategory::MediaFunction
+            }
+            _ => KeyCategory::Other,
+        }
+    }
+
+    /// Resolves a name as printed by `to_name`, ignoring case. The active
+    /// naming is tried before the US legends, so `Q` means 0x00 under AZERTY;
+    /// a hex code such as `0x0E` is accepted too.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(hex) = name.strip_prefix("0x").or_else(|| name.strip_prefix("0X")) {
+            return u32::from_str_radix(hex, 16).ok().map(KeyCode);
+        }
+        let codes = || (0..=0xFF).map(KeyCode).filter(KeyCode::is_named);
+        let us_name = |code: &KeyCode| code.to_name_for(KeyNaming::Us);
+        codes()
+            .find(|code| code.to_name().eq_ignore_ascii_case(name))
+            .or_else(|| codes().find(|code| us_name(code).eq_ignore_ascii_case(name)))
+    }
+
+    /// Shift, Control, Option or Command on either side: the modifiers held
+    /// down for a combo. Caps Lock (which toggles and may stay on for hours)
+    /// and Fn are `KeyCategory::Modifier` too, but not these.
+    pub fn is_held_modifier(&self) -> bool {
+        matches!(self.0, 0x36..=0x38 | 0x3A..=0x3E)
+    }
+
+    /// Whether the code has a name of its own rather than `Unknown(0x..)`.
+    pub fn is_named(&self) -> bool {
+        us_name(self.0).is_some()
+    }
+
+    /// Name using the process-wide naming set by `set_key_naming` (US by default).
+    pub fn to_name(&self) -> String {
+        self.to_name_for(key_naming())
+    }
+
+    pub fn to_name_for(&self, naming: KeyNaming) -> String {
+        let localized = match naming {
+            KeyNaming::Us => None,
+            KeyNaming::Azerty => azerty_name(self.0),
+            KeyNaming::Qwertz => qwertz_name(self.0),
+        };
+        if let Some(name) = localized {
+            return name.to_string();
+        }
+
+        match us_name(self.0) {
+            Some(name) => name.to_string(),
+            None => format!("Unknown(0x{:02X})", self.0),
         }
     }
 }
@@ -248,6 +611,106 @@ mod tests {
         assert!(name.starts_with("Unknown"));
     }
 
+    #[test]
+    fn test_is_named() {
+        assert!(KeyCode(0x00).is_named());
+        assert!(KeyCode(0x7E).is_named());
+        assert!(!KeyCode(0xFF).is_named());
+    }
+
+    #[test]
+    fn test_media_and_function_keys() {
+        use rdev::Key;
+
+        for (code, name) in [(0x48, "VolumeUp"), (0x4A, "Mute"), (0x69, "F13"), (0xA0, "MissionControl")] {
+            let key = KeyCode::from_rdev_key(&Key::Unknown(code));
+            assert_eq!(key.to_name(), name);
+            assert_eq!(key.category(), KeyCategory::MediaFunction);
+        }
+        assert_eq!(KeyCode(0x60).category(), KeyCategory::MediaFunction);
+    }
+
+    #[test]
+    fn test_keypad_keys() {
+        assert_eq!(KeyCode::from_rdev_key(&rdev::Key::Kp7).to_name(), "Keypad7");
+        assert_eq!(KeyCode::from_rdev_key(&rdev::Key::KpReturn).to_name(), "Enter");
+        assert_eq!(KeyCode(0x53).category(), KeyCategory::Keypad);
+    }
+
+    #[test]
+    fn test_category() {
+        assert_eq!(KeyCode(0x00).category(), KeyCategory::Letter);
+        assert_eq!(KeyCode(0x2E).category(), KeyCategory::Letter);
+        assert_eq!(KeyCode(0x1D).category(), KeyCategory::Digit);
+        assert_eq!(KeyCode(0x2B).category(), KeyCategory::Symbol);
+        assert_eq!(KeyCode(0x31).category(), KeyCategory::Whitespace);
+        assert_eq!(KeyCode(0x33).category(), KeyCategory::Editing);
+        assert_eq!(KeyCode(0x38).category(), KeyCategory::Modifier);
+        assert!(KeyCode(0x38).is_held_modifier());
+        assert!(!KeyCode(0x39).is_held_modifier());
+        assert!(!KeyCode(0x3F).is_held_modifier());
+        assert_eq!(KeyCode(0x7B).category(), KeyCategory::Navigation);
+        assert_eq!(KeyCode(0x35).category(), KeyCategory::Other);
+        assert_eq!(KeyCode(0xFF).category(), KeyCategory::Other);
+    }
+
+    #[test]
+    fn test_keycode_to_name_azerty() {
+        assert_eq!(KeyCode(0x0C).to_name_for(KeyNaming::Azerty), "A");
+        assert_eq!(KeyCode(0x00).to_name_for(KeyNaming::Azerty), "Q");
+        assert_eq!(KeyCode(0x29).to_name_for(KeyNaming::Azerty), "M");
+        assert_eq!(KeyCode(0x13).to_name_for(KeyNaming::Azerty), "é");
+        assert_eq!(KeyCode(0x02).to_name_for(KeyNaming::Azerty), "D");
+    }
+
+    #[test]
+    fn test_keycode_to_name_qwertz() {
+        assert_eq!(KeyCode(0x10).to_name_for(KeyNaming::Qwertz), "Z");
+        assert_eq!(KeyCode(0x06).to_name_for(KeyNaming::Qwertz), "Y");
+        assert_eq!(KeyCode(0x29).to_name_for(KeyNaming::Qwertz), "Ö");
+        assert_eq!(KeyCode(0x31).to_name_for(KeyNaming::Qwertz), "Space");
+    }
+
+    #[test]
+    fn test_naming_from_input_source() {
+        let naming = KeyNaming::from_input_source;
+        assert_eq!(naming("com.apple.keylayout.US"), Some(KeyNaming::Us));
+        assert_eq!(naming("com.apple.keylayout.French-PC"), Some(KeyNaming::Azerty));
+        assert_eq!(naming("com.apple.keylayout.SwissFrench"), Some(KeyNaming::Qwertz));
+        assert_eq!(naming("com.apple.keylayout.Russian"), None);
+        assert_eq!(naming("com.apple.keylayout.Dvorak"), None);
+        assert_eq!(naming("com.apple.inputmethod.Kotoeri.RomajiTyping"), None);
+
+        let namings = InputSourceNamings::from_presses([
+            Some("com.apple.keylayout.German"),
+            None,
+            Some("com.apple.keylayout.US"),
+            Some("com.apple.keylayout.German"),
+            Some("com.apple.keylayout.Russian"),
+        ]);
+        assert_eq!(namings.predominant(), Some(KeyNaming::Qwertz));
+        assert!(namings.is_mixed());
+        assert_eq!(namings.summary(), "QWERTZ 67%, US 33%");
+
+        let unknown = InputSourceNamings::from_presses([None, Some("com.apple.keylayout.Greek")]);
+        assert_eq!(unknown.predominant(), None);
+        assert!(!unknown.is_mixed());
+    }
+
+    #[test]
+    fn test_keycode_to_name_us_default() {
+        assert_eq!(KeyCode(0x0C).to_name_for(KeyNaming::default()), "Q");
+    }
+
+    #[test]
+    fn test_keycode_from_name() {
+        assert_eq!(KeyCode::from_name("E"), Some(KeyCode(0x0E)));
+        assert_eq!(KeyCode::from_name("space"), Some(KeyCode(0x31)));
+        assert_eq!(KeyCode::from_name("LeftShift"), Some(KeyCode(0x38)));
+        assert_eq!(KeyCode::from_name("0x24"), Some(KeyCode(0x24)));
+        assert_eq!(KeyCode::from_name("NoSuchKey"), None);
+    }
+
     #[test]
     fn test_keycode_display() {
         assert_eq!(format!("{}", KeyCode(0x00)), "A");
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::models::keyboard_layout::{build_finger_map, Finger, KeyInfo, KeyboardLayout};
+use crate::models::keycode::{KeyCategory, KeyCode};
+
+/// Width of a key that doesn't give one, as on the built-in layouts.
+const DEFAULT_KEY_WIDTH: u16 = 2;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutFile {
+    name: String,
+    rows: Vec<Vec<KeyEntry>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyEntry {
+    /// A name as lurk prints it (`E`, `Space`) or a code such as `0x0E`.
+    key: String,
+    finger: String,
+    /// Shown on the key; defaults to the key's name.
+    label: Option<String>,
+    width: Option<u16>,
+    /// Extra space before the key, e.g. between the halves of a split board.
+    gap: Option<u16>,
+}
+
+/// A layout read from a JSON file, so custom boards can be analyzed like the
+/// built-in ones. Rows run top to bottom; the home row is the one holding
+/// the F position (0x03), as for every layout.
+///
+/// ```json
+/// { "name": "My split", "rows": [[{ "key": "Q", "finger": "left_pinky" }, ...], ...] }
+/// ```
+pub struct FileLayout {
+    name: &'static str,
+    rows: Vec<Vec<KeyInfo>>,
+    gaps: HashMap<(usize, usize), u16>,
+    finger_map: HashMap<u32, Finger>,
+}
+
+impl FileLayout {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read layout file {}", path.display()))?;
+        Self::from_json(&contents)
+            .with_context(|| format!("Invalid layout file {}", path.display()))
+    }
+
+    /// Parses and validates a layout: every key known and assigned once, and
+    /// every letter position on the board, so no typing falls off it.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: LayoutFile = serde_json::from_str(json)?;
+
+        let mut seen = HashSet::new();
+        let mut gaps = HashMap::new();
+        let mut rows = Vec::with_capacity(file.rows.len());
+        for (row_index, entries) in file.rows.into_iter().enumerate() {
+            let mut row = Vec::with_capacity(entries.len());
+            for (col, entry) in entries.into_iter().enumerate() {
+                let key_code = KeyCode::from_name(&entry.key)
+                    .ok_or_else(|| anyhow!("Unknown key '{}' in row {}", entry.key, row_index + 1))?;
+                if !seen.insert(key_code.0) {
+                    return Err(anyhow!("Key {} is assigned more than once", key_code));
+                }
+                let finger = entry
+                    .finger
+                    .parse()
+                    .with_context(|| format!("Key {} has no valid finger", key_code))?;
+                if let Some(gap) = entry.gap {
+                    gaps.insert((row_index, col), gap);
+                }
+                row.push(KeyInfo {
+                    keycode: key_code.0,
+                    label: leak(entry.label.unwrap_or_else(|| key_code.to_name())),
+                    width: entry.width.unwrap_or(DEFAULT_KEY_WIDTH).max(1),
+                    finger,
+                });
+            }
+            rows.push(row);
+        }
+
+        let missing: Vec<String> = (0..=0xFF)
+            .map(KeyCode)
+            .filter(|code| code.category() == KeyCategory::Letter && !seen.contains(&code.0))
+            .map(|code| code.to_name())
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow!("Letter keys not assigned: {}", missing.join(", ")));
+        }
+
+        let finger_map = build_finger_map(&rows);
+        Ok(Self {
+            name: leak(file.name),
+            rows,
+            gaps,
+            finger_map,
+        })
+    }
+}
+
+/// `KeyboardLayout` hands out static names and labels, which suits the
+/// built-in layouts. A layout file is loaded once per run, so its strings
+/// are leaked rather than threading lifetimes through every layout.
+fn leak(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+impl KeyboardLayout for FileLayout {
+    fn display_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn rows(&self) -> &[Vec<KeyInfo>] {
+        &self.rows
+    }
+
+    fn get_finger(&self, keycode: u32) -> Option<Finger> {
+        self.finger_map.get(&keycode).copied()
+    }
+
+    fn indent(&self, row: usize, col: usize) -> u16 {
+        self.gaps.get(&(row, col)).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fingers::finger_loads;
+
+    /// QWERTY letters on a 3x10 grid, with Z, X and C moved one finger
+    /// over as on an angle-modded board.
+    const ANGLE_MOD: &str = r#"{
+        "name": "Angle mod",
+        "rows": [
+            [{"key": "Q", "finger": "L4"}, {"key": "W", "finger": "L3"}, {"key": "E", "finger": "L2"},
+             {"key": "R", "finger": "L1"}, {"key": "T", "finger": "L1"},
+             {"key": "Y", "finger": "R1", "gap": 4}, {"key": "U", "finger": "R1"},
+             {"key": "I", "finger": "R2"}, {"key": "O", "finger": "R3"}, {"key": "P", "finger": "R4"}],
+            [{"key": "A", "finger": "L4"}, {"key": "S", "finger": "L3"}, {"key": "D", "finger": "L2"},
+             {"key": "F", "finger": "L1"}, {"key": "G", "finger": "L1"},
+             {"key": "H", "finger": "R1", "gap": 4}, {"key": "J", "finger": "R1"},
+             {"key": "K", "finger": "R2"}, {"key": "L", "finger": "R3"}, {"key": "0x29", "finger": "R4"}],
+            [{"key": "Z", "finger": "left_ring"}, {"key": "X", "finger": "left_middle"},
+             {"key": "C", "finger": "left_index"}, {"key": "V", "finger": "L1"}, {"key": "B", "finger": "L1"},
+             {"key": "N", "finger": "R1", "gap": 4}, {"key": "M", "finger": "R1"},
+             {"key": "Space", "finger": "thumb", "label": "SPC", "width": 4}]
+        ]
+    }"#;
+
+    #[test]
+    fn test_layout_file_drives_finger_load() {
+        let layout = FileLayout::from_json(ANGLE_MOD).unwrap();
+        assert_eq!(layout.display_name(), "Angle mod");
+        assert_eq!(layout.home_row(), Some(1));
+        assert_eq!(layout.get_finger(0x06), Some(Finger::LeftRing));
+        assert_eq!(layout.rows()[2][7].label, "SPC");
+        assert_eq!(layout.indent(0, 5), 4);
+
+        // Z, X and C once each, A twice.
+        let percentages = HashMap::from([(0x06, 20.0), (0x07, 20.0), (0x08, 20.0), (0x00, 40.0)]);
+        let loads: HashMap<Finger, f64> = finger_loads(&percentages, &layout).into_iter().collect();
+        assert_eq!(loads[&Finger::LeftPinky], 40.0);
+        assert_eq!(loads[&Finger::LeftRing], 20.0);
+        assert_eq!(loads[&Finger::LeftMiddle], 20.0);
+        assert_eq!(loads[&Finger::LeftIndex], 20.0);
+    }
+
+    #[test]
+    fn test_layout_file_must_cover_letters_once() {
+        let without_q = ANGLE_MOD.replace(r#"{"key": "Q", "finger": "L4"}, "#, "");
+        let err = FileLayout::from_json(&without_q).err().expect("missing Q accepted");
+        assert!(err.to_string().contains("not assigned: Q"), "{}", err);
+
+        let twice = ANGLE_MOD.replace(r#""key": "W""#, r#""key": "Q""#);
+        assert!(FileLayout::from_json(&twice).is_err());
+
+        let bad_finger = ANGLE_MOD.replace(r#""finger": "L4"}"#, r#""finger": "L5"}"#);
+        assert!(FileLayout::from_json(&bad_finger).is_err());
+    }
+}
@@ -1,4 +1,7 @@
 pub mod event;
+pub mod hours;
+pub mod keyboard_layout;
 pub mod keycode;
+pub mod layout_file;
 
 pub use event::{EventType, KeystrokeEvent};
@@ -1,37 +1,101 @@
 #![allow(dead_code)]
 
-use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
-use std::fs::{self, File};
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write};
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 use std::path::{Path, PathBuf};
 use tracing::info;
 
+use crate::models::hours::HourWindow;
+use crate::models::keycode::{InputSourceNamings, KeyCode};
 use crate::models::{EventType, KeystrokeEvent};
 
+/// Path SQLite treats as a private in-memory database. Such a database is
+/// never encrypted and has no key file.
+pub const IN_MEMORY_PATH: &str = ":memory:";
+
+/// Metadata entry marking a database created with `daemon --no-encryption`.
+/// Such a file stays plain SQLite on every later open instead of being
+/// migrated to an encrypted one.
+const ENCRYPTION_METADATA_KEY: &str = "encryption";
+const NO_ENCRYPTION: &str = "none";
+
 const KEY_FILE_NAME: &str = ".key";
 const KEY_LENGTH: usize = 32;
 const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
 
+const EVENT_COLUMNS: &str =
+    "timestamp, key_code, event_type, modifiers, application, window_title, character, input_source";
+
+/// Columns `migrate_schema` adds to databases created before them, with their types.
+const ADDED_COLUMNS: [(&str, &str); 3] =
+    [("window_title", "TEXT"), ("character", "TEXT"), ("input_source", "TEXT")];
+
 pub struct Database {
     conn: Connection,
 }
 
+/// Where `open_read_only_conn` gets the encryption key from.
+enum KeySource<'a> {
+    /// The key file next to the database.
+    File,
+    Given(&'a str),
+    /// A database marked as unencrypted.
+    Plaintext,
+}
+
 impl Database {
     pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
         let db_path = db_path.as_ref();
-        let is_memory = db_path.to_str() == Some(":memory:");
+        let is_memory = is_in_memory(db_path);
+        if is_memory || Self::is_marked_plaintext(db_path)? {
+            return Self::open_read_write(db_path, false);
+        }
 
-        if !is_memory && db_path.exists() && Self::is_unencrypted_sqlite(db_path)? {
+        if db_path.exists() && Self::is_unencrypted_sqlite(db_path)? {
             Self::migrate_to_encrypted(db_path)?;
         }
 
+        // Anything left at the path now is encrypted: a fresh key could never
+        // open it, so don't write one over the missing original.
+        let has_data = fs::metadata(db_path).is_ok_and(|m| m.len() > 0);
+        if has_data && !Self::key_path(db_path)?.exists() {
+            return Err(missing_key_error(db_path, &Self::key_path(db_path)?));
+        }
+
+        Self::open_read_write(db_path, true)
+    }
+
+    /// Opens or creates a database that is never encrypted, for throwaway
+    /// machines or for reading with plain SQLite tools. No key file is
+    /// written, and the database is marked so that `new` and
+    /// `open_read_only` keep it plaintext. An existing encrypted database
+    /// can't be switched.
+    pub fn new_unencrypted<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        let db_path = db_path.as_ref();
+        let has_data = fs::metadata(db_path).is_ok_and(|m| m.len() > 0);
+        if has_data && !Self::is_unencrypted_sqlite(db_path)? {
+            return Err(anyhow!(
+                "{:?} is already encrypted; export its events and remove it to start an \
+                 unencrypted database",
+                db_path
+            ));
+        }
+
+        let db = Self::open_read_write(db_path, false)?;
+        db.set_metadata(ENCRYPTION_METADATA_KEY, NO_ENCRYPTION)?;
+        Ok(db)
+    }
+
+    fn open_read_write(db_path: &Path, encrypted: bool) -> Result<Self> {
         let conn = Connection::open(db_path)?;
 
-        if !is_memory {
+        if encrypted {
             let key = Self::get_or_create_key(db_path)?;
             Self::apply_encryption(&conn, &key)?;
+            Self::check_key(&conn, db_path)?;
         }
 
         conn.pragma_update(None, "journal_mode", "WAL")?;
@@ -48,6 +112,122 @@ impl Database {
         Ok(db)
     }
 
+    /// A fresh in-memory database holding `events`, so commands that query
+    /// the database can run on events read from elsewhere (e.g. stdin).
+    pub fn from_events(events: &[KeystrokeEvent]) -> Result<Self> {
+        let mut db = Self::new(IN_MEMORY_PATH)?;
+        db.insert_events_batch(events)?;
+        Ok(db)
+    }
+
+    /// Opens an existing database for commands that only read. Schema setup
+    /// and the persistent pragmas are skipped, so the file is never written
+    /// and the daemon keeps the write lock to itself. A database that still
+    /// needs migrating (to encryption or to the current schema) is an error:
+    /// the daemon migrates it when it next starts.
+    pub fn open_read_only<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        let db_path = db_path.as_ref();
+        // Each connection to `:memory:` is a new, empty database, so there is
+        // nothing to read; `new` opens one to write to.
+        if is_in_memory(db_path) {
+            return Err(anyhow!(
+                "An in-memory database can't be opened read-only; use Database::new"
+            ));
+        }
+        let source = if Self::is_marked_plaintext(db_path)? {
+            KeySource::Plaintext
+        } else {
+            if db_path.exists() && Self::is_unencrypted_sqlite(db_path)? {
+                return Err(outdated_database_error(db_path));
+            }
+            KeySource::File
+        };
+
+        let db = Self::open_read_only_conn(db_path, &source)?;
+        if !db.is_schema_current()? {
+            return Err(outdated_database_error(db_path));
+        }
+        Ok(db)
+    }
+
+    /// Like `open_read_only`, but with an explicit encryption key instead of
+    /// the key file next to the database, e.g. for a database copied from
+    /// another machine. Fails if the key is wrong or the schema is out of date.
+    pub fn open_read_only_with_key<P: AsRef<Path>>(db_path: P, key: &str) -> Result<Self> {
+        let db_path = db_path.as_ref();
+        let db = Self::open_read_only_conn(db_path, &KeySource::Given(key))?;
+        if !db.is_schema_current()? {
+            return Err(anyhow!(
+                "{:?} uses an older schema; open it with lurk on its own machine first",
+                db_path
+            ));
+        }
+        Ok(db)
+    }
+
+    fn open_read_only_conn(db_path: &Path, source: &KeySource) -> Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = Connection::open_with_flags(db_path, flags)
+            .with_context(|| format!("Failed to open {:?} read-only", db_path))?;
+
+        let key = match source {
+            KeySource::Given(key) => Some(key.to_string()),
+            KeySource::File => {
+                let key_path = Self::key_path(db_path)?;
+                if !key_path.exists() {
+                    return Err(missing_key_error(db_path, &key_path));
+                }
+                Some(Self::read_key(&key_path)?)
+            }
+            KeySource::Plaintext => None,
+        };
+        if let Some(key) = key {
+            Self::apply_encryption(&conn, &key)?;
+            Self::check_key(&conn, db_path)?;
+        }
+
+        conn.pragma_update(None, "cache_size", -20000)?;
+        conn.pragma_update(None, "temp_store", "MEMORY")?;
+        conn.pragma_update(None, "mmap_size", 268435456)?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+
+        Ok(Self { conn })
+    }
+
+    /// Whether the file already has every column `migrate_schema` adds.
+    fn is_schema_current(&self) -> Result<bool> {
+        for (column, _) in ADDED_COLUMNS {
+            if !self.has_column("keystroke_events", column)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Whether `path` is a plain SQLite file created by `new_unencrypted`, as
+    /// opposed to a legacy one from before encryption that `new` migrates.
+    fn is_marked_plaintext(path: &Path) -> Result<bool> {
+        if !path.exists() || !Self::is_unencrypted_sqlite(path)? {
+            return Ok(false);
+        }
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        // Legacy files have no metadata table at all.
+        let marker: Option<String> = conn
+            .query_row(
+                "SELECT value FROM metadata WHERE key = ?1",
+                params![ENCRYPTION_METADATA_KEY],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(marker.as_deref() == Some(NO_ENCRYPTION))
+    }
+
+    /// Whether this is a database created by `new_unencrypted`, which stores
+    /// keystrokes in plaintext.
+    pub fn is_plaintext(&self) -> Result<bool> {
+        Ok(self.get_metadata(ENCRYPTION_METADATA_KEY)?.as_deref() == Some(NO_ENCRYPTION))
+    }
+
     fn is_unencrypted_sqlite(path: &Path) -> Result<bool> {
         let mut file = File::open(path)?;
         let mut header = [0u8; 16];
@@ -133,55 +313,173 @@ impl Database {
         let key_path = Self::key_path(db_path)?;
 
         if key_path.exists() {
-            let mut key = String::new();
-            File::open(&key_path)
-                .context("Failed to open key file")?
-                .read_to_string(&mut key)
-                .context("Failed to read key file")?;
-            return Ok(key.trim().to_string());
+            return Self::read_key(&key_path);
         }
 
-        let key = Self::generate_random_key();
+        let key = Self::generate_random_key()?;
 
         if let Some(parent) = key_path.parent() {
             fs::create_dir_all(parent)?;
         }
+        Self::write_key(&key_path, &key)?;
 
-        let mut file = File::create(&key_path).context("Failed to create key file")?;
+        Ok(key)
+    }
+
+    /// Writes the key readable only by its owner from the start, and syncs
+    /// it: a database encrypted with a key that never reached the disk is lost.
+    fn write_key(key_path: &Path, key: &str) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(key_path)
+            .context("Failed to create key file")?;
+        // `mode` only applies to new files.
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
         file.write_all(key.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
 
-        let mut perms = fs::metadata(&key_path)?.permissions();
-        perms.set_mode(0o600);
-        fs::set_permissions(&key_path, perms)?;
+    fn read_key(key_path: &Path) -> Result<String> {
+        let mut key = String::new();
+        File::open(key_path)
+            .context("Failed to open key file")?
+            .read_to_string(&mut key)
+            .context("Failed to read key file")?;
+        Ok(key.trim().to_string())
+    }
 
-        Ok(key)
+    /// Re-encrypts the database at `db_path` with a freshly generated key and
+    /// replaces the key file. The rekey runs on a copy, which is renamed over
+    /// the original only once it reads back with the new key, so a failure
+    /// leaves the database and key as they were. The new key is written to
+    /// `.key.new` first: if the process dies between the two renames, that
+    /// file holds the key the database now needs.
+    ///
+    /// Nothing else may have the database open, or its writes would go to
+    /// the old file: the rotation fails unless it can take the database out
+    /// of WAL mode, which SQLite only allows for the sole connection, and it
+    /// holds an exclusive lock until the new file is in place.
+    pub fn rotate_key(db_path: &Path) -> Result<()> {
+        if Self::is_marked_plaintext(db_path)? {
+            return Err(anyhow!("{:?} is not encrypted, so it has no key to rotate", db_path));
+        }
+        let key_path = Self::key_path(db_path)?;
+        if !key_path.exists() {
+            return Err(missing_key_error(db_path, &key_path));
+        }
+        let old_key = Self::read_key(&key_path)?;
+
+        let conn = Connection::open(db_path)?;
+        Self::apply_encryption(&conn, &old_key)?;
+        Self::check_key(&conn, db_path)?;
+        // Leaving WAL mode folds the WAL into the main file, so copying it
+        // copies everything, and fails while any other connection is open.
+        let in_use = || {
+            anyhow!(
+                "{:?} is in use; stop the daemon and close any dashboard before rotating the key",
+                db_path
+            )
+        };
+        let journal_mode: String = conn
+            .query_row("PRAGMA journal_mode = DELETE", [], |row| row.get(0))
+            .map_err(|_| in_use())?;
+        if !journal_mode.eq_ignore_ascii_case("delete") {
+            return Err(in_use());
+        }
+        conn.execute_batch("BEGIN EXCLUSIVE").map_err(|_| in_use())?;
+
+        let copy_path = db_path.with_extension("db.rotating");
+        fs::copy(db_path, &copy_path).context("Failed to copy the database")?;
+        let new_key = loop {
+            let key = Self::generate_random_key()?;
+            if key != old_key {
+                break key;
+            }
+        };
+        let rekeyed = (|| -> Result<()> {
+            let conn = Connection::open(&copy_path)?;
+            Self::apply_encryption(&conn, &old_key)?;
+            // SQLCipher can't rekey a database in WAL mode.
+            conn.pragma_update(None, "journal_mode", "DELETE")?;
+            conn.pragma_update(None, "rekey", &new_key)?;
+            conn.pragma_update(None, "journal_mode", "WAL")?;
+            drop(conn);
+
+            let conn = Connection::open(&copy_path)?;
+            Self::apply_encryption(&conn, &new_key)?;
+            Self::check_key(&conn, &copy_path)?;
+            drop(conn);
+            File::open(&copy_path)?.sync_all()?;
+            Ok(())
+        })();
+        if let Err(e) = rekeyed {
+            let _ = fs::remove_file(&copy_path);
+            return Err(e.context("Failed to re-encrypt the database; nothing was changed"));
+        }
+
+        let new_key_path = key_path.with_extension("new");
+        Self::write_key(&new_key_path, &new_key)?;
+        let dir = key_path.parent().context("Key path has no parent directory")?;
+        sync_dir(dir)?;
+        fs::rename(&copy_path, db_path).context("Failed to replace the database")?;
+        fs::rename(&new_key_path, &key_path).with_context(|| {
+            format!(
+                "The database now needs the key in {:?}; move it to {:?}",
+                new_key_path, key_path
+            )
+        })?;
+        // Journal files of the old database would otherwise be applied to the
+        // new one the next time it's opened.
+        for suffix in ["-wal", "-shm", "-journal"] {
+            let mut stale = db_path.as_os_str().to_owned();
+            stale.push(suffix);
+            match fs::remove_file(&stale) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| format!("Failed to remove {:?}", stale));
+                }
+                _ => {}
+            }
+        }
+        sync_dir(dir)?;
+        drop(conn);
+
+        info!("Rotated the encryption key of {:?}", db_path);
+        Ok(())
     }
 
     fn key_path(db_path: &Path) -> Result<PathBuf> {
+        if is_in_memory(db_path) {
+            return Err(anyhow!("In-memory databases are not encrypted and have no key"));
+        }
         let parent = db_path
             .parent()
             .context("Database path has no parent directory")?;
         Ok(parent.join(KEY_FILE_NAME))
     }
 
-    fn generate_random_key() -> String {
-        use std::time::{SystemTime, UNIX_EPOCH};
-
-        let seed = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-
-        let mut state = seed;
-        let charset: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-
-        (0..KEY_LENGTH)
-            .map(|_| {
-                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
-                let idx = ((state >> 33) as usize) % charset.len();
-                charset[idx] as char
-            })
-            .collect()
+    /// `KEY_LENGTH` alphanumerics from the OS's random source.
+    fn generate_random_key() -> Result<String> {
+        const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+        // Bytes from here up are skipped so every character is equally likely.
+        let limit = 256 / CHARSET.len() * CHARSET.len();
+
+        let mut urandom = File::open("/dev/urandom").context("Failed to open /dev/urandom")?;
+        let mut key = String::with_capacity(KEY_LENGTH);
+        let mut bytes = [0u8; KEY_LENGTH * 2];
+        while key.len() < KEY_LENGTH {
+            urandom.read_exact(&mut bytes)?;
+            let chars = bytes
+                .iter()
+                .map(|&byte| byte as usize)
+                .filter(|&byte| byte < limit)
+                .map(|byte| CHARSET[byte % CHARSET.len()] as char);
+            key.extend(chars.take(KEY_LENGTH - key.len()));
+        }
+        Ok(key)
     }
 
     fn apply_encryption(conn: &Connection, key: &str) -> Result<()> {
@@ -189,6 +487,23 @@ impl Database {
         Ok(())
     }
 
+    /// SQLCipher only notices a wrong key on the first read, where it reports
+    /// "file is not a database".
+    fn check_key(conn: &Connection, db_path: &Path) -> Result<()> {
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+            .map(drop)
+            .map_err(|e| {
+                anyhow!(
+                    "Cannot decrypt {:?} with the key in {:?} ({}). The database was encrypted \
+                     with a different key, and its data can't be recovered without that key. \
+                     Restore the original key file, or move the database aside to start over.",
+                    db_path,
+                    Self::key_path(db_path).unwrap_or_default(),
+                    e
+                )
+            })
+    }
+
     fn initialize_schema(&mut self) -> Result<()> {
         self.conn.execute_batch(
             r#"
@@ -224,21 +539,82 @@ impl Database {
             "#,
         )?;
 
+        self.migrate_schema()?;
+
+        Ok(())
+    }
+
+    /// Adds columns introduced after the original schema to existing databases.
+    fn migrate_schema(&self) -> Result<()> {
+        for (column, column_type) in ADDED_COLUMNS {
+            if !self.has_column("keystroke_events", column)? {
+                self.conn.execute(
+                    &format!("ALTER TABLE keystroke_events ADD COLUMN {} {}", column, column_type),
+                    [],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+
+        for name in names {
+            if name? == column {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn event_from_row(row: &Row) -> rusqlite::Result<KeystrokeEvent> {
+        let event_type_str: String = row.get(2)?;
+        let modifiers_json: String = row.get(3)?;
+
+        Ok(KeystrokeEvent {
+            timestamp: row.get(0)?,
+            key_code: row.get(1)?,
+            event_type: if event_type_str == "press" {
+                EventType::Press
+            } else {
+                EventType::Release
+            },
+            modifiers: serde_json::from_str(&modifiers_json).unwrap_or_default(),
+            application: row.get(4)?,
+            window_title: row.get(5)?,
+            character: row.get(6)?,
+            input_source: row.get(7)?,
+        })
+    }
+
+    /// Negative timestamps would sort before every real event and break range queries.
+    fn validate_event(event: &KeystrokeEvent) -> Result<()> {
+        if event.timestamp < 0 {
+            return Err(anyhow!("Invalid negative timestamp: {}", event.timestamp));
+        }
         Ok(())
     }
 
     pub fn insert_event(&self, event: &KeystrokeEvent) -> Result<()> {
+        Self::validate_event(event)?;
         let modifiers_json = serde_json::to_string(&event.modifiers)?;
 
         self.conn.execute(
-            "INSERT INTO keystroke_events (timestamp, key_code, event_type, modifiers, application)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            &format!(
+                "INSERT INTO keystroke_events ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                EVENT_COLUMNS
+            ),
             params![
                 event.timestamp,
                 event.key_code,
                 event.event_type.as_str(),
                 modifiers_json,
                 event.application,
+                event.window_title,
+                event.character,
+                event.input_source,
             ],
         )?;
 
@@ -249,17 +625,23 @@ impl Database {
         let tx = self.conn.transaction()?;
 
         for event in events {
+            Self::validate_event(event)?;
             let modifiers_json = serde_json::to_string(&event.modifiers)?;
 
             tx.execute(
-                "INSERT INTO keystroke_events (timestamp, key_code, event_type, modifiers, application)
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                &format!(
+                    "INSERT INTO keystroke_events ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    EVENT_COLUMNS
+                ),
                 params![
                     event.timestamp,
                     event.key_code,
                     event.event_type.as_str(),
                     modifiers_json,
                     event.application,
+                    event.window_title,
+                    event.character,
+                    event.input_source,
                 ],
             )?;
         }
@@ -269,29 +651,15 @@ impl Database {
     }
 
     pub fn get_events_in_range(&self, start: i64, end: i64) -> Result<Vec<KeystrokeEvent>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT timestamp, key_code, event_type, modifiers, application
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
              FROM keystroke_events
              WHERE timestamp >= ?1 AND timestamp <= ?2
              ORDER BY timestamp ASC",
-        )?;
+            EVENT_COLUMNS
+        ))?;
 
-        let rows = stmt.query_map(params![start, end], |row| {
-            let event_type_str: String = row.get(2)?;
-            let modifiers_json: String = row.get(3)?;
-
-            Ok(KeystrokeEvent {
-                timestamp: row.get(0)?,
-                key_code: row.get(1)?,
-                event_type: if event_type_str == "press" {
-                    EventType::Press
-                } else {
-                    EventType::Release
-                },
-                modifiers: serde_json::from_str(&modifiers_json).unwrap_or_default(),
-                application: row.get(4)?,
-            })
-        })?;
+        let rows = stmt.query_map(params![start, end], Self::event_from_row)?;
 
         let mut events = Vec::new();
         for row in rows {
@@ -302,71 +670,249 @@ impl Database {
     }
 
     pub fn get_all_events(&self) -> Result<Vec<KeystrokeEvent>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT timestamp, key_code, event_type, modifiers, application
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
              FROM keystroke_events
              ORDER BY timestamp ASC",
+            EVENT_COLUMNS
+        ))?;
+
+        let rows = stmt.query_map([], Self::event_from_row)?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            events.push(row?);
+        }
+
+        Ok(events)
+    }
+
+    /// Streams every event in timestamp order without collecting them, so
+    /// exports of long histories run in constant memory. Returns the count.
+    pub fn for_each_event<F>(&self, mut f: F) -> Result<usize>
+    where
+        F: FnMut(KeystrokeEvent) -> Result<()>,
+    {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM keystroke_events
+             ORDER BY timestamp ASC",
+            EVENT_COLUMNS
+        ))?;
+
+        let rows = stmt.query_map([], Self::event_from_row)?;
+
+        let mut count = 0;
+        for row in rows {
+            f(row?)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Inserts the events not already stored, in one transaction, treating
+    /// events with the same timestamp, key, event type and application as
+    /// duplicates. Returns `(inserted, skipped)`.
+    pub fn insert_missing_events(&mut self, events: &[KeystrokeEvent]) -> Result<(usize, usize)> {
+        let tx = self.conn.transaction()?;
+        let (mut inserted, mut skipped) = (0, 0);
+        {
+            let mut exists = tx.prepare(
+                "SELECT EXISTS(SELECT 1 FROM keystroke_events
+                 WHERE timestamp = ?1 AND key_code = ?2 AND event_type = ?3 AND application = ?4)",
+            )?;
+            let mut insert = tx.prepare(&format!(
+                "INSERT INTO keystroke_events ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                EVENT_COLUMNS
+            ))?;
+
+            for event in events {
+                Self::validate_event(event)?;
+                let event_type = event.event_type.as_str();
+                let duplicate: bool = exists.query_row(
+                    params![event.timestamp, event.key_code, event_type, event.application],
+                    |row| row.get(0),
+                )?;
+                if duplicate {
+                    skipped += 1;
+                    continue;
+                }
+                insert.execute(params![
+                    event.timestamp,
+                    event.key_code,
+                    event_type,
+                    serde_json::to_string(&event.modifiers)?,
+                    event.application,
+                    event.window_title,
+                    event.character,
+                    event.input_source,
+                ])?;
+                inserted += 1;
+            }
+        }
+        tx.commit()?;
+        Ok((inserted, skipped))
+    }
+
+    /// Streams the timestamp of every event since `start`, in order.
+    pub fn for_each_timestamp_since<F>(&self, start: i64, mut f: F) -> Result<usize>
+    where
+        F: FnMut(i64),
+    {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp FROM keystroke_events
+             WHERE timestamp >= ?1
+             ORDER BY timestamp ASC",
         )?;
 
+        let rows = stmt.query_map(params![start], |row| row.get::<_, i64>(0))?;
+
+        let mut count = 0;
+        for row in rows {
+            f(row?);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Like `for_each_event`, but in insertion order and with each row's id,
+    /// for scans that care about the order events were written in.
+    pub fn for_each_event_with_id<F>(&self, mut f: F) -> Result<usize>
+    where
+        F: FnMut(i64, KeystrokeEvent) -> Result<()>,
+    {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, id
+             FROM keystroke_events
+             ORDER BY id ASC",
+            EVENT_COLUMNS
+        ))?;
+
         let rows = stmt.query_map([], |row| {
-            let event_type_str: String = row.get(2)?;
-            let modifiers_json: String = row.get(3)?;
-
-            Ok(KeystrokeEvent {
-                timestamp: row.get(0)?,
-                key_code: row.get(1)?,
-                event_type: if event_type_str == "press" {
-                    EventType::Press
-                } else {
-                    EventType::Release
-                },
-                modifiers: serde_json::from_str(&modifiers_json).unwrap_or_default(),
-                application: row.get(4)?,
-            })
+            Ok((row.get::<_, i64>(8)?, Self::event_from_row(row)?))
         })?;
 
+        let mut count = 0;
+        for row in rows {
+            let (id, event) = row?;
+            f(id, event)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Deletes rows by id in one transaction. Returns how many were removed.
+    pub fn delete_events(&mut self, ids: &[i64]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut deleted = 0;
+        {
+            let mut stmt = tx.prepare("DELETE FROM keystroke_events WHERE id = ?1")?;
+            for id in ids {
+                deleted += stmt.execute(params![id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// The newest `limit` events, returned oldest first like the other queries.
+    pub fn get_recent_events(&self, limit: usize) -> Result<Vec<KeystrokeEvent>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM keystroke_events
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?1",
+            EVENT_COLUMNS
+        ))?;
+
+        let rows = stmt.query_map(params![limit as i64], Self::event_from_row)?;
+
         let mut events = Vec::new();
         for row in rows {
             events.push(row?);
         }
+        events.reverse();
 
         Ok(events)
     }
 
     pub fn get_events_since(&self, days_ago: u32) -> Result<Vec<KeystrokeEvent>> {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
-        
-        let start = now - (days_ago as i64 * 24 * 60 * 60 * 1000);
-        self.get_events_in_range(start, now)
+        self.get_events_in_range(timestamp_days_ago(days_ago), now_millis())
     }
 
     pub fn get_total_count(&self) -> Result<i64> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM keystroke_events", [], |row| {
-                row.get(0)
-            })?;
+        self.get_total_count_since(0, None)
+    }
+
+    /// Like `get_total_count`, counting only events at or after `start`
+    /// (and within `hours`, when given).
+    pub fn get_total_count_since(&self, start: i64, hours: Option<HourWindow>) -> Result<i64> {
+        let count: i64 = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM keystroke_events WHERE timestamp >= ?1{}",
+                hour_condition(hours)
+            ),
+            params![start],
+            |row| row.get(0),
+        )?;
         Ok(count)
     }
 
     pub fn get_press_count(&self) -> Result<i64> {
+        self.get_press_count_since(0, None)
+    }
+
+    pub fn get_press_count_since(&self, start: i64, hours: Option<HourWindow>) -> Result<i64> {
         let count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM keystroke_events WHERE event_type = 'press'",
-            [],
+            &format!(
+                "SELECT COUNT(*) FROM keystroke_events
+                 WHERE event_type = 'press' AND timestamp >= ?1{}",
+                hour_condition(hours)
+            ),
+            params![start],
             |row| row.get(0),
         )?;
         Ok(count)
     }
 
+    /// Presses of any of `key_codes` since `start`.
+    pub fn get_key_press_count_since(
+        &self,
+        start: i64,
+        hours: Option<HourWindow>,
+        key_codes: &[u32],
+    ) -> Result<i64> {
+        let mut count = 0;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT COUNT(*) FROM keystroke_events
+             WHERE event_type = 'press' AND timestamp >= ?1 AND key_code = ?2{}",
+            hour_condition(hours)
+        ))?;
+        for key_code in key_codes {
+            count += stmt.query_row(params![start, key_code], |row| row.get::<_, i64>(0))?;
+        }
+        Ok(count)
+    }
+
     pub fn get_date_range(&self) -> Result<Option<(i64, i64)>> {
+        self.get_date_range_since(0, None)
+    }
+
+    pub fn get_date_range_since(
+        &self,
+        start: i64,
+        hours: Option<HourWindow>,
+    ) -> Result<Option<(i64, i64)>> {
         let result: Result<(i64, i64), _> = self.conn.query_row(
-            "SELECT MIN(timestamp), MAX(timestamp) FROM keystroke_events",
-            [],
+            &format!(
+                "SELECT MIN(timestamp), MAX(timestamp) FROM keystroke_events
+                 WHERE timestamp >= ?1{}",
+                hour_condition(hours)
+            ),
+            params![start],
             |row| Ok((row.get(0)?, row.get(1)?)),
         );
 
@@ -376,17 +922,69 @@ impl Database {
         }
     }
 
-    pub fn get_top_keys(&self, limit: usize) -> Result<Vec<(u32, i64)>> {
+    /// Presses per naming of the input sources they were typed with, for
+    /// `use_input_source_naming`.
+    pub fn get_input_source_namings(&self) -> Result<InputSourceNamings> {
         let mut stmt = self.conn.prepare(
+            "SELECT input_source, COUNT(*) FROM keystroke_events
+             WHERE event_type = 'press' AND input_source IS NOT NULL
+             GROUP BY input_source",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?;
+
+        let mut namings = InputSourceNamings::default();
+        for row in rows {
+            let (input_source, presses) = row?;
+            namings.add(Some(&input_source), presses.max(0) as u64);
+        }
+        Ok(namings)
+    }
+
+    pub fn get_top_keys(&self, limit: usize) -> Result<Vec<(u32, i64)>> {
+        self.get_top_keys_since(0, None, limit)
+    }
+
+    pub fn get_top_keys_since(
+        &self,
+        start: i64,
+        hours: Option<HourWindow>,
+        limit: usize,
+    ) -> Result<Vec<(u32, i64)>> {
+        self.top_keys_where(start, &hour_condition(hours), limit)
+    }
+
+    /// `get_top_keys_since` counting only keys that type a character (see
+    /// `KeyCategory::produces_character`), as the dashboard's `c` toggle does.
+    pub fn get_top_character_keys_since(
+        &self,
+        start: i64,
+        hours: Option<HourWindow>,
+        limit: usize,
+    ) -> Result<Vec<(u32, i64)>> {
+        let character_keys: Vec<String> = (0..=u8::MAX as u32)
+            .filter(|&code| KeyCode(code).category().produces_character())
+            .map(|code| code.to_string())
+            .collect();
+        let condition = format!(
+            "{} AND key_code IN ({})",
+            hour_condition(hours),
+            character_keys.join(", ")
+        );
+        self.top_keys_where(start, &condition, limit)
+    }
+
+    fn top_keys_where(&self, start: i64, condition: &str, limit: usize) -> Result<Vec<(u32, i64)>> {
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT key_code, COUNT(*) as count
              FROM keystroke_events
-             WHERE event_type = 'press'
+             WHERE event_type = 'press' AND timestamp >= ?1{}
              GROUP BY key_code
              ORDER BY count DESC
-             LIMIT ?1",
-        )?;
+             LIMIT ?2",
+            condition
+        ))?;
 
-        let rows = stmt.query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let rows = stmt.query_map(params![start, limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
 
         let mut results = Vec::new();
         for row in rows {
@@ -397,16 +995,49 @@ impl Database {
     }
 
     pub fn get_top_applications(&self, limit: usize) -> Result<Vec<(String, i64)>> {
-        let mut stmt = self.conn.prepare(
+        self.get_top_applications_since(0, None, limit)
+    }
+
+    pub fn get_top_applications_since(
+        &self,
+        start: i64,
+        hours: Option<HourWindow>,
+        limit: usize,
+    ) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT application, COUNT(*) as count
              FROM keystroke_events
-             WHERE event_type = 'press'
+             WHERE event_type = 'press' AND timestamp >= ?1{}
              GROUP BY application
              ORDER BY count DESC
-             LIMIT ?1",
+             LIMIT ?2",
+            hour_condition(hours)
+        ))?;
+
+        let rows = stmt.query_map(params![start, limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+
+        Ok(results)
+    }
+
+    /// Press counts per application and UTC day (`YYYY-MM-DD`) since
+    /// `since_timestamp`, ordered by day.
+    pub fn get_app_daily_counts(&self, since_timestamp: i64) -> Result<Vec<(String, String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT application, date(timestamp / 1000, 'unixepoch') as day, COUNT(*) as count
+             FROM keystroke_events
+             WHERE event_type = 'press' AND timestamp >= ?1
+             GROUP BY application, day
+             ORDER BY day, application",
         )?;
 
-        let rows = stmt.query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let rows = stmt.query_map(params![since_timestamp], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
 
         let mut results = Vec::new();
         for row in rows {
@@ -428,21 +1059,345 @@ impl Database {
 
         Ok(deleted)
     }
+
+    pub fn get_config(&self, key: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM config WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn set_config(&self, key: &str, value: &str) -> Result<()> {
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_millis() as i64;
+
+        self.conn.execute(
+            "INSERT INTO config (key, value, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            params![key, value, now_ms],
+        )?;
+        Ok(())
+    }
+
+    /// SQLite's own consistency check: `PRAGMA quick_check`, or the slower
+    /// `integrity_check` (which also verifies indexes match their tables)
+    /// when `full`. Returns the problems found, empty for a healthy file.
+    pub fn storage_problems(&self, full: bool) -> Result<Vec<String>> {
+        let pragma = if full { "PRAGMA integrity_check" } else { "PRAGMA quick_check" };
+        let mut stmt = self.conn.prepare(pragma)?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut problems = Vec::new();
+        for row in rows {
+            match row {
+                Ok(row) if row == "ok" => {}
+                Ok(row) => problems.push(row),
+                // SQLCipher fails the check outright on a page it can't
+                // decrypt rather than listing it.
+                Err(e) => {
+                    problems.push(format!("unreadable page: {}", e));
+                    break;
+                }
+            }
+        }
+        Ok(problems)
+    }
+
+    pub fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+        let value: Option<Option<String>> = self
+            .conn
+            .query_row(
+                "SELECT value FROM metadata WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.flatten())
+    }
+
+    pub fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO metadata (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_all_config(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT key, value FROM config ORDER BY key")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+}
+
+fn is_in_memory(db_path: &Path) -> bool {
+    db_path.to_str() == Some(IN_MEMORY_PATH)
+}
+
+fn now_millis() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    i64::try_from(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis(),
+    )
+    .unwrap_or(i64::MAX)
+}
+
+/// SQL to append to a `WHERE` clause restricting rows to `hours` in local
+/// time; empty for `None`. The bounds are numbers, so formatting them in is safe.
+fn hour_condition(hours: Option<HourWindow>) -> String {
+    let Some(HourWindow { start, end }) = hours else {
+        return String::new();
+    };
+    let hour = "CAST(strftime('%H', timestamp / 1000, 'unixepoch', 'localtime') AS INTEGER)";
+    let joiner = if start < end { "AND" } else { "OR" };
+    format!(" AND ({hour} >= {start} {joiner} {hour} < {end})")
+}
+
+/// Makes renames and new files in `dir` durable.
+fn sync_dir(dir: &Path) -> Result<()> {
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .with_context(|| format!("Failed to sync {:?}", dir))
+}
+
+/// The key file is gone but the encrypted database it unlocked is still there.
+fn missing_key_error(db_path: &Path, key_path: &Path) -> anyhow::Error {
+    anyhow!(
+        "{:?} is encrypted but its key file {:?} is missing. The data can't be decrypted \
+         without the original key; restore it from a backup, or move the database aside \
+         to start over.",
+        db_path,
+        key_path
+    )
+}
+
+fn outdated_database_error(db_path: &Path) -> anyhow::Error {
+    anyhow!(
+        "{:?} was written by an older version of lurk and needs migrating. Start the daemon \
+         once (`lurk daemon`) to bring it up to date.",
+        db_path
+    )
+}
+
+/// Start timestamp (ms) of the window covering the last `days_ago` days.
+pub fn timestamp_days_ago(days_ago: u32) -> i64 {
+    let span = i64::from(days_ago).saturating_mul(24 * 60 * 60 * 1000);
+    now_millis().saturating_sub(span).max(0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::models::event::Modifier;
+    use crate::analysis::FrequencyAnalysis;
+    use crate::testutil::{event, synthetic_stream, TEST_APP};
+
+    #[test]
+    fn test_aggregates_since_exclude_older_events() {
+        let db = Database::new(":memory:").unwrap();
+        let recent = timestamp_days_ago(1);
+        let old = timestamp_days_ago(30);
+
+        db.insert_event(&event(old, 0x01, EventType::Press)).unwrap();
+        db.insert_event(&event(old + 100, 0x01, EventType::Release)).unwrap();
+        db.insert_event(&event(recent, 0x00, EventType::Press)).unwrap();
+        db.insert_event(&event(recent + 50, 0x00, EventType::Press)).unwrap();
+
+        let start = timestamp_days_ago(7);
+        assert_eq!(db.get_total_count_since(start, None).unwrap(), 2);
+        assert_eq!(db.get_press_count_since(start, None).unwrap(), 2);
+        assert_eq!(db.get_date_range_since(start, None).unwrap(), Some((recent, recent + 50)));
+        assert_eq!(db.get_top_keys_since(start, None, 10).unwrap(), vec![(0x00, 2)]);
+        assert_eq!(
+            db.get_top_applications_since(start, None, 5).unwrap(),
+            vec![(TEST_APP.to_string(), 2)]
+        );
+
+        assert_eq!(db.get_key_press_count_since(start, None, &[0x00, 0x33]).unwrap(), 2);
+        assert_eq!(db.get_key_press_count_since(0, None, &[0x01]).unwrap(), 1);
+
+        assert_eq!(db.get_total_count().unwrap(), 4);
+        assert_eq!(db.get_top_keys(10).unwrap().len(), 2);
+        assert_eq!(db.get_date_range_since(timestamp_days_ago(0) + 60_000, None).unwrap(), None);
+
+        // Shift outnumbers A, but doesn't type a character.
+        for offset in [90, 95, 99] {
+            db.insert_event(&event(recent + offset, 0x38, EventType::Press)).unwrap();
+        }
+        assert_eq!(db.get_top_keys_since(start, None, 1).unwrap(), vec![(0x38, 3)]);
+        assert_eq!(db.get_top_character_keys_since(start, None, 10).unwrap(), vec![(0x00, 2)]);
+    }
+
+    #[test]
+    fn test_hour_windows_split_events() {
+        let db = Database::new(":memory:").unwrap();
+        // One press every 20 minutes for a day, so every local hour has some
+        // whatever the machine's time zone.
+        for i in 0..72 {
+            db.insert_event(&event(i * 1_200_000, 0x00, EventType::Press))
+                .unwrap();
+        }
+        let count = |hours: &str| {
+            db.get_press_count_since(0, Some(hours.parse().unwrap()))
+                .unwrap()
+        };
+
+        assert_eq!(count("0-24"), 72);
+        assert_eq!(count("9-17"), 24);
+        assert_eq!(count("9-17") + count("17-9"), 72);
+        assert_eq!(count("22-6"), 24);
+    }
+
+    #[test]
+    fn test_read_only_handle_rejects_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lurk.db");
+        let event = event(1000, 0x00, EventType::Press);
+        Database::new(&path).unwrap().insert_event(&event).unwrap();
+
+        let db = Database::open_read_only(&path).unwrap();
+        assert_eq!(db.get_all_events().unwrap()[0].timestamp, 1000);
+        assert!(db.insert_event(&event).is_err());
+        assert!(db.set_config("retention_days", "30").is_err());
+        assert_eq!(db.get_total_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_missing_or_wrong_key_is_not_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lurk.db");
+        let key_path = dir.path().join(KEY_FILE_NAME);
+        let event = event(1000, 0x00, EventType::Press);
+        Database::new(&path).unwrap().insert_event(&event).unwrap();
+        let key = fs::read_to_string(&key_path).unwrap();
+
+        fs::write(&key_path, "not-the-key").unwrap();
+        let err = Database::new(&path).err().expect("wrong key accepted");
+        assert!(err.to_string().contains("Cannot decrypt"), "{}", err);
+        assert!(Database::open_read_only(&path).is_err());
+        assert_eq!(fs::read_to_string(&key_path).unwrap(), "not-the-key");
+
+        fs::remove_file(&key_path).unwrap();
+        let err = Database::new(&path).err().expect("missing key accepted");
+        assert!(err.to_string().contains("key file"), "{}", err);
+        assert!(!key_path.exists());
+
+        fs::write(&key_path, key).unwrap();
+        assert_eq!(Database::new(&path).unwrap().get_total_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_rotated_key_replaces_the_old_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lurk.db");
+        let key_path = dir.path().join(KEY_FILE_NAME);
+        let event = event(1000, 0x00, EventType::Press);
+        Database::new(&path).unwrap().insert_event(&event).unwrap();
+        let old_key = fs::read_to_string(&key_path).unwrap();
+
+        Database::rotate_key(&path).unwrap();
+        let new_key = fs::read_to_string(&key_path).unwrap();
+        assert_ne!(new_key, old_key);
+        assert!(!path.with_extension("db.rotating").exists());
+        assert!(!key_path.with_extension("new").exists());
+
+        let db = Database::open_read_only(&path).unwrap();
+        assert_eq!(db.get_all_events().unwrap(), vec![event]);
+        assert!(Database::open_read_only_with_key(&path, &new_key).is_ok());
+        assert!(Database::open_read_only_with_key(&path, &old_key).is_err());
+        Database::new(&path).unwrap();
+        assert_eq!(fs::metadata(&key_path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_rotation_refuses_an_open_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lurk.db");
+        let key_path = dir.path().join(KEY_FILE_NAME);
+        let writer = Database::new(&path).unwrap();
+        writer.insert_event(&event(1000, 0x00, EventType::Press)).unwrap();
+        let old_key = fs::read_to_string(&key_path).unwrap();
+
+        let err = Database::rotate_key(&path).unwrap_err();
+        assert!(err.to_string().contains("in use"), "{}", err);
+        assert_eq!(fs::read_to_string(&key_path).unwrap(), old_key);
+        assert!(!path.with_extension("db.rotating").exists());
+
+        writer.insert_event(&event(2000, 0x00, EventType::Press)).unwrap();
+        drop(writer);
+        Database::rotate_key(&path).unwrap();
+        assert_eq!(Database::open_read_only(&path).unwrap().get_total_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_unencrypted_database_stays_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lurk.db");
+        let event = event(1000, 0x00, EventType::Press);
+        Database::new_unencrypted(&path).unwrap().insert_event(&event).unwrap();
+
+        let plain = Connection::open(&path).unwrap();
+        let count: i64 = plain
+            .query_row("SELECT COUNT(*) FROM keystroke_events", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        drop(plain);
+
+        // Later opens neither migrate it nor create a key.
+        let db = Database::new(&path).unwrap();
+        assert!(db.is_plaintext().unwrap());
+        db.insert_event(&event).unwrap();
+        drop(db);
+        assert_eq!(Database::open_read_only(&path).unwrap().get_total_count().unwrap(), 2);
+        assert!(Database::is_unencrypted_sqlite(&path).unwrap());
+        assert!(!dir.path().join(KEY_FILE_NAME).exists());
+        assert!(Database::rotate_key(&path).is_err());
+
+        let encrypted = dir.path().join("encrypted.db");
+        let db = Database::new(&encrypted).unwrap();
+        db.insert_event(&event).unwrap();
+        assert!(!db.is_plaintext().unwrap());
+        drop(db);
+        assert!(Database::new_unencrypted(&encrypted).is_err());
+    }
 
-    fn create_test_event(timestamp: i64, key_code: u32, event_type: EventType) -> KeystrokeEvent {
-        KeystrokeEvent {
-            timestamp,
-            key_code,
-            event_type,
-            modifiers: vec![],
-            application: "com.test.app".to_string(),
+    #[test]
+    fn test_healthy_database_passes_storage_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lurk.db");
+        let db = Database::new(&path).unwrap();
+        for i in 0..100 {
+            db.insert_event(&event(i * 10, 0x00, EventType::Press)).unwrap();
         }
+        assert!(db.storage_problems(false).unwrap().is_empty());
+        assert!(db.storage_problems(true).unwrap().is_empty());
+        drop(db);
+        let db = Database::open_read_only(&path).unwrap();
+        assert!(db.storage_problems(true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_only_open_does_not_create() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Database::open_read_only(dir.path().join("missing.db")).is_err());
     }
 
     #[test]
@@ -455,7 +1410,7 @@ mod tests {
     fn test_insert_and_retrieve_event() {
         let db = Database::new(":memory:").unwrap();
         
-        let event = create_test_event(1000, 0x00, EventType::Press);
+        let event = event(1000, 0x00, EventType::Press);
         db.insert_event(&event).unwrap();
 
         assert_eq!(db.get_total_count().unwrap(), 1);
@@ -467,21 +1422,36 @@ mod tests {
         let db = Database::new(":memory:").unwrap();
         
         for i in 0..10 {
-            let event = create_test_event(1000 + i, 0x00, EventType::Press);
+            let event = event(1000 + i, 0x00, EventType::Press);
             db.insert_event(&event).unwrap();
         }
 
         assert_eq!(db.get_total_count().unwrap(), 10);
     }
 
+    #[test]
+    fn test_synthetic_stream_round_trips_into_analysis() {
+        let mut db = Database::new(":memory:").unwrap();
+        let events = synthetic_stream(2_000);
+        db.insert_events_batch(&events).unwrap();
+
+        let stored = db.get_all_events().unwrap();
+        assert_eq!(stored, events);
+
+        let freq = FrequencyAnalysis::from_events(&stored);
+        assert_eq!(freq.total_presses, 2_000);
+        let counted: u64 = freq.key_frequencies.iter().map(|k| k.count).sum();
+        assert_eq!(counted, 2_000);
+    }
+
     #[test]
     fn test_press_vs_release_count() {
         let db = Database::new(":memory:").unwrap();
         
-        db.insert_event(&create_test_event(1000, 0x00, EventType::Press)).unwrap();
-        db.insert_event(&create_test_event(1050, 0x00, EventType::Release)).unwrap();
-        db.insert_event(&create_test_event(1100, 0x01, EventType::Press)).unwrap();
-        db.insert_event(&create_test_event(1150, 0x01, EventType::Release)).unwrap();
+        db.insert_event(&event(1000, 0x00, EventType::Press)).unwrap();
+        db.insert_event(&event(1050, 0x00, EventType::Release)).unwrap();
+        db.insert_event(&event(1100, 0x01, EventType::Press)).unwrap();
+        db.insert_event(&event(1150, 0x01, EventType::Release)).unwrap();
 
         assert_eq!(db.get_total_count().unwrap(), 4);
         assert_eq!(db.get_press_count().unwrap(), 2);
@@ -491,8 +1461,8 @@ mod tests {
     fn test_get_all_events() {
         let db = Database::new(":memory:").unwrap();
         
-        db.insert_event(&create_test_event(1000, 0x00, EventType::Press)).unwrap();
-        db.insert_event(&create_test_event(1050, 0x00, EventType::Release)).unwrap();
+        db.insert_event(&event(1000, 0x00, EventType::Press)).unwrap();
+        db.insert_event(&event(1050, 0x00, EventType::Release)).unwrap();
 
         let events = db.get_all_events().unwrap();
         assert_eq!(events.len(), 2);
@@ -504,21 +1474,74 @@ mod tests {
     fn test_get_events_in_range() {
         let db = Database::new(":memory:").unwrap();
         
-        db.insert_event(&create_test_event(1000, 0x00, EventType::Press)).unwrap();
-        db.insert_event(&create_test_event(2000, 0x01, EventType::Press)).unwrap();
-        db.insert_event(&create_test_event(3000, 0x02, EventType::Press)).unwrap();
+        db.insert_event(&event(1000, 0x00, EventType::Press)).unwrap();
+        db.insert_event(&event(2000, 0x01, EventType::Press)).unwrap();
+        db.insert_event(&event(3000, 0x02, EventType::Press)).unwrap();
 
         let events = db.get_events_in_range(1500, 2500).unwrap();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].key_code, 0x01);
     }
 
+    #[test]
+    fn test_get_events_since_more_days_than_epoch() {
+        let db = Database::new(":memory:").unwrap();
+        db.insert_event(&event(0, 0x00, EventType::Press)).unwrap();
+        db.insert_event(&event(1000, 0x01, EventType::Press)).unwrap();
+
+        let events = db.get_events_since(u32::MAX).unwrap();
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_negative_timestamp() {
+        let mut db = Database::new(":memory:").unwrap();
+        let event = event(-5, 0x00, EventType::Press);
+
+        assert!(db.insert_event(&event).is_err());
+        assert!(db.insert_events_batch(&[event]).is_err());
+        assert_eq!(db.get_total_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_for_each_event_streams_in_order() {
+        let db = Database::new(":memory:").unwrap();
+        for ts in [3000, 1000, 2000] {
+            db.insert_event(&event(ts, 0x00, EventType::Press)).unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let count = db
+            .for_each_event(|event| {
+                seen.push(event.timestamp);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(count, 3);
+        assert_eq!(seen, vec![1000, 2000, 3000]);
+    }
+
+    #[test]
+    fn test_get_recent_events() {
+        let db = Database::new(":memory:").unwrap();
+        for ts in [3000, 1000, 5000, 2000, 4000] {
+            db.insert_event(&event(ts, 0x00, EventType::Press)).unwrap();
+        }
+
+        let recent = db.get_recent_events(3).unwrap();
+        let timestamps: Vec<i64> = recent.iter().map(|e| e.timestamp).collect();
+        assert_eq!(timestamps, vec![3000, 4000, 5000]);
+
+        assert_eq!(db.get_recent_events(10).unwrap().len(), 5);
+    }
+
     #[test]
     fn test_get_date_range() {
         let db = Database::new(":memory:").unwrap();
         
-        db.insert_event(&create_test_event(1000, 0x00, EventType::Press)).unwrap();
-        db.insert_event(&create_test_event(5000, 0x01, EventType::Press)).unwrap();
+        db.insert_event(&event(1000, 0x00, EventType::Press)).unwrap();
+        db.insert_event(&event(5000, 0x01, EventType::Press)).unwrap();
 
         let range = db.get_date_range().unwrap().unwrap();
         assert_eq!(range, (1000, 5000));
@@ -529,12 +1552,12 @@ mod tests {
         let db = Database::new(":memory:").unwrap();
         
         for _ in 0..5 {
-            db.insert_event(&create_test_event(1000, 0x00, EventType::Press)).unwrap();
+            db.insert_event(&event(1000, 0x00, EventType::Press)).unwrap();
         }
         for _ in 0..3 {
-            db.insert_event(&create_test_event(1000, 0x01, EventType::Press)).unwrap();
+            db.insert_event(&event(1000, 0x01, EventType::Press)).unwrap();
         }
-        db.insert_event(&create_test_event(1000, 0x02, EventType::Press)).unwrap();
+        db.insert_event(&event(1000, 0x02, EventType::Press)).unwrap();
 
         let top = db.get_top_keys(2).unwrap();
         assert_eq!(top.len(), 2);
@@ -546,10 +1569,10 @@ mod tests {
     fn test_get_top_applications() {
         let db = Database::new(":memory:").unwrap();
         
-        let mut event1 = create_test_event(1000, 0x00, EventType::Press);
+        let mut event1 = event(1000, 0x00, EventType::Press);
         event1.application = "com.app.one".to_string();
         
-        let mut event2 = create_test_event(1001, 0x00, EventType::Press);
+        let mut event2 = event(1001, 0x00, EventType::Press);
         event2.application = "com.app.two".to_string();
 
         db.insert_event(&event1).unwrap();
@@ -562,29 +1585,204 @@ mod tests {
         assert_eq!(top[0].1, 2);
     }
 
+    #[test]
+    fn test_get_app_daily_counts() {
+        let db = Database::new(":memory:").unwrap();
+        const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+        let mut editor = event(DAY_MS + 1000, 0x00, EventType::Press);
+        editor.application = "com.editor".to_string();
+        let mut browser = event(2 * DAY_MS + 1000, 0x00, EventType::Press);
+        browser.application = "com.browser".to_string();
+        let mut old = event(1000, 0x00, EventType::Press);
+        old.application = "com.editor".to_string();
+        let release = event(DAY_MS + 2000, 0x00, EventType::Release);
+
+        for event in [&editor, &editor, &browser, &old, &release] {
+            db.insert_event(event).unwrap();
+        }
+
+        let counts = db.get_app_daily_counts(DAY_MS).unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                ("com.editor".to_string(), "1970-01-02".to_string(), 2),
+                ("com.browser".to_string(), "1970-01-03".to_string(), 1),
+            ]
+        );
+    }
+
     #[test]
     fn test_cleanup_old_events() {
         let db = Database::new(":memory:").unwrap();
         
-        db.insert_event(&create_test_event(1000, 0x00, EventType::Press)).unwrap();
-        db.insert_event(&create_test_event(2000, 0x01, EventType::Press)).unwrap();
-        db.insert_event(&create_test_event(3000, 0x02, EventType::Press)).unwrap();
+        db.insert_event(&event(1000, 0x00, EventType::Press)).unwrap();
+        db.insert_event(&event(2000, 0x01, EventType::Press)).unwrap();
+        db.insert_event(&event(3000, 0x02, EventType::Press)).unwrap();
 
         let deleted = db.cleanup_old_events(2500).unwrap();
         assert_eq!(deleted, 2);
         assert_eq!(db.get_total_count().unwrap(), 1);
     }
 
+    #[test]
+    fn test_config_round_trip() {
+        let db = Database::new(":memory:").unwrap();
+        assert_eq!(db.get_config("retention_days").unwrap(), None);
+
+        db.set_config("retention_days", "30").unwrap();
+        db.set_config("filter.max_gap_ms", "2000").unwrap();
+
+        assert_eq!(db.get_config("retention_days").unwrap().as_deref(), Some("30"));
+        assert_eq!(
+            db.get_all_config().unwrap(),
+            vec![
+                ("filter.max_gap_ms".to_string(), "2000".to_string()),
+                ("retention_days".to_string(), "30".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_config_upserts() {
+        let db = Database::new(":memory:").unwrap();
+        db.set_config("retention_days", "30").unwrap();
+        db.conn
+            .execute("UPDATE config SET updated_at = 0 WHERE key = 'retention_days'", [])
+            .unwrap();
+
+        db.set_config("retention_days", "7").unwrap();
+
+        let (count, value, updated_at): (i64, String, i64) = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*), MAX(value), MAX(updated_at) FROM config",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(value, "7");
+        assert!(updated_at > 0);
+    }
+
+    #[test]
+    fn test_keys_are_not_derived_from_the_time() {
+        // Back to back, well within one millisecond.
+        let first = Database::generate_random_key().unwrap();
+        let second = Database::generate_random_key().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.len(), KEY_LENGTH);
+        assert!(first.chars().all(|c| c.is_ascii_alphanumeric()));
+    }
+
+    #[test]
+    fn test_in_memory_database_has_no_key() {
+        assert!(Database::key_path(Path::new(IN_MEMORY_PATH)).is_err());
+        assert!(Database::key_path(Path::new("/tmp/lurk/events.db")).is_ok());
+
+        assert!(Database::open_read_only(IN_MEMORY_PATH).is_err());
+        let db = Database::new(IN_MEMORY_PATH).unwrap();
+        db.insert_event(&event(1000, 0x00, EventType::Press)).unwrap();
+        assert_eq!(db.get_total_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_window_title_round_trip() {
+        let db = Database::new(":memory:").unwrap();
+
+        let mut titled = event(1000, 0x00, EventType::Press);
+        titled.window_title = Some("README.md — lurk".to_string());
+        db.insert_event(&titled).unwrap();
+        db.insert_event(&event(1050, 0x00, EventType::Release)).unwrap();
+
+        let events = db.get_all_events().unwrap();
+        assert_eq!(events[0].window_title.as_deref(), Some("README.md — lurk"));
+        assert_eq!(events[1].window_title, None);
+    }
+
+    #[test]
+    fn test_migrates_legacy_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE keystroke_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                key_code INTEGER NOT NULL,
+                event_type TEXT NOT NULL,
+                modifiers TEXT,
+                application TEXT NOT NULL
+            );
+            INSERT INTO keystroke_events (timestamp, key_code, event_type, modifiers, application)
+            VALUES (1000, 0, 'press', '[]', 'com.test.app');",
+        )
+        .unwrap();
+
+        let mut db = Database { conn };
+        db.initialize_schema().unwrap();
+
+        let events = db.get_all_events().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].window_title, None);
+        assert_eq!(events[0].character, None);
+        assert_eq!(events[0].input_source, None);
+    }
+
+    #[test]
+    fn test_read_only_open_leaves_legacy_schema_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lurk.db");
+        Database::new_unencrypted(&path).unwrap();
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch("ALTER TABLE keystroke_events DROP COLUMN input_source").unwrap();
+        drop(conn);
+
+        let err = Database::open_read_only(&path).err().expect("outdated schema accepted");
+        assert!(err.to_string().contains("needs migrating"), "{}", err);
+        let db = Database { conn: Connection::open(&path).unwrap() };
+        assert!(!db.is_schema_current().unwrap());
+        drop(db);
+
+        Database::new(&path).unwrap();
+        assert!(Database::open_read_only(&path).is_ok());
+    }
+
+    #[test]
+    fn test_character_round_trip() {
+        let db = Database::new(":memory:").unwrap();
+
+        let typed = event(1000, 0x0C, EventType::Press).with_character(Some("é".to_string()));
+        db.insert_event(&typed).unwrap();
+        db.insert_event(&event(1050, 0x0C, EventType::Release)).unwrap();
+
+        let events = db.get_all_events().unwrap();
+        assert_eq!(events[0].character.as_deref(), Some("é"));
+        assert_eq!(events[1].character, None);
+    }
+
+    #[test]
+    fn test_input_source_round_trip() {
+        let mut db = Database::new(":memory:").unwrap();
+        let source = Some("com.apple.keylayout.German".to_string());
+        let typed = event(1000, 0x10, EventType::Press);
+        db.insert_event(&typed.with_input_source(source.clone())).unwrap();
+        db.insert_events_batch(&[event(1050, 0x10, EventType::Release)]).unwrap();
+
+        let events = db.get_all_events().unwrap();
+        assert_eq!(events[0].input_source, source);
+        assert_eq!(events[1].input_source, None);
+
+        let namings = db.get_input_source_namings().unwrap();
+        assert_eq!(namings.predominant(), Some(crate::models::keycode::KeyNaming::Qwertz));
+    }
+
     #[test]
     fn test_event_with_modifiers() {
         let db = Database::new(":memory:").unwrap();
         
         let event = KeystrokeEvent {
-            timestamp: 1000,
-            key_code: 0x00,
-            event_type: EventType::Press,
             modifiers: vec![Modifier::Shift, Modifier::Command],
-            application: "com.test.app".to_string(),
+            ..event(1000, 0x00, EventType::Press)
         };
         
         db.insert_event(&event).unwrap();
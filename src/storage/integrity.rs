@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use crate::models::{EventType, KeystrokeEvent};
+
+/// A press with no release for this long, while later presses of the same
+/// key keep arriving, was never released (key repeat stays well below this).
+const STALE_PRESS_MS: i64 = 10_000;
+
+/// Counts of suspicious rows found by `IntegrityScan`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IntegrityReport {
+    pub events: u64,
+    pub releases: u64,
+    /// Releases with no earlier, still-held press of the same key. Mostly
+    /// legitimate: a key already down when the daemon started, a press in a
+    /// skipped app, or one removed by retention. Reported, never removed.
+    pub orphan_releases: u64,
+    /// Presses whose key was never released. Always 0 when the database has
+    /// no releases at all (`capture.record_releases` off).
+    pub unreleased_presses: u64,
+    /// Modifier presses held too long without a release, which would leave
+    /// later keys looking modified. Not found by `IntegrityScan`; the caller
+    /// fills it in, see `cli::check::scan_database`.
+    pub stuck_modifiers: u64,
+    /// Events written with an earlier timestamp than the row before them.
+    pub out_of_order: u64,
+    pub empty_application: u64,
+    /// Ids of rows that are clearly corrupt and safe to drop: events without
+    /// an application.
+    pub corrupt_ids: Vec<i64>,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.orphan_releases == 0
+            && self.unreleased_presses == 0
+            && self.stuck_modifiers == 0
+            && self.out_of_order == 0
+            && self.empty_application == 0
+    }
+}
+
+/// Streaming integrity check: feed rows in insertion order with `push`, then
+/// call `finish`. Memory stays bounded by the number of held keys plus the
+/// ids of corrupt rows.
+#[derive(Debug, Default)]
+pub struct IntegrityScan {
+    report: IntegrityReport,
+    /// Key code -> timestamp of its currently held press.
+    held: HashMap<u32, i64>,
+    last_timestamp: Option<i64>,
+}
+
+impl IntegrityScan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, id: i64, event: &KeystrokeEvent) {
+        let report = &mut self.report;
+        report.events += 1;
+
+        if self.last_timestamp.is_some_and(|last| event.timestamp < last) {
+            report.out_of_order += 1;
+        }
+        self.last_timestamp = Some(event.timestamp);
+
+        if event.application.trim().is_empty() {
+            report.empty_application += 1;
+            report.corrupt_ids.push(id);
+        }
+
+        match event.event_type {
+            EventType::Press => {
+                match self.held.get(&event.key_code) {
+                    Some(&since) if event.timestamp - since > STALE_PRESS_MS => {
+                        report.unreleased_presses += 1;
+                        self.held.insert(event.key_code, event.timestamp);
+                    }
+                    // Key repeat: the original press is still held.
+                    Some(_) => {}
+                    None => {
+                        self.held.insert(event.key_code, event.timestamp);
+                    }
+                }
+            }
+            EventType::Release => {
+                report.releases += 1;
+                if self.held.remove(&event.key_code).is_none() {
+                    report.orphan_releases += 1;
+                }
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> IntegrityReport {
+        if self.report.releases == 0 {
+            self.report.unreleased_presses = 0;
+        } else {
+            self.report.unreleased_presses += self.held.len() as u64;
+        }
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{in_app, press, release};
+
+    fn scan(events: &[KeystrokeEvent]) -> IntegrityReport {
+        let mut scan = IntegrityScan::new();
+        for (i, event) in events.iter().enumerate() {
+            scan.push(i as i64 + 1, event);
+        }
+        scan.finish()
+    }
+
+    #[test]
+    fn test_clean_sequence() {
+        let report = scan(&[
+            press(100, 0x00),
+            press(150, 0x00),
+            release(200, 0x00),
+        ]);
+        assert!(report.is_clean());
+        assert_eq!(report.events, 3);
+    }
+
+    #[test]
+    fn test_malformed_sequence() {
+        let report = scan(&[
+            release(100, 0x01),
+            press(200, 0x00),
+            in_app(press(150, 0x02), ""),
+            in_app(release(160, 0x02), ""),
+            press(30_000, 0x00),
+            release(30_100, 0x00),
+            press(30_200, 0x03),
+        ]);
+
+        assert_eq!(report.orphan_releases, 1);
+        assert_eq!(report.out_of_order, 1);
+        assert_eq!(report.empty_application, 2);
+        // The 0x00 press at 200 was superseded; the 0x03 press is never released.
+        assert_eq!(report.unreleased_presses, 2);
+        assert_eq!(report.corrupt_ids, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_unreleased_ignored_without_release_recording() {
+        let report = scan(&[
+            press(100, 0x00),
+            press(200, 0x01),
+        ]);
+        assert!(report.is_clean());
+    }
+}
@@ -1,3 +1,5 @@
 pub mod database;
+pub mod integrity;
 
-pub use database::Database;
+pub use database::{timestamp_days_ago, Database};
+pub use integrity::{IntegrityReport, IntegrityScan};
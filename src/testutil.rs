@@ -0,0 +1,63 @@
+//! Event builders shared by the unit tests, so each module doesn't define
+//! its own `press`/`release` helpers.
+
+use crate::models::{EventType, KeystrokeEvent};
+
+/// A long, typing-like stream; see `synthetic_events`.
+pub use crate::analysis::synthetic::synthetic_events as synthetic_stream;
+
+/// Application the builders record events under, unless `in_app` says otherwise.
+pub const TEST_APP: &str = "test";
+
+pub fn event(timestamp: i64, key_code: u32, event_type: EventType) -> KeystrokeEvent {
+    KeystrokeEvent {
+        timestamp,
+        key_code,
+        event_type,
+        modifiers: vec![],
+        application: TEST_APP.to_string(),
+        window_title: None,
+        character: None,
+        input_source: None,
+    }
+}
+
+pub fn press(timestamp: i64, key_code: u32) -> KeystrokeEvent {
+    event(timestamp, key_code, EventType::Press)
+}
+
+pub fn release(timestamp: i64, key_code: u32) -> KeystrokeEvent {
+    event(timestamp, key_code, EventType::Release)
+}
+
+pub fn in_app(event: KeystrokeEvent, application: &str) -> KeystrokeEvent {
+    KeystrokeEvent {
+        application: application.to_string(),
+        ..event
+    }
+}
+
+/// A press of key 0x00 in `application`, for tests about apps rather than keys.
+pub fn app_press(timestamp: i64, application: &str) -> KeystrokeEvent {
+    in_app(press(timestamp, 0x00), application)
+}
+
+/// Presses of `key_codes`, one every `step_ms` starting at 0, without releases.
+pub fn typed(key_codes: &[u32], step_ms: i64) -> Vec<KeystrokeEvent> {
+    key_codes
+        .iter()
+        .enumerate()
+        .map(|(i, &key_code)| press(i as i64 * step_ms, key_code))
+        .collect()
+}
+
+/// Like `typed`, with each key released `hold_ms` after its press.
+pub fn typed_held(key_codes: &[u32], step_ms: i64, hold_ms: i64) -> Vec<KeystrokeEvent> {
+    typed(key_codes, step_ms)
+        .into_iter()
+        .flat_map(|press| {
+            let release = release(press.timestamp + hold_ms, press.key_code);
+            [press, release]
+        })
+        .collect()
+}
@@ -1,28 +1,48 @@
 use std::collections::HashMap;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::Result;
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use chrono::{DateTime, Local, Utc};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::Line,
     widgets::{Block, Borders, Paragraph, Tabs},
     Frame, Terminal,
 };
 
-use crate::analysis::{FilterConfig, FrequencyAnalysis, TimingAnalysis};
+use crate::analysis::{
+    fingers, frequency, FilterConfig, FingerBalance, FingerTargets, FrequencyAnalysis, HandRhythm,
+    HandRunAnalysis, RollingWpm, SameFingerAnalysis, ScissorAnalysis, TimingAnalysis,
+};
+use crate::analysis::filters::sorted_by_time;
+use crate::analysis::flow::{self, BigramFlow};
+use crate::analysis::histogram::{timing_histogram, HistogramEdges};
+use crate::analysis::layout_score::{LayoutMetrics, LayoutScore, LayoutScoreWeights};
+use crate::analysis::rolling_wpm::DEFAULT_WPM_WINDOW_MS;
+use crate::analysis::smoothing::moving_average;
+use crate::analysis::timing::{self, FingerHold};
+use crate::cli::stats::{StatsLimits, StatsReport};
+use crate::config::Config;
+use crate::models::keycode::use_input_source_naming;
 use crate::models::KeystrokeEvent;
-use crate::storage::Database;
-use crate::tui::keyboard_layout::{Finger, Hand, QwertyLayout};
+use crate::storage::{timestamp_days_ago, Database};
+use crate::models::keyboard_layout::{Finger, Hand, KeyboardLayout, QwertyLayout};
+use crate::tui::terminal::{install_panic_hook, TerminalGuard};
+use crate::tui::theme::Theme;
 use crate::tui::views;
+use crate::tui::widgets::HeatmapMetric;
+
+/// How often the dashboard checks the database for new presses.
+const LIVE_POLL_MS: i64 = 1000;
+
+/// Every key, so a search can rank any of them, and the apps the Overview's
+/// distribution lists.
+const REPORT_LIMITS: StatsLimits = StatsLimits { keys: usize::MAX, apps: 5 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum View {
@@ -88,6 +108,11 @@ impl TimeRange {
         }
     }
 
+    /// Earliest timestamp (ms) inside the range; 0 for all time.
+    pub fn start_timestamp(&self) -> i64 {
+        self.days().map_or(0, timestamp_days_ago)
+    }
+
     pub fn next(&self) -> Self {
         match self {
             TimeRange::Days7 => TimeRange::Days30,
@@ -125,63 +150,238 @@ pub struct SpeedMetrics {
     pub consistency: String,
 }
 
-pub struct BigramFingerStats {
-    pub same_finger_pct: f64,
-    pub alternation_pct: f64,
-    pub worst_same_finger: Vec<(String, u64)>,
+pub struct AppTrend {
+    pub application: String,
+    pub daily: Vec<u64>,
+    pub share_pct: f64,
+}
+
+/// An app's share of presses next to how fast it's typed in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppSpeed {
+    pub application: String,
+    pub share_pct: f64,
+    /// `None` with fewer than `low_confidence_samples` intervals in the app.
+    pub wpm: Option<f64>,
+}
+
+/// Left hand's share of one day's presses; the right hand has the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyHandBalance {
+    pub day: String,
+    pub left_pct: f64,
+}
+
+/// Days in the moving average the Trends view can draw under its daily chart.
+pub const DAILY_AVERAGE_DAYS: usize = 7;
+
+/// Events the dashboard holds in memory when `dashboard.max_cached_events`
+/// isn't set; a couple of hundred MB at most.
+pub const DEFAULT_MAX_CACHED_EVENTS: usize = 2_000_000;
+
+/// Whether a time range holding `event_count` events is too big to cache
+/// whole under `cap`.
+pub fn exceeds_cache_cap(event_count: i64, cap: usize) -> bool {
+    usize::try_from(event_count).is_ok_and(|count| count > cap)
 }
 
 pub struct App {
     pub current_view: View,
     pub time_range: TimeRange,
     pub should_quit: bool,
-    db: Database,
+    pub confirm_quit: bool,
+    pub quit_pending: bool,
+    pub layout: Box<dyn KeyboardLayout>,
+    pub theme: Theme,
+    pub heatmap_metric: HeatmapMetric,
+    /// Show a `DAILY_AVERAGE_DAYS` moving average under the Trends view's
+    /// daily chart. Toggled with `s` there.
+    pub smooth_daily: bool,
+    /// Count only character-producing keys in the heatmap and finger stats.
+    pub character_keys_only: bool,
+    pub finger_targets: FingerTargets,
+    pub daily_goal: Option<u64>,
+    /// Frequency heatmap scale cap; see `KeyboardHeatmap::cap_percentile`.
+    pub heatmap_cap_percentile: Option<f64>,
+    /// The configured `[filter]` thresholds, passed to every analysis so the
+    /// views agree with `lurk analyze`. `letters_only_ngrams` is toggled with `l`.
+    pub filter: FilterConfig,
+    pub layout_score_weights: LayoutScoreWeights,
+    /// Buckets of the Timing view's inter-key histogram.
+    pub histogram_edges: HistogramEdges,
+    /// See `exceeds_cache_cap`.
+    pub max_cached_events: usize,
+    /// Where `c` and `l` save their preferences. `None` leaves them unsaved.
+    pub config_path: Option<PathBuf>,
+    /// While set, typed characters go to `search_query` instead of commands.
+    pub search_active: bool,
+    pub search_query: String,
+    /// `None` for an app built from fixed events, which then never reloads.
+    db: Option<Database>,
     events_cache: Option<Vec<KeystrokeEvent>>,
     cache_time_range: Option<TimeRange>,
+    /// Events in the time range when it was over `max_cached_events` and
+    /// only the latest were cached. Key counts then come from SQL; timing and
+    /// n-gram views work on the cached sample.
+    sampled_from: Option<i64>,
+    /// Totals, top keys and top apps for the time range, the same numbers
+    /// `lurk stats` prints. Reloaded with `events_cache`.
+    stats_report: Option<StatsReport>,
+    /// Presses per character-producing key for the time range, the top-keys
+    /// list under `character_keys_only`.
+    character_key_counts: Vec<(u32, i64)>,
+    /// Presses since the dashboard opened, polled from the database as the
+    /// daemon writes them, for a WPM that reacts while you type.
+    rolling_wpm: RollingWpm,
+    /// Newest timestamp already fed to `rolling_wpm`.
+    live_cursor: i64,
+    live_wpm: Option<f64>,
+    #[cfg(test)]
+    panic_on_render: bool,
 }
 
 impl App {
     pub fn new(db_path: &Path) -> Result<Self> {
-        let db = Database::new(db_path)?;
-        Ok(Self {
+        let db = Database::open_read_only(db_path)?;
+        Ok(Self::with_source(Some(db)))
+    }
+
+    /// An app over a fixed set of events and no database, so the getters can
+    /// be exercised without SQLite. Changing the time range keeps the events.
+    pub fn new_with_events(events: Vec<KeystrokeEvent>) -> Self {
+        let mut app = Self::with_source(None);
+        app.events_cache = Some(events);
+        app.cache_time_range = Some(app.time_range);
+        app
+    }
+
+    fn with_source(db: Option<Database>) -> Self {
+        let now = Utc::now().timestamp_millis();
+        Self {
             current_view: View::Overview,
             time_range: TimeRange::Days7,
             should_quit: false,
+            confirm_quit: false,
+            quit_pending: false,
+            layout: Box::new(QwertyLayout::new()),
+            theme: Theme::default(),
+            heatmap_metric: HeatmapMetric::default(),
+            smooth_daily: false,
+            character_keys_only: false,
+            finger_targets: FingerTargets::default(),
+            daily_goal: None,
+            heatmap_cap_percentile: None,
+            filter: FilterConfig::default(),
+            layout_score_weights: LayoutScoreWeights::default(),
+            histogram_edges: HistogramEdges::default(),
+            max_cached_events: DEFAULT_MAX_CACHED_EVENTS,
+            config_path: None,
+            search_active: false,
+            search_query: String::new(),
             db,
             events_cache: None,
             cache_time_range: None,
-        })
+            sampled_from: None,
+            stats_report: None,
+            character_key_counts: Vec::new(),
+            rolling_wpm: RollingWpm::new(DEFAULT_WPM_WINDOW_MS, now),
+            live_cursor: now,
+            live_wpm: None,
+            #[cfg(test)]
+            panic_on_render: false,
+        }
     }
 
     fn get_events(&mut self) -> &[KeystrokeEvent] {
-        if self.cache_time_range != Some(self.time_range) {
-            let events = match self.time_range.days() {
-                Some(days) => self.db.get_events_since(days).unwrap_or_default(),
-                None => self.db.get_all_events().unwrap_or_default(),
+        if let (Some(db), true) = (&self.db, self.cache_time_range != Some(self.time_range)) {
+            let start = self.time_range.start_timestamp();
+            let total = db.get_total_count_since(start, None).unwrap_or(0);
+            // Ranges run up to now, so the latest events all fall inside it.
+            self.sampled_from = exceeds_cache_cap(total, self.max_cached_events).then_some(total);
+            let events = match (self.sampled_from, self.time_range.days()) {
+                (Some(_), _) => db.get_recent_events(self.max_cached_events).unwrap_or_default(),
+                (None, Some(days)) => db.get_events_since(days).unwrap_or_default(),
+                (None, None) => db.get_all_events().unwrap_or_default(),
             };
             self.events_cache = Some(events);
+            self.stats_report =
+                StatsReport::collect(db, self.time_range.days(), None, REPORT_LIMITS).ok();
+            self.character_key_counts =
+                db.get_top_character_keys_since(start, None, usize::MAX).unwrap_or_default();
             self.cache_time_range = Some(self.time_range);
         }
         self.events_cache.as_ref().map(|v| v.as_slice()).unwrap_or(&[])
     }
 
+    /// Events in the time range when only a sample of them is cached; see
+    /// `exceeds_cache_cap`.
+    pub fn sampled_from(&self) -> Option<i64> {
+        self.sampled_from
+    }
+
+    /// The database, when the cache holds only a sample and counts should
+    /// come from SQL instead.
+    fn aggregate_db(&self) -> Option<&Database> {
+        self.db.as_ref().filter(|_| self.sampled_from.is_some())
+    }
+
     pub fn get_key_frequencies(&self) -> HashMap<u32, f64> {
+        if let Some(db) = self.aggregate_db() {
+            let start = self.time_range.start_timestamp();
+            let counts = db.get_top_keys_since(start, None, usize::MAX).unwrap_or_default();
+            return frequency::count_percentages(&counts, self.character_keys_only);
+        }
+
         let events = self.events_cache.as_ref().map(|v| v.as_slice()).unwrap_or(&[]);
         if events.is_empty() {
             return HashMap::new();
         }
 
-        let freq = FrequencyAnalysis::from_events(events);
-        let mut result = HashMap::new();
-        
-        for key in freq.top_keys(100) {
-            result.insert(key.key_code, key.percentage);
+        let analysis = FrequencyAnalysis::from_events(events);
+        if self.character_keys_only {
+            analysis.character_key_percentages()
+        } else {
+            analysis.key_percentages()
         }
-        
-        result
+    }
+
+    /// Per-key values for the Overview heatmap under the selected metric.
+    pub fn get_heatmap_values(&self) -> HashMap<u32, f64> {
+        if self.heatmap_metric == HeatmapMetric::Frequency {
+            return self.get_key_frequencies();
+        }
+
+        let events = self.events_cache.as_ref().map(|v| v.as_slice()).unwrap_or(&[]);
+        let timing = TimingAnalysis::from_events(events, self.filter.clone());
+        let medians = match self.heatmap_metric {
+            HeatmapMetric::HoldTime => timing.hold_medians(),
+            _ => timing.outgoing_medians,
+        };
+        medians
+            .into_iter()
+            .map(|(key_code, ms)| (key_code, ms as f64))
+            .collect()
     }
 
     pub fn get_top_keys(&self, n: usize) -> Vec<(String, u64, f64)> {
+        if let Some(report) = &self.stats_report {
+            let counts: Vec<(u32, i64)> = if self.character_keys_only {
+                self.character_key_counts.clone()
+            } else {
+                report.top_keys.iter().map(|k| (k.key_code, k.presses)).collect()
+            };
+            // Both lists hold every key, so their sum is the total.
+            let total: i64 = counts.iter().map(|(_, count)| count).sum();
+            return counts
+                .into_iter()
+                .take(n)
+                .map(|(key_code, count)| {
+                    let name = crate::models::keycode::KeyCode(key_code).to_name();
+                    (name, count as u64, count as f64 / total as f64 * 100.0)
+                })
+                .collect();
+        }
+
         let events = self.events_cache.as_ref().map(|v| v.as_slice()).unwrap_or(&[]);
         if events.is_empty() {
             return vec![];
@@ -197,10 +397,13 @@ impl App {
     pub fn get_stats(&self) -> DashboardStats {
         let events = self.events_cache.as_ref().map(|v| v.as_slice()).unwrap_or(&[]);
         
-        let total_presses = events
-            .iter()
-            .filter(|e| matches!(e.event_type, crate::models::EventType::Press))
-            .count() as u64;
+        let total_presses = match &self.stats_report {
+            Some(report) => report.presses as u64,
+            None => events
+                .iter()
+                .filter(|e| matches!(e.event_type, crate::models::EventType::Press))
+                .count() as u64,
+        };
 
         let days_active = self.time_range.days().unwrap_or(365) as u32;
         let daily_average = if days_active > 0 {
@@ -209,8 +412,7 @@ impl App {
             0
         };
 
-        let config = FilterConfig::default();
-        let timing = TimingAnalysis::from_events(events, config);
+        let timing = TimingAnalysis::from_events(events, self.filter.clone());
         
         let estimated_wpm = if timing.overall_inter_key.mean_ms > 0.0 {
             ((60000.0 / timing.overall_inter_key.mean_ms) / 5.0) as u32
@@ -228,25 +430,51 @@ impl App {
     }
 
     pub fn get_daily_counts(&self) -> Vec<u64> {
-        let events = self.events_cache.as_ref().map(|v| v.as_slice()).unwrap_or(&[]);
-        if events.is_empty() {
-            return vec![];
-        }
-
         let mut daily: HashMap<String, u64> = HashMap::new();
-        
-        for event in events {
-            if matches!(event.event_type, crate::models::EventType::Press) {
-                let date = chrono::DateTime::from_timestamp_millis(event.timestamp)
-                    .map(|dt| dt.format("%Y-%m-%d").to_string())
-                    .unwrap_or_default();
-                *daily.entry(date).or_insert(0) += 1;
+        if let Some(db) = self.aggregate_db() {
+            let since = self.time_range.start_timestamp();
+            for (_, day, count) in db.get_app_daily_counts(since).unwrap_or_default() {
+                *daily.entry(day).or_insert(0) += count as u64;
+            }
+        } else {
+            let events = self.events_cache.as_deref().unwrap_or(&[]);
+            for event in events {
+                if matches!(event.event_type, crate::models::EventType::Press) {
+                    let date = chrono::DateTime::from_timestamp_millis(event.timestamp)
+                        .map(|dt| dt.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default();
+                    *daily.entry(date).or_insert(0) += 1;
+                }
             }
         }
 
-        let mut dates: Vec<_> = daily.into_iter().collect();
-        dates.sort_by(|a, b| a.0.cmp(&b.0));
-        dates.into_iter().map(|(_, count)| count).collect()
+        zero_filled_days(&daily)
+    }
+
+    /// `get_daily_counts` averaged over the trailing `DAILY_AVERAGE_DAYS`.
+    pub fn get_daily_average(&self) -> Vec<f64> {
+        moving_average(&self.get_daily_counts(), DAILY_AVERAGE_DAYS)
+    }
+
+    /// Presses since local midnight, for the daily goal gauge.
+    pub fn get_today_count(&self) -> u64 {
+        let start = local_day_start(Local::now());
+        if let Some(db) = &self.db {
+            return db.get_press_count_since(start, None).unwrap_or(0) as u64;
+        }
+        let events = self.events_cache.as_deref().unwrap_or(&[]);
+        events
+            .iter()
+            .filter(|e| e.timestamp >= start)
+            .filter(|e| matches!(e.event_type, crate::models::EventType::Press))
+            .count() as u64
+    }
+
+    /// `(today, goal)` when a daily goal is set.
+    pub fn get_daily_goal_progress(&self) -> Option<(u64, u64)> {
+        self.daily_goal
+            .filter(|&goal| goal > 0)
+            .map(|goal| (self.get_today_count(), goal))
     }
 
     pub fn get_weekly_comparison(&self) -> Vec<(String, Vec<f64>, String)> {
@@ -267,81 +495,112 @@ impl App {
     }
 
     pub fn get_app_distribution(&self) -> Vec<(String, f64)> {
-        self.db
-            .get_top_applications(5)
-            .unwrap_or_default()
-            .into_iter()
-            .map(|(app, count)| {
-                let total = self.events_cache.as_ref().map(|v| v.len()).unwrap_or(1) as f64;
-                let pct = (count as f64 / total) * 100.0;
-                (app, pct)
-            })
+        let Some(report) = &self.stats_report else {
+            return vec![];
+        };
+        let presses = report.presses.max(1) as f64;
+        report
+            .top_apps
+            .iter()
+            .map(|a| (a.application.clone(), a.presses as f64 / presses * 100.0))
             .collect()
     }
 
+    /// Daily press counts for the `n` busiest apps in the selected range, one
+    /// series per app over the same days, with each app's share of the total.
+    pub fn get_app_trends(&self, n: usize) -> Vec<AppTrend> {
+        let since = self.time_range.start_timestamp();
+        let Some(db) = &self.db else {
+            return vec![];
+        };
+        let rows = db.get_app_daily_counts(since).unwrap_or_default();
+        app_trends(&rows, n)
+    }
+
+    /// The `n` apps with the most presses in the selected range, each with
+    /// an estimated WPM from its own inter-key timing.
+    pub fn get_app_speeds(&self, n: usize) -> Vec<AppSpeed> {
+        let events = self.events_cache.as_deref().unwrap_or(&[]);
+        app_speeds(events, n, &self.filter)
+    }
+
+    /// Left/right balance per day over the selected range, oldest first.
+    pub fn get_hand_balance_trend(&self) -> Vec<DailyHandBalance> {
+        let events = self.events_cache.as_deref().unwrap_or(&[]);
+        daily_hand_balance(events, self.layout.as_ref())
+    }
+
+    /// Whether the time range has any key presses for the views to show.
+    pub fn has_data(&self) -> bool {
+        let events = self.events_cache.as_deref().unwrap_or(&[]);
+        events.iter().any(|e| e.event_type == crate::models::EventType::Press)
+    }
+
     pub fn get_finger_loads(&self) -> Vec<(Finger, f64)> {
-        let layout = QwertyLayout::new();
-        let frequencies = self.get_key_frequencies();
-        
-        let mut finger_totals: HashMap<Finger, f64> = HashMap::new();
-        
-        for (keycode, pct) in &frequencies {
-            if let Some(finger) = layout.get_finger(*keycode) {
-                *finger_totals.entry(finger).or_insert(0.0) += pct;
-            }
-        }
+        fingers::finger_loads(&self.get_key_frequencies(), self.layout.as_ref())
+    }
 
-        let fingers = [
-            Finger::LeftPinky,
-            Finger::LeftRing,
-            Finger::LeftMiddle,
-            Finger::LeftIndex,
-            Finger::RightIndex,
-            Finger::RightMiddle,
-            Finger::RightRing,
-            Finger::RightPinky,
-        ];
+    /// Hold durations per finger, for spotting fingers that linger on keys.
+    pub fn get_finger_holds(&self) -> Vec<FingerHold> {
+        let events = self.events_cache.as_deref().unwrap_or(&[]);
+        TimingAnalysis::from_events(events, self.filter.clone())
+            .finger_holds(self.layout.as_ref())
+    }
 
-        fingers
-            .into_iter()
-            .map(|f| (f, *finger_totals.get(&f).unwrap_or(&0.0)))
-            .collect()
+    pub fn get_finger_balance(&self) -> Option<FingerBalance> {
+        FingerBalance::from_loads(&self.get_finger_loads(), &self.finger_targets)
+    }
+
+    pub fn get_hand_runs(&self) -> HandRunAnalysis {
+        let events = self.events_cache.as_ref().map(|v| v.as_slice()).unwrap_or(&[]);
+        HandRunAnalysis::from_events(events, self.layout.as_ref())
+    }
+
+    pub fn get_hand_rhythm(&self) -> HandRhythm {
+        let events = self.events_cache.as_deref().unwrap_or(&[]);
+        HandRhythm::from_events(events, self.layout.as_ref(), &self.filter)
+    }
+
+    /// N-gram counts as `analyze` makes them with the same gap and
+    /// letters-only settings.
+    fn ngram_frequencies(&self) -> FrequencyAnalysis {
+        let events = self.events_cache.as_deref().unwrap_or(&[]);
+        FrequencyAnalysis::with_filter(events, &self.filter)
+    }
+
+    /// Most common transitions between keys, with their direction on the layout.
+    pub fn get_top_flows(&self, n: usize) -> Vec<BigramFlow> {
+        let freq = self.ngram_frequencies();
+        flow::top_flows(&freq.bigram_frequencies, self.layout.as_ref(), n)
+    }
+
+    pub fn get_scissors(&self) -> ScissorAnalysis {
+        let freq = self.ngram_frequencies();
+        ScissorAnalysis::from_bigrams(&freq.bigram_frequencies, self.layout.as_ref())
     }
 
     pub fn get_hand_balance(&self) -> (f64, f64) {
-        let finger_loads = self.get_finger_loads();
-        
-        let left: f64 = finger_loads
-            .iter()
-            .filter(|(f, _)| f.hand() == Hand::Left)
-            .map(|(_, pct)| pct)
-            .sum();
-        
-        let right: f64 = finger_loads
-            .iter()
-            .filter(|(f, _)| f.hand() == Hand::Right)
-            .map(|(_, pct)| pct)
-            .sum();
+        fingers::hand_balance(&self.get_finger_loads())
+    }
 
-        let total = left + right;
-        if total > 0.0 {
-            ((left / total) * 100.0, (right / total) * 100.0)
-        } else {
-            (50.0, 50.0)
-        }
+    pub fn get_same_finger(&self) -> SameFingerAnalysis {
+        let freq = self.ngram_frequencies();
+        SameFingerAnalysis::from_bigrams(
+            &freq.bigram_frequencies,
+            self.layout.as_ref(),
+            self.filter.same_finger_repeats,
+        )
     }
 
-    pub fn get_bigram_finger_stats(&self) -> BigramFingerStats {
-        BigramFingerStats {
-            same_finger_pct: 8.5,
-            alternation_pct: 54.2,
-            worst_same_finger: vec![
-                ("ED".to_string(), 2845),
-                ("UN".to_string(), 2234),
-                ("CE".to_string(), 1892),
-                ("MY".to_string(), 1456),
-            ],
-        }
+    /// How well the configured layout fits the typing in the time range.
+    pub fn get_layout_score(&self) -> LayoutScore {
+        let freq = self.ngram_frequencies();
+        let metrics = LayoutMetrics::from_frequencies(
+            &freq,
+            self.layout.as_ref(),
+            self.filter.same_finger_repeats,
+        );
+        LayoutScore::new(&metrics, &self.layout_score_weights)
     }
 
     pub fn get_timing_histogram(&self) -> Vec<(String, u64)> {
@@ -350,24 +609,13 @@ impl App {
             return vec![];
         }
 
-        let config = FilterConfig::default();
-        let timing = TimingAnalysis::from_events(events, config);
-        
-        vec![
-            ("0-50".to_string(), (timing.overall_inter_key.count as f64 * 0.15) as u64),
-            ("50-100".to_string(), (timing.overall_inter_key.count as f64 * 0.35) as u64),
-            ("100-150".to_string(), (timing.overall_inter_key.count as f64 * 0.25) as u64),
-            ("150-200".to_string(), (timing.overall_inter_key.count as f64 * 0.12) as u64),
-            ("200-250".to_string(), (timing.overall_inter_key.count as f64 * 0.08) as u64),
-            ("250+".to_string(), (timing.overall_inter_key.count as f64 * 0.05) as u64),
-        ]
+        timing_histogram(events, &self.filter, &self.histogram_edges)
     }
 
     pub fn get_speed_metrics(&self) -> SpeedMetrics {
         let events = self.events_cache.as_ref().map(|v| v.as_slice()).unwrap_or(&[]);
         
-        let config = FilterConfig::default();
-        let timing = TimingAnalysis::from_events(events, config);
+        let timing = TimingAnalysis::from_events(events, self.filter.clone());
         
         let mean_ms = timing.overall_inter_key.mean_ms;
         let estimated_wpm = if mean_ms > 0.0 {
@@ -399,35 +647,56 @@ impl App {
         }
     }
 
+    /// Key pairs by median interval, fastest first, as `(pair, median_ms, samples)`.
+    fn get_pairs_by_median(&self) -> Vec<(String, i64, u64)> {
+        let events = self.events_cache.as_deref().unwrap_or(&[]);
+        let timing = TimingAnalysis::from_events(events, self.filter.clone());
+        timing
+            .pairs_by_median()
+            .into_iter()
+            .map(|pair| {
+                (
+                    pair_label(pair.from_key, pair.to_key),
+                    pair.median_ms,
+                    pair.intervals_ms.len() as u64,
+                )
+            })
+            .collect()
+    }
+
     pub fn get_fastest_pairs(&self) -> Vec<(String, i64, u64)> {
-        vec![
-            ("TH".to_string(), 42, 12845),
-            ("ER".to_string(), 45, 11234),
-            ("AN".to_string(), 48, 10892),
-            ("IN".to_string(), 51, 9234),
-            ("HE".to_string(), 52, 8945),
-            ("RE".to_string(), 54, 8234),
-            ("ON".to_string(), 55, 7892),
-            ("ES".to_string(), 56, 7456),
-        ]
+        self.get_pairs_by_median()
     }
 
     pub fn get_slowest_pairs(&self) -> Vec<(String, i64, u64)> {
-        vec![
-            ("QU".to_string(), 185, 1234),
-            ("ZX".to_string(), 198, 89),
-            ("XC".to_string(), 142, 456),
-            ("PL".to_string(), 138, 892),
-            ("KL".to_string(), 135, 567),
-            ("JK".to_string(), 132, 234),
-            ("MN".to_string(), 128, 1892),
-            ("BN".to_string(), 125, 2345),
-        ]
+        let mut pairs = self.get_pairs_by_median();
+        pairs.reverse();
+        pairs
+    }
+
+    /// Whether a pair's timing rests on too few samples to trust.
+    pub fn is_low_confidence(&self, samples: u64) -> bool {
+        samples < self.filter.low_confidence_samples as u64
     }
 
     pub fn handle_key(&mut self, key: KeyCode) {
+        if self.search_active {
+            self.handle_search_key(key);
+            return;
+        }
+
+        if !matches!(key, KeyCode::Char('q') | KeyCode::Esc) {
+            self.quit_pending = false;
+        }
+
         match key {
-            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if !self.confirm_quit || self.quit_pending {
+                    self.should_quit = true;
+                } else {
+                    self.quit_pending = true;
+                }
+            }
             KeyCode::Char('1') => self.current_view = View::Overview,
             KeyCode::Char('2') => self.current_view = View::Trends,
             KeyCode::Char('3') => self.current_view = View::Fingers,
@@ -446,40 +715,303 @@ impl App {
                 self.events_cache = None;
                 self.cache_time_range = None;
             }
+            KeyCode::Char('t') => self.theme = self.theme.toggled(),
+            KeyCode::Char('m') if self.current_view == View::Overview => {
+                self.heatmap_metric = self.heatmap_metric.next();
+            }
+            KeyCode::Char('s') if self.current_view == View::Trends => {
+                self.smooth_daily = !self.smooth_daily;
+            }
+            KeyCode::Char('/') => self.search_active = true,
+            KeyCode::Char('c') => {
+                self.character_keys_only = !self.character_keys_only;
+                let character_keys_only = self.character_keys_only;
+                self.save_preference(|config| {
+                    config.dashboard.character_keys_only = character_keys_only
+                });
+            }
+            KeyCode::Char('l') => {
+                self.filter.letters_only_ngrams = !self.filter.letters_only_ngrams;
+                let letters_only_ngrams = self.filter.letters_only_ngrams;
+                self.save_preference(|config| {
+                    config.filter.letters_only_ngrams = letters_only_ngrams
+                });
+            }
             _ => {}
         }
     }
 
+    /// Writes a toggle back to the config file. Failing to save (e.g. a
+    /// read-only file) only loses the preference, so errors are ignored.
+    fn save_preference(&self, update: impl FnOnce(&mut Config)) {
+        let Some(path) = &self.config_path else {
+            return;
+        };
+        if let Ok(mut config) = Config::load(path) {
+            update(&mut config);
+            let _ = config.save(path);
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc => {
+                self.search_query.clear();
+                self.search_active = false;
+            }
+            KeyCode::Enter => self.search_active = false,
+            KeyCode::Backspace => {
+                self.search_query.pop();
+            }
+            KeyCode::Char(c) => self.search_query.push(c),
+            _ => {}
+        }
+    }
+
+    /// Top keys with their overall rank, narrowed to the search query if one is set.
+    pub fn get_top_keys_ranked(&self, n: usize) -> Vec<(usize, (String, u64, f64))> {
+        let limit = if self.search_query.is_empty() { n } else { usize::MAX };
+        filter_ranked(self.get_top_keys(limit), &self.search_query, |(name, _, _)| name)
+            .into_iter()
+            .take(n)
+            .collect()
+    }
+
     pub fn refresh_data(&mut self) {
         self.get_events();
     }
+
+    /// Feeds presses written since the last tick into the rolling WPM.
+    /// At most once per `LIVE_POLL_MS`, so the render loop can call it freely.
+    pub fn tick(&mut self, now_ms: i64) {
+        if now_ms - self.live_cursor < LIVE_POLL_MS {
+            return;
+        }
+        if let Some(db) = &self.db {
+            let recent = db.get_events_in_range(self.live_cursor + 1, now_ms).unwrap_or_default();
+            let presses = recent
+                .iter()
+                .filter(|e| matches!(e.event_type, crate::models::EventType::Press));
+            for event in presses {
+                self.rolling_wpm.push(event.timestamp);
+            }
+        }
+        self.live_cursor = now_ms;
+        self.live_wpm = self.rolling_wpm.wpm(now_ms);
+    }
+
+    /// WPM over the last minute; `None` until enough time has been observed.
+    pub fn live_wpm(&self) -> Option<f64> {
+        self.live_wpm
+    }
+
+    pub fn live_wpm_window_secs(&self) -> i64 {
+        self.rolling_wpm.window_ms() / 1000
+    }
 }
 
-pub fn run_dashboard(db_path: &Path) -> Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// Compact label for a key pair: "TH" for single-character names,
+/// "Space T" when either name is longer.
+fn pair_label(from_key: u32, to_key: u32) -> String {
+    let name = |key_code| crate::models::keycode::KeyCode(key_code).to_name();
+    let (from, to) = (name(from_key), name(to_key));
+    if from.chars().count() == 1 && to.chars().count() == 1 {
+        format!("{}{}", from, to)
+    } else {
+        format!("{} {}", from, to)
+    }
+}
+
+/// Epoch millis of the local midnight that starts `now`'s day. Falls back to
+/// `now` itself if midnight doesn't exist locally (a DST gap at 00:00).
+pub fn local_day_start(now: DateTime<Local>) -> i64 {
+    now.date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|dt| dt.and_local_timezone(Local).earliest())
+        .unwrap_or(now)
+        .timestamp_millis()
+}
 
+/// Left-hand share of presses per day, bucketed by date like
+/// `get_daily_counts`. Thumbs and keys the layout doesn't map are left out,
+/// as in `fingers::hand_balance`; days without any hand presses are skipped.
+fn daily_hand_balance(
+    events: &[KeystrokeEvent],
+    layout: &dyn KeyboardLayout,
+) -> Vec<DailyHandBalance> {
+    let mut per_day: HashMap<String, (u64, u64)> = HashMap::new();
+    for event in events {
+        if event.event_type != crate::models::EventType::Press {
+            continue;
+        }
+        let Some(finger) = layout.get_finger(event.key_code).filter(|&f| f != Finger::Thumb) else {
+            continue;
+        };
+        let day = chrono::DateTime::from_timestamp_millis(event.timestamp)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let (left, right) = per_day.entry(day).or_insert((0, 0));
+        if finger.hand() == Hand::Left {
+            *left += 1;
+        } else {
+            *right += 1;
+        }
+    }
+
+    let mut days: Vec<_> = per_day
+        .into_iter()
+        .map(|(day, (left, right))| DailyHandBalance {
+            day,
+            left_pct: left as f64 / (left + right) as f64 * 100.0,
+        })
+        .collect();
+    days.sort_by(|a, b| a.day.cmp(&b.day));
+    days
+}
+
+/// Top `n` apps by presses in `events`. WPM comes from the mean interval
+/// between presses within the app, the same estimate `get_speed_metrics`
+/// makes overall.
+fn app_speeds(events: &[KeystrokeEvent], n: usize, filter: &FilterConfig) -> Vec<AppSpeed> {
+    let mut presses: HashMap<&str, u64> = HashMap::new();
+    for event in events.iter().filter(|e| e.event_type == crate::models::EventType::Press) {
+        *presses.entry(event.application.as_str()).or_insert(0) += 1;
+    }
+    let total: u64 = presses.values().sum();
+    let timing = timing::per_app_inter_key(&sorted_by_time(events), filter);
+
+    let mut ranked: Vec<_> = presses.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    ranked
+        .into_iter()
+        .take(n)
+        .map(|(app, count)| AppSpeed {
+            application: app.to_string(),
+            share_pct: count as f64 / total as f64 * 100.0,
+            wpm: timing
+                .get(app)
+                .filter(|&&(mean_ms, samples)| {
+                    samples >= filter.low_confidence_samples && mean_ms > 0.0
+                })
+                .map(|&(mean_ms, _)| 60_000.0 / mean_ms / 5.0),
+        })
+        .collect()
+}
+
+/// Counts keyed by `YYYY-MM-DD`, oldest first, with a 0 for every day between
+/// the first and last that has none, so idle days show as dips.
+fn zero_filled_days(daily: &HashMap<String, u64>) -> Vec<u64> {
+    let parse = |day: &str| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").ok();
+    let days: Vec<_> = daily.keys().filter_map(|day| parse(day)).collect();
+    let (Some(first), Some(last)) = (days.iter().min(), days.iter().max()) else {
+        return Vec::new();
+    };
+    first
+        .iter_days()
+        .take_while(|day| day <= last)
+        .map(|day| daily.get(&day.format("%Y-%m-%d").to_string()).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Groups `(app, day, count)` rows into per-app daily series for the top `n`
+/// apps. Days with no presses from an app count as zero so series line up.
+fn app_trends(rows: &[(String, String, i64)], n: usize) -> Vec<AppTrend> {
+    let mut days: Vec<&str> = rows.iter().map(|(_, day, _)| day.as_str()).collect();
+    days.sort_unstable();
+    days.dedup();
+
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    for (app, _, count) in rows {
+        *totals.entry(app.as_str()).or_insert(0) += *count as u64;
+    }
+    let grand_total: u64 = totals.values().sum();
+
+    let mut ranked: Vec<_> = totals.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    ranked
+        .into_iter()
+        .take(n)
+        .map(|(app, total)| {
+            let mut daily = vec![0; days.len()];
+            for (row_app, day, count) in rows {
+                if row_app == app {
+                    if let Ok(i) = days.binary_search(&day.as_str()) {
+                        daily[i] = *count as u64;
+                    }
+                }
+            }
+            AppTrend {
+                application: app.to_string(),
+                daily,
+                share_pct: if grand_total > 0 {
+                    total as f64 / grand_total as f64 * 100.0
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect()
+}
+
+/// Pairs each item with its 1-based rank, then keeps those whose name
+/// contains `query` (case-insensitive). Ranks refer to the unfiltered list.
+pub fn filter_ranked<T>(
+    items: Vec<T>,
+    query: &str,
+    name: impl Fn(&T) -> &str,
+) -> Vec<(usize, T)> {
+    let query = query.to_lowercase();
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| (i + 1, item))
+        .filter(|(_, item)| query.is_empty() || name(item).to_lowercase().contains(&query))
+        .collect()
+}
+
+pub fn run_dashboard(
+    db_path: &Path,
+    config_path: &Path,
+    confirm_quit: bool,
+    layout: Box<dyn KeyboardLayout>,
+    theme: Theme,
+    config: &Config,
+) -> Result<()> {
+    let dashboard = config.dashboard.clone();
     let mut app = App::new(db_path)?;
+    if let Some(db) = &app.db {
+        use_input_source_naming(&db.get_input_source_namings()?);
+    }
+    app.confirm_quit = confirm_quit;
+    app.layout = layout;
+    app.theme = theme;
+    app.character_keys_only = dashboard.character_keys_only;
+    app.finger_targets = dashboard.finger_targets;
+    app.daily_goal = dashboard.daily_goal;
+    app.heatmap_cap_percentile = dashboard.heatmap_cap_percentile;
+    app.filter = config.filter.clone();
+    app.layout_score_weights = config.layout_score.clone();
+    if let Some(edges) = dashboard.timing_histogram_edges {
+        app.histogram_edges = HistogramEdges::new(edges)?;
+    }
+    if let Some(max) = dashboard.max_cached_events {
+        app.max_cached_events = max;
+    }
+    app.config_path = Some(config_path.to_path_buf());
     app.refresh_data();
 
-    let result = run_app(&mut terminal, &mut app);
-
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    install_panic_hook();
+    let _guard = TerminalGuard::new()?;
+    let backend = CrosstermBackend::new(io::stdout());
+    let mut terminal = Terminal::new(backend)?;
 
-    result
+    run_app(&mut terminal, &mut app)
 }
 
 fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
     loop {
+        app.tick(Utc::now().timestamp_millis());
         terminal.draw(|f| ui(f, app))?;
 
         if event::poll(Duration::from_millis(100))? {
@@ -508,7 +1040,7 @@ fn ui(f: &mut Frame, app: &mut App) {
 
     render_header(f, app, chunks[0]);
     render_content(f, app, chunks[1]);
-    render_footer(f, chunks[2]);
+    render_footer(f, app, chunks[2]);
 }
 
 fn render_header(f: &mut Frame, app: &App, area: Rect) {
@@ -520,15 +1052,25 @@ fn render_header(f: &mut Frame, app: &App, area: Rect) {
     let tabs = Tabs::new(titles)
         .block(
             Block::default()
-                .title(format!(" Lurk Dashboard [{}] ", app.time_range.label()))
+                .title(format!(
+                    " Lurk Dashboard [{}]{}{}{} ",
+                    app.time_range.label(),
+                    if app.character_keys_only { " [Characters only]" } else { "" },
+                    if app.filter.letters_only_ngrams { " [Letter n-grams]" } else { "" },
+                    app.sampled_from()
+                        .map(|total| {
+                            format!(" [Latest {} of {} events]", app.max_cached_events, total)
+                        })
+                        .unwrap_or_default()
+                ))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::DarkGray)),
+                .border_style(Style::default().fg(app.theme.muted)),
         )
         .select(app.current_view.index())
-        .style(Style::default().fg(Color::Gray))
+        .style(Style::default().fg(app.theme.label))
         .highlight_style(
             Style::default()
-                .fg(Color::White)
+                .fg(app.theme.text)
                 .add_modifier(Modifier::BOLD),
         );
 
@@ -537,7 +1079,17 @@ fn render_header(f: &mut Frame, app: &App, area: Rect) {
 
 fn render_content(f: &mut Frame, app: &mut App, area: Rect) {
     app.refresh_data();
+
+    #[cfg(test)]
+    if app.panic_on_render {
+        panic!("forced render panic");
+    }
     
+    if !app.has_data() {
+        views::render_empty(f, app, area);
+        return;
+    }
+
     match app.current_view {
         View::Overview => views::render_overview(f, app, area),
         View::Trends => views::render_trends(f, app, area),
@@ -546,8 +1098,447 @@ fn render_content(f: &mut Frame, app: &mut App, area: Rect) {
     }
 }
 
-fn render_footer(f: &mut Frame, area: Rect) {
-    let help = Paragraph::new(" q:Quit  1-4:Views  ←→:Time Range  Tab:Next View  r:Refresh")
-        .style(Style::default().fg(Color::DarkGray));
+fn render_footer(f: &mut Frame, app: &App, area: Rect) {
+    let help = if app.search_active {
+        Paragraph::new(format!(" /{}█  Enter:Keep  Esc:Clear", app.search_query))
+            .style(Style::default().fg(app.theme.text))
+    } else if app.quit_pending {
+        Paragraph::new(" Press q again to quit, any other key to stay")
+            .style(Style::default().fg(app.theme.warn))
+    } else {
+        let metric_hint = if app.current_view == View::Overview { "  m:Metric" } else { "" };
+        Paragraph::new(format!(
+            " q:Quit  1-4:Views  ←→:Time Range  Tab:Next View  r:Refresh  t:Theme  c:Chars  l:Letters  /:Search{}",
+            metric_hint
+        ))
+        .style(Style::default().fg(app.theme.muted))
+    };
     f.render_widget(help, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{in_app, press, release, typed};
+    use chrono::TimeZone;
+    use ratatui::backend::TestBackend;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static RESTORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn record_restore() {
+        RESTORE_CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn test_app() -> App {
+        App::with_source(Some(Database::new(":memory:").unwrap()))
+    }
+
+    #[test]
+    fn test_filter_ranked_keeps_original_rank() {
+        let items = vec!["Space", "E", "T", "Return", "A"];
+        let filtered = filter_ranked(items, "e", |name| name);
+        assert_eq!(filtered, vec![(1, "Space"), (2, "E"), (4, "Return")]);
+    }
+
+    #[test]
+    fn test_filter_ranked_empty_query_keeps_all() {
+        let filtered = filter_ranked(vec!["A", "B"], "", |name| name);
+        assert_eq!(filtered, vec![(1, "A"), (2, "B")]);
+    }
+
+    #[test]
+    fn test_search_mode_captures_keys() {
+        let mut app = test_app();
+        app.handle_key(KeyCode::Char('/'));
+        assert!(app.search_active);
+
+        app.handle_key(KeyCode::Char('q'));
+        app.handle_key(KeyCode::Char('u'));
+        app.handle_key(KeyCode::Backspace);
+        assert_eq!(app.search_query, "q");
+        assert!(!app.should_quit);
+
+        app.handle_key(KeyCode::Enter);
+        assert!(!app.search_active);
+        assert_eq!(app.search_query, "q");
+
+        app.handle_key(KeyCode::Char('/'));
+        app.handle_key(KeyCode::Esc);
+        assert!(!app.search_active);
+        assert!(app.search_query.is_empty());
+        assert!(!app.should_quit);
+    }
+
+    #[test]
+    fn test_daily_counts_fill_idle_days_for_the_average() {
+        const DAY_MS: i64 = 86_400_000;
+        let noon = DAY_MS / 2;
+        let events = vec![
+            press(noon, 0x00),
+            press(3 * DAY_MS + noon, 0x00),
+            press(3 * DAY_MS + noon + 100, 0x01),
+        ];
+        let mut app = App::new_with_events(events);
+        assert_eq!(app.get_daily_counts(), vec![1, 0, 0, 2]);
+        assert_eq!(app.get_daily_average(), vec![1.0, 0.5, 1.0 / 3.0, 0.75]);
+
+        assert!(!app.smooth_daily);
+        app.handle_key(KeyCode::Char('s'));
+        assert!(!app.smooth_daily);
+        app.handle_key(KeyCode::Char('2'));
+        app.handle_key(KeyCode::Char('s'));
+        assert!(app.smooth_daily);
+    }
+
+    #[test]
+    fn test_metric_key_only_cycles_on_overview() {
+        let mut app = test_app();
+        app.handle_key(KeyCode::Char('m'));
+        assert_eq!(app.heatmap_metric, HeatmapMetric::HoldTime);
+
+        app.handle_key(KeyCode::Char('2'));
+        app.handle_key(KeyCode::Char('m'));
+        assert_eq!(app.heatmap_metric, HeatmapMetric::HoldTime);
+    }
+
+    #[test]
+    fn test_daily_hand_balance() {
+        const DAY_MS: i64 = 86_400_000;
+        // Day 1: A, S, D left and J right. Day 2: only J and K, plus a Space
+        // (thumb) and a release, neither of which counts.
+        let mut events = vec![
+            press(0, 0x00),
+            press(100, 0x01),
+            press(200, 0x02),
+            press(300, 0x26),
+            press(DAY_MS, 0x26),
+            press(DAY_MS + 100, 0x28),
+            press(DAY_MS + 200, 0x31),
+        ];
+        events.push(release(DAY_MS + 300, 0x00));
+
+        let balance = daily_hand_balance(&events, &QwertyLayout::new());
+        assert_eq!(
+            balance,
+            vec![
+                DailyHandBalance { day: "1970-01-01".to_string(), left_pct: 75.0 },
+                DailyHandBalance { day: "1970-01-02".to_string(), left_pct: 0.0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_app_speeds_hide_thin_samples() {
+        // Editor: 40 presses 100ms apart (120 WPM). Chat: 3 presses, too few to time.
+        let mut events: Vec<_> = (0..40).map(|i| in_app(press(i * 100, 0x00), "editor")).collect();
+        events.extend((0..3).map(|i| in_app(press(10_000 + i * 300, 0x00), "chat")));
+
+        let mut app = App::new_with_events(events);
+        app.filter.low_confidence_samples = 30;
+        let speeds = app.get_app_speeds(5);
+        assert_eq!(speeds.len(), 2);
+        assert_eq!(speeds[0].application, "editor");
+        assert!((speeds[0].share_pct - 40.0 / 43.0 * 100.0).abs() < 1e-9);
+        assert!((speeds[0].wpm.unwrap() - 120.0).abs() < 1e-9);
+        assert_eq!(speeds[1].application, "chat");
+        assert_eq!(speeds[1].wpm, None);
+    }
+
+    #[test]
+    fn test_views_use_configured_filter() {
+        let events = vec![press(0, 0x00), release(2500, 0x00)];
+
+        let mut app = App::new_with_events(events);
+        assert!(app.get_finger_holds().is_empty());
+
+        app.filter.max_hold_ms = 3000;
+        let holds = app.get_finger_holds();
+        assert_eq!(holds.len(), 1);
+        assert_eq!(holds[0].median_ms, 2500);
+    }
+
+    #[test]
+    fn test_app_trends_align_days_and_rank_apps() {
+        let row = |app: &str, day: &str, count| (app.to_string(), day.to_string(), count);
+        let rows = vec![
+            row("com.editor", "2024-01-01", 900),
+            row("com.browser", "2024-01-01", 50),
+            row("com.editor", "2024-01-02", 800),
+            row("com.chat", "2024-01-02", 20),
+            row("com.browser", "2024-01-03", 30),
+        ];
+
+        let trends = app_trends(&rows, 2);
+        assert_eq!(trends.len(), 2);
+        assert_eq!(trends[0].application, "com.editor");
+        assert_eq!(trends[0].daily, vec![900, 800, 0]);
+        assert_eq!(trends[1].application, "com.browser");
+        assert_eq!(trends[1].daily, vec![50, 0, 30]);
+        assert!((trends[0].share_pct - 1700.0 / 1800.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_character_keys_toggle_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        Config::default().save(&path).unwrap();
+
+        let mut app = test_app();
+        app.config_path = Some(path.clone());
+        app.handle_key(KeyCode::Char('c'));
+
+        assert!(app.character_keys_only);
+        assert!(Config::load(&path).unwrap().dashboard.character_keys_only);
+    }
+
+    #[test]
+    fn test_letters_toggle_filters_flows_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        Config::default().save(&path).unwrap();
+
+        // "the the": T H E Space T H E
+        let mut app = App::new_with_events(typed(&[0x11, 0x04, 0x0E, 0x31, 0x11, 0x04, 0x0E], 100));
+        app.config_path = Some(path.clone());
+        let flows = |app: &App| -> Vec<String> {
+            app.get_top_flows(10).into_iter().map(|f| f.bigram.display).collect()
+        };
+        assert!(flows(&app).contains(&"Space -> T".to_string()));
+
+        app.handle_key(KeyCode::Char('l'));
+        assert!(app.filter.letters_only_ngrams);
+        assert_eq!(flows(&app), vec!["H -> E", "T -> H"]);
+        assert!(Config::load(&path).unwrap().filter.letters_only_ngrams);
+
+        app.handle_key(KeyCode::Char('l'));
+        assert_eq!(flows(&app).len(), 4);
+    }
+
+    #[test]
+    fn test_top_keys_and_apps_respect_time_range() {
+        let mut app = test_app();
+        let db = app.db.as_ref().unwrap();
+        let day = 24 * 60 * 60 * 1000;
+        let now = timestamp_days_ago(0);
+        let seeded = [
+            (1, 0x00, "editor"),
+            (2, 0x00, "editor"),
+            (3, 0x26, "browser"),
+            (20, 0x26, "browser"),
+            (40, 0x26, "browser"),
+        ];
+        for (days_ago, key_code, application) in seeded {
+            db.insert_event(&in_app(press(now - days_ago * day, key_code), application)).unwrap();
+        }
+
+        app.refresh_data();
+        let keys = app.get_top_keys(10);
+        assert_eq!(keys[0].0, "A");
+        assert_eq!(keys[0].1, 2);
+        assert!((keys[0].2 - 200.0 / 3.0).abs() < 0.01);
+        assert_eq!(app.get_app_distribution()[0].0, "editor");
+
+        app.time_range = TimeRange::AllTime;
+        app.refresh_data();
+        let keys = app.get_top_keys(10);
+        assert_eq!((keys[0].0.as_str(), keys[0].1), ("J", 3));
+        let apps = app.get_app_distribution();
+        assert_eq!(apps[0].0, "browser");
+        assert!((apps[0].1 - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_top_keys_follow_character_keys_only() {
+        let mut app = test_app();
+        let db = app.db.as_ref().unwrap();
+        let now = timestamp_days_ago(0);
+        for (i, key_code) in [0x38, 0x38, 0x38, 0x00, 0x00].into_iter().enumerate() {
+            db.insert_event(&press(now - 1000 + i as i64, key_code)).unwrap();
+        }
+        app.refresh_data();
+        assert_eq!(app.get_top_keys(10)[0].0, "LeftShift");
+
+        app.character_keys_only = true;
+        let keys = app.get_top_keys(10);
+        assert_eq!(keys.len(), 1);
+        assert_eq!((keys[0].0.as_str(), keys[0].1), ("A", 2));
+        assert!((keys[0].2 - 100.0).abs() < 0.01);
+        assert!((app.get_key_frequencies()[&0x00] - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_over_cache_cap_counts_come_from_sql() {
+        assert!(!exceeds_cache_cap(100, 100));
+        assert!(exceeds_cache_cap(101, 100));
+        assert!(!exceeds_cache_cap(0, 0));
+
+        let mut app = test_app();
+        app.time_range = TimeRange::AllTime;
+        let db = app.db.as_ref().unwrap();
+        let now = timestamp_days_ago(0);
+        for (i, key_code) in [0x00, 0x00, 0x00, 0x26, 0x26].into_iter().enumerate() {
+            db.insert_event(&press(now - 1000 + i as i64, key_code)).unwrap();
+        }
+
+        app.max_cached_events = 5;
+        app.refresh_data();
+        assert_eq!(app.sampled_from(), None);
+        assert_eq!(app.get_stats().total_presses, 5);
+
+        app.max_cached_events = 2;
+        app.handle_key(KeyCode::Char('r'));
+        app.refresh_data();
+        assert_eq!(app.sampled_from(), Some(5));
+        assert_eq!(app.events_cache.as_ref().unwrap().len(), 2);
+        assert_eq!(app.get_stats().total_presses, 5);
+        assert_eq!(app.get_daily_counts().iter().sum::<u64>(), 5);
+        assert!((app.get_key_frequencies()[&0x00] - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_today_count_starts_at_local_midnight() {
+        let mut app = test_app();
+        let now = Local::now();
+        let midnight = local_day_start(now);
+        assert!(midnight <= now.timestamp_millis());
+        assert_eq!(
+            Local.timestamp_millis_opt(midnight).unwrap().date_naive(),
+            now.date_naive()
+        );
+
+        let db = app.db.as_ref().unwrap();
+        for timestamp in [midnight - 1, midnight, midnight + 1] {
+            db.insert_event(&press(timestamp, 0x00)).unwrap();
+        }
+        assert_eq!(app.get_today_count(), 2);
+        assert!(app.get_daily_goal_progress().is_none());
+
+        app.daily_goal = Some(20_000);
+        assert_eq!(app.get_daily_goal_progress(), Some((2, 20_000)));
+    }
+
+    /// A, A, A on the left pinky and J on the right index, 100ms apart.
+    fn fixed_app() -> App {
+        App::new_with_events(vec![
+            press(0, 0x00),
+            press(100, 0x00),
+            press(200, 0x00),
+            press(300, 0x26),
+        ])
+    }
+
+    #[test]
+    fn test_finger_loads_from_fixed_events() {
+        let loads: HashMap<Finger, f64> = fixed_app().get_finger_loads().into_iter().collect();
+        assert!((loads[&Finger::LeftPinky] - 75.0).abs() < 0.01);
+        assert!((loads[&Finger::RightIndex] - 25.0).abs() < 0.01);
+        assert_eq!(loads[&Finger::LeftRing], 0.0);
+    }
+
+    #[test]
+    fn test_hand_balance_from_fixed_events() {
+        let (left, right) = fixed_app().get_hand_balance();
+        assert!((left - 75.0).abs() < 0.01);
+        assert!((right - 25.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_hand_balance_without_events_is_even() {
+        assert_eq!(App::new_with_events(vec![]).get_hand_balance(), (50.0, 50.0));
+    }
+
+    #[test]
+    fn test_stats_from_fixed_events() {
+        let stats = fixed_app().get_stats();
+        assert_eq!(stats.total_presses, 4);
+        assert_eq!(stats.days_active, 7);
+        assert_eq!(stats.median_delay_ms, 100);
+        assert_eq!(stats.estimated_wpm, 120);
+    }
+
+    #[test]
+    fn test_fixed_events_survive_time_range_change() {
+        let mut app = fixed_app();
+        app.handle_key(KeyCode::Right);
+        app.refresh_data();
+        assert_eq!(app.get_stats().total_presses, 4);
+        assert!(app.get_app_distribution().is_empty());
+    }
+
+    #[test]
+    fn test_quit_without_confirmation() {
+        let mut app = test_app();
+        app.handle_key(KeyCode::Char('q'));
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_quit_requires_second_press_when_confirming() {
+        let mut app = test_app();
+        app.confirm_quit = true;
+
+        app.handle_key(KeyCode::Char('q'));
+        assert!(!app.should_quit);
+        assert!(app.quit_pending);
+
+        app.handle_key(KeyCode::Char('q'));
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_other_key_cancels_pending_quit() {
+        let mut app = test_app();
+        app.confirm_quit = true;
+
+        app.handle_key(KeyCode::Char('q'));
+        app.handle_key(KeyCode::Char('2'));
+        assert!(!app.quit_pending);
+        assert_eq!(app.current_view, View::Trends);
+
+        app.handle_key(KeyCode::Char('q'));
+        assert!(!app.should_quit);
+    }
+
+    /// Everything drawn to `terminal`, row after row.
+    fn screen_text(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn test_every_view_renders_empty_state_without_data() {
+        for index in 0..4 {
+            let mut app = test_app();
+            app.current_view = View::from_index(index);
+            let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+            terminal.draw(|f| ui(f, &mut app)).unwrap();
+            assert!(
+                screen_text(&terminal).contains(views::empty::NO_DATA_MESSAGE),
+                "{} view has no empty state",
+                app.current_view.title()
+            );
+        }
+
+        let mut app = App::new_with_events(vec![press(0, 0x00), release(50, 0x00)]);
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert!(!screen_text(&terminal).contains(views::empty::NO_DATA_MESSAGE));
+    }
+
+    #[test]
+    fn test_guard_restores_terminal_when_view_panics() {
+        let mut app = test_app();
+        app.panic_on_render = true;
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = TerminalGuard::with_restore(record_restore);
+            let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+            terminal.draw(|f| ui(f, &mut app)).unwrap();
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(RESTORE_CALLS.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,6 +1,8 @@
 mod app;
-mod keyboard_layout;
+mod terminal;
+mod theme;
 mod views;
 mod widgets;
 
-pub use app::run_dashboard;
+pub use app::{run_dashboard, App};
+pub use theme::Theme;
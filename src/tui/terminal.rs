@@ -0,0 +1,60 @@
+use std::io;
+use std::panic;
+
+use anyhow::Result;
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+/// Puts the terminal into raw mode on the alternate screen and restores it on drop,
+/// so an early return or a panic inside the draw loop never leaves the shell garbled.
+pub struct TerminalGuard {
+    restore: fn(),
+}
+
+impl TerminalGuard {
+    pub fn new() -> Result<Self> {
+        enable_raw_mode()?;
+        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture) {
+            let _ = disable_raw_mode();
+            return Err(e.into());
+        }
+        Ok(Self {
+            restore: restore_terminal,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn with_restore(restore: fn()) -> Self {
+        Self { restore }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        (self.restore)();
+    }
+}
+
+pub fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        crossterm::cursor::Show
+    );
+}
+
+/// Restores the terminal before the default hook prints the panic message.
+/// Without this the message is written to the alternate screen and lost when
+/// the guard later leaves it during unwinding.
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        previous(info);
+    }));
+}
@@ -0,0 +1,135 @@
+use ratatui::style::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeKind {
+    Dark,
+    Light,
+    NoColor,
+}
+
+/// Every color the dashboard draws with. Views read these instead of naming
+/// `Color` variants directly, so switching themes is a single assignment.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub kind: ThemeKind,
+    pub text: Color,
+    pub label: Color,
+    pub muted: Color,
+    pub accent: Color,
+    pub left_hand: Color,
+    pub right_hand: Color,
+    pub good: Color,
+    pub warn: Color,
+    pub bad: Color,
+    /// Foreground for text drawn on top of a colored bar.
+    pub on_bar: Color,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            kind: ThemeKind::Dark,
+            text: Color::White,
+            label: Color::Gray,
+            muted: Color::DarkGray,
+            accent: Color::Cyan,
+            left_hand: Color::Cyan,
+            right_hand: Color::Magenta,
+            good: Color::Green,
+            warn: Color::Yellow,
+            bad: Color::Red,
+            on_bar: Color::Black,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            kind: ThemeKind::Light,
+            text: Color::Black,
+            label: Color::DarkGray,
+            muted: Color::Gray,
+            accent: Color::Blue,
+            left_hand: Color::Blue,
+            right_hand: Color::Magenta,
+            good: Color::Green,
+            warn: Color::Rgb(176, 112, 0),
+            bad: Color::Red,
+            on_bar: Color::White,
+        }
+    }
+
+    /// Terminal default colors only. The heatmap falls back to shade characters.
+    pub fn no_color() -> Self {
+        Self {
+            kind: ThemeKind::NoColor,
+            text: Color::Reset,
+            label: Color::Reset,
+            muted: Color::Reset,
+            accent: Color::Reset,
+            left_hand: Color::Reset,
+            right_hand: Color::Reset,
+            good: Color::Reset,
+            warn: Color::Reset,
+            bad: Color::Reset,
+            on_bar: Color::Reset,
+        }
+    }
+
+    /// Picks the starting theme: no colors when requested or when `NO_COLOR`
+    /// is set (see no-color.org), otherwise light or dark.
+    pub fn from_options(no_color: bool, light: bool) -> Self {
+        let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        if no_color || no_color_env {
+            Self::no_color()
+        } else if light {
+            Self::light()
+        } else {
+            Self::dark()
+        }
+    }
+
+    /// Switches between light and dark. No-color stays as it is.
+    pub fn toggled(&self) -> Self {
+        match self.kind {
+            ThemeKind::Dark => Self::light(),
+            ThemeKind::Light => Self::dark(),
+            ThemeKind::NoColor => *self,
+        }
+    }
+
+    /// Grayscale ramp for `level` in 0..=1, brightest at 1 on a dark
+    /// background and darkest at 1 on a light one. `None` without colors.
+    pub fn shade(&self, level: f64) -> Option<Color> {
+        let v = (level.clamp(0.0, 1.0) * 255.0) as u8;
+        match self.kind {
+            ThemeKind::Dark => Some(Color::Rgb(v, v, v)),
+            ThemeKind::Light => Some(Color::Rgb(255 - v, 255 - v, 255 - v)),
+            ThemeKind::NoColor => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_switches_light_and_dark() {
+        assert_eq!(Theme::dark().toggled().kind, ThemeKind::Light);
+        assert_eq!(Theme::light().toggled().kind, ThemeKind::Dark);
+        assert_eq!(Theme::no_color().toggled().kind, ThemeKind::NoColor);
+    }
+
+    #[test]
+    fn test_shade() {
+        assert_eq!(Theme::dark().shade(1.0), Some(Color::Rgb(255, 255, 255)));
+        assert_eq!(Theme::light().shade(1.0), Some(Color::Rgb(0, 0, 0)));
+        assert_eq!(Theme::no_color().shade(1.0), None);
+    }
+}
@@ -0,0 +1,32 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::Style,
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::tui::app::App;
+
+pub const NO_DATA_MESSAGE: &str = "No data for this range";
+
+/// Stands in for a whole view when the time range has no key presses, so a
+/// new or filtered-out database shows why it's blank rather than zeros.
+pub fn render_empty(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(format!(" {} ", app.current_view.title()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.theme.muted));
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    render_no_data(f, app, inner);
+}
+
+/// The empty-state message, centered in `area`.
+pub fn render_no_data(f: &mut Frame, app: &App, area: Rect) {
+    let middle = Rect::new(area.x, area.y + area.height / 2, area.width, area.height.min(1));
+    let msg = Paragraph::new(NO_DATA_MESSAGE)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(app.theme.muted));
+    f.render_widget(msg, middle);
+}
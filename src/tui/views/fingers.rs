@@ -2,12 +2,13 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, List, ListItem, Paragraph},
     Frame,
 };
 
 use crate::tui::app::App;
-use crate::tui::keyboard_layout::{Finger, Hand, QwertyLayout};
+use crate::analysis::hand_runs::LONG_RUN_THRESHOLD;
+use crate::models::keyboard_layout::{Finger, Hand};
 use crate::tui::widgets::KeyboardHeatmap;
 
 pub fn render_fingers(f: &mut Frame, app: &App, area: Rect) {
@@ -24,57 +25,120 @@ pub fn render_fingers(f: &mut Frame, app: &App, area: Rect) {
     let bottom_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Percentage(40),
-            Constraint::Percentage(30),
-            Constraint::Percentage(30),
+            Constraint::Percentage(35),
+            Constraint::Percentage(20),
+            Constraint::Percentage(22),
+            Constraint::Percentage(23),
         ])
         .split(chunks[1]);
 
     render_finger_load(f, app, bottom_chunks[0]);
-    render_hand_balance(f, app, bottom_chunks[1]);
-    render_same_finger_bigrams(f, app, bottom_chunks[2]);
+    render_finger_holds(f, app, bottom_chunks[1]);
+    render_hand_balance(f, app, bottom_chunks[2]);
+    render_same_finger_bigrams(f, app, bottom_chunks[3]);
 }
 
 fn render_keyboard_with_fingers(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
-        .title(" Finger Assignments (QWERTY) ")
+        .title(format!(" Finger Assignments ({}) ", app.layout.display_name()))
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let layout = QwertyLayout::new();
     let frequencies = app.get_key_frequencies();
-    let heatmap = KeyboardHeatmap::new(&layout, &frequencies).show_fingers(true);
+    let heatmap = KeyboardHeatmap::new(app.layout.as_ref(), &frequencies)
+        .show_fingers(true)
+        .theme(app.theme);
     f.render_widget(heatmap, inner);
 }
 
 fn render_finger_load(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
+    let balance = app.get_finger_balance();
+    let title = match &balance {
+        Some(balance) => format!(" Finger Load vs Target (balance {:.0}/100) ", balance.score),
+        None => " Finger Load ".to_string(),
+    };
     let block = Block::default()
-        .title(" Finger Load ")
+        .title(title)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let finger_loads = app.get_finger_loads();
 
-    let items: Vec<ListItem> = finger_loads
+    // BarChart scales every bar against the largest value, so the busiest
+    // finger always spans the full width. Tenths of a percent keep precision.
+    let bars: Vec<Bar> = finger_loads
         .iter()
         .map(|(finger, pct)| {
-            let bar_width = (pct / 3.0) as usize;
-            let bar: String = "█".repeat(bar_width.min(12));
-            
             let color = match finger.hand() {
-                Hand::Left => Color::Cyan,
-                Hand::Right => Color::Magenta,
+                Hand::Left => theme.left_hand,
+                Hand::Right => theme.right_hand,
+            };
+
+            // Deviation from target, in points of the finger-column share.
+            let deviation = balance
+                .as_ref()
+                .and_then(|balance| balance.deviation_for(*finger))
+                .map(|d| d.deviation());
+            let (text, value_color) = match deviation {
+                Some(dev) => (format!("{:.1}% ({:+.1})", pct, dev), deviation_color(app, dev)),
+                None => (format!("{:.1}%", pct), color),
             };
 
-            let line = Line::from(vec![
-                Span::styled(format!("{:<12}", finger_name(finger)), Style::default().fg(color)),
-                Span::styled(format!("{:>5.1}% ", pct), Style::default().fg(Color::Gray)),
-                Span::styled(bar, Style::default().fg(color)),
-            ]);
-            ListItem::new(line)
+            Bar::default()
+                .label(Line::from(finger_name(finger)))
+                .value((pct * 10.0).round() as u64)
+                .text_value(text)
+                .style(Style::default().fg(color))
+                .value_style(Style::default().fg(theme.on_bar).bg(value_color))
+        })
+        .collect();
+
+    let chart = BarChart::default()
+        .block(block)
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, area);
+}
+
+/// Within 3 points of target is fine, within 6 worth a look, beyond that off.
+fn deviation_color(app: &App, deviation: f64) -> Color {
+    match deviation.abs() {
+        d if d <= 3.0 => app.theme.good,
+        d if d <= 6.0 => app.theme.warn,
+        _ => app.theme.bad,
+    }
+}
+
+/// Median hold per finger. The longest is highlighted: a finger that
+/// lingers on its keys is slower to move on to the next one.
+fn render_finger_holds(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
+    let block = Block::default()
+        .title(" Hold (median) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.muted));
+
+    let holds = app.get_finger_holds();
+    let longest = holds.iter().map(|hold| hold.median_ms).max().unwrap_or(0);
+
+    let items: Vec<ListItem> = holds
+        .iter()
+        .map(|hold| {
+            let color = if hold.median_ms == longest { theme.warn } else { theme.text };
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<13}", finger_name(&hold.finger)),
+                    Style::default().fg(theme.label),
+                ),
+                Span::styled(format!("{:>4}ms", hold.median_ms), Style::default().fg(color)),
+            ]))
         })
         .collect();
 
@@ -83,47 +147,93 @@ fn render_finger_load(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_hand_balance(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .title(" Hand Balance ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
 
     let (left_pct, right_pct) = app.get_hand_balance();
+    let runs = app.get_hand_runs();
+    let rhythm = app.get_hand_rhythm();
 
     let balance_status = if left_pct >= 45.0 && left_pct <= 55.0 {
-        ("✓ Good", Color::Green)
+        ("✓ Good", theme.good)
     } else if left_pct >= 40.0 && left_pct <= 60.0 {
-        ("○ Fair", Color::Yellow)
+        ("○ Fair", theme.warn)
     } else {
-        ("✗ Imbalanced", Color::Red)
+        ("✗ Imbalanced", theme.bad)
     };
 
     let text = vec![
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Left Hand:   ", Style::default().fg(Color::Cyan)),
+            Span::styled("  Left Hand:   ", Style::default().fg(theme.left_hand)),
             Span::styled(
                 format!("{:>5.1}%", left_pct),
-                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(vec![
-            Span::styled("  Right Hand:  ", Style::default().fg(Color::Magenta)),
+            Span::styled("  Right Hand:  ", Style::default().fg(theme.right_hand)),
             Span::styled(
                 format!("{:>5.1}%", right_pct),
-                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Balance:     ", Style::default().fg(Color::Gray)),
+            Span::styled("  Balance:     ", Style::default().fg(theme.label)),
             Span::styled(balance_status.0, Style::default().fg(balance_status.1)),
         ]),
         Line::from(vec![
-            Span::styled("  (Ideal: 45-55%)", Style::default().fg(Color::DarkGray)),
+            Span::styled("  (Ideal: 45-55%)", Style::default().fg(theme.muted)),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("  Runs {}+:     ", LONG_RUN_THRESHOLD),
+                Style::default().fg(theme.label),
+            ),
+            Span::styled(
+                format!("{:>5.1}%", runs.pct_runs_at_least(LONG_RUN_THRESHOLD)),
+                Style::default().fg(theme.text),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Longest run: ", Style::default().fg(theme.label)),
+            Span::styled(
+                match runs.longest_run_hand {
+                    Some(Hand::Left) => format!("{:>5} (L)", runs.longest_run),
+                    Some(Hand::Right) => format!("{:>5} (R)", runs.longest_run),
+                    None => format!("{:>5}", "-"),
+                },
+                Style::default().fg(theme.text),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Alternate:   ", Style::default().fg(theme.label)),
+            Span::styled(
+                format_median(rhythm.alternating_median_ms),
+                Style::default().fg(theme.text),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Same hand:   ", Style::default().fg(theme.label)),
+            Span::styled(
+                format_median(rhythm.same_hand_median_ms),
+                Style::default().fg(theme.text),
+            ),
+            match rhythm.ratio() {
+                // Alternation should be the faster of the two.
+                Some(ratio) => Span::styled(
+                    format!(" ({:.2}x)", ratio),
+                    Style::default().fg(if ratio >= 1.0 { theme.good } else { theme.warn }),
+                ),
+                None => Span::raw(""),
+            },
         ]),
     ];
 
@@ -131,43 +241,59 @@ fn render_hand_balance(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, inner);
 }
 
+fn format_median(median_ms: Option<i64>) -> String {
+    match median_ms {
+        Some(ms) => format!("{:>5}ms", ms),
+        None => format!("{:>7}", "-"),
+    }
+}
+
 fn render_same_finger_bigrams(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .title(" Same-Finger Bigrams ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
-    let bigram_stats = app.get_bigram_finger_stats();
+    let same_finger = app.get_same_finger();
+    let scissors = app.get_scissors();
 
     let mut items = vec![
         ListItem::new(Line::from(vec![
-            Span::styled("Same Finger: ", Style::default().fg(Color::Gray)),
+            Span::styled("Same Finger: ", Style::default().fg(theme.label)),
             Span::styled(
-                format!("{:.1}%", bigram_stats.same_finger_pct),
-                Style::default().fg(if bigram_stats.same_finger_pct > 10.0 {
-                    Color::Red
+                format!("{:.1}%", same_finger.same_finger_pct()),
+                Style::default().fg(if same_finger.same_finger_pct() > 10.0 {
+                    theme.bad
                 } else {
-                    Color::Green
+                    theme.good
                 }),
             ),
         ])),
         ListItem::new(Line::from(vec![
-            Span::styled("Alternating: ", Style::default().fg(Color::Gray)),
+            Span::styled("Alternating: ", Style::default().fg(theme.label)),
+            Span::styled(
+                format!("{:.1}%", same_finger.alternation_pct()),
+                Style::default().fg(theme.good),
+            ),
+        ])),
+        ListItem::new(Line::from(vec![
+            Span::styled("Scissors:    ", Style::default().fg(theme.label)),
             Span::styled(
-                format!("{:.1}%", bigram_stats.alternation_pct),
-                Style::default().fg(Color::Green),
+                format!("{:.1}%", scissors.scissor_pct()),
+                Style::default().fg(theme.text),
             ),
         ])),
         ListItem::new(Line::from("")),
         ListItem::new(Line::from(vec![
-            Span::styled("Worst (same finger):", Style::default().fg(Color::Gray)),
+            Span::styled("Worst (same finger):", Style::default().fg(theme.label)),
         ])),
     ];
 
-    for (bigram, count) in bigram_stats.worst_same_finger.iter().take(4) {
+    for bigram in same_finger.worst.iter().take(4) {
         items.push(ListItem::new(Line::from(vec![
-            Span::styled(format!("  {} ", bigram), Style::default().fg(Color::Yellow)),
-            Span::styled(format!("{}", count), Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("  {} ", bigram.display), Style::default().fg(theme.warn)),
+            Span::styled(format!("{}", bigram.count), Style::default().fg(theme.muted)),
         ])));
     }
 
@@ -1,8 +1,10 @@
+pub mod empty;
 mod overview;
 mod trends;
 mod fingers;
 mod timing;
 
+pub use empty::render_empty;
 pub use overview::render_overview;
 pub use trends::render_trends;
 pub use fingers::render_fingers;
@@ -1,13 +1,13 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph},
     Frame,
 };
 
+use crate::models::keycode::KeyCode;
 use crate::tui::app::App;
-use crate::tui::keyboard_layout::QwertyLayout;
 use crate::tui::widgets::KeyboardHeatmap;
 
 pub fn render_overview(f: &mut Frame, app: &App, area: Rect) {
@@ -24,68 +24,106 @@ pub fn render_overview(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_keyboard_section(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
-        .title(" Keyboard Heatmap ")
+        .title(format!(" Keyboard Heatmap [{}] ", app.heatmap_metric.label()))
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let layout = QwertyLayout::new();
-    let frequencies = app.get_key_frequencies();
-    let heatmap = KeyboardHeatmap::new(&layout, &frequencies);
+    let values = app.get_heatmap_values();
+    let heatmap = KeyboardHeatmap::new(app.layout.as_ref(), &values)
+        .metric(app.heatmap_metric)
+        .cap_percentile(app.heatmap_cap_percentile)
+        .theme(app.theme);
     f.render_widget(heatmap, inner);
 }
 
 fn render_stats_section(f: &mut Frame, app: &App, area: Rect) {
+    let area = match app.get_daily_goal_progress() {
+        Some((today, goal)) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(0)])
+                .split(area);
+            render_daily_goal(f, app, rows[0], today, goal);
+            rows[1]
+        }
+        None => area,
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Percentage(50),
-            Constraint::Percentage(50),
+            Constraint::Percentage(38),
+            Constraint::Percentage(24),
+            Constraint::Percentage(38),
         ])
         .split(area);
 
     render_top_keys(f, app, chunks[0]);
-    render_stats_box(f, app, chunks[1]);
+    render_top_flows(f, app, chunks[1]);
+    render_stats_box(f, app, chunks[2]);
+}
+
+fn render_daily_goal(f: &mut Frame, app: &App, area: Rect, today: u64, goal: u64) {
+    let theme = app.theme;
+    let block = Block::default()
+        .title(" Today's Goal ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.muted));
+
+    let color = if today >= goal { theme.good } else { theme.accent };
+    let gauge = Gauge::default()
+        .block(block)
+        .gauge_style(Style::default().fg(color))
+        .ratio((today as f64 / goal as f64).min(1.0))
+        .label(format!("{} / {}", format_grouped(today), format_grouped(goal)));
+    f.render_widget(gauge, area);
 }
 
 fn render_top_keys(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
+    let title = if app.search_query.is_empty() {
+        " Top Keys ".to_string()
+    } else {
+        format!(" Top Keys [/{}] ", app.search_query)
+    };
     let block = Block::default()
-        .title(" Top Keys ")
+        .title(title)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
-    let top_keys = app.get_top_keys(10);
+    let top_keys = app.get_top_keys_ranked(10);
 
     let items: Vec<ListItem> = top_keys
         .iter()
-        .enumerate()
-        .map(|(i, (name, count, pct))| {
+        .map(|(rank, (name, count, pct))| {
             let bar_width = ((pct / 20.0) * 10.0) as usize;
             let bar: String = "█".repeat(bar_width.min(10));
             
             let line = Line::from(vec![
                 Span::styled(
-                    format!("{:2}. ", i + 1),
-                    Style::default().fg(Color::DarkGray),
+                    format!("{:2}. ", rank),
+                    Style::default().fg(theme.muted),
                 ),
                 Span::styled(
                     format!("{:8}", name),
-                    Style::default().fg(Color::White),
+                    Style::default().fg(theme.text),
                 ),
                 Span::styled(
                     format!("{:>8} ", count),
-                    Style::default().fg(Color::Gray),
+                    Style::default().fg(theme.label),
                 ),
                 Span::styled(
                     format!("({:>5.2}%) ", pct),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(theme.muted),
                 ),
                 Span::styled(
                     bar,
-                    Style::default().fg(Color::White),
+                    Style::default().fg(theme.text),
                 ),
             ]);
             ListItem::new(line)
@@ -96,57 +134,113 @@ fn render_top_keys(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(list, area);
 }
 
+/// Top transitions with the direction the hand travels on the layout.
+fn render_top_flows(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
+    let block = Block::default()
+        .title(" Top Transitions ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.muted));
+
+    let items: Vec<ListItem> = app
+        .get_top_flows(10)
+        .iter()
+        .map(|flow| {
+            let names = format!(
+                "{} {} {}",
+                KeyCode(flow.bigram.first_key),
+                flow.arrow,
+                KeyCode(flow.bigram.second_key)
+            );
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<14}", names), Style::default().fg(theme.text)),
+                Span::styled(
+                    format!("{:>5.1}%", flow.bigram.percentage),
+                    Style::default().fg(theme.muted),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).block(block);
+    f.render_widget(list, area);
+}
+
 fn render_stats_box(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .title(" Statistics ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let stats = app.get_stats();
 
     let text = vec![
         Line::from(vec![
-            Span::styled("Total Presses:  ", Style::default().fg(Color::Gray)),
+            Span::styled("Total Presses:  ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>12}", format_number(stats.total_presses)),
-                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(vec![
-            Span::styled("Daily Average:  ", Style::default().fg(Color::Gray)),
+            Span::styled("Daily Average:  ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>12}", format_number(stats.daily_average)),
-                Style::default().fg(Color::White),
+                Style::default().fg(theme.text),
             ),
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Est. WPM:       ", Style::default().fg(Color::Gray)),
+            Span::styled("Est. WPM:       ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>12}", stats.estimated_wpm),
-                Style::default().fg(Color::White),
+                Style::default().fg(theme.text),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:<16}", format!("Last {}s WPM:", app.live_wpm_window_secs())),
+                Style::default().fg(theme.label),
             ),
+            match app.live_wpm() {
+                Some(wpm) => Span::styled(
+                    format!("{:>12.0}", wpm),
+                    Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+                ),
+                None => Span::styled(
+                    format!("{:>12}", "warming up"),
+                    Style::default().fg(theme.muted),
+                ),
+            },
         ]),
         Line::from(vec![
-            Span::styled("Median Delay:   ", Style::default().fg(Color::Gray)),
+            Span::styled("Median Delay:   ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>10}ms", stats.median_delay_ms),
-                Style::default().fg(Color::White),
+                Style::default().fg(theme.text),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Layout Fit:     ", Style::default().fg(theme.label)),
+            Span::styled(
+                format!("{:>8.0}/100", app.get_layout_score().total),
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Time Range:     ", Style::default().fg(Color::Gray)),
+            Span::styled("Time Range:     ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{}", app.time_range.label()),
-                Style::default().fg(Color::Cyan),
+                Style::default().fg(theme.accent),
             ),
         ]),
         Line::from(vec![
-            Span::styled("Days Active:    ", Style::default().fg(Color::Gray)),
+            Span::styled("Days Active:    ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>12}", stats.days_active),
-                Style::default().fg(Color::White),
+                Style::default().fg(theme.text),
             ),
         ]),
     ];
@@ -164,3 +258,16 @@ fn format_number(n: u64) -> String {
         n.to_string()
     }
 }
+
+/// `12430` -> `12,430`.
+fn format_grouped(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
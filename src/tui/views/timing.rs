@@ -1,12 +1,13 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{BarChart, Block, Borders, List, ListItem, Paragraph},
     Frame,
 };
 
-use crate::tui::app::App;
+use crate::tui::app::{filter_ranked, App};
+use crate::tui::views::empty::render_no_data;
 
 pub fn render_timing(f: &mut Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
@@ -34,10 +35,11 @@ pub fn render_timing(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_timing_histogram(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .title(" Inter-Key Timing Distribution ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
@@ -45,9 +47,7 @@ fn render_timing_histogram(f: &mut Frame, app: &App, area: Rect) {
     let histogram = app.get_timing_histogram();
     
     if histogram.is_empty() {
-        let msg = Paragraph::new("No timing data available")
-            .style(Style::default().fg(Color::DarkGray));
-        f.render_widget(msg, inner);
+        render_no_data(f, app, inner);
         return;
     }
 
@@ -56,77 +56,80 @@ fn render_timing_histogram(f: &mut Frame, app: &App, area: Rect) {
         .map(|(label, count)| (label.as_str(), *count))
         .collect();
 
+    // Narrower bars when many buckets are configured, so they all fit.
+    let bar_width = (inner.width / data.len() as u16).saturating_sub(1).clamp(1, 5);
     let chart = BarChart::default()
         .data(&data)
-        .bar_width(5)
+        .bar_width(bar_width)
         .bar_gap(1)
-        .bar_style(Style::default().fg(Color::White))
-        .value_style(Style::default().fg(Color::Gray));
+        .bar_style(Style::default().fg(theme.text))
+        .value_style(Style::default().fg(theme.label));
 
     f.render_widget(chart, inner);
 }
 
 fn render_speed_metrics(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .title(" Speed Metrics ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let metrics = app.get_speed_metrics();
 
     let consistency_color = match metrics.consistency.as_str() {
-        "Excellent" => Color::Green,
-        "Good" => Color::Cyan,
-        "Fair" => Color::Yellow,
-        _ => Color::Red,
+        "Excellent" => theme.good,
+        "Good" => theme.accent,
+        "Fair" => theme.warn,
+        _ => theme.bad,
     };
 
     let text = vec![
         Line::from(vec![
-            Span::styled("Mean Inter-Key:   ", Style::default().fg(Color::Gray)),
+            Span::styled("Mean Inter-Key:   ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>6.0}ms", metrics.mean_ms),
-                Style::default().fg(Color::White),
+                Style::default().fg(theme.text),
             ),
         ]),
         Line::from(vec![
-            Span::styled("Median:           ", Style::default().fg(Color::Gray)),
+            Span::styled("Median:           ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>6}ms", metrics.median_ms),
-                Style::default().fg(Color::White),
+                Style::default().fg(theme.text),
             ),
         ]),
         Line::from(vec![
-            Span::styled("P95:              ", Style::default().fg(Color::Gray)),
+            Span::styled("P95:              ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>6}ms", metrics.p95_ms),
-                Style::default().fg(Color::White),
+                Style::default().fg(theme.text),
             ),
         ]),
         Line::from(vec![
-            Span::styled("P99:              ", Style::default().fg(Color::Gray)),
+            Span::styled("P99:              ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>6}ms", metrics.p99_ms),
-                Style::default().fg(Color::White),
+                Style::default().fg(theme.text),
             ),
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Estimated WPM:    ", Style::default().fg(Color::Gray)),
+            Span::styled("Estimated WPM:    ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>6}", metrics.estimated_wpm),
-                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(vec![
-            Span::styled("Burst WPM:        ", Style::default().fg(Color::Gray)),
+            Span::styled("Burst WPM:        ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>6}", metrics.burst_wpm),
-                Style::default().fg(Color::Cyan),
+                Style::default().fg(theme.accent),
             ),
         ]),
         Line::from(vec![
-            Span::styled("Consistency:      ", Style::default().fg(Color::Gray)),
+            Span::styled("Consistency:      ", Style::default().fg(theme.label)),
             Span::styled(
                 format!("{:>6}", metrics.consistency),
                 Style::default().fg(consistency_color),
@@ -139,28 +142,27 @@ fn render_speed_metrics(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_fastest_pairs(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .title(" Fastest Pairs ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
-    let pairs = app.get_fastest_pairs();
+    let pairs = filter_ranked(app.get_fastest_pairs(), &app.search_query, |(pair, _, _)| pair);
 
     let header = Line::from(vec![
         Span::styled(
             format!("{:<6} {:>6} {:>6}", "Pair", "Med", "Count"),
-            Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.label).add_modifier(Modifier::BOLD),
         ),
     ]);
 
     let mut items = vec![ListItem::new(header)];
 
-    for (pair, median_ms, count) in pairs.iter().take(8) {
-        let line = Line::from(vec![
-            Span::styled(format!("{:<6}", pair), Style::default().fg(Color::Green)),
-            Span::styled(format!("{:>5}ms", median_ms), Style::default().fg(Color::White)),
-            Span::styled(format!("{:>6}", count), Style::default().fg(Color::DarkGray)),
-        ]);
+    for (_, (pair, median_ms, count)) in pairs.iter().take(8) {
+        let mut spans = vec![Span::styled(format!("{:<6}", pair), Style::default().fg(theme.good))];
+        spans.extend(pair_stat_spans(app, *median_ms, *count));
+        let line = Line::from(spans);
         items.push(ListItem::new(line));
     }
 
@@ -169,31 +171,47 @@ fn render_fastest_pairs(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_slowest_pairs(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .title(" Slowest Pairs ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
-    let pairs = app.get_slowest_pairs();
+    let pairs = filter_ranked(app.get_slowest_pairs(), &app.search_query, |(pair, _, _)| pair);
 
     let header = Line::from(vec![
         Span::styled(
             format!("{:<6} {:>6} {:>6}", "Pair", "Med", "Count"),
-            Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.label).add_modifier(Modifier::BOLD),
         ),
     ]);
 
     let mut items = vec![ListItem::new(header)];
 
-    for (pair, median_ms, count) in pairs.iter().take(8) {
-        let line = Line::from(vec![
-            Span::styled(format!("{:<6}", pair), Style::default().fg(Color::Red)),
-            Span::styled(format!("{:>5}ms", median_ms), Style::default().fg(Color::White)),
-            Span::styled(format!("{:>6}", count), Style::default().fg(Color::DarkGray)),
-        ]);
+    for (_, (pair, median_ms, count)) in pairs.iter().take(8) {
+        let mut spans = vec![Span::styled(format!("{:<6}", pair), Style::default().fg(theme.bad))];
+        spans.extend(pair_stat_spans(app, *median_ms, *count));
+        let line = Line::from(spans);
         items.push(ListItem::new(line));
     }
 
     let list = List::new(items).block(block);
     f.render_widget(list, area);
 }
+
+/// Median and count cells for a pair row. Pairs below the low-confidence
+/// threshold get a dimmed median and a `?` after the count.
+fn pair_stat_spans(app: &App, median_ms: i64, count: u64) -> [Span<'static>; 2] {
+    let theme = app.theme;
+    if app.is_low_confidence(count) {
+        [
+            Span::styled(format!("{:>5}ms", median_ms), Style::default().fg(theme.muted)),
+            Span::styled(format!("{:>6}?", count), Style::default().fg(theme.warn)),
+        ]
+    } else {
+        [
+            Span::styled(format!("{:>5}ms", median_ms), Style::default().fg(theme.text)),
+            Span::styled(format!("{:>6}", count), Style::default().fg(theme.muted)),
+        ]
+    }
+}
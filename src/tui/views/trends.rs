@@ -1,12 +1,13 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph, Sparkline},
     Frame,
 };
 
-use crate::tui::app::App;
+use crate::tui::app::{App, DAILY_AVERAGE_DAYS};
+use crate::tui::views::empty::render_no_data;
 
 pub fn render_trends(f: &mut Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
@@ -14,20 +15,147 @@ pub fn render_trends(f: &mut Frame, app: &App, area: Rect) {
         .constraints([
             Constraint::Length(10),
             Constraint::Min(8),
+            Constraint::Length(APP_TREND_COUNT as u16 + 2),
+            Constraint::Length(4),
             Constraint::Length(8),
         ])
         .split(area);
 
     render_daily_chart(f, app, chunks[0]);
     render_weekly_comparison(f, app, chunks[1]);
-    render_app_distribution(f, app, chunks[2]);
+    render_app_trends(f, app, chunks[2]);
+    render_hand_balance_trend(f, app, chunks[3]);
+    render_app_distribution(f, app, chunks[4]);
 }
 
+const APP_TREND_COUNT: usize = 3;
+const APP_LABEL_WIDTH: u16 = 29;
+
+/// One sparkline row per top app. Each line is scaled to its own peak so a
+/// dominant app doesn't flatten the others; the share column keeps the
+/// absolute proportions visible.
+fn render_app_trends(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
+    let block = Block::default()
+        .title(" Top Apps Over Time ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.muted));
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let trends = app.get_app_trends(APP_TREND_COUNT);
+    if trends.is_empty() {
+        render_no_data(f, app, inner);
+        return;
+    }
+
+    let colors = [theme.accent, theme.right_hand, theme.good];
+    for (i, trend) in trends.iter().enumerate() {
+        let y = inner.y + i as u16;
+        if y >= inner.y + inner.height {
+            break;
+        }
+
+        let label = Line::from(vec![
+            Span::styled(
+                format!("{:<20}", truncate_app_name(&trend.application)),
+                Style::default().fg(theme.text),
+            ),
+            Span::styled(format!("{:>6.1}% ", trend.share_pct), Style::default().fg(theme.label)),
+        ]);
+        let label_width = APP_LABEL_WIDTH.min(inner.width);
+        f.render_widget(Paragraph::new(label), Rect::new(inner.x, y, label_width, 1));
+
+        let peak = trend.daily.iter().max().copied().unwrap_or(0).max(1);
+        let sparkline = Sparkline::default()
+            .data(&trend.daily)
+            .max(peak)
+            .style(Style::default().fg(colors[i % colors.len()]));
+        let spark_area = Rect::new(
+            inner.x + label_width,
+            y,
+            inner.width.saturating_sub(label_width),
+            1,
+        );
+        f.render_widget(sparkline, spark_area);
+    }
+}
+
+/// A diverging chart split over two rows: each day's bar goes on the row of
+/// the hand that did more than half the typing, sized by how far past 50%.
+fn render_hand_balance_trend(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
+    let block = Block::default()
+        .title(" Hand Balance by Day (lean past 50/50) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.muted));
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let days = app.get_hand_balance_trend();
+    if days.is_empty() {
+        render_no_data(f, app, inner);
+        return;
+    }
+
+    // Tenths of a percentage point, so a 50.4/49.6 day still shows.
+    let left: Vec<u64> = days
+        .iter()
+        .map(|d| ((d.left_pct - 50.0) * 10.0).max(0.0).round() as u64)
+        .collect();
+    let right: Vec<u64> = days
+        .iter()
+        .map(|d| ((50.0 - d.left_pct) * 10.0).max(0.0).round() as u64)
+        .collect();
+    let peak = left.iter().chain(&right).max().copied().unwrap_or(0).max(1);
+    let average = days.iter().map(|d| d.left_pct).sum::<f64>() / days.len() as f64;
+
+    let rows = [
+        ("Left", average, &left, theme.left_hand),
+        ("Right", 100.0 - average, &right, theme.right_hand),
+    ];
+    for (i, (hand, share, data, color)) in rows.into_iter().enumerate() {
+        let y = inner.y + i as u16;
+        if y >= inner.y + inner.height {
+            break;
+        }
+
+        let label = Line::from(vec![
+            Span::styled(format!("{:<20}", hand), Style::default().fg(theme.text)),
+            Span::styled(format!("{:>6.1}% ", share), Style::default().fg(theme.label)),
+        ]);
+        let label_width = APP_LABEL_WIDTH.min(inner.width);
+        f.render_widget(Paragraph::new(label), Rect::new(inner.x, y, label_width, 1));
+
+        let sparkline = Sparkline::default()
+            .data(data)
+            .max(peak)
+            .style(Style::default().fg(color));
+        let spark_area = Rect::new(
+            inner.x + label_width,
+            y,
+            inner.width.saturating_sub(label_width),
+            1,
+        );
+        f.render_widget(sparkline, spark_area);
+    }
+}
+
+/// Raw daily counts, with their moving average as a second sparkline below
+/// on the same scale when `App::smooth_daily` is on.
 fn render_daily_chart(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
+    let title = if app.smooth_daily {
+        format!(" Daily Key Presses (below: {}-day average; s: hide) ", DAILY_AVERAGE_DAYS)
+    } else {
+        format!(" Daily Key Presses (s: {}-day average) ", DAILY_AVERAGE_DAYS)
+    };
     let block = Block::default()
-        .title(" Daily Key Presses ")
+        .title(title)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
@@ -35,9 +163,7 @@ fn render_daily_chart(f: &mut Frame, app: &App, area: Rect) {
     let daily_counts = app.get_daily_counts();
     
     if daily_counts.is_empty() {
-        let msg = Paragraph::new("No data available")
-            .style(Style::default().fg(Color::DarkGray));
-        f.render_widget(msg, inner);
+        render_no_data(f, app, inner);
         return;
     }
 
@@ -47,16 +173,33 @@ fn render_daily_chart(f: &mut Frame, app: &App, area: Rect) {
     let sparkline = Sparkline::default()
         .data(&data)
         .max(max_count)
-        .style(Style::default().fg(Color::White));
+        .style(Style::default().fg(theme.text));
 
-    f.render_widget(sparkline, inner);
+    if !app.smooth_daily {
+        f.render_widget(sparkline, inner);
+        return;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+        .split(inner);
+    let average: Vec<u64> = app.get_daily_average().iter().map(|avg| avg.round() as u64).collect();
+    let smoothed = Sparkline::default()
+        .data(&average)
+        .max(max_count)
+        .style(Style::default().fg(theme.accent));
+
+    f.render_widget(sparkline, rows[0]);
+    f.render_widget(smoothed, rows[1]);
 }
 
 fn render_weekly_comparison(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .title(" Top Keys Over Time ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
     let weekly_data = app.get_weekly_comparison();
 
@@ -64,7 +207,7 @@ fn render_weekly_comparison(f: &mut Frame, app: &App, area: Rect) {
         Span::styled(
             format!("{:<8} {:>8} {:>8} {:>8} {:>8}  {:<10}",
                 "Key", "Week 1", "Week 2", "Week 3", "Week 4", "Trend"),
-            Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.label).add_modifier(Modifier::BOLD),
         ),
     ]);
 
@@ -72,28 +215,28 @@ fn render_weekly_comparison(f: &mut Frame, app: &App, area: Rect) {
 
     for (key_name, percentages, trend) in weekly_data.iter().take(8) {
         let trend_style = match trend.as_str() {
-            t if t.starts_with('↗') => Style::default().fg(Color::Green),
-            t if t.starts_with('↘') => Style::default().fg(Color::Red),
-            _ => Style::default().fg(Color::Gray),
+            t if t.starts_with('↗') => Style::default().fg(theme.good),
+            t if t.starts_with('↘') => Style::default().fg(theme.bad),
+            _ => Style::default().fg(theme.label),
         };
 
         let line = Line::from(vec![
-            Span::styled(format!("{:<8}", key_name), Style::default().fg(Color::White)),
+            Span::styled(format!("{:<8}", key_name), Style::default().fg(theme.text)),
             Span::styled(
                 format!(" {:>7.1}%", percentages.first().unwrap_or(&0.0)),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(theme.label),
             ),
             Span::styled(
                 format!(" {:>7.1}%", percentages.get(1).unwrap_or(&0.0)),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(theme.label),
             ),
             Span::styled(
                 format!(" {:>7.1}%", percentages.get(2).unwrap_or(&0.0)),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(theme.label),
             ),
             Span::styled(
                 format!(" {:>7.1}%", percentages.get(3).unwrap_or(&0.0)),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(theme.label),
             ),
             Span::styled(format!("  {:<10}", trend), trend_style),
         ]);
@@ -104,25 +247,37 @@ fn render_weekly_comparison(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(list, area);
 }
 
+/// Share of presses and typing speed side by side. Apps with too few
+/// intervals to time show a dash rather than a misleading WPM.
 fn render_app_distribution(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
-        .title(" Per-App Distribution ")
+        .title(" Per-App Distribution & Speed ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(theme.muted));
 
-    let apps = app.get_app_distribution();
+    let apps = app.get_app_speeds(5);
 
     let items: Vec<ListItem> = apps
         .iter()
-        .take(5)
-        .map(|(name, pct)| {
-            let bar_width = (pct / 2.0) as usize;
-            let bar: String = "█".repeat(bar_width.min(30));
-            
+        .map(|speed| {
+            let bar_width = (speed.share_pct / 2.0) as usize;
+            let bar = format!("{:<25}", "█".repeat(bar_width.min(25)));
+            let wpm = speed
+                .wpm
+                .map_or_else(|| "—".to_string(), |wpm| format!("{:.0}", wpm));
+
             let line = Line::from(vec![
-                Span::styled(format!("{:<20}", truncate_app_name(name)), Style::default().fg(Color::White)),
-                Span::styled(format!("{:>6.1}% ", pct), Style::default().fg(Color::Gray)),
-                Span::styled(bar, Style::default().fg(Color::White)),
+                Span::styled(
+                    format!("{:<20}", truncate_app_name(&speed.application)),
+                    Style::default().fg(theme.text),
+                ),
+                Span::styled(
+                    format!("{:>6.1}% ", speed.share_pct),
+                    Style::default().fg(theme.label),
+                ),
+                Span::styled(bar, Style::default().fg(theme.text)),
+                Span::styled(format!(" {:>4} WPM", wpm), Style::default().fg(theme.accent)),
             ]);
             ListItem::new(line)
         })
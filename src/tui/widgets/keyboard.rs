@@ -7,34 +7,118 @@ use ratatui::{
     widgets::Widget,
 };
 
-use crate::tui::keyboard_layout::{QwertyLayout, Finger};
+use crate::analysis::frequency::heat_reference;
+use crate::models::keyboard_layout::{Finger, KeyboardLayout};
+use crate::tui::theme::Theme;
+
+/// What the heatmap values mean, which decides how they are scaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeatmapMetric {
+    /// Share of key presses. Scaled against the busiest key, or a percentile
+    /// cap if one is set.
+    #[default]
+    Frequency,
+    /// Median hold duration per key, in ms.
+    HoldTime,
+    /// Median time from this key to the next press, in ms.
+    OutgoingSpeed,
+}
+
+impl HeatmapMetric {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HeatmapMetric::Frequency => "Frequency",
+            HeatmapMetric::HoldTime => "Hold Time",
+            HeatmapMetric::OutgoingSpeed => "Outgoing Speed",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            HeatmapMetric::Frequency => HeatmapMetric::HoldTime,
+            HeatmapMetric::HoldTime => HeatmapMetric::OutgoingSpeed,
+            HeatmapMetric::OutgoingSpeed => HeatmapMetric::Frequency,
+        }
+    }
+
+    fn legend(&self) -> &'static str {
+        match self {
+            HeatmapMetric::Frequency => "░Low ▒Med ▓High █Max",
+            HeatmapMetric::HoldTime | HeatmapMetric::OutgoingSpeed => "░Fast ▒ ▓ █Slow",
+        }
+    }
+
+    /// Maps a value to 0..=1. Frequency is relative to `max` (values above a
+    /// capped `max` saturate at 1); timings
+    /// are spread between the fastest and slowest key so small differences
+    /// stay visible, with the fastest key still drawn at the lowest shade.
+    fn normalize(&self, value: f64, min: f64, max: f64) -> f64 {
+        match self {
+            HeatmapMetric::Frequency => {
+                if max > 0.0 {
+                    (value / max).min(1.0)
+                } else {
+                    0.0
+                }
+            }
+            HeatmapMetric::HoldTime | HeatmapMetric::OutgoingSpeed => {
+                if max > min {
+                    0.1 + 0.9 * (value - min) / (max - min)
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
 
 pub struct KeyboardHeatmap<'a> {
-    layout: &'a QwertyLayout,
-    frequencies: &'a HashMap<u32, f64>,
+    layout: &'a dyn KeyboardLayout,
+    values: &'a HashMap<u32, f64>,
+    metric: HeatmapMetric,
     show_fingers: bool,
+    cap_percentile: Option<f64>,
+    theme: Theme,
 }
 
 impl<'a> KeyboardHeatmap<'a> {
-    pub fn new(layout: &'a QwertyLayout, frequencies: &'a HashMap<u32, f64>) -> Self {
+    /// `values` are per-key numbers in the units of the metric, frequency
+    /// percentages unless `metric` says otherwise.
+    pub fn new(layout: &'a dyn KeyboardLayout, values: &'a HashMap<u32, f64>) -> Self {
         Self {
             layout,
-            frequencies,
+            values,
+            metric: HeatmapMetric::default(),
             show_fingers: false,
+            cap_percentile: None,
+            theme: Theme::default(),
         }
     }
 
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn metric(mut self, metric: HeatmapMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Full frequency intensity at this percentile of key values instead of
+    /// the busiest key; see `heat_reference`.
+    pub fn cap_percentile(mut self, cap_percentile: Option<f64>) -> Self {
+        self.cap_percentile = cap_percentile;
+        self
+    }
+
     pub fn show_fingers(mut self, show: bool) -> Self {
         self.show_fingers = show;
         self
     }
 
-    fn frequency_to_char(percentage: f64, max_percentage: f64) -> char {
-        if max_percentage <= 0.0 {
-            return ' ';
-        }
-        let normalized = (percentage / max_percentage) * 100.0;
-        match normalized {
+    fn level_to_char(level: f64) -> char {
+        match level * 100.0 {
             p if p >= 75.0 => '█',
             p if p >= 50.0 => '▓',
             p if p >= 25.0 => '▒',
@@ -43,14 +127,17 @@ impl<'a> KeyboardHeatmap<'a> {
         }
     }
 
-    fn finger_to_gray(finger: Finger) -> Color {
-        match finger {
-            Finger::LeftPinky | Finger::RightPinky => Color::Rgb(80, 80, 80),
-            Finger::LeftRing | Finger::RightRing => Color::Rgb(120, 120, 120),
-            Finger::LeftMiddle | Finger::RightMiddle => Color::Rgb(160, 160, 160),
-            Finger::LeftIndex | Finger::RightIndex => Color::Rgb(200, 200, 200),
-            Finger::Thumb => Color::Rgb(220, 220, 220),
-        }
+    fn finger_to_gray(&self, finger: Finger) -> Color {
+        let level = match finger {
+            Finger::LeftPinky | Finger::RightPinky => 80,
+            Finger::LeftRing | Finger::RightRing => 120,
+            Finger::LeftMiddle | Finger::RightMiddle => 160,
+            Finger::LeftIndex | Finger::RightIndex => 200,
+            Finger::Thumb => 220,
+        };
+        self.theme
+            .shade(f64::from(level) / 255.0)
+            .unwrap_or(self.theme.text)
     }
 }
 
@@ -60,59 +147,82 @@ impl<'a> Widget for KeyboardHeatmap<'a> {
             return;
         }
 
-        let max_freq = self
-            .frequencies
-            .values()
-            .copied()
-            .fold(0.0_f64, f64::max);
-
-        let start_x = area.x + 1;
-        let mut y = area.y;
-
-        for row in &self.layout.rows {
-            let mut x = start_x;
-
-            if row.len() == 1 && row[0].label == "␣" {
-                x = start_x + 8;
+        let max_value = match self.metric {
+            HeatmapMetric::Frequency => {
+                heat_reference(self.values.values().copied(), self.cap_percentile)
             }
+            _ => self.values.values().copied().fold(0.0_f64, f64::max),
+        };
+        let min_value = self.values.values().copied().fold(f64::INFINITY, f64::min);
 
-            for key in row {
-                let freq = self.frequencies.get(&key.keycode).copied().unwrap_or(0.0);
-                let heat_char = Self::frequency_to_char(freq, max_freq);
-
-                let style = if self.show_fingers {
-                    Style::default().fg(Self::finger_to_gray(key.finger))
+        let start_x = area.x + 1;
+        let rows = self.layout.rows().len() as u16;
+
+        for (row, offset, key) in self.layout.key_positions() {
+            let x = start_x + offset;
+            let y = area.y + row as u16;
+            let level = match self.values.get(&key.keycode) {
+                Some(&value) => self.metric.normalize(value, min_value, max_value),
+                None => 0.0,
+            };
+            let heat_char = Self::level_to_char(level);
+
+            let style = if self.show_fingers {
+                Style::default().fg(self.finger_to_gray(key.finger))
+            } else {
+                Style::default().fg(self.theme.text)
+            };
+
+            if x + key.width <= area.x + area.width && y < area.y + area.height {
+                let display = if key.width >= 3 {
+                    format!("{}{}", heat_char, key.label)
                 } else {
-                    Style::default().fg(Color::White)
+                    key.label.to_string()
                 };
 
-                if x + key.width <= area.x + area.width && y < area.y + area.height {
-                    let display = if key.width >= 3 {
-                        format!("{}{}", heat_char, key.label)
-                    } else {
-                        key.label.to_string()
-                    };
-
-                    buf.set_string(x, y, &display, style);
+                buf.set_string(x, y, &display, style);
 
-                    if freq > 0.0 && key.width >= 2 {
-                        let heat_style = Style::default().fg(Color::Rgb(
-                            ((freq / max_freq) * 255.0) as u8,
-                            ((freq / max_freq) * 255.0) as u8,
-                            ((freq / max_freq) * 255.0) as u8,
-                        ));
-                        buf.set_string(x, y, &heat_char.to_string(), heat_style);
-                    }
+                if level > 0.0 && key.width >= 2 {
+                    let heat_style = match self.theme.shade(level) {
+                        Some(color) => Style::default().fg(color),
+                        None => Style::default(),
+                    };
+                    buf.set_string(x, y, &heat_char.to_string(), heat_style);
                 }
-
-                x += key.width + 1;
             }
-            y += 1;
         }
 
+        let y = area.y + rows;
         if y < area.y + area.height {
-            let legend = "░Low ▒Med ▓High █Max";
-            buf.set_string(start_x, y, legend, Style::default().fg(Color::DarkGray));
+            buf.set_string(start_x, y, self.metric.legend(), Style::default().fg(self.theme.muted));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frequency_scales_against_max() {
+        let metric = HeatmapMetric::Frequency;
+        assert_eq!(metric.normalize(5.0, 1.0, 10.0), 0.5);
+        assert_eq!(metric.normalize(0.0, 0.0, 0.0), 0.0);
+        // Above a percentile cap, keys saturate instead of exceeding full scale.
+        assert_eq!(metric.normalize(50.0, 1.0, 9.0), 1.0);
+    }
+
+    #[test]
+    fn test_timing_spreads_fastest_to_slowest() {
+        let metric = HeatmapMetric::HoldTime;
+        assert!((metric.normalize(80.0, 80.0, 160.0) - 0.1).abs() < 1e-9);
+        assert!((metric.normalize(160.0, 80.0, 160.0) - 1.0).abs() < 1e-9);
+        assert!(KeyboardHeatmap::level_to_char(metric.normalize(80.0, 80.0, 160.0)) != ' ');
+    }
+
+    #[test]
+    fn test_metric_cycles() {
+        let metric = HeatmapMetric::Frequency;
+        assert_eq!(metric.next().next().next(), metric);
+    }
+}
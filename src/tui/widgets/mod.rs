@@ -1,3 +1,3 @@
 mod keyboard;
 
-pub use keyboard::KeyboardHeatmap;
+pub use keyboard::{HeatmapMetric, KeyboardHeatmap};